@@ -0,0 +1,145 @@
+//! Compares serializing documents through a [`zdoc::Builder`] with
+//! serializing them directly into a reused [`BuildCache`].
+//!
+//! Run with `cargo bench --features serde --bench serialize`. Prints the
+//! number of allocations and the time per document for each path.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::BTreeMap,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use zdoc::{
+    builder::BuildCache,
+    serde::{SerializerOptions, to_document_with, to_document_with_cache},
+};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(serde::Serialize)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(serde::Serialize)]
+struct User {
+    id: u64,
+    name: String,
+    email: String,
+    status: Status,
+    roles: Vec<String>,
+    scores: Vec<f64>,
+    settings: BTreeMap<String, String>,
+}
+
+#[derive(serde::Serialize)]
+struct Response {
+    request_id: String,
+    page: u32,
+    users: Vec<User>,
+}
+
+fn response(users: usize) -> Response {
+    Response {
+        request_id: "0af7651916cd43dd8448eb211c80319c".to_owned(),
+        page: 1,
+        users: (0..users)
+            .map(|i| User {
+                id: i as u64,
+                name: format!("user{i}"),
+                email: format!("user{i}@example.com"),
+                status: if i % 3 == 0 {
+                    Status::Suspended {
+                        reason: "billing".to_owned(),
+                    }
+                } else {
+                    Status::Active
+                },
+                roles: vec!["reader".to_owned(), "writer".to_owned()],
+                scores: vec![0.5, 1.5, 2.5],
+                settings: BTreeMap::from([
+                    ("theme".to_owned(), "dark".to_owned()),
+                    ("locale".to_owned(), "en-US".to_owned()),
+                ]),
+            })
+            .collect(),
+    }
+}
+
+struct Measurement {
+    allocations: usize,
+    bytes: usize,
+    time: Duration,
+}
+
+fn measure(iterations: u32, mut f: impl FnMut()) -> Measurement {
+    // Warm up caches, including the `BuildCache`.
+    f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let time = start.elapsed();
+    Measurement {
+        allocations: (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / iterations as usize,
+        bytes: (BYTES.load(Ordering::Relaxed) - bytes) / iterations as usize,
+        time: time / iterations,
+    }
+}
+
+fn report(name: &str, m: &Measurement) {
+    println!(
+        "  {name:<24} {:>8} allocs {:>10} bytes {:>12.2?}",
+        m.allocations, m.bytes, m.time
+    );
+}
+
+fn main() {
+    let options = SerializerOptions::new();
+    for users in [1, 10, 100, 1000] {
+        let value = response(users);
+        let iterations = (10_000 / users as u32).max(10);
+        println!("{users} users, per document:");
+
+        let m = measure(iterations, || {
+            black_box(to_document_with(black_box(&value), &options).unwrap());
+        });
+        report("to_document_with", &m);
+
+        let mut cache = BuildCache::default();
+        let m = measure(iterations, || {
+            black_box(to_document_with_cache(black_box(&value), &options, &mut cache).unwrap());
+        });
+        report("to_document_with_cache", &m);
+    }
+}
@@ -0,0 +1,532 @@
+//! ANSI syntax highlighting of text output formats.
+//!
+//! The highlighters are simple lexers that only need to understand the output
+//! produced by the zdoc emitters, so they are forgiving rather than strict:
+//! anything that is not recognized is passed through unstyled.
+
+#![cfg_attr(
+    not(all(feature = "json", feature = "kdl", feature = "yaml", feature = "xml")),
+    allow(dead_code)
+)]
+
+use std::fmt::Write as _;
+
+use anstyle::{AnsiColor, Style};
+
+/// Keys, property names, and node names.
+const KEY: Style = AnsiColor::Blue.on_default().bold();
+/// Special fields, like `$type`, `$items`, and `$value`.
+const SPECIAL: Style = AnsiColor::Magenta.on_default().bold();
+/// Node types, YAML tags, and XML element names.
+const TYPE: Style = AnsiColor::Yellow.on_default().bold();
+const STRING: Style = AnsiColor::Green.on_default();
+const NUMBER: Style = AnsiColor::Cyan.on_default();
+/// Booleans and null.
+const LITERAL: Style = AnsiColor::Red.on_default();
+/// Comments and XML declarations.
+const COMMENT: Style = AnsiColor::BrightBlack.on_default();
+/// Inserted, removed, and modified entries in diffs.
+const INSERTED: Style = AnsiColor::Green.on_default();
+const REMOVED: Style = AnsiColor::Red.on_default();
+const MODIFIED: Style = AnsiColor::Yellow.on_default();
+
+#[derive(Default)]
+struct Painter {
+    out: String,
+}
+
+impl Painter {
+    fn paint(&mut self, style: Style, s: &str) {
+        if !s.is_empty() {
+            _ = write!(self.out, "{}{s}{}", style.render(), style.render_reset());
+        }
+    }
+
+    fn plain(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+}
+
+fn key_style(key: &str) -> Style {
+    if key.trim_start_matches(['"', '\'']).starts_with('$') {
+        SPECIAL
+    } else {
+        KEY
+    }
+}
+
+/// Returns the end of the quoted string starting at `start` (after the closing
+/// quote). Single-quoted strings use YAML escaping (`''`), double-quoted
+/// strings use backslash escapes.
+fn scan_quoted(s: &str, start: usize) -> usize {
+    let bytes = s.as_bytes();
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote == b'"' => i += 2,
+            b if b == quote => {
+                if quote == b'\'' && bytes.get(i + 1) == Some(&b'\'') {
+                    i += 2;
+                    continue;
+                }
+                return i + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the end of the run of bytes starting at `start` that match `pred`.
+///
+/// `pred` must only stop at ASCII bytes, so the result is a char boundary.
+fn scan_while(s: &str, start: usize, pred: impl Fn(u8) -> bool) -> usize {
+    s.as_bytes()[start..]
+        .iter()
+        .position(|&b| !pred(b))
+        .map_or(s.len(), |len| start + len)
+}
+
+/// Returns the position of `pattern` at or after `start`, or the end of `s`.
+fn scan_until(s: &str, start: usize, pattern: &str) -> usize {
+    s[start..]
+        .find(pattern)
+        .map_or(s.len(), |pos| start + pos + pattern.len())
+}
+
+fn skip_spaces(s: &str, start: usize) -> usize {
+    scan_while(s, start, |b| b == b' ' || b == b'\t')
+}
+
+#[cfg(feature = "json")]
+pub fn json(text: &str) -> String {
+    let mut p = Painter::default();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'"' => {
+                let end = scan_quoted(text, i);
+                let token = &text[i..end];
+                let next = scan_while(text, end, |b| b.is_ascii_whitespace());
+                if bytes.get(next) == Some(&b':') {
+                    p.paint(key_style(token), token);
+                } else {
+                    p.paint(STRING, token);
+                }
+                end
+            }
+            b'-' | b'0'..=b'9' => {
+                let end = scan_while(text, i, |b| {
+                    b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E')
+                });
+                p.paint(NUMBER, &text[i..end]);
+                end
+            }
+            b if b.is_ascii_alphabetic() => {
+                let end = scan_while(text, i, |b| b.is_ascii_alphabetic());
+                p.paint(LITERAL, &text[i..end]);
+                end
+            }
+            _ => {
+                let end = scan_while(text, i, |b| {
+                    !matches!(b, b'"' | b'-' | b'0'..=b'9') && !b.is_ascii_alphabetic()
+                });
+                p.plain(&text[i..end]);
+                end
+            }
+        };
+        i = end;
+    }
+    p.out
+}
+
+#[cfg(feature = "yaml")]
+pub fn yaml(text: &str) -> String {
+    let mut p = Painter::default();
+    // Open flow collections (`{` or `[`), which may span multiple lines.
+    let mut flow = Vec::new();
+    for line in text.split_inclusive('\n') {
+        yaml_line(&mut p, line, &mut flow);
+    }
+    p.out
+}
+
+fn yaml_line(p: &mut Painter, line: &str, flow: &mut Vec<u8>) {
+    let bytes = line.as_bytes();
+    let is_flow_indicator = |b: u8| matches!(b, b',' | b'[' | b']' | b'{' | b'}');
+    let mut i = 0;
+    while i < bytes.len() {
+        let in_flow = !flow.is_empty();
+        let end = match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                let end = scan_while(line, i, |b| b.is_ascii_whitespace());
+                p.plain(&line[i..end]);
+                end
+            }
+            b'#' if i == 0 || bytes[i - 1] == b' ' => {
+                let end = line.trim_end_matches(['\r', '\n']).len();
+                p.paint(COMMENT, &line[i..end]);
+                end
+            }
+            b'-' if !in_flow && bytes.get(i + 1).is_none_or(u8::is_ascii_whitespace) => {
+                p.plain("-");
+                i + 1
+            }
+            b'!' => {
+                let end = scan_while(line, i, |b| {
+                    !b.is_ascii_whitespace() && !is_flow_indicator(b)
+                });
+                p.paint(TYPE, &line[i..end]);
+                end
+            }
+            b @ (b'{' | b'[') => {
+                flow.push(b);
+                p.plain(&line[i..=i]);
+                i + 1
+            }
+            b'}' | b']' => {
+                flow.pop();
+                p.plain(&line[i..=i]);
+                i + 1
+            }
+            b',' | b':' => {
+                p.plain(&line[i..=i]);
+                i + 1
+            }
+            b'\'' | b'"' => {
+                let end = scan_quoted(line, i);
+                let token = &line[i..end];
+                if is_yaml_key(line, end, in_flow) {
+                    p.paint(key_style(token), token);
+                } else {
+                    p.paint(STRING, token);
+                }
+                end
+            }
+            _ => {
+                // Plain scalar.
+                let mut end = i;
+                while end < bytes.len() {
+                    let b = bytes[end];
+                    if matches!(b, b'\r' | b'\n')
+                        || (b == b':' && is_yaml_value_indicator(bytes.get(end + 1), in_flow))
+                        || (b == b'#' && bytes[end - 1] == b' ')
+                        || (in_flow && is_flow_indicator(b))
+                    {
+                        break;
+                    }
+                    end += 1;
+                }
+                let token = line[i..end].trim_end();
+                let end = i + token.len();
+                if is_yaml_key(line, end, in_flow) {
+                    p.paint(key_style(token), token);
+                } else {
+                    p.paint(yaml_plain_style(token), token);
+                }
+                end
+            }
+        };
+        i = end;
+    }
+}
+
+fn is_yaml_value_indicator(next: Option<&u8>, in_flow: bool) -> bool {
+    match next {
+        None | Some(b' ' | b'\t' | b'\r' | b'\n') => true,
+        Some(b',' | b']' | b'}') => in_flow,
+        _ => false,
+    }
+}
+
+fn is_yaml_key(line: &str, end: usize, in_flow: bool) -> bool {
+    let next = skip_spaces(line, end);
+    line.as_bytes().get(next) == Some(&b':')
+        && is_yaml_value_indicator(line.as_bytes().get(next + 1), in_flow)
+}
+
+fn yaml_plain_style(token: &str) -> Style {
+    match token {
+        "~" | "null" | "Null" | "NULL" | "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => {
+            LITERAL
+        }
+        ".inf" | "-.inf" | "+.inf" | ".nan" | ".NaN" => NUMBER,
+        _ if token.parse::<f64>().is_ok() => NUMBER,
+        _ => STRING,
+    }
+}
+
+#[cfg(feature = "kdl")]
+pub fn kdl(text: &str) -> String {
+    let mut p = Painter::default();
+    let bytes = text.as_bytes();
+    let is_ident = |b: u8| {
+        !b.is_ascii_whitespace()
+            && !matches!(
+                b,
+                b'(' | b')' | b'{' | b'}' | b'[' | b']' | b'/' | b'\\' | b'"' | b'#' | b';' | b'='
+            )
+    };
+    // True when the next identifier or string is the name of a node.
+    let mut node_start = true;
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &text[i..];
+        let (end, style) = if rest.starts_with("//") {
+            (scan_while(text, i, |b| b != b'\n'), Some(COMMENT))
+        } else if rest.starts_with("/*") {
+            (scan_until(text, i + 2, "*/"), Some(COMMENT))
+        } else if rest.starts_with("/-") {
+            (i + 2, Some(COMMENT))
+        } else {
+            match bytes[i] {
+                b'\n' | b';' | b'{' => {
+                    node_start = true;
+                    (i + 1, None)
+                }
+                b'}' => {
+                    node_start = false;
+                    (i + 1, None)
+                }
+                b'(' => (scan_until(text, i, ")"), Some(TYPE)),
+                b'"' if rest.starts_with("\"\"\"") => {
+                    let end = scan_until(text, i + 3, "\"\"\"");
+                    (end, Some(kdl_string_style(text, i..end, &mut node_start)))
+                }
+                b'"' => {
+                    let end = scan_quoted(text, i);
+                    (end, Some(kdl_string_style(text, i..end, &mut node_start)))
+                }
+                b'#' if matches!(bytes.get(i + 1), Some(b'"' | b'#')) => {
+                    // Raw string.
+                    let hashes = scan_while(text, i, |b| b == b'#') - i;
+                    let mut terminator = String::from("\"");
+                    terminator.extend(std::iter::repeat_n('#', hashes));
+                    let end = scan_until(text, i + hashes + 1, &terminator);
+                    (end, Some(kdl_string_style(text, i..end, &mut node_start)))
+                }
+                b'#' => {
+                    // Keyword, like `#true` or `#null`.
+                    node_start = false;
+                    (scan_while(text, i + 1, is_ident), Some(LITERAL))
+                }
+                b if b.is_ascii_digit()
+                    || (matches!(b, b'-' | b'+')
+                        && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) =>
+                {
+                    node_start = false;
+                    (scan_while(text, i, is_ident), Some(NUMBER))
+                }
+                b if is_ident(b) => {
+                    let end = scan_while(text, i, is_ident);
+                    let ident = &text[i..end];
+                    let style = if ident == "-" && node_start {
+                        None
+                    } else {
+                        Some(kdl_string_style(text, i..end, &mut node_start))
+                    };
+                    node_start = false;
+                    (end, style)
+                }
+                _ => {
+                    let len = rest.chars().next().map_or(1, char::len_utf8);
+                    (i + len, None)
+                }
+            }
+        };
+        match style {
+            Some(style) => p.paint(style, &text[i..end]),
+            None => p.plain(&text[i..end]),
+        }
+        i = end;
+    }
+    p.out
+}
+
+/// Style of the string or identifier at `range`, which may be a node name, a
+/// property key, or a value.
+fn kdl_string_style(text: &str, range: core::ops::Range<usize>, node_start: &mut bool) -> Style {
+    let style = if text.as_bytes().get(range.end) == Some(&b'=') || core::mem::take(node_start) {
+        key_style(&text[range])
+    } else {
+        STRING
+    };
+    *node_start = false;
+    style
+}
+
+#[cfg(feature = "xml")]
+pub fn xml(text: &str) -> String {
+    let mut p = Painter::default();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &text[i..];
+        if rest.starts_with("<!--") {
+            let end = scan_until(text, i, "-->");
+            p.paint(COMMENT, &text[i..end]);
+            i = end;
+        } else if rest.starts_with("<?") {
+            let end = scan_until(text, i, "?>");
+            p.paint(COMMENT, &text[i..end]);
+            i = end;
+        } else if rest.starts_with("<![CDATA[") {
+            let end = scan_until(text, i, "]]>");
+            p.paint(STRING, &text[i..end]);
+            i = end;
+        } else if bytes[i] == b'<' {
+            i = xml_tag(&mut p, text, i);
+        } else {
+            let end = scan_while(text, i, |b| b != b'<');
+            p.plain(&text[i..end]);
+            i = end;
+        }
+    }
+    p.out
+}
+
+/// Highlight the tag starting at `start`, returning the end of the tag.
+fn xml_tag(p: &mut Painter, text: &str, start: usize) -> usize {
+    let bytes = text.as_bytes();
+    let is_name = |b: u8| !b.is_ascii_whitespace() && !matches!(b, b'/' | b'>' | b'=');
+
+    let mut i = start + 1;
+    if bytes.get(i) == Some(&b'/') {
+        i += 1;
+    }
+    p.plain(&text[start..i]);
+    let end = scan_while(text, i, is_name);
+    p.paint(TYPE, &text[i..end]);
+    i = end;
+
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'>' => {
+                p.plain(">");
+                return i + 1;
+            }
+            b'"' | b'\'' => {
+                let end = scan_quoted(text, i);
+                p.paint(STRING, &text[i..end]);
+                end
+            }
+            b if is_name(b) => {
+                let end = scan_while(text, i, is_name);
+                let name = &text[i..end];
+                p.paint(if name == "name" { SPECIAL } else { KEY }, name);
+                end
+            }
+            _ => {
+                p.plain(&text[i..=i]);
+                i + 1
+            }
+        };
+        i = end;
+    }
+    i
+}
+
+/// Highlight the output of `zdoc diff`, where each line starts with `+`, `-`,
+/// or `~`.
+pub fn diff(text: &str) -> String {
+    let mut p = Painter::default();
+    for line in text.split_inclusive('\n') {
+        let (line, newline) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+        match line.as_bytes().first() {
+            Some(b'+') => p.paint(INSERTED, line),
+            Some(b'-') => p.paint(REMOVED, line),
+            Some(b'~') => p.paint(MODIFIED, line),
+            _ => p.plain(line),
+        }
+        p.plain(newline);
+    }
+    p.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn painted(style: Style, s: &str) -> String {
+        let mut p = Painter::default();
+        p.paint(style, s);
+        p.out
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_tokens() {
+        let out = json(r#"{"$type":"Root","a":[1.5,-2,true,null,"x"]}"#);
+        assert!(out.contains(&painted(SPECIAL, r#""$type""#)));
+        assert!(out.contains(&painted(STRING, r#""Root""#)));
+        assert!(out.contains(&painted(KEY, r#""a""#)));
+        assert!(out.contains(&painted(NUMBER, "1.5")));
+        assert!(out.contains(&painted(NUMBER, "-2")));
+        assert!(out.contains(&painted(LITERAL, "true")));
+        assert!(out.contains(&painted(LITERAL, "null")));
+        assert!(out.contains(&painted(STRING, r#""x""#)));
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn yaml_tokens() {
+        let out = yaml("a: !Foo\n- 1\n- 'it''s: x'\nb:\n  $value: s\n  c: {d: null, e: [x, 2]}\n");
+        assert!(out.contains(&painted(KEY, "a")));
+        assert!(out.contains(&painted(TYPE, "!Foo")));
+        assert!(out.contains(&painted(NUMBER, "1")));
+        assert!(out.contains(&painted(STRING, "'it''s: x'")));
+        assert!(out.contains(&painted(SPECIAL, "$value")));
+        assert!(out.contains(&painted(STRING, "s")));
+        assert!(out.contains(&painted(KEY, "d")));
+        assert!(out.contains(&painted(LITERAL, "null")));
+        assert!(out.contains(&painted(STRING, "x")));
+        assert!(out.contains(&painted(NUMBER, "2")));
+    }
+
+    #[test]
+    #[cfg(feature = "kdl")]
+    fn kdl_tokens() {
+        let out = kdl("(Root)node 1 \"two\" key=#true {\n    - 3\n    $value s\n}\n");
+        assert!(out.contains(&painted(TYPE, "(Root)")));
+        assert!(out.contains(&painted(KEY, "node")));
+        assert!(out.contains(&painted(NUMBER, "1")));
+        assert!(out.contains(&painted(STRING, "\"two\"")));
+        assert!(out.contains(&painted(KEY, "key")));
+        assert!(out.contains(&painted(LITERAL, "#true")));
+        assert!(out.contains("    - "));
+        assert!(out.contains(&painted(SPECIAL, "$value")));
+        assert!(out.contains(&painted(STRING, "s")));
+    }
+
+    #[test]
+    #[cfg(feature = "xml")]
+    fn xml_tokens() {
+        let out = xml(r#"<?xml version="1.0"?><Root><item name="a" x="2"/></Root>"#);
+        assert!(out.contains(&painted(COMMENT, r#"<?xml version="1.0"?>"#)));
+        assert!(out.contains(&painted(TYPE, "Root")));
+        assert!(out.contains(&painted(TYPE, "item")));
+        assert!(out.contains(&painted(SPECIAL, "name")));
+        assert!(out.contains(&painted(KEY, "x")));
+        assert!(out.contains(&painted(STRING, r#""2""#)));
+    }
+
+    #[test]
+    fn diff_lines() {
+        let out = diff("+ a: 1\n- b: 2\n~ c: 3 -> 4\n");
+        assert_eq!(
+            out,
+            [
+                painted(INSERTED, "+ a: 1"),
+                painted(REMOVED, "- b: 2"),
+                painted(MODIFIED, "~ c: 3 -> 4"),
+                String::new(),
+            ]
+            .join("\n")
+        );
+    }
+}
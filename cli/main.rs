@@ -1,12 +1,21 @@
-use std::io::Read as _;
+use std::io::{IsTerminal as _, Read as _, Write as _};
 use std::{error::Error, path::Path};
 
 use clap::ColorChoice;
 use clap::Parser;
 
+mod highlight;
+
 #[derive(clap::Parser)]
-#[clap(name = "zdoc", styles = clap_cargo::style::CLAP_STYLING)]
+#[clap(
+    name = "zdoc",
+    styles = clap_cargo::style::CLAP_STYLING,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input file, use '-' for stdin
     #[clap(value_parser, default_value = "-")]
     input: clio::Input,
@@ -23,14 +32,15 @@ struct Args {
     format: Option<Format>,
     /// Attempt to pretty-print the output. This is the default when printing to
     /// stdout, and stdout is a terminal.
-    #[clap(long)]
+    #[clap(long, conflicts_with = "compact")]
     pretty: bool,
     /// Opposite of `pretty`, overriding the default when printing to a
     /// terminal.
     #[clap(long)]
     compact: bool,
-    /// When using `--pretty`, whether or not to output colorized
-    /// (syntax-highlighted) output (when available for that format).
+    /// Whether or not to output colorized (syntax-highlighted) output, when
+    /// available for that format. `auto` colorizes terminal output, unless
+    /// the `NO_COLOR` environment variable is set.
     #[arg(long, default_value_t = ColorChoice::Auto, value_name = "WHEN", value_enum, global = true)]
     color: ColorChoice,
 
@@ -43,6 +53,126 @@ struct Args {
     json: JsonArgs,
 }
 
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Set the entry at a path in a document, rewriting the file in place.
+    Set {
+        /// Document file to edit.
+        file: std::path::PathBuf,
+        /// Path of the entry, like `servers[0].port`, `servers[0].@host`, or
+        /// `#type`. Intermediate nodes are created as needed.
+        path: String,
+        /// New value in zdoc text syntax, like `8080`, `"text"`, or
+        /// `(Type) { a = 1 }`. Values that are not valid zdoc text are set as
+        /// strings.
+        value: String,
+        /// Always set the value as a string.
+        #[clap(long, short)]
+        string: bool,
+        /// Format of the file. When absent, guess the format from the file
+        /// extension.
+        #[clap(long, short)]
+        format: Option<Format>,
+        #[command(flatten)]
+        style: StyleArgs,
+    },
+    /// Remove the entry at a path in a document, rewriting the file in place.
+    Rm {
+        /// Document file to edit.
+        file: std::path::PathBuf,
+        /// Path of the entry, like `servers[0].port`.
+        path: String,
+        /// Format of the file. When absent, guess the format from the file
+        /// extension.
+        #[clap(long, short)]
+        format: Option<Format>,
+        #[command(flatten)]
+        style: StyleArgs,
+    },
+    /// Print the structural differences between two documents.
+    ///
+    /// Each line shows an inserted (`+`), removed (`-`), or modified (`~`)
+    /// entry.
+    Diff {
+        /// Old document.
+        old: std::path::PathBuf,
+        /// New document.
+        new: std::path::PathBuf,
+        /// Format of the old document. When absent, guess the format from the
+        /// file extension.
+        #[clap(long)]
+        old_format: Option<Format>,
+        /// Format of the new document. When absent, guess the format from the
+        /// file extension.
+        #[clap(long)]
+        new_format: Option<Format>,
+        /// Print the patch as a document in the zdoc text format, which can be
+        /// applied with `zdoc::diff::Patch`.
+        #[clap(long)]
+        patch: bool,
+    },
+    /// Print the entries of a document that match a KDL query, like
+    /// `top() > server[port > 8000]`.
+    ///
+    /// Matches are printed as the entries of an unnamed root node.
+    Query {
+        /// Document file to query.
+        file: std::path::PathBuf,
+        /// KDL Query Language (KQL) query.
+        query: String,
+        /// Input format. When absent, guess the format from the file
+        /// extension.
+        #[clap(long, short)]
+        input_format: Option<Format>,
+        /// Output format.
+        #[clap(long, short, default_value = "text")]
+        format: Format,
+    },
+    /// Validate a document against a schema in the KDL Schema Language,
+    /// printing all errors.
+    Check {
+        /// Document file to validate.
+        file: std::path::PathBuf,
+        /// Schema file.
+        #[clap(long, short)]
+        schema: std::path::PathBuf,
+        /// Format of the document. When absent, guess the format from the file
+        /// extension.
+        #[clap(long, short)]
+        format: Option<Format>,
+        /// Format of the schema. When absent, guess the format from the file
+        /// extension.
+        #[clap(long)]
+        schema_format: Option<Format>,
+    },
+    /// Work with schemas in the KDL Schema Language.
+    #[command(subcommand)]
+    Schema(SchemaCommand),
+}
+
+#[derive(clap::Subcommand)]
+enum SchemaCommand {
+    /// Infer a schema from example documents, and print it.
+    Infer {
+        /// Example documents.
+        #[clap(required = true)]
+        files: Vec<std::path::PathBuf>,
+        /// Format of the documents. When absent, guess the format from the
+        /// file extension of each document.
+        #[clap(long, short)]
+        input_format: Option<Format>,
+        /// Output format of the schema document.
+        #[clap(long, short, default_value = "kdl")]
+        format: Format,
+        /// Print a JSON Schema for the JSON representation of the documents
+        /// instead of a schema document.
+        #[clap(long, conflicts_with = "format")]
+        json_schema: bool,
+        #[command(flatten)]
+        json: JsonArgs,
+    },
+}
+
 #[derive(clap::Args)]
 #[command(next_help_heading = "JSON options")]
 struct JsonArgs {
@@ -55,6 +185,10 @@ struct JsonArgs {
 }
 
 fn try_main(args: Args) -> Result<(), Box<dyn Error>> {
+    if let Some(command) = args.command {
+        return run_command(command, args.color);
+    }
+
     if args.input.is_std() && args.input_format.is_none() {
         return Err("--format is required when input is terminal/stdout".into());
     }
@@ -79,11 +213,7 @@ fn try_main(args: Args) -> Result<(), Box<dyn Error>> {
             }
         }
     };
-    let color = match args.color {
-        ColorChoice::Auto => args.output.is_tty(),
-        ColorChoice::Always => true,
-        ColorChoice::Never => false,
-    };
+    let color = use_color(args.color, args.output.is_tty());
 
     let mut input = args.input;
     let mut output = args.output;
@@ -124,10 +254,270 @@ fn try_main(args: Args) -> Result<(), Box<dyn Error>> {
     if output.is_tty() && output_format.is_binary() {
         eprintln!("Warning: Writing binary data to a terminal.");
     }
-    output_format.emit(&mut output, doc)?;
+    let style = if args.pretty {
+        Style::Pretty
+    } else if args.compact {
+        Style::Compact
+    } else if output.is_tty() {
+        Style::Pretty
+    } else {
+        Style::Default
+    };
+    output_format.emit(&mut output, doc, style, color)?;
     Ok(())
 }
 
+fn use_color(choice: ColorChoice, is_tty: bool) -> bool {
+    match choice {
+        ColorChoice::Auto => is_tty && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    }
+}
+
+fn run_command(command: Command, color: ColorChoice) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Set {
+            file,
+            path,
+            value,
+            string,
+            format,
+            style,
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => guess_format(&file)?,
+            };
+            let input = std::fs::read(&file)?;
+            let style = style.in_place(format, &input);
+            let mut builder = format.parse_with_style(&input, style)?;
+            let entry = match zdoc::text::entry_from_text(&value) {
+                Ok(entry) if !string => entry,
+                _ => zdoc::builder::Value::String(value.as_str().into()).into(),
+            };
+            builder.set_path(&path, entry)?;
+            write_in_place(&file, format, &builder, style)
+        }
+        Command::Rm {
+            file,
+            path,
+            format,
+            style,
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => guess_format(&file)?,
+            };
+            let input = std::fs::read(&file)?;
+            let style = style.in_place(format, &input);
+            let mut builder = format.parse_with_style(&input, style)?;
+            if builder.remove_path(&path)?.is_none() {
+                return Err(format!("Path does not exist: {path}").into());
+            }
+            write_in_place(&file, format, &builder, style)
+        }
+        Command::Diff {
+            old,
+            new,
+            old_format,
+            new_format,
+            patch,
+        } => {
+            let old_format = match old_format {
+                Some(format) => format,
+                None => guess_format(&old)?,
+            };
+            let new_format = match new_format {
+                Some(format) => format,
+                None => guess_format(&new)?,
+            };
+            let old_input = std::fs::read(&old)?;
+            let new_input = std::fs::read(&new)?;
+            let old_builder = old_format.parse(&old_input)?;
+            let new_builder = new_format.parse(&new_input)?;
+            let diff = zdoc::diff::diff_builder(old_builder.root(), new_builder.root());
+
+            let mut stdout = std::io::stdout().lock();
+            if patch {
+                let text = zdoc::text::document_to_text(&diff.to_document());
+                stdout.write_all(text.as_bytes())?;
+            } else if use_color(color, stdout.is_terminal()) {
+                stdout.write_all(highlight::diff(&diff.to_string()).as_bytes())?;
+            } else {
+                write!(stdout, "{diff}")?;
+            }
+            Ok(())
+        }
+        Command::Query {
+            file,
+            query,
+            input_format,
+            format,
+        } => {
+            let input_format = match input_format {
+                Some(format) => format,
+                None => guess_format(&file)?,
+            };
+            let query = zdoc::query::Query::parse(&query)?;
+            let input = std::fs::read(&file)?;
+            let builder = input_format.parse(&input)?;
+
+            let mut matches = zdoc::Builder::new();
+            let root = matches.root_mut();
+            for entry in query.select_builder(builder.root()) {
+                match entry {
+                    zdoc::builder::PathRef::Node(node) => {
+                        root.push(node.clone());
+                    }
+                    zdoc::builder::PathRef::Arg(arg) => {
+                        root.push(arg.clone());
+                    }
+                    zdoc::builder::PathRef::Type(_) => unreachable!(),
+                }
+            }
+
+            let mut stdout = std::io::stdout().lock();
+            let is_tty = stdout.is_terminal();
+            if is_tty && format.is_binary() {
+                eprintln!("Warning: Writing binary data to a terminal.");
+            }
+            let style = if is_tty {
+                Style::Pretty
+            } else {
+                Style::Default
+            };
+            let color = use_color(color, is_tty);
+            format.emit(&mut stdout, &matches.build(), style, color)?;
+            Ok(())
+        }
+        Command::Check {
+            file,
+            schema,
+            format,
+            schema_format,
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => guess_format(&file)?,
+            };
+            let schema_format = match schema_format {
+                Some(format) => format,
+                None => guess_format(&schema)?,
+            };
+            let schema_input = std::fs::read(&schema)?;
+            let schema =
+                zdoc::schema::Schema::from_node(schema_format.parse(&schema_input)?.root())
+                    .map_err(|e| format!("Invalid schema: {e}"))?;
+            let input = std::fs::read(&file)?;
+            let builder = format.parse(&input)?;
+
+            let Err(errors) = schema.validate_builder(builder.root()) else {
+                return Ok(());
+            };
+            for error in &errors {
+                eprintln!("{}: {}", error.path, error.error);
+            }
+            Err(format!(
+                "{} does not match the schema ({} errors)",
+                file.display(),
+                errors.len()
+            )
+            .into())
+        }
+        Command::Schema(SchemaCommand::Infer {
+            files,
+            input_format,
+            format,
+            json_schema,
+            json,
+        }) => {
+            let mut inferred = zdoc::schema::InferredSchema::new();
+            for file in &files {
+                let input_format = match input_format {
+                    Some(format) => format,
+                    None => guess_format(file)?,
+                };
+                let input = std::fs::read(file)?;
+                let doc = input_format.parse(&input)?.build();
+                inferred.add(doc.root());
+            }
+
+            let mut stdout = std::io::stdout().lock();
+            let is_tty = stdout.is_terminal();
+            let style = if is_tty {
+                Style::Pretty
+            } else {
+                Style::Default
+            };
+            let color = use_color(color, is_tty);
+            if json_schema {
+                return emit_json_schema(&mut stdout, &inferred, &json, style, color);
+            }
+            format.emit(&mut stdout, &inferred.to_builder().build(), style, color)
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn emit_json_schema(
+    output: &mut impl std::io::Write,
+    inferred: &zdoc::schema::InferredSchema,
+    json: &JsonArgs,
+    style: Style,
+    color: bool,
+) -> Result<(), Box<dyn Error>> {
+    let settings = zdoc::json::JsonSettings {
+        type_tag: &json.json_type_tag,
+        items_tag: &json.json_items_tag,
+        value_tag: &json.json_value_tag,
+        ..Default::default()
+    };
+    let schema = inferred.to_json_schema(&settings);
+    let json = if style == Style::Pretty {
+        format!("{schema:#}")
+    } else {
+        schema.to_string()
+    };
+    write_text(output, &json, color.then_some(highlight::json))?;
+    writeln!(output)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "json"))]
+fn emit_json_schema(
+    _output: &mut impl std::io::Write,
+    _inferred: &zdoc::schema::InferredSchema,
+    _json: &JsonArgs,
+    _style: Style,
+    _color: bool,
+) -> Result<(), Box<dyn Error>> {
+    Err(UnsupportedFormatError("json".to_string()).into())
+}
+
+/// Replace the contents of `file` with the document, writing to a temporary
+/// file first so that `file` is left intact if anything fails.
+fn write_in_place(
+    file: &Path,
+    format: Format,
+    builder: &zdoc::Builder,
+    style: Style,
+) -> Result<(), Box<dyn Error>> {
+    let doc = builder.build();
+    let mut output = Vec::new();
+    format.emit(&mut output, &doc, style, false)?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file.file_name().ok_or("Not a file")?);
+    temp_name.push(".tmp");
+    let temp = file.with_file_name(temp_name);
+    let result = std::fs::write(&temp, output).and_then(|()| std::fs::rename(&temp, file));
+    if result.is_err() {
+        _ = std::fs::remove_file(&temp);
+    }
+    result.map_err(Into::into)
+}
+
 fn analyze(doc: &zdoc::Document) {
     let zdoc::codec::Header {
         magic: _,
@@ -218,6 +608,7 @@ fn guess_format(path: &Path) -> Result<Format, Box<dyn Error>> {
         "bincode" => Format::Bincode,
         #[cfg(feature = "toml")]
         "toml" => Format::Toml,
+        "zdt" => Format::Text,
         "zdoc" => Format::Zdoc,
         _ => {
             return Err(UnsupportedFormatError(ext.to_string()).into());
@@ -225,6 +616,55 @@ fn guess_format(path: &Path) -> Result<Format, Box<dyn Error>> {
     })
 }
 
+/// Output style of text formats.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// The default settings of the format.
+    Default,
+    Pretty,
+    Compact,
+}
+
+/// Output style of documents that are rewritten in place.
+#[derive(clap::Args)]
+struct StyleArgs {
+    /// Pretty-print the rewritten document. By default, JSON and XML documents
+    /// that span multiple lines are pretty-printed.
+    #[clap(long, conflicts_with = "compact")]
+    pretty: bool,
+    /// Write the rewritten document in the compact style.
+    #[clap(long)]
+    compact: bool,
+}
+
+impl StyleArgs {
+    /// Style to rewrite `input` with, preserving its layout unless overridden.
+    fn in_place(&self, format: Format, input: &[u8]) -> Style {
+        if self.pretty {
+            Style::Pretty
+        } else if self.compact {
+            Style::Compact
+        } else if matches!(format, Format::Json | Format::Xml)
+            && input.trim_ascii().contains(&b'\n')
+        {
+            Style::Pretty
+        } else {
+            Style::Default
+        }
+    }
+}
+
+impl Style {
+    /// Indentation to use, or `None` for the format's default.
+    fn indent(self, pretty: usize) -> Option<usize> {
+        match self {
+            Style::Default => None,
+            Style::Pretty => Some(pretty),
+            Style::Compact => Some(0),
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
 enum Format {
     Json,
@@ -233,6 +673,8 @@ enum Format {
     Xml,
     Bincode,
     Toml,
+    /// Lossless zdoc text format.
+    Text,
     Zdoc,
 }
 
@@ -242,6 +684,17 @@ impl Format {
     }
 
     pub fn parse<'a>(self, input: &'a [u8]) -> Result<zdoc::Builder<'a>, Box<dyn Error + 'static>> {
+        self.parse_with_style(input, Style::Default)
+    }
+
+    /// Parse a document that was written in `style`. Whitespace between the
+    /// elements of pretty-printed XML is indentation, not text.
+    #[cfg_attr(not(feature = "xml"), allow(unused_variables))]
+    pub fn parse_with_style<'a>(
+        self,
+        input: &'a [u8],
+        style: Style,
+    ) -> Result<zdoc::Builder<'a>, Box<dyn Error + 'static>> {
         match self {
             #[cfg(feature = "json")]
             Format::Json => zdoc::json::builder_from_json(std::str::from_utf8(input)?)
@@ -259,18 +712,31 @@ impl Format {
             #[cfg(not(feature = "yaml"))]
             Format::Yaml => Err(UnsupportedFormatError("yaml".to_string()).into()),
             #[cfg(feature = "xml")]
-            Format::Xml => zdoc::xml::builder_from_xml(std::str::from_utf8(input)?)
-                .map_err(|e| format!("Failed to parse XML: {e}").into()),
+            Format::Xml => {
+                let settings = zdoc::xml::XmlSettings {
+                    ignore_whitespace: style == Style::Pretty,
+                    ..Default::default()
+                };
+                zdoc::xml::builder_from_xml_with_settings(std::str::from_utf8(input)?, &settings)
+                    .map_err(|e| format!("Failed to parse XML: {e}").into())
+            }
             #[cfg(not(feature = "xml"))]
             Format::Xml => Err(UnsupportedFormatError("xml".to_string()).into()),
-            // #[cfg(feature = "bincode")]
-            // Format::Bincode => todo!(),
-            // #[cfg(not(feature = "bincode"))]
+            #[cfg(feature = "bincode")]
+            Format::Bincode => {
+                let doc = zdoc::bincode::document_from_bincode(input)
+                    .map_err(|e| format!("Failed to parse bincode: {e}"))?;
+                Ok(zdoc::Builder::from_document(&doc).into_static())
+            }
+            #[cfg(not(feature = "bincode"))]
             Format::Bincode => Err(UnsupportedFormatError("bincode".to_string()).into()),
-            // #[cfg(feature = "toml")]
-            // Format::Toml => todo!(),
-            // #[cfg(not(feature = "toml"))]
+            #[cfg(feature = "toml")]
+            Format::Toml => zdoc::toml::builder_from_toml(std::str::from_utf8(input)?)
+                .map_err(|e| format!("Failed to parse TOML: {e}").into()),
+            #[cfg(not(feature = "toml"))]
             Format::Toml => Err(UnsupportedFormatError("toml".to_string()).into()),
+            Format::Text => zdoc::text::builder_from_text(std::str::from_utf8(input)?)
+                .map_err(|e| format!("Failed to parse zdoc text: {e}").into()),
             Format::Zdoc => {
                 let doc = zdoc::Document::from_slice(input)
                     .map_err(|e| format!("Failed to parse zdoc: {e}"))?;
@@ -280,61 +746,117 @@ impl Format {
         }
     }
 
+    #[cfg_attr(
+        not(any(feature = "json", feature = "kdl", feature = "yaml", feature = "xml")),
+        allow(unused_variables)
+    )]
     pub fn emit<W: std::io::Write>(
         self,
         output: &mut W,
         doc: &zdoc::Document,
+        style: Style,
+        color: bool,
     ) -> Result<(), Box<dyn Error>> {
         match self {
             #[cfg(feature = "json")]
             Format::Json => {
-                let json = zdoc::json::document_to_json(doc)
+                let mut settings = zdoc::json::JsonSettings::default();
+                if let Some(indent) = style.indent(2) {
+                    settings.indent = indent;
+                }
+                let json = zdoc::json::document_to_json_with_settings(doc, &settings)
                     .map_err(|e| format!("Failed to emit JSON: {e}"))?;
-                output.write_all(json.as_bytes())?;
+                write_text(output, &json, color.then_some(highlight::json))?;
                 Ok(())
             }
             #[cfg(not(feature = "json"))]
             Format::Json => Err(UnsupportedFormatError("json".to_string()).into()),
             #[cfg(feature = "kdl")]
             Format::Kdl => {
-                let kdl = zdoc::kdl::document_to_kdl(doc)
+                let mut settings = zdoc::kdl::KdlSettings::default();
+                if let Some(indent) = style.indent(4) {
+                    settings.indent = indent;
+                }
+                let kdl = zdoc::kdl::document_to_kdl_with_settings(doc, &settings)
                     .map_err(|e| format!("Failed to emit KDL: {e}"))?;
-                output.write_all(kdl.as_bytes())?;
+                write_text(output, &kdl, color.then_some(highlight::kdl))?;
                 Ok(())
             }
             #[cfg(not(feature = "kdl"))]
             Format::Kdl => Err(UnsupportedFormatError("kdl".to_string()).into()),
             #[cfg(feature = "yaml")]
             Format::Yaml => {
-                let yaml = zdoc::yaml::document_to_yaml(doc)
+                let mut settings = zdoc::yaml::YamlSettings::default();
+                if let Some(indent) = style.indent(2) {
+                    settings.indent = indent;
+                }
+                if style == Style::Pretty {
+                    settings.line_width = Some(80);
+                }
+                let yaml = zdoc::yaml::document_to_yaml_with_settings(doc, &settings)
                     .map_err(|e| format!("Failed to emit YAML: {e}"))?;
-                output.write_all(yaml.as_bytes())?;
+                write_text(output, &yaml, color.then_some(highlight::yaml))?;
                 Ok(())
             }
             #[cfg(not(feature = "yaml"))]
             Format::Yaml => Err(UnsupportedFormatError("yaml".to_string()).into()),
             #[cfg(feature = "xml")]
             Format::Xml => {
-                let xml = zdoc::xml::document_to_xml(doc)
+                let mut settings = zdoc::xml::XmlSettings::default();
+                if let Some(indent) = style.indent(2) {
+                    settings.indent = indent;
+                }
+                let xml = zdoc::xml::document_to_xml_with_settings(doc, &settings)
                     .map_err(|e| format!("Failed to emit XML: {e}"))?;
-                output.write_all(xml.as_bytes())?;
+                write_text(output, &xml, color.then_some(highlight::xml))?;
                 Ok(())
             }
             #[cfg(not(feature = "xml"))]
             Format::Xml => Err(UnsupportedFormatError("xml".to_string()).into()),
-            // #[cfg(feature = "bincode")]
-            // Format::Bincode => todo!(),
-            // #[cfg(not(feature = "bincode"))]
+            #[cfg(feature = "bincode")]
+            Format::Bincode => {
+                let bincode = zdoc::bincode::document_to_bincode(doc)
+                    .map_err(|e| format!("Failed to emit bincode: {e}"))?;
+                output.write_all(&bincode)?;
+                Ok(())
+            }
+            #[cfg(not(feature = "bincode"))]
             Format::Bincode => Err(UnsupportedFormatError("bincode".to_string()).into()),
-            // #[cfg(feature = "toml")]
-            // Format::Toml => todo!(),
-            // #[cfg(not(feature = "toml"))]
+            #[cfg(feature = "toml")]
+            Format::Toml => {
+                let toml = zdoc::toml::document_to_toml(doc)
+                    .map_err(|e| format!("Failed to emit TOML: {e}"))?;
+                output.write_all(toml.as_bytes())?;
+                Ok(())
+            }
+            #[cfg(not(feature = "toml"))]
             Format::Toml => Err(UnsupportedFormatError("toml".to_string()).into()),
+            Format::Text => {
+                let mut settings = zdoc::text::TextSettings::default();
+                if let Some(indent) = style.indent(4) {
+                    settings.indent = indent;
+                }
+                let text = zdoc::text::document_to_text_with_settings(doc, &settings);
+                output.write_all(text.as_bytes())?;
+                Ok(())
+            }
             Format::Zdoc => output.write_all(doc.as_bytes()).map_err(Into::into),
         }
     }
 }
 
+#[cfg(any(feature = "json", feature = "kdl", feature = "yaml", feature = "xml"))]
+fn write_text<W: std::io::Write>(
+    output: &mut W,
+    text: &str,
+    highlight: Option<fn(&str) -> String>,
+) -> std::io::Result<()> {
+    match highlight {
+        Some(highlight) => output.write_all(highlight(text).as_bytes()),
+        None => output.write_all(text.as_bytes()),
+    }
+}
+
 #[derive(Debug)]
 struct UnsupportedFormatError(String);
 impl std::fmt::Display for UnsupportedFormatError {
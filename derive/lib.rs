@@ -0,0 +1,179 @@
+//! Derive macros for [zdoc](https://docs.rs/zdoc).
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Field, Fields, Ident, ext::IdentExt as _, parse_macro_input,
+    punctuated::Punctuated, token::Comma,
+};
+
+/// Generate a typed zero-copy view over a `zdoc::Node` for a struct with named
+/// fields.
+///
+/// For a struct `Foo`, this generates `FooView<'a>`, which has a getter for
+/// each field of `Foo` and a `validate()` method, and implements
+/// `zdoc::view::Viewable` for `Foo`, so it can be used as a field of other
+/// views. See the `zdoc::view` module for details.
+#[proc_macro_derive(ZdocView)]
+pub fn derive_view(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    view(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn view(input: &DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "ZdocView does not support generic structs",
+        ));
+    }
+    let fields = named_fields(input)?;
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let view = format_ident!("{ident}View");
+    let doc = format!("Typed view over a node representing a [`{ident}`].");
+
+    let mut getters = Vec::new();
+    let mut validations = Vec::new();
+    for field in fields {
+        let getter = field.ident.as_ref().expect("named field");
+        let name = getter.unraw().to_string();
+        let ty = &field.ty;
+        let docs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"));
+        getters.push(quote! {
+            #(#docs)*
+            #[inline]
+            #vis fn #getter(&self) -> <#ty as ::zdoc::view::Viewable>::Field<'a> {
+                <#ty as ::zdoc::view::Viewable>::field(
+                    self.node,
+                    ::zdoc::view::ViewEntry::Field(#name),
+                    self.node.get(#name),
+                )
+            }
+        });
+        validations.push(quote! {
+            <#ty as ::zdoc::view::Viewable>::validate(
+                self.node,
+                ::zdoc::view::ViewEntry::Field(#name),
+                self.node.get(#name),
+            )?;
+        });
+    }
+
+    let viewable = viewable(ident, &view);
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Clone, Copy, Debug)]
+        #vis struct #view<'a> {
+            node: ::zdoc::Node<'a>,
+        }
+
+        impl<'a> #view<'a> {
+            /// Wrap `node` without checking its contents.
+            #[inline]
+            #[must_use]
+            #vis fn new(node: ::zdoc::Node<'a>) -> Self {
+                Self { node }
+            }
+
+            /// The viewed node.
+            #[inline]
+            #[must_use]
+            #vis fn node(&self) -> ::zdoc::Node<'a> {
+                self.node
+            }
+
+            /// Check that the getters of all fields succeed, recursively.
+            ///
+            /// # Errors
+            ///
+            /// Returns the first field that cannot be accessed.
+            #vis fn validate(&self) -> ::core::result::Result<(), ::zdoc::view::ViewError> {
+                #(#validations)*
+                ::core::result::Result::Ok(())
+            }
+
+            #(#getters)*
+        }
+
+        impl<'a> ::zdoc::view::View<'a> for #view<'a> {
+            #[inline]
+            fn from_node(node: ::zdoc::Node<'a>) -> Self {
+                Self::new(node)
+            }
+
+            #[inline]
+            fn node(&self) -> ::zdoc::Node<'a> {
+                self.node
+            }
+
+            #[inline]
+            fn validate(&self) -> ::core::result::Result<(), ::zdoc::view::ViewError> {
+                #view::validate(self)
+            }
+        }
+
+        #viewable
+    })
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<&Punctuated<Field, Comma>> {
+    if let Data::Struct(data) = &input.data {
+        if let Fields::Named(fields) = &data.fields {
+            Ok(&fields.named)
+        } else {
+            Err(syn::Error::new_spanned(
+                &data.fields,
+                "ZdocView only supports structs with named fields",
+            ))
+        }
+    } else {
+        Err(syn::Error::new_spanned(
+            &input.ident,
+            "ZdocView only supports structs with named fields",
+        ))
+    }
+}
+
+/// Implement `Viewable` for the original struct, so it can be used in fields of
+/// other views.
+fn viewable(ident: &Ident, view: &Ident) -> TokenStream {
+    quote! {
+        impl ::zdoc::view::Viewable for #ident {
+            type Field<'a> = ::core::result::Result<#view<'a>, ::zdoc::view::ViewError>;
+            type Item<'a> = #view<'a>;
+
+            #[inline]
+            fn field<'a>(
+                parent: ::zdoc::Node<'a>,
+                at: ::zdoc::view::ViewEntry,
+                entry: ::core::option::Option<::zdoc::Entry<'a>>,
+            ) -> Self::Field<'a> {
+                ::zdoc::view::expect_node(parent, at, entry).map(#view::new)
+            }
+
+            #[inline]
+            fn item<'a>(
+                _list: ::zdoc::Node<'a>,
+                _index: usize,
+                entry: ::zdoc::Entry<'a>,
+            ) -> ::core::option::Option<Self::Item<'a>> {
+                entry.as_node().map(#view::new)
+            }
+
+            fn validate(
+                parent: ::zdoc::Node<'_>,
+                at: ::zdoc::view::ViewEntry,
+                entry: ::core::option::Option<::zdoc::Entry<'_>>,
+            ) -> ::core::result::Result<(), ::zdoc::view::ViewError> {
+                #view::new(::zdoc::view::expect_node(parent, at, entry)?).validate()
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Embed documents in [zdoc](https://docs.rs/zdoc) binaries at compile time.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{LitStr, parse_macro_input};
+
+/// Convert a file to a zdoc document at compile time, and embed it in the
+/// binary as a `&'static zdoc::Document`.
+///
+/// ```ignore
+/// let defaults: &'static zdoc::Document = include_zdoc!("defaults.kdl");
+/// ```
+///
+/// The path is relative to the file containing the invocation, like
+/// [`include_bytes!()`]. The format of the file is detected from its
+/// extension:
+///
+/// | Extension       | Format                   | Feature |
+/// | --------------- | ------------------------ | ------- |
+/// | `json`          | JSON                     | `json`  |
+/// | `kdl`           | KDL                      | `kdl`   |
+/// | `yaml`, `yml`   | YAML                     | `yaml`  |
+/// | `xml`           | XML                      | `xml`   |
+/// | `toml`          | TOML                     | `toml`  |
+/// | `zdt`           | zdoc text format         |         |
+/// | `zdoc`          | zdoc binary format       |         |
+///
+/// The document is built and validated at compile time, and embedded with the
+/// 4-byte alignment required by the binary format, so accessing it at runtime
+/// has no overhead.
+#[proc_macro]
+pub fn include_zdoc(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    include(&path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn include(path: &LitStr) -> syn::Result<TokenStream> {
+    let error = |msg: String| syn::Error::new(path.span(), msg);
+
+    let full_path = resolve(&path.value());
+    let display = full_path.display();
+    let input = std::fs::read(&full_path)
+        .map_err(|err| error(format!("couldn't read `{display}`: {err}")))?;
+    let bytes = convert(&full_path, input).map_err(|err| error(format!("`{display}`: {err}")))?;
+
+    let len = bytes.len();
+    let bytes = Literal::byte_string(&bytes);
+    let full_path = full_path
+        .to_str()
+        .ok_or_else(|| error(format!("path is not valid UTF-8: `{display}`")))?;
+
+    Ok(quote! {{
+        #[repr(C, align(4))]
+        struct Aligned([u8; #len]);
+        static DOCUMENT: Aligned = Aligned(*#bytes);
+
+        // Rebuild when the file changes.
+        const _: &[u8] = include_bytes!(#full_path);
+
+        unsafe {
+            // SAFETY: The document was validated when it was embedded, and it
+            // is 4-byte aligned.
+            ::zdoc::Document::from_slice_unchecked(&DOCUMENT.0)
+        }
+    }})
+}
+
+/// Resolve `path` relative to the file containing the invocation, or the
+/// manifest directory when the file is unknown.
+fn resolve(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_owned();
+    }
+    let base = proc_macro::Span::call_site()
+        .local_file()
+        .and_then(|file| Some(file.parent()?.to_owned()))
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+        .unwrap_or_default();
+    let path = base.join(path);
+    // `include_bytes!()` resolves relative paths differently.
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// Convert the contents of the file at `path` to a document, returning the
+/// bytes of the document.
+fn convert(path: &Path, input: Vec<u8>) -> Result<Vec<u8>, String> {
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    if ext == "zdoc" {
+        let doc = zdoc::DocumentBuffer::from_buffer(input).map_err(|err| err.to_string())?;
+        return Ok(doc.as_bytes().to_owned());
+    }
+
+    let text = std::str::from_utf8(&input).map_err(|err| err.to_string())?;
+    let doc = match ext {
+        #[cfg(feature = "json")]
+        "json" => zdoc::json::document_from_json(text).map_err(|err| err.to_string())?,
+        #[cfg(feature = "kdl")]
+        "kdl" => zdoc::kdl::document_from_kdl(text).map_err(|err| err.to_string())?,
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => zdoc::yaml::document_from_yaml(text).map_err(|err| err.to_string())?,
+        #[cfg(feature = "xml")]
+        "xml" => zdoc::xml::document_from_xml(text).map_err(|err| err.to_string())?,
+        #[cfg(feature = "toml")]
+        "toml" => zdoc::toml::document_from_toml(text).map_err(|err| err.to_string())?,
+        "zdt" => zdoc::text::document_from_text(text).map_err(|err| err.to_string())?,
+        _ => return Err(format!("unsupported format: `{ext}`")),
+    };
+    Ok(doc.as_bytes().to_owned())
+}
@@ -0,0 +1,30 @@
+use zdoc::Document;
+use zdoc_include::include_zdoc;
+
+#[test]
+fn formats() {
+    let text = zdoc::text::document_from_text(include_str!("fixtures/defaults.zdt")).unwrap();
+    let zdt: &'static Document = include_zdoc!("fixtures/defaults.zdt");
+    assert_eq!(zdt, &*text);
+    assert_eq!(include_zdoc!("fixtures/defaults.zdoc"), &*text);
+
+    let kdl = zdoc::kdl::document_from_kdl(include_str!("fixtures/defaults.kdl")).unwrap();
+    assert_eq!(include_zdoc!("fixtures/defaults.kdl"), &*kdl);
+
+    let json = zdoc::json::document_from_json(include_str!("fixtures/defaults.json")).unwrap();
+    assert_eq!(include_zdoc!("fixtures/defaults.json"), &*json);
+
+    let yaml = zdoc::yaml::document_from_yaml(include_str!("fixtures/defaults.yaml")).unwrap();
+    assert_eq!(include_zdoc!("fixtures/defaults.yaml"), &*yaml);
+}
+
+#[test]
+fn aligned() {
+    let doc = include_zdoc!("fixtures/defaults.kdl");
+    assert_eq!(doc.as_bytes().as_ptr().align_offset(4), 0);
+    assert_eq!(doc.root().children().len(), 2);
+    assert_eq!(
+        doc.root().children().get("server").unwrap().get_u64("port"),
+        Some(8080)
+    );
+}
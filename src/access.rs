@@ -19,6 +19,7 @@ pub trait NodeRef<'a>: Sized + 'a {
     fn ty(&self) -> &'a str;
     fn children(&self) -> Self::ChildrenIter<'a>;
     fn args(&self) -> Self::ArgsIter<'a>;
+    fn concrete(&self) -> ConcreteNode<'a>;
     fn entries(&self) -> EntryRefIter<Self::ArgsIter<'a>, Self::ChildrenIter<'a>> {
         EntryRefIter::new(self.args(), self.children())
     }
@@ -34,6 +35,26 @@ pub trait NodeRef<'a>: Sized + 'a {
     }
 }
 
+/// The node behind a [`NodeRef`].
+#[allow(unused)]
+#[derive(Clone, Copy)]
+pub enum ConcreteNode<'a> {
+    Document(crate::Node<'a>),
+    #[cfg(feature = "alloc")]
+    Builder(&'a crate::builder::Node<'a>),
+}
+
+impl ConcreteNode<'_> {
+    #[allow(unused)]
+    pub fn classify(&self) -> ClassifyNode {
+        match self {
+            ConcreteNode::Document(node) => node.classify(),
+            #[cfg(feature = "alloc")]
+            ConcreteNode::Builder(node) => node.classify(),
+        }
+    }
+}
+
 pub trait ArgRef<'a> {
     fn name(&self) -> &'a str;
     fn value(&self) -> ValueRef<'a>;
@@ -43,6 +64,7 @@ pub trait ArgRef<'a> {
 /// of a document in a generic way (builder or serialized).
 ///
 /// Also, this can be used to hook into the general `Debug` implementation.
+#[derive(Clone, Copy)]
 pub enum EntryRef<Arg, Child> {
     Arg(Arg),
     Child(Child),
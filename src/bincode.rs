@@ -0,0 +1,108 @@
+//! [`bincode`] support for [`Document`] and [`DocumentBuffer`].
+//!
+//! Documents are encoded as a length-prefixed byte slice containing the binary
+//! representation of the document, so zdoc payloads can be embedded in other
+//! bincode messages. Decoding copies the bytes into a correctly aligned buffer
+//! and validates the document.
+//!
+//! Note that [`BorrowDecode`](bincode::BorrowDecode) also copies, because the
+//! borrowed bytes are not guaranteed to be suitably aligned.
+
+use alloc::{string::ToString as _, vec::Vec};
+
+use bincode::{
+    de::Decoder,
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+};
+
+use crate::{Document, DocumentBuffer, Error, Result};
+
+impl bincode::Encode for Document {
+    #[inline]
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        bincode::Encode::encode(self.as_bytes(), encoder)
+    }
+}
+
+impl bincode::Encode for DocumentBuffer {
+    #[inline]
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        bincode::Encode::encode(self.as_document(), encoder)
+    }
+}
+
+impl<Context> bincode::Decode<Context> for DocumentBuffer {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let buffer: Vec<u8> = bincode::Decode::decode(decoder)?;
+        DocumentBuffer::from_buffer(buffer).map_err(|err| DecodeError::OtherString(err.to_string()))
+    }
+}
+
+bincode::impl_borrow_decode!(DocumentBuffer);
+
+/// Encode [`Document`] with bincode, using the standard configuration.
+///
+/// # Errors
+///
+/// If encoding fails, this returns an error.
+#[inline]
+pub fn document_to_bincode(doc: &Document) -> Result<Vec<u8>> {
+    bincode::encode_to_vec(doc, bincode::config::standard()).map_err(Error::custom)
+}
+
+/// Decode [`Document`] from bincode, using the standard configuration.
+///
+/// # Errors
+///
+/// If the input is not a valid bincode-encoded document, or if there are
+/// trailing bytes after the document, this returns an error.
+pub fn document_from_bincode(bytes: &[u8]) -> Result<DocumentBuffer> {
+    let (doc, len) =
+        bincode::decode_from_slice(bytes, bincode::config::standard()).map_err(Error::custom)?;
+    if len != bytes.len() {
+        return Err(Error::msg("trailing bytes after bincode-encoded document"));
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, builder};
+
+    #[test]
+    fn roundtrip() {
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.set_ty("Root");
+            root.push(builder::Arg::new("key", builder::Value::Int(-1)));
+            root.push(builder::Value::Binary(b"hello"[..].into()));
+        });
+        let doc = builder.build();
+
+        let encoded = document_to_bincode(&doc).unwrap();
+        let decoded = document_from_bincode(&encoded).unwrap();
+        assert_eq!(decoded.as_bytes(), doc.as_bytes());
+
+        // Embedded in another message.
+        let message = (42u32, doc.clone(), "trailer");
+        let encoded = bincode::encode_to_vec(&message, bincode::config::standard()).unwrap();
+        let ((n, decoded, trailer), _): ((u32, DocumentBuffer, &str), _) =
+            bincode::borrow_decode_from_slice(&encoded, bincode::config::standard()).unwrap();
+        assert_eq!(n, 42);
+        assert_eq!(decoded.as_bytes(), doc.as_bytes());
+        assert_eq!(trailer, "trailer");
+    }
+
+    #[test]
+    fn invalid() {
+        let encoded =
+            bincode::encode_to_vec(&b"not a document"[..], bincode::config::standard()).unwrap();
+        assert!(document_from_bincode(&encoded).is_err());
+
+        let mut encoded = document_to_bincode(&DocumentBuffer::default()).unwrap();
+        encoded.push(0);
+        assert!(document_from_bincode(&encoded).is_err());
+    }
+}
@@ -8,14 +8,22 @@ use crate::{Document, DocumentBuffer, ValueRef, access, codec::StringRange};
 
 mod arg;
 mod entry;
+mod macros;
+mod merge;
 mod node;
+mod path;
 mod value;
 
 pub use arg::*;
 pub use entry::*;
+pub use merge::*;
 pub use node::*;
+pub use path::*;
 pub use value::*;
 
+/// The default of [`Builder::auto_intern_limit()`].
+pub(crate) const DEFAULT_AUTO_INTERN_LIMIT: usize = 128;
+
 /// Builder for [`Document`](crate::Document)s.
 ///
 /// The builder can be used as a mutable document, i.e. it also supports
@@ -24,6 +32,7 @@ pub use value::*;
 pub struct Builder<'a> {
     root: Node<'a>,
     auto_intern_limit: usize,
+    build_index: bool,
 }
 
 impl Default for Builder<'_> {
@@ -38,7 +47,8 @@ impl<'a> Builder<'a> {
     pub const fn new() -> Self {
         Self {
             root: Node::empty(),
-            auto_intern_limit: 128,
+            auto_intern_limit: DEFAULT_AUTO_INTERN_LIMIT,
+            build_index: false,
         }
     }
 
@@ -67,6 +77,21 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Whether [`build()`](Self::build) emits a name index.
+    #[must_use]
+    pub fn build_index(&self) -> bool {
+        self.build_index
+    }
+
+    /// Emit a name index when building the document.
+    ///
+    /// See [`RawBuilder::set_build_index()`].
+    #[inline]
+    pub fn set_build_index(&mut self, build_index: bool) -> &mut Self {
+        self.build_index = build_index;
+        self
+    }
+
     #[inline]
     pub fn set_root(&mut self, node: Node<'a>) {
         self.root = node;
@@ -100,6 +125,7 @@ impl<'a> Builder<'a> {
             return DocumentBuffer::default();
         }
         cache.set_auto_intern_limit(self.auto_intern_limit);
+        cache.raw.set_build_index(self.build_index);
 
         // This recursively serializes the document to the binary format.
         cache.raw.set_root(root);
@@ -113,21 +139,30 @@ impl<'a> Builder<'a> {
         Builder {
             root: self.root.into_static(),
             auto_intern_limit: self.auto_intern_limit,
+            build_index: self.build_index,
         }
     }
 }
 
 /// Allocation cache for [`Builder`], to amortize allocations between calls to
 /// [`build()`](Builder::build).
+///
+/// The cache can also be passed to
+/// [`serde::to_document_with_cache()`](crate::serde::to_document_with_cache),
+/// which then serializes values without building a [`Builder`] first.
 #[derive(Clone, Default)]
 pub struct BuildCache {
-    raw: RawBuilder,
+    pub(crate) raw: RawBuilder,
+    #[cfg(feature = "serde")]
+    pub(crate) serde: crate::serde::Tree,
 }
 
 impl BuildCache {
     #[inline]
     pub fn reset(&mut self) {
         self.raw.clear();
+        #[cfg(feature = "serde")]
+        self.serde.clear();
     }
 
     /// Reset the cache and reclaim any memory that it is currently consuming.
@@ -136,7 +171,7 @@ impl BuildCache {
         *self = Self::default();
     }
 
-    fn set_auto_intern_limit(&mut self, limit: usize) {
+    pub(crate) fn set_auto_intern_limit(&mut self, limit: usize) {
         self.raw.strings.limit = limit;
     }
 }
@@ -220,6 +255,11 @@ impl<'a, 'c: 'a> access::NodeRef<'a> for &'a Node<'c> {
     fn args(&self) -> Self::ArgsIter<'a> {
         self.args.iter()
     }
+
+    #[inline]
+    fn concrete(&self) -> access::ConcreteNode<'a> {
+        access::ConcreteNode::Builder(self)
+    }
 }
 
 impl<'a> access::ArgRef<'a> for &'a Arg<'_> {
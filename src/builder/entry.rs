@@ -3,6 +3,7 @@ use alloc::{borrow::Cow, vec::Vec};
 use super::{Arg, Node, Value};
 
 /// Argument or child of a [`Node`] belonging to a [`Builder`].
+#[derive(Clone)]
 pub enum Entry<'a> {
     Arg(Arg<'a>),
     Child(Node<'a>),
@@ -61,6 +62,15 @@ impl<'a> Entry<'a> {
             Entry::Child(node) => node,
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub fn into_static(self) -> Entry<'static> {
+        match self {
+            Entry::Arg(arg) => Entry::Arg(arg.into_static()),
+            Entry::Child(node) => Entry::Child(node.into_static()),
+        }
+    }
 }
 impl<'a> From<Arg<'a>> for Entry<'a> {
     #[inline]
@@ -0,0 +1,155 @@
+/// Construct a [`builder::Node`](crate::builder::Node) from a KDL-like
+/// literal.
+///
+/// The macro returns an unnamed root node, and its input is the list of
+/// entries in the root node:
+///
+/// ```ignore
+/// let port = 8080u32;
+/// let root = zdoc! {
+///     name "app";
+///     (Http)server host="a" port=(port) {
+///         tls #true;
+///         allow "10.0.0.1" "10.0.0.2"
+///     }
+///     - 1 2 3
+///     (extra_child);
+/// };
+/// ```
+///
+/// - A node is an optional type in parentheses, a name, arguments, and
+///   optionally children in braces. Nodes without children end with `;` (or at
+///   the end of the input), since the macro cannot see line breaks.
+/// - Types, names, and the names of arguments are identifiers (which may
+///   contain `-`), or string literals. The name `-` is an unnamed node, as in
+///   KDL.
+/// - Arguments are `value` (unnamed) or `name=value` (named). Values are Rust
+///   literals, the KDL keywords `#true`, `#false`, `#null`, `#inf`, `#-inf`,
+///   and `#nan`, or a Rust expression in parentheses, which is converted with
+///   [`Into<Value>`](crate::builder::Value).
+/// - A Rust expression in parentheses followed by `;` (or at the end of the
+///   input) in place of a node is pushed as an entry with
+///   [`IntoEntry`](crate::builder::IntoEntry).
+///
+/// Each entry is a step of macro recursion, so very large literals may need a
+/// higher `#![recursion_limit]`.
+#[macro_export]
+macro_rules! zdoc {
+    ($($tt:tt)*) => {{
+        let mut root = $crate::builder::Node::empty();
+        $crate::zdoc_internal!(@entries root $($tt)*);
+        root
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! zdoc_internal {
+    // Entries in a node.
+    (@entries $parent:ident) => {};
+    (@entries $parent:ident ; $($rest:tt)*) => {
+        $crate::zdoc_internal!(@entries $parent $($rest)*);
+    };
+    // Expressions are matched as token trees, because an `expr` fragment
+    // would fail to parse types like `(type)`.
+    (@entries $parent:ident ($($entry:tt)+) ; $($rest:tt)*) => {
+        $parent.push(($($entry)+));
+        $crate::zdoc_internal!(@entries $parent $($rest)*);
+    };
+    (@entries $parent:ident ($($entry:tt)+)) => {
+        $parent.push(($($entry)+));
+    };
+    (@entries $parent:ident ($($ty:tt)+) $($rest:tt)*) => {
+        $crate::zdoc_internal!(@name $parent [$crate::zdoc_internal!(@ident $($ty)+)] $($rest)*);
+    };
+    (@entries $parent:ident $($rest:tt)+) => {
+        $crate::zdoc_internal!(@name $parent [""] $($rest)+);
+    };
+
+    // Name of a node.
+    (@name $parent:ident [$ty:expr] - $($rest:tt)*) => {
+        $crate::zdoc_internal!(@node $parent [$ty] [""] $($rest)*);
+    };
+    (@name $parent:ident [$ty:expr] $first:ident $(- $more:ident)+ $($rest:tt)*) => {
+        $crate::zdoc_internal!(@node $parent [$ty] [$crate::zdoc_internal!(@ident $first $(- $more)+)] $($rest)*);
+    };
+    (@name $parent:ident [$ty:expr] $name:ident $($rest:tt)*) => {
+        $crate::zdoc_internal!(@node $parent [$ty] [stringify!($name)] $($rest)*);
+    };
+    (@name $parent:ident [$ty:expr] $name:literal $($rest:tt)*) => {
+        $crate::zdoc_internal!(@node $parent [$ty] [$name] $($rest)*);
+    };
+
+    (@node $parent:ident [$ty:expr] [$name:expr] $($rest:tt)*) => {
+        let mut node = $crate::builder::Node::empty();
+        node.set_ty($ty).set_name($name);
+        $crate::zdoc_internal!(@args $parent node $($rest)*);
+    };
+
+    // Arguments and children of a node, followed by the siblings of the node.
+    (@args $parent:ident $node:ident) => {
+        $parent.push($node);
+    };
+    (@args $parent:ident $node:ident ; $($rest:tt)*) => {
+        $parent.push($node);
+        $crate::zdoc_internal!(@entries $parent $($rest)*);
+    };
+    (@args $parent:ident $node:ident { $($children:tt)* } $($rest:tt)*) => {
+        $crate::zdoc_internal!(@entries $node $($children)*);
+        $parent.push($node);
+        $crate::zdoc_internal!(@entries $parent $($rest)*);
+    };
+    (@args $parent:ident $node:ident $first:ident $(- $more:ident)* = $($rest:tt)*) => {
+        $crate::zdoc_internal!(@value $parent $node [$crate::zdoc_internal!(@ident $first $(- $more)*)] $($rest)*);
+    };
+    (@args $parent:ident $node:ident $name:literal = $($rest:tt)*) => {
+        $crate::zdoc_internal!(@value $parent $node [$name] $($rest)*);
+    };
+    (@args $parent:ident $node:ident $($rest:tt)+) => {
+        $crate::zdoc_internal!(@value $parent $node [] $($rest)+);
+    };
+
+    // Value of an argument.
+    (@value $parent:ident $node:ident [$($name:expr)?] #true $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [true] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] #false $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [false] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] #null $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [$crate::builder::Value::Null] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] #inf $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [f64::INFINITY] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] #-inf $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [f64::NEG_INFINITY] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] #nan $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [f64::NAN] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] ($value:expr) $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [$value] $($rest)*);
+    };
+    (@value $parent:ident $node:ident [$($name:expr)?] $value:literal $($rest:tt)*) => {
+        $crate::zdoc_internal!(@arg $parent $node [$($name)?] [$value] $($rest)*);
+    };
+
+    (@arg $parent:ident $node:ident [] [$value:expr] $($rest:tt)*) => {
+        $node.push_unnamed_arg($crate::builder::Value::from($value));
+        $crate::zdoc_internal!(@args $parent $node $($rest)*);
+    };
+    (@arg $parent:ident $node:ident [$name:expr] [$value:expr] $($rest:tt)*) => {
+        $node.push_named_arg($name, $crate::builder::Value::from($value));
+        $crate::zdoc_internal!(@args $parent $node $($rest)*);
+    };
+
+    // Names and types as string literals.
+    (@ident $name:literal) => {
+        $name
+    };
+    (@ident $first:ident $(- $more:ident)*) => {
+        concat!(stringify!($first) $(, "-", stringify!($more))*)
+    };
+
+}
@@ -0,0 +1,444 @@
+use core::mem;
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use super::{Arg, Builder, Node};
+use crate::path::{Path, Segment};
+
+/// Policy for [`Node::merge()`].
+///
+/// The default policy is suitable for layered configuration: named children
+/// are merged recursively, lists are replaced, the last entry with a given
+/// name wins, and nodes with different types are replaced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// How to merge a named child that exists in both nodes.
+    pub children: MergeChildren,
+    /// How to merge the unnamed entries of a node.
+    pub sequences: MergeSequences,
+    /// How to handle names that occur more than once in a node.
+    pub duplicates: MergeDuplicates,
+    /// How to merge nodes that have different types.
+    pub types: MergeTypes,
+}
+
+/// How to merge a named child that exists in both nodes. See [`MergePolicy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeChildren {
+    /// Merge the children recursively.
+    #[default]
+    Recurse,
+    /// Replace the existing child with the overlay child.
+    Replace,
+}
+
+/// How to merge the unnamed entries of a node. See [`MergePolicy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeSequences {
+    /// If the overlay node has any unnamed entries, they replace the unnamed
+    /// entries of the existing node.
+    #[default]
+    Replace,
+    /// Append the unnamed entries of the overlay node to the existing node.
+    Append,
+}
+
+/// How to handle names that occur more than once in a node. See
+/// [`MergePolicy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeDuplicates {
+    /// Merge into the last entry with the name, which is the entry returned by
+    /// [`Node::get()`](crate::Node::get). Repeated names in the overlay node
+    /// are merged one after another, so the last one wins.
+    #[default]
+    LastWins,
+    /// Return an error if a name occurs more than once in the overlay node, or
+    /// if a name in the overlay node occurs more than once in the existing
+    /// node.
+    Error,
+}
+
+/// How to merge nodes that both have a type, but different ones. An empty
+/// type never conflicts: the overlay type is used when the existing node has
+/// none. See [`MergePolicy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeTypes {
+    /// Replace the existing node with the overlay node.
+    #[default]
+    Replace,
+    /// Use the type of the overlay node, and merge the contents.
+    Overlay,
+    /// Keep the type of the existing node, and merge the contents.
+    Keep,
+    /// Return an error.
+    Error,
+}
+
+/// Error when merging nodes with [`Node::merge()`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, path {path}")]
+pub struct MergeError {
+    /// Path of the node where the error occurred, relative to the merged node.
+    pub path: Path<'static>,
+    /// Kind of error that occurred.
+    pub error: MergeErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum MergeErrorKind {
+    #[error("duplicate name")]
+    DuplicateName,
+    #[error("conflicting node types")]
+    TypeConflict,
+}
+
+impl<'a> Node<'a> {
+    /// Merge `overlay` into this node, according to `policy`.
+    ///
+    /// Named arguments in `overlay` replace the value of the argument with the
+    /// same name in this node, and named children are merged according to
+    /// [`MergePolicy::children`]. Named entries that do not exist in this node
+    /// are appended. When an argument is merged with a child of the same name
+    /// (or vice versa), the entry from `overlay` replaces the existing entry.
+    ///
+    /// If an error is returned, this node is not modified.
+    ///
+    /// # Errors
+    ///
+    /// If the policy disallows duplicate names or type conflicts, and
+    /// `overlay` contains them, this returns an error.
+    pub fn merge(&mut self, overlay: &Node<'a>, policy: MergePolicy) -> Result<(), MergeError> {
+        self.merge_overlay(overlay, policy)
+    }
+
+    /// Merge a node from a [`Document`](crate::Document) into this node,
+    /// according to `policy`.
+    ///
+    /// This is equivalent to [`merge()`](Self::merge), but does not require
+    /// converting `overlay` to a builder node first. Strings and binary data
+    /// are borrowed from `overlay`.
+    ///
+    /// # Errors
+    ///
+    /// If the policy disallows duplicate names or type conflicts, and
+    /// `overlay` contains them, this returns an error.
+    pub fn merge_document(
+        &mut self,
+        overlay: crate::Node<'a>,
+        policy: MergePolicy,
+    ) -> Result<(), MergeError> {
+        self.merge_overlay(overlay, policy)
+    }
+
+    fn merge_overlay(
+        &mut self,
+        overlay: impl Overlay<'a>,
+        policy: MergePolicy,
+    ) -> Result<(), MergeError> {
+        let mut node = self.clone();
+        let mut merger = Merger {
+            policy,
+            path: Vec::new(),
+        };
+        if policy.duplicates == MergeDuplicates::Error {
+            merger.check_overlay(overlay)?;
+        }
+        merger.merge_node(&mut node, overlay)?;
+        if !overlay.name().is_empty() {
+            node.name = overlay.to_name();
+        }
+        *self = node;
+        Ok(())
+    }
+}
+
+impl<'a> Builder<'a> {
+    /// Merge `overlay` into the root node, according to `policy`.
+    ///
+    /// See [`Node::merge()`].
+    ///
+    /// # Errors
+    ///
+    /// If the policy disallows duplicate names or type conflicts, and
+    /// `overlay` contains them, this returns an error.
+    #[inline]
+    pub fn merge(&mut self, overlay: &Node<'a>, policy: MergePolicy) -> Result<(), MergeError> {
+        self.root_mut().merge(overlay, policy)
+    }
+
+    /// Merge a node from a [`Document`](crate::Document) into the root node,
+    /// according to `policy`.
+    ///
+    /// See [`Node::merge_document()`].
+    ///
+    /// # Errors
+    ///
+    /// If the policy disallows duplicate names or type conflicts, and
+    /// `overlay` contains them, this returns an error.
+    #[inline]
+    pub fn merge_document(
+        &mut self,
+        overlay: crate::Node<'a>,
+        policy: MergePolicy,
+    ) -> Result<(), MergeError> {
+        self.root_mut().merge_document(overlay, policy)
+    }
+}
+
+/// Node that can be merged into a builder node, which is either a builder node
+/// or a document node.
+trait Overlay<'a>: Copy {
+    fn name(&self) -> &str;
+    fn ty(&self) -> &str;
+    fn to_name(self) -> Cow<'a, str>;
+    fn to_ty(self) -> Cow<'a, str>;
+    fn args(self) -> impl Iterator<Item = Arg<'a>>;
+    fn children(self) -> impl Iterator<Item = Self>;
+    fn to_node(self) -> Node<'a>;
+}
+
+impl<'a> Overlay<'a> for &Node<'a> {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    #[inline]
+    fn to_name(self) -> Cow<'a, str> {
+        self.name.clone()
+    }
+
+    #[inline]
+    fn to_ty(self) -> Cow<'a, str> {
+        self.ty.clone()
+    }
+
+    #[inline]
+    fn args(self) -> impl Iterator<Item = Arg<'a>> {
+        self.args.iter().cloned()
+    }
+
+    #[inline]
+    fn children(self) -> impl Iterator<Item = Self> {
+        self.children.iter()
+    }
+
+    #[inline]
+    fn to_node(self) -> Node<'a> {
+        self.clone()
+    }
+}
+
+impl<'a> Overlay<'a> for crate::Node<'a> {
+    #[inline]
+    fn name(&self) -> &str {
+        crate::Node::name(self).unwrap_or_default()
+    }
+
+    #[inline]
+    fn ty(&self) -> &str {
+        crate::Node::ty(self).unwrap_or_default()
+    }
+
+    #[inline]
+    fn to_name(self) -> Cow<'a, str> {
+        Cow::Borrowed(crate::Node::name(&self).unwrap_or_default())
+    }
+
+    #[inline]
+    fn to_ty(self) -> Cow<'a, str> {
+        Cow::Borrowed(crate::Node::ty(&self).unwrap_or_default())
+    }
+
+    #[inline]
+    fn args(self) -> impl Iterator<Item = Arg<'a>> {
+        crate::Node::args(&self).into_iter().map(Arg::from_document)
+    }
+
+    #[inline]
+    fn children(self) -> impl Iterator<Item = Self> {
+        crate::Node::children(&self).into_iter()
+    }
+
+    #[inline]
+    fn to_node(self) -> Node<'a> {
+        Node::from_document(self)
+    }
+}
+
+/// Position of the last entry with a given name.
+#[derive(Clone, Copy)]
+enum Target {
+    Arg(usize),
+    Child(usize),
+}
+
+struct Merger {
+    policy: MergePolicy,
+    path: Vec<Segment<'static>>,
+}
+
+impl Merger {
+    fn error(&self, error: MergeErrorKind) -> MergeError {
+        MergeError {
+            path: self.path.iter().cloned().collect(),
+            error,
+        }
+    }
+
+    fn merge_node<'a>(
+        &mut self,
+        base: &mut Node<'a>,
+        overlay: impl Overlay<'a>,
+    ) -> Result<(), MergeError> {
+        let ty = overlay.ty();
+        if !ty.is_empty() && base.ty != ty {
+            match self.policy.types {
+                _ if base.ty.is_empty() => base.ty = overlay.to_ty(),
+                MergeTypes::Replace => {
+                    let name = mem::take(&mut base.name);
+                    *base = overlay.to_node();
+                    base.name = name;
+                    return Ok(());
+                }
+                MergeTypes::Overlay => base.ty = overlay.to_ty(),
+                MergeTypes::Keep => {}
+                MergeTypes::Error => return Err(self.error(MergeErrorKind::TypeConflict)),
+            }
+        }
+
+        if self.policy.duplicates == MergeDuplicates::Error {
+            self.check_base(base, overlay)?;
+        }
+
+        let has_unnamed = overlay.args().any(|arg| arg.name.is_none())
+            || overlay.children().any(|child| child.name().is_empty());
+        if has_unnamed && self.policy.sequences == MergeSequences::Replace {
+            base.args.retain(|arg| arg.name.is_some());
+            base.children.retain(|child| !child.name.is_empty());
+        }
+
+        for arg in overlay.args() {
+            let Some(name) = &arg.name else {
+                base.args.push(arg);
+                continue;
+            };
+            match find_last(base, name) {
+                Some(Target::Arg(index)) => base.args[index].value = arg.value,
+                Some(Target::Child(index)) => {
+                    base.children.remove(index);
+                    base.args.push(arg);
+                }
+                None => base.args.push(arg),
+            }
+        }
+
+        for child in overlay.children() {
+            let name = child.name();
+            if name.is_empty() {
+                base.children.push(child.to_node());
+                continue;
+            }
+            match find_last(base, name) {
+                Some(Target::Child(index)) => match self.policy.children {
+                    MergeChildren::Recurse => {
+                        let segment = if count_named(base, name) == 1 {
+                            Segment::Name(Cow::Owned(name.into()))
+                        } else {
+                            Segment::Index(base.args.len() + index)
+                        };
+                        self.path.push(segment);
+                        self.merge_node(&mut base.children[index], child)?;
+                        self.path.pop();
+                    }
+                    MergeChildren::Replace => base.children[index] = child.to_node(),
+                },
+                Some(Target::Arg(index)) => {
+                    base.args.remove(index);
+                    base.children.push(child.to_node());
+                }
+                None => base.children.push(child.to_node()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that names are unique in each node of `overlay`.
+    fn check_overlay<'a>(&mut self, overlay: impl Overlay<'a>) -> Result<(), MergeError> {
+        let mut names = overlay
+            .args()
+            .filter_map(|arg| arg.name)
+            .chain(overlay.children().map(Overlay::to_name))
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            self.path
+                .push(Segment::Name(Cow::Owned(pair[0].as_ref().into())));
+            return Err(self.error(MergeErrorKind::DuplicateName));
+        }
+
+        let args_len = overlay.args().count();
+        for (index, child) in overlay.children().enumerate() {
+            let segment = if child.name().is_empty() {
+                Segment::Index(args_len + index)
+            } else {
+                Segment::Name(Cow::Owned(child.name().into()))
+            };
+            self.path.push(segment);
+            self.check_overlay(child)?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    /// Check that the names in `overlay` are unique in `base`.
+    fn check_base<'a>(
+        &mut self,
+        base: &Node<'a>,
+        overlay: impl Overlay<'a>,
+    ) -> Result<(), MergeError> {
+        let duplicate = overlay
+            .args()
+            .filter_map(|arg| arg.name)
+            .chain(overlay.children().map(Overlay::to_name))
+            .find(|name| !name.is_empty() && count_named(base, name) > 1);
+        if let Some(name) = duplicate {
+            self.path.push(Segment::Name(Cow::Owned(name.into_owned())));
+            return Err(self.error(MergeErrorKind::DuplicateName));
+        }
+        Ok(())
+    }
+}
+
+/// Find the entry with `name` like [`Node::get()`](crate::Node::get): the last
+/// argument with the name, or else the last child with the name.
+fn find_last(node: &Node, name: &str) -> Option<Target> {
+    node.args
+        .iter()
+        .rposition(|arg| arg.name.as_deref() == Some(name))
+        .map(Target::Arg)
+        .or_else(|| {
+            node.children
+                .iter()
+                .rposition(|child| child.name == name)
+                .map(Target::Child)
+        })
+}
+
+fn count_named(node: &Node, name: &str) -> usize {
+    node.args
+        .iter()
+        .filter(|arg| arg.name.as_deref() == Some(name))
+        .count()
+        + node
+            .children
+            .iter()
+            .filter(|child| child.name == name)
+            .count()
+}
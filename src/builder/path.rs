@@ -0,0 +1,365 @@
+use core::mem;
+
+use alloc::{borrow::Cow, string::String};
+
+use super::{Arg, Builder, Entry, IntoEntry, Node, Value};
+use crate::path::{Path, PathError, Segment};
+
+/// Reference to the target of a [`Path`] within a [`Node`].
+#[derive(Clone, Copy, Debug)]
+pub enum PathRef<'b, 'a> {
+    Node(&'b Node<'a>),
+    Arg(&'b Arg<'a>),
+    Type(&'b str),
+}
+
+/// Position of an entry within a node.
+#[derive(Clone, Copy)]
+enum Slot {
+    Arg(usize),
+    Child(usize),
+}
+
+impl<'a> Node<'a> {
+    /// Get the entry at `path`, relative to this node.
+    ///
+    /// See [`path`](crate::path) for the path syntax. The empty path refers to
+    /// this node.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax, this returns an error.
+    pub fn get_path(&self, path: &str) -> Result<Option<PathRef<'_, 'a>>, PathError> {
+        let path = Path::parse(path)?;
+        let Some((last, init)) = path.segments().split_last() else {
+            return Ok(Some(PathRef::Node(self)));
+        };
+        let Some(node) = self.node_at(init) else {
+            return Ok(None);
+        };
+        Ok(match last {
+            Segment::Type => Some(PathRef::Type(&node.ty)),
+            _ => node.find(last).map(|slot| match slot {
+                Slot::Arg(index) => PathRef::Arg(&node.args[index]),
+                Slot::Child(index) => PathRef::Node(&node.children[index]),
+            }),
+        })
+    }
+
+    /// Set the entry at `path`, relative to this node, creating intermediate
+    /// nodes as needed.
+    ///
+    /// If an entry with the same name already exists, it is replaced.
+    /// Replacing an argument with a node (or vice versa) moves the entry after
+    /// the existing arguments (or children) of the node, and replacing an
+    /// argument with a node removes the other arguments with the same name.
+    /// Setting the index one past the last entry appends the entry, and setting
+    /// `#type` requires a string value.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax, is empty, goes through an argument,
+    /// or if the value cannot be stored at the path, this returns an error.
+    pub fn set_path(&mut self, path: &str, value: impl IntoEntry<'a>) -> Result<(), PathError> {
+        let path = Path::parse(path)?;
+        let (last, init) = path.segments().split_last().ok_or(PathError::Empty)?;
+        self.node_at_mut_or_create(init)?
+            .set_entry(last, value.into_entry())
+    }
+
+    /// Remove the entry at `path`, relative to this node, returning the removed
+    /// entry.
+    ///
+    /// Removing `#type` clears the type of the node, and returns it as an
+    /// unnamed string argument.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax or is empty, this returns an error.
+    pub fn remove_path(&mut self, path: &str) -> Result<Option<Entry<'a>>, PathError> {
+        let path = Path::parse(path)?;
+        let (last, init) = path.segments().split_last().ok_or(PathError::Empty)?;
+        let Some(node) = self.node_at_mut(init) else {
+            return Ok(None);
+        };
+        Ok(match last {
+            Segment::Type if node.ty.is_empty() => None,
+            Segment::Type => Some(Entry::Arg(Arg::unnamed(Value::String(mem::take(
+                &mut node.ty,
+            ))))),
+            _ => node.find(last).map(|slot| match slot {
+                Slot::Arg(index) => Entry::Arg(node.args.remove(index)),
+                Slot::Child(index) => Entry::Child(node.children.remove(index)),
+            }),
+        })
+    }
+
+    /// Find the entry selected by `segment`. Like readers of documents, names
+    /// resolve to the last entry with that name.
+    fn find(&self, segment: &Segment) -> Option<Slot> {
+        match segment {
+            Segment::Name(name) => self
+                .args
+                .iter()
+                .rposition(|arg| arg.name.as_deref() == Some(name))
+                .map(Slot::Arg)
+                .or_else(|| {
+                    self.children
+                        .iter()
+                        .rposition(|child| child.name == *name)
+                        .map(Slot::Child)
+                }),
+            Segment::Arg(name) => self
+                .args
+                .iter()
+                .rposition(|arg| arg.name.as_deref() == Some(name))
+                .map(Slot::Arg),
+            Segment::Index(index) => {
+                let index = *index;
+                if index < self.args.len() {
+                    Some(Slot::Arg(index))
+                } else if index - self.args.len() < self.children.len() {
+                    Some(Slot::Child(index - self.args.len()))
+                } else {
+                    None
+                }
+            }
+            Segment::Type => None,
+        }
+    }
+
+    fn node_at(&self, segments: &[Segment]) -> Option<&Node<'a>> {
+        let mut node = self;
+        for segment in segments {
+            match node.find(segment)? {
+                Slot::Child(index) => node = &node.children[index],
+                Slot::Arg(_) => return None,
+            }
+        }
+        Some(node)
+    }
+
+    pub(crate) fn node_at_mut(&mut self, segments: &[Segment]) -> Option<&mut Node<'a>> {
+        let mut node = self;
+        for segment in segments {
+            match node.find(segment)? {
+                Slot::Child(index) => node = &mut node.children[index],
+                Slot::Arg(_) => return None,
+            }
+        }
+        Some(node)
+    }
+
+    fn node_at_mut_or_create(&mut self, segments: &[Segment]) -> Result<&mut Node<'a>, PathError> {
+        let mut node = self;
+        for segment in segments {
+            let index = match node.find(segment) {
+                Some(Slot::Child(index)) => index,
+                Some(Slot::Arg(_)) => return Err(PathError::NotANode),
+                None => {
+                    let mut child = Node::empty();
+                    match segment {
+                        Segment::Name(name) => child.name = Cow::Owned(String::from(&**name)),
+                        Segment::Index(index) if *index == node.entry_len() => {}
+                        Segment::Index(index) => return Err(PathError::IndexOutOfBounds(*index)),
+                        Segment::Arg(_) | Segment::Type => return Err(PathError::NotANode),
+                    }
+                    node.children.push(child);
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+        }
+        Ok(node)
+    }
+
+    fn entry_len(&self) -> usize {
+        self.args.len() + self.children.len()
+    }
+
+    fn set_entry(&mut self, segment: &Segment, entry: Entry<'a>) -> Result<(), PathError> {
+        let name = match segment {
+            Segment::Type => {
+                let Entry::Arg(Arg {
+                    value: Value::String(ty),
+                    ..
+                }) = entry
+                else {
+                    return Err(PathError::InvalidValue);
+                };
+                self.ty = ty;
+                return Ok(());
+            }
+            Segment::Arg(_) if matches!(entry, Entry::Child(_)) => {
+                return Err(PathError::InvalidValue);
+            }
+            Segment::Name(name) | Segment::Arg(name) => Some(Cow::Owned(String::from(&**name))),
+            Segment::Index(_) => None,
+        };
+
+        match (self.find(segment), entry) {
+            (Some(Slot::Arg(index)), Entry::Arg(arg)) => {
+                self.args[index].value = arg.value;
+            }
+            (Some(Slot::Child(index)), Entry::Child(mut child)) => {
+                let existing = &mut self.children[index];
+                child.name = name.unwrap_or_else(|| mem::take(&mut existing.name));
+                *existing = child;
+            }
+            (Some(Slot::Arg(index)), Entry::Child(mut child)) => {
+                let existing = self.args.remove(index);
+                child.name = name.or(existing.name).unwrap_or_default();
+                // Other arguments with the name would shadow the node.
+                if !child.name.is_empty() {
+                    self.args
+                        .retain(|arg| arg.name.as_deref() != Some(&*child.name));
+                }
+                self.children.insert(0, child);
+            }
+            (Some(Slot::Child(index)), Entry::Arg(arg)) => {
+                let existing = self.children.remove(index);
+                let name = name.unwrap_or(existing.name);
+                self.args.push(Arg {
+                    name: (!name.is_empty()).then_some(name),
+                    value: arg.value,
+                });
+            }
+            (None, entry) => match segment {
+                Segment::Index(index) if *index != self.entry_len() => {
+                    return Err(PathError::IndexOutOfBounds(*index));
+                }
+                _ => match entry {
+                    Entry::Arg(mut arg) => {
+                        if name.is_some() {
+                            arg.name = name;
+                        }
+                        self.args.push(arg);
+                    }
+                    Entry::Child(mut child) => {
+                        if let Some(name) = name {
+                            child.name = name;
+                        }
+                        self.children.push(child);
+                    }
+                },
+            },
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Builder<'a> {
+    /// Get the entry at `path`, relative to the root node.
+    ///
+    /// See [`Node::get_path()`].
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax, this returns an error.
+    #[inline]
+    pub fn get_path(&self, path: &str) -> Result<Option<PathRef<'_, 'a>>, PathError> {
+        self.root().get_path(path)
+    }
+
+    /// Set the entry at `path`, relative to the root node, creating
+    /// intermediate nodes as needed.
+    ///
+    /// See [`Node::set_path()`].
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax, is empty, goes through an argument,
+    /// or if the value cannot be stored at the path, this returns an error.
+    #[inline]
+    pub fn set_path(&mut self, path: &str, value: impl IntoEntry<'a>) -> Result<(), PathError> {
+        self.root_mut().set_path(path, value)
+    }
+
+    /// Remove the entry at `path`, relative to the root node.
+    ///
+    /// See [`Node::remove_path()`].
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not valid path syntax or is empty, this returns an error.
+    #[inline]
+    pub fn remove_path(&mut self, path: &str) -> Result<Option<Entry<'a>>, PathError> {
+        self.root_mut().remove_path(path)
+    }
+}
+
+/// Get the child node at a path.
+///
+/// # Panics
+///
+/// This panics if the path is invalid, or does not refer to a node.
+impl<'a> core::ops::Index<&str> for Node<'a> {
+    type Output = Node<'a>;
+
+    #[track_caller]
+    fn index(&self, path: &str) -> &Self::Output {
+        match self.get_path(path) {
+            Ok(Some(PathRef::Node(node))) => node,
+            Ok(_) => panic!("path does not refer to a node: {path}"),
+            Err(err) => panic!("{err}: {path}"),
+        }
+    }
+}
+
+/// Get the child node at a path, creating it and any intermediate nodes if
+/// they do not exist.
+///
+/// # Panics
+///
+/// This panics if the path is invalid, or refers to an argument or a type.
+impl core::ops::IndexMut<&str> for Node<'_> {
+    #[track_caller]
+    fn index_mut(&mut self, path: &str) -> &mut Self::Output {
+        let path = match Path::parse(path) {
+            Ok(path) => path,
+            Err(err) => panic!("{err}: {path}"),
+        };
+        assert!(
+            path.segments().last() != Some(&Segment::Type),
+            "path does not refer to a node: {path}"
+        );
+        match self.node_at_mut_or_create(path.segments()) {
+            Ok(node) => node,
+            Err(err) => panic!("{err}: {path}"),
+        }
+    }
+}
+
+/// Get the entry at an index, which must be a child node.
+///
+/// # Panics
+///
+/// This panics if the index is out of bounds, or refers to an argument.
+impl<'a> core::ops::Index<usize> for Node<'a> {
+    type Output = Node<'a>;
+
+    #[track_caller]
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.find(&Segment::Index(index)) {
+            Some(Slot::Child(child)) => &self.children[child],
+            Some(Slot::Arg(_)) => panic!("entry {index} is an argument"),
+            None => panic!("index out of bounds: {index}"),
+        }
+    }
+}
+
+/// Get the entry at an index, which must be a child node.
+///
+/// # Panics
+///
+/// This panics if the index is out of bounds, or refers to an argument.
+impl core::ops::IndexMut<usize> for Node<'_> {
+    #[track_caller]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match self.find(&Segment::Index(index)) {
+            Some(Slot::Child(child)) => &mut self.children[child],
+            Some(Slot::Arg(_)) => panic!("entry {index} is an argument"),
+            None => panic!("index out of bounds: {index}"),
+        }
+    }
+}
@@ -20,6 +20,7 @@ pub struct RawBuilder {
     args: Vec<codec::Arg>,
     pub(super) strings: Strings,
     binary: Vec<u8>,
+    build_index: bool,
 }
 
 impl RawBuilder {
@@ -39,6 +40,26 @@ impl RawBuilder {
             + self.binary.len()
     }
 
+    /// Whether [`build()`](Self::build) emits a name index.
+    #[inline]
+    #[must_use]
+    pub fn build_index(&self) -> bool {
+        self.build_index
+    }
+
+    /// Emit a name index when building the document, which makes looking up
+    /// arguments and children by name O(1) at the cost of 8-16 bytes per named
+    /// entry. See [`IndexEntry`](codec::IndexEntry).
+    ///
+    /// Documents with a name index have format version
+    /// [`INDEXED_VERSION`](codec::INDEXED_VERSION). If names collide so much
+    /// that lookups could not find them, the document is built without one.
+    #[inline]
+    pub fn set_build_index(&mut self, build_index: bool) -> &mut Self {
+        self.build_index = build_index;
+        self
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.nodes.clear();
@@ -160,22 +181,34 @@ impl RawBuilder {
             self.nodes.len() as u32
         };
 
-        let size = self
-            .file_size()
+        let index = if self.build_index {
+            self.build_name_index()
+        } else {
+            Vec::new()
+        };
+
+        let size = (self.file_size() + size_of::<codec::IndexEntry>() * index.len())
             .try_into()
             .expect("document would be too large (> 4 GiB)");
 
         let args_offset =
             size_of::<codec::Header>() as u32 + nodes_len * size_of::<codec::Node>() as u32;
         let args_len = self.args.len() as u32;
-        let strings_offset = args_offset + args_len * size_of::<codec::Arg>() as u32;
+        let index_len = index.len() as u32;
+        let index_start = args_offset + args_len * size_of::<codec::Arg>() as u32;
+        let index_offset = if index.is_empty() { 0 } else { index_start };
+        let strings_offset = index_start + index_len * size_of::<codec::IndexEntry>() as u32;
         let strings_len = self.strings.buffer.len() as u32;
         let binary_offset = strings_offset + strings_len;
         let binary_len = self.binary.len() as u32;
 
         let header = codec::Header {
             magic: codec::MAGIC,
-            version: codec::VERSION,
+            version: if index.is_empty() {
+                codec::VERSION
+            } else {
+                codec::INDEXED_VERSION
+            },
             root_node_index: 0,
             size,
             nodes_offset: size_of::<codec::Header>() as u32,
@@ -186,9 +219,9 @@ impl RawBuilder {
             strings_len,
             binary_offset,
             binary_len,
-            reserved1: 0,
-            reserved2: 0,
-            reserved3: 0,
+            index_offset,
+            index_len,
+            reserved: 0,
         };
 
         let mut buffer = Vec::with_capacity(size as usize);
@@ -197,6 +230,7 @@ impl RawBuilder {
         buffer.extend_from_slice(cast_slice(&self.nodes));
         debug_assert_eq!(buffer.len(), args_offset as usize);
         buffer.extend_from_slice(cast_slice(&self.args));
+        buffer.extend_from_slice(cast_slice(&index));
         debug_assert_eq!(buffer.len(), strings_offset as usize);
         buffer.extend_from_slice(self.strings.buffer.as_bytes());
         debug_assert_eq!(buffer.len(), binary_offset as usize);
@@ -211,6 +245,105 @@ impl RawBuilder {
             DocumentBuffer::from_raw_unchecked(raw)
         }
     }
+
+    /// Build the name index, mapping each name of an argument or child of a
+    /// node to the last entry with that name.
+    fn build_name_index(&self) -> Vec<codec::IndexEntry> {
+        let named_len = self
+            .args
+            .iter()
+            .map(|arg| arg.name)
+            .chain(self.nodes.iter().map(|node| node.name))
+            .filter(|name| name.len != 0)
+            .count();
+        if named_len == 0 {
+            return Vec::new();
+        }
+
+        // Keep the load factor at or below 50%, so probe sequences stay short.
+        // When names collide so much that an entry would be out of reach of
+        // lookups, retry with a larger table. Names whose hashes are equal
+        // cannot be separated, so eventually the document is built without an
+        // index instead.
+        let mut len = (named_len * 2).next_power_of_two();
+        for _ in 0..4 {
+            if let Some(index) = self.try_build_name_index(len) {
+                return index;
+            }
+            len *= 2;
+        }
+        Vec::new()
+    }
+
+    fn try_build_name_index(&self, len: usize) -> Option<Vec<codec::IndexEntry>> {
+        let mut index = alloc::vec![codec::IndexEntry::EMPTY; len];
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            let args = &self.args[node.args.start as usize..][..node.args.len as usize];
+            let children =
+                &self.nodes[node.children.start as usize..][..node.children.len as usize];
+            let names = args
+                .iter()
+                .map(|arg| (false, arg.name))
+                .chain(children.iter().map(|child| (true, child.name)));
+            for (entry, (is_child, name)) in names.enumerate() {
+                if name.len != 0 {
+                    self.insert_into_index(
+                        &mut index,
+                        node_index as u32,
+                        entry as u32,
+                        is_child,
+                        name,
+                    )?;
+                }
+            }
+        }
+        Some(index)
+    }
+
+    fn insert_into_index(
+        &self,
+        index: &mut [codec::IndexEntry],
+        node_index: u32,
+        entry: u32,
+        is_child: bool,
+        name: codec::StringRange,
+    ) -> Option<()> {
+        let node = &self.nodes[node_index as usize];
+        let name = self.string_bytes(name);
+        let mask = index.len() - 1;
+        let mut bucket_index = codec::index_hash(node_index, name) as usize & mask;
+        for _ in 0..codec::MAX_INDEX_PROBE {
+            let bucket = &mut index[bucket_index];
+            if bucket.is_empty() {
+                *bucket = codec::IndexEntry {
+                    node: node_index,
+                    entry,
+                };
+                return Some(());
+            }
+
+            // Later entries replace earlier entries of the same kind with the
+            // same name.
+            if bucket.node == node_index && (bucket.entry >= node.args.len) == is_child {
+                let existing = if is_child {
+                    self.nodes[(node.children.start + bucket.entry - node.args.len) as usize].name
+                } else {
+                    self.args[(node.args.start + bucket.entry) as usize].name
+                };
+                if self.string_bytes(existing) == name {
+                    bucket.entry = entry;
+                    return Some(());
+                }
+            }
+            bucket_index = (bucket_index + 1) & mask;
+        }
+        None
+    }
+
+    #[inline]
+    fn string_bytes(&self, range: codec::StringRange) -> &[u8] {
+        &self.strings.buffer.as_bytes()[range.start as usize..][..range.len as usize]
+    }
 }
 
 /// Raw node builder, agnostic about the type of its children and arguments.
@@ -0,0 +1,707 @@
+//! Structural diff and patch between documents.
+//!
+//! [`diff()`] compares two nodes and produces a [`Patch`], which is a list of
+//! [`Change`]s that turn the old node into the new node when applied with
+//! [`Patch::apply()`].
+//!
+//! Entries of a node are compared separately for arguments and children.
+//! Named entries are matched by name, where the n-th entry with a given name
+//! in the old node is matched with the n-th entry with the same name in the
+//! new node. Unnamed entries are matched by their position among the unnamed
+//! entries. Matched arguments with different values become
+//! [`Op::Set`] changes, and matched children are compared recursively. Entries
+//! that were moved relative to the other entries are removed and inserted
+//! again.
+//!
+//! A patch can itself be stored as a document with
+//! [`Patch::to_builder()`], and read back with [`Patch::from_node()`]:
+//!
+//! ```text
+//! (Patch) {
+//!     (set) { path = "server", index = 1u, name = "port", old = 80u, new = 8080u }
+//!     (insert) { path = "", index = 2u, name = "debug", value = true }
+//!     (remove) { path = "", index = 3u, value = (Log) { level = "info" } }
+//!     (type) { path = "server", old = "Http", new = "Https" }
+//! }
+//! ```
+//!
+//! The `path` of a change is the [`Path`] of the node containing the changed
+//! entry, and `index` is the index of the entry within that node, counting
+//! arguments before children. Both refer to the document as it is when the
+//! change is applied, after all previous changes have been applied.
+
+use alloc::{borrow::Cow, string::ToString as _, vec, vec::Vec};
+use core::fmt;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    Builder, DocumentBuffer, ValueRef,
+    access::{ArgRef, NodeRef},
+    builder::{self, Arg, Entry, Value},
+    path::{Path, PathError, Segment},
+    text::{self, TextSettings},
+};
+
+/// Compute the changes that turn `old` into `new`.
+#[must_use]
+pub fn diff<'a>(old: &crate::Node<'a>, new: &crate::Node<'a>) -> Patch<'a> {
+    diff_nodes(old, new)
+}
+
+/// Compute the changes that turn `old` into `new`, where both are nodes
+/// belonging to a [`Builder`].
+#[must_use]
+pub fn diff_builder<'a>(old: &'a builder::Node, new: &'a builder::Node) -> Patch<'a> {
+    diff_nodes(&old, &new)
+}
+
+fn diff_nodes<'a, N: NodeRef<'a>>(old: &N, new: &N) -> Patch<'a> {
+    let mut differ = Differ {
+        path: Vec::new(),
+        changes: Vec::new(),
+    };
+    if old.name() != new.name() {
+        differ.push(Op::Rename {
+            old: Cow::Borrowed(old.name()),
+            new: Cow::Borrowed(new.name()),
+        });
+    }
+    differ.diff_node(old, new);
+    Patch {
+        changes: differ.changes,
+    }
+}
+
+/// List of changes between two nodes.
+///
+/// The [`Display`](fmt::Display) implementation prints one line per change,
+/// prefixed with `+` for insertions, `-` for removals, and `~` for
+/// modifications.
+#[derive(Clone, Debug, Default)]
+pub struct Patch<'a> {
+    changes: Vec<Change<'a>>,
+}
+
+/// Single change in a [`Patch`].
+#[derive(Clone, Debug)]
+pub struct Change<'a> {
+    /// Path of the node containing the changed entry.
+    pub path: Path<'a>,
+    pub op: Op<'a>,
+}
+
+/// Operation of a [`Change`].
+#[derive(Clone, Debug)]
+pub enum Op<'a> {
+    /// Insert the entry at `index`.
+    Insert { index: usize, entry: Entry<'a> },
+    /// Remove the entry at `index`, which must be equal to `entry`.
+    Remove { index: usize, entry: Entry<'a> },
+    /// Change the value of the argument at `index`, which must have the given
+    /// name and value `old`.
+    Set {
+        index: usize,
+        name: Option<Cow<'a, str>>,
+        old: Value<'a>,
+        new: Value<'a>,
+    },
+    /// Change the type of the node.
+    SetType {
+        old: Cow<'a, str>,
+        new: Cow<'a, str>,
+    },
+    /// Change the name of the node. This only happens for the root node,
+    /// because other entries are matched by name.
+    Rename {
+        old: Cow<'a, str>,
+        new: Cow<'a, str>,
+    },
+}
+
+/// Error when applying a [`Patch`], or reading it from a document.
+///
+/// Each variant contains the index of the offending change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("invalid path in change {change}: {error}")]
+    Path { change: usize, error: PathError },
+    #[error("node not found in change {0}")]
+    NodeNotFound(usize),
+    #[error("index out of bounds in change {0}")]
+    IndexOutOfBounds(usize),
+    #[error("change {0} does not match the document")]
+    Conflict(usize),
+    #[error("invalid change {0}")]
+    InvalidChange(usize),
+}
+
+impl<'a> Patch<'a> {
+    #[inline]
+    #[must_use]
+    pub fn changes(&self) -> &[Change<'a>] {
+        &self.changes
+    }
+
+    /// Returns `true` if the patch has no changes, i.e. the nodes were equal.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Apply the patch to the root node of `builder`.
+    ///
+    /// Each change is checked against the current contents of the builder,
+    /// so a patch only applies to a document that is equal to the old node it
+    /// was computed from (at least in the parts that were changed). If any
+    /// change fails, the builder is left unmodified.
+    ///
+    /// # Errors
+    ///
+    /// If a change refers to a node or an index that does not exist, or the
+    /// existing entry does not match the change, this returns an error.
+    pub fn apply<'b>(&self, builder: &mut Builder<'b>) -> Result<(), PatchError>
+    where
+        'a: 'b,
+    {
+        let mut root = builder.root().clone();
+        for (index, change) in self.changes.iter().enumerate() {
+            change.apply(&mut root, index)?;
+        }
+        builder.set_root(root);
+        Ok(())
+    }
+
+    /// Represent the patch as a document.
+    ///
+    /// See the [module documentation](self) for the format.
+    #[must_use]
+    pub fn to_builder(&self) -> Builder<'a> {
+        let mut root = builder::Node::empty();
+        root.set_ty("Patch");
+        root.set_children(self.changes.iter().map(Change::to_node));
+        let mut builder = Builder::new();
+        builder.set_root(root);
+        builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn to_document(&self) -> DocumentBuffer {
+        self.to_builder().build()
+    }
+
+    /// Read a patch from a document produced by
+    /// [`to_builder()`](Self::to_builder).
+    ///
+    /// # Errors
+    ///
+    /// If a change is not valid, this returns an error.
+    pub fn from_node(node: crate::Node<'a>) -> Result<Self, PatchError> {
+        let changes = node
+            .children()
+            .into_iter()
+            .enumerate()
+            .map(|(index, change)| Change::from_node(change, index))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { changes })
+    }
+
+    #[must_use]
+    pub fn into_static(self) -> Patch<'static> {
+        Patch {
+            changes: self.changes.into_iter().map(Change::into_static).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Patch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Change<'a> {
+    fn apply<'b>(&self, root: &mut builder::Node<'b>, change: usize) -> Result<(), PatchError>
+    where
+        'a: 'b,
+    {
+        let node = root
+            .node_at_mut(self.path.segments())
+            .ok_or(PatchError::NodeNotFound(change))?;
+        let args_len = node.args.len();
+        match &self.op {
+            Op::Insert { index, entry } => match entry {
+                Entry::Arg(arg) if *index <= args_len => node.args.insert(*index, arg.clone()),
+                Entry::Child(child)
+                    if (args_len..=args_len + node.children.len()).contains(index) =>
+                {
+                    node.children.insert(index - args_len, child.clone());
+                }
+                _ => return Err(PatchError::IndexOutOfBounds(change)),
+            },
+            Op::Remove { index, entry } => match entry {
+                Entry::Arg(arg) => {
+                    let existing = node
+                        .args
+                        .get(*index)
+                        .ok_or(PatchError::IndexOutOfBounds(change))?;
+                    if !same_arg(existing, arg) {
+                        return Err(PatchError::Conflict(change));
+                    }
+                    node.args.remove(*index);
+                }
+                Entry::Child(child) => {
+                    let index = index
+                        .checked_sub(args_len)
+                        .filter(|index| *index < node.children.len())
+                        .ok_or(PatchError::IndexOutOfBounds(change))?;
+                    if !same_node(&&node.children[index], &child) {
+                        return Err(PatchError::Conflict(change));
+                    }
+                    node.children.remove(index);
+                }
+            },
+            Op::Set {
+                index,
+                name,
+                old,
+                new,
+            } => {
+                let arg = node
+                    .args
+                    .get_mut(*index)
+                    .ok_or(PatchError::IndexOutOfBounds(change))?;
+                if arg.name.as_deref().unwrap_or_default() != name.as_deref().unwrap_or_default()
+                    || !same_value((&arg.value).into(), old.into())
+                {
+                    return Err(PatchError::Conflict(change));
+                }
+                arg.value = new.clone();
+            }
+            Op::SetType { old, new } => {
+                if node.ty != *old {
+                    return Err(PatchError::Conflict(change));
+                }
+                node.ty.clone_from(new);
+            }
+            Op::Rename { old, new } => {
+                if node.name != *old {
+                    return Err(PatchError::Conflict(change));
+                }
+                node.name.clone_from(new);
+            }
+        }
+        Ok(())
+    }
+
+    fn to_node(&self) -> builder::Node<'a> {
+        let mut node = builder::Node::empty();
+        node.push_named_arg("path", self.path.to_string());
+        match &self.op {
+            Op::Insert { index, entry } | Op::Remove { index, entry } => {
+                let ty = if matches!(self.op, Op::Insert { .. }) {
+                    "insert"
+                } else {
+                    "remove"
+                };
+                node.set_ty(ty).push_named_arg("index", *index as u64);
+                if let Some(name) = entry_name(entry) {
+                    node.push_named_arg("name", name.clone());
+                }
+                let mut entry = entry.clone();
+                entry.set_name("value");
+                node.push(entry);
+            }
+            Op::Set {
+                index,
+                name,
+                old,
+                new,
+            } => {
+                node.set_ty("set").push_named_arg("index", *index as u64);
+                if let Some(name) = name {
+                    node.push_named_arg("name", name.clone());
+                }
+                node.push_named_arg("old", old.clone())
+                    .push_named_arg("new", new.clone());
+            }
+            Op::SetType { old, new } | Op::Rename { old, new } => {
+                let ty = if matches!(self.op, Op::SetType { .. }) {
+                    "type"
+                } else {
+                    "rename"
+                };
+                node.set_ty(ty)
+                    .push_named_arg("old", old.clone())
+                    .push_named_arg("new", new.clone());
+            }
+        }
+        node
+    }
+
+    fn from_node(node: crate::Node<'a>, change: usize) -> Result<Self, PatchError> {
+        let invalid = PatchError::InvalidChange(change);
+        let path = node.get_str("path").ok_or(invalid)?;
+        let path = Path::parse(path).map_err(|error| PatchError::Path { change, error })?;
+        let index = || {
+            node.get_u64("index")
+                .and_then(|index| usize::try_from(index).ok())
+                .ok_or(invalid)
+        };
+        let name = node.get_str("name").map(Cow::Borrowed);
+        let value = |key| {
+            node.args()
+                .get(key)
+                .map(|arg| Value::from(arg.value))
+                .ok_or(invalid)
+        };
+        let string = |key| node.get_str(key).map(Cow::Borrowed).ok_or(invalid);
+        let entry = || {
+            let mut entry = match node.get("value").ok_or(invalid)? {
+                crate::Entry::Arg(arg) => Entry::Arg(Arg::from_document(arg)),
+                crate::Entry::Child(child) => Entry::Child(builder::Node::from_document(child)),
+            };
+            entry.set_name(name.clone().unwrap_or_default());
+            Ok(entry)
+        };
+
+        let op = match node.ty().unwrap_or_default() {
+            "insert" => Op::Insert {
+                index: index()?,
+                entry: entry()?,
+            },
+            "remove" => Op::Remove {
+                index: index()?,
+                entry: entry()?,
+            },
+            "set" => Op::Set {
+                index: index()?,
+                name: name.clone(),
+                old: value("old")?,
+                new: value("new")?,
+            },
+            "type" => Op::SetType {
+                old: string("old")?,
+                new: string("new")?,
+            },
+            "rename" => Op::Rename {
+                old: string("old")?,
+                new: string("new")?,
+            },
+            _ => return Err(invalid),
+        };
+        Ok(Self { path, op })
+    }
+
+    #[must_use]
+    pub fn into_static(self) -> Change<'static> {
+        let own = |s: Cow<'a, str>| Cow::Owned(s.into_owned());
+        let op = match self.op {
+            Op::Insert { index, entry } => Op::Insert {
+                index,
+                entry: entry.into_static(),
+            },
+            Op::Remove { index, entry } => Op::Remove {
+                index,
+                entry: entry.into_static(),
+            },
+            Op::Set {
+                index,
+                name,
+                old,
+                new,
+            } => Op::Set {
+                index,
+                name: name.map(own),
+                old: old.into_static(),
+                new: new.into_static(),
+            },
+            Op::SetType { old, new } => Op::SetType {
+                old: own(old),
+                new: own(new),
+            },
+            Op::Rename { old, new } => Op::Rename {
+                old: own(old),
+                new: own(new),
+            },
+        };
+        Change {
+            path: self.path.into_static(),
+            op,
+        }
+    }
+
+    /// Path of the changed entry, for display.
+    fn target(&self) -> Path<'_> {
+        let mut path = self.path.clone();
+        let segment = match &self.op {
+            Op::Insert { index, entry } | Op::Remove { index, entry } => match entry_name(entry) {
+                Some(name) => Segment::Name(Cow::Borrowed(name)),
+                None => Segment::Index(*index),
+            },
+            Op::Set { index, name, .. } => match name {
+                Some(name) => Segment::Name(Cow::Borrowed(name)),
+                None => Segment::Index(*index),
+            },
+            Op::SetType { .. } => Segment::Type,
+            Op::Rename { .. } => return path,
+        };
+        path.push(segment);
+        path
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settings = TextSettings {
+            indent: 0,
+            ..TextSettings::default()
+        };
+        let target = self.target();
+        match &self.op {
+            Op::Insert { entry, .. } => {
+                write!(f, "+ {target}: ")?;
+                text::write_entry(f, entry, &settings)
+            }
+            Op::Remove { entry, .. } => {
+                write!(f, "- {target}: ")?;
+                text::write_entry(f, entry, &settings)
+            }
+            Op::Set { old, new, .. } => {
+                write!(f, "~ {target}: ")?;
+                text::write_entry(f, &Entry::from(old.clone()), &settings)?;
+                f.write_str(" -> ")?;
+                text::write_entry(f, &Entry::from(new.clone()), &settings)
+            }
+            Op::SetType { old, new } => write!(f, "~ {target}: {old:?} -> {new:?}"),
+            Op::Rename { old, new } => write!(f, "~ {target}#name: {old:?} -> {new:?}"),
+        }
+    }
+}
+
+struct Differ<'a> {
+    path: Vec<Segment<'a>>,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a> {
+    fn push(&mut self, op: Op<'a>) {
+        self.changes.push(Change {
+            path: self.path.iter().cloned().collect(),
+            op,
+        });
+    }
+
+    fn diff_node<N: NodeRef<'a>>(&mut self, old: &N, new: &N) {
+        if old.ty() != new.ty() {
+            self.push(Op::SetType {
+                old: Cow::Borrowed(old.ty()),
+                new: Cow::Borrowed(new.ty()),
+            });
+        }
+
+        let old_args = old.args().collect::<Vec<_>>();
+        let new_args = new.args().collect::<Vec<_>>();
+        let old_children = old.children().collect::<Vec<_>>();
+        let new_children = new.children().collect::<Vec<_>>();
+        let arg_matches = match_entries(
+            old_args.iter().map(ArgRef::name),
+            new_args.iter().map(ArgRef::name),
+        );
+        let child_matches = match_entries(
+            old_children.iter().map(NodeRef::name),
+            new_children.iter().map(NodeRef::name),
+        );
+
+        // Remove entries that were not matched, starting from the end so that
+        // the indices of the remaining entries do not change.
+        let mut kept = vec![false; old_children.len()];
+        for &index in child_matches.iter().flatten() {
+            kept[index] = true;
+        }
+        for (index, child) in old_children.iter().enumerate().rev() {
+            if !kept[index] {
+                self.push(Op::Remove {
+                    index: old_args.len() + index,
+                    entry: Entry::Child(to_builder_node(child)),
+                });
+            }
+        }
+        let mut kept = vec![false; old_args.len()];
+        for &index in arg_matches.iter().flatten() {
+            kept[index] = true;
+        }
+        for (index, arg) in old_args.iter().enumerate().rev() {
+            if !kept[index] {
+                self.push(Op::Remove {
+                    index,
+                    entry: Entry::Arg(to_builder_arg(arg)),
+                });
+            }
+        }
+
+        // The remaining entries are in the same order as in the new node, so
+        // the new node can be built front to back.
+        for (index, (arg, matched)) in new_args.iter().zip(&arg_matches).enumerate() {
+            match matched {
+                Some(old_index) => {
+                    let old_value = old_args[*old_index].value();
+                    if !same_value(old_value, arg.value()) {
+                        let name = arg.name();
+                        self.push(Op::Set {
+                            index,
+                            name: (!name.is_empty()).then_some(Cow::Borrowed(name)),
+                            old: old_value.into(),
+                            new: arg.value().into(),
+                        });
+                    }
+                }
+                None => self.push(Op::Insert {
+                    index,
+                    entry: Entry::Arg(to_builder_arg(arg)),
+                }),
+            }
+        }
+
+        // Names resolve to the last argument with the name, then to the last
+        // child with the name.
+        let mut seen = new_args.iter().map(ArgRef::name).collect::<HashSet<_>>();
+        let mut shadowed = vec![false; new_children.len()];
+        for (index, child) in new_children.iter().enumerate().rev() {
+            shadowed[index] = !seen.insert(child.name());
+        }
+        for (index, (child, matched)) in new_children.iter().zip(&child_matches).enumerate() {
+            let name = child.name();
+            let shadowed = shadowed[index];
+            match matched {
+                Some(old_index) => {
+                    // Refer to the child by name when that finds the same
+                    // child when the change is applied.
+                    let segment = if name.is_empty() || shadowed {
+                        Segment::Index(new_args.len() + index)
+                    } else {
+                        Segment::Name(Cow::Borrowed(name))
+                    };
+                    self.path.push(segment);
+                    self.diff_node(&old_children[*old_index], child);
+                    self.path.pop();
+                }
+                None => self.push(Op::Insert {
+                    index: new_args.len() + index,
+                    entry: Entry::Child(to_builder_node(child)),
+                }),
+            }
+        }
+    }
+}
+
+/// Match entries by name, returning the index of the matching old entry for
+/// each new entry.
+///
+/// The n-th entry with a given name is matched with the n-th old entry with the
+/// same name, which also matches unnamed entries by position. Only the largest
+/// set of matches that preserves the order of the entries is kept.
+fn match_entries<'a>(
+    old: impl DoubleEndedIterator<Item = &'a str> + ExactSizeIterator,
+    new: impl Iterator<Item = &'a str>,
+) -> Vec<Option<usize>> {
+    // Indices of old entries by name, in reverse order.
+    let mut by_name = HashMap::<&str, Vec<usize>>::new();
+    for (index, name) in old.enumerate().rev() {
+        by_name.entry(name).or_default().push(index);
+    }
+    let mut matches = new
+        .map(|name| by_name.get_mut(name).and_then(Vec::pop))
+        .collect::<Vec<_>>();
+
+    // Longest increasing subsequence of old indices. `tails[n]` is the
+    // position of the smallest last element of an increasing subsequence of
+    // length `n + 1`.
+    let mut tails = Vec::<usize>::new();
+    let mut prev = vec![None; matches.len()];
+    for (pos, matched) in matches.iter().enumerate() {
+        let Some(old_index) = *matched else {
+            continue;
+        };
+        let len = tails.partition_point(|&tail| matches[tail] < Some(old_index));
+        prev[pos] = len.checked_sub(1).map(|len| tails[len]);
+        if len == tails.len() {
+            tails.push(pos);
+        } else {
+            tails[len] = pos;
+        }
+    }
+
+    let mut keep = vec![false; matches.len()];
+    let mut pos = tails.last().copied();
+    while let Some(p) = pos {
+        keep[p] = true;
+        pos = prev[p];
+    }
+    for (matched, keep) in matches.iter_mut().zip(keep) {
+        if !keep {
+            *matched = None;
+        }
+    }
+    matches
+}
+
+fn entry_name<'b, 'a>(entry: &'b Entry<'a>) -> Option<&'b Cow<'a, str>> {
+    match entry {
+        Entry::Arg(arg) => arg.name.as_ref(),
+        Entry::Child(node) => (!node.name.is_empty()).then_some(&node.name),
+    }
+}
+
+/// Compare values, treating floats as equal when they have the same bits.
+fn same_value(lhs: ValueRef, rhs: ValueRef) -> bool {
+    match (lhs, rhs) {
+        (ValueRef::Float(lhs), ValueRef::Float(rhs)) => lhs.to_bits() == rhs.to_bits(),
+        _ => lhs == rhs,
+    }
+}
+
+fn same_arg(lhs: &Arg, rhs: &Arg) -> bool {
+    lhs.name.as_deref().unwrap_or_default() == rhs.name.as_deref().unwrap_or_default()
+        && same_value((&lhs.value).into(), (&rhs.value).into())
+}
+
+fn same_node<'a, N: NodeRef<'a>>(lhs: &N, rhs: &N) -> bool {
+    lhs.name() == rhs.name()
+        && lhs.ty() == rhs.ty()
+        && lhs.args().len() == rhs.args().len()
+        && lhs.children().len() == rhs.children().len()
+        && lhs
+            .args()
+            .zip(rhs.args())
+            .all(|(lhs, rhs)| lhs.name() == rhs.name() && same_value(lhs.value(), rhs.value()))
+        && lhs
+            .children()
+            .zip(rhs.children())
+            .all(|(lhs, rhs)| same_node(&lhs, &rhs))
+}
+
+fn to_builder_arg<'a>(arg: &impl ArgRef<'a>) -> Arg<'a> {
+    let name = arg.name();
+    Arg {
+        name: (!name.is_empty()).then_some(Cow::Borrowed(name)),
+        value: arg.value().into(),
+    }
+}
+
+fn to_builder_node<'a>(node: &impl NodeRef<'a>) -> builder::Node<'a> {
+    builder::Node {
+        children: node
+            .children()
+            .map(|child| to_builder_node(&child))
+            .collect(),
+        args: node.args().map(|arg| to_builder_arg(&arg)).collect(),
+        name: Cow::Borrowed(node.name()),
+        ty: Cow::Borrowed(node.ty()),
+    }
+}
@@ -4,11 +4,17 @@ use crate::ValidationError;
 use alloc::vec::Vec;
 
 pub mod codec;
+#[cfg(feature = "alloc")]
+mod cursor;
 mod node;
 pub mod raw;
+mod value;
 
+#[cfg(feature = "alloc")]
+pub use cursor::Cursor;
 pub use node::*;
 pub use raw::ValueRef;
+pub use value::ValueKind;
 
 /// Immutable document that owns its memory.
 #[cfg(feature = "alloc")]
@@ -218,10 +224,42 @@ impl Document {
         self.raw.args()
     }
 
+    /// Get the node with the given ID.
+    ///
+    /// Returns `None` if the ID is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn node(&self, id: NodeId) -> Option<Node<'_>> {
+        if id.0 < self.header().nodes_len {
+            unsafe {
+                // SAFETY: Checked bounds, and the document is valid.
+                Some(Node::from_raw(self.raw.get_node_unchecked(id.0)))
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Buckets of the name index of the document, which is empty if the
+    /// document does not have a name index.
+    #[inline]
+    #[must_use]
+    pub fn name_index(&self) -> &[codec::IndexEntry] {
+        self.raw.name_index()
+    }
+
+    /// Whether the document has a name index, which makes looking up arguments
+    /// and children by name O(1).
+    #[inline]
+    #[must_use]
+    pub fn has_name_index(&self) -> bool {
+        self.raw.has_name_index()
+    }
+
     /// Get the root node.
     #[inline]
     #[must_use]
-    pub fn root(&self) -> Node {
+    pub fn root(&self) -> Node<'_> {
         unsafe {
             // SAFETY: Invariants of Self.
             Node::from_raw(self.raw.root_unchecked())
@@ -15,6 +15,12 @@ use crate::ValidationErrorKind;
 
 pub const MAGIC: [u8; 8] = *b"zdoc\0\0\0\0";
 pub const VERSION: u32 = 1;
+/// Version of documents with a name index. Readers of version 1 require the
+/// index fields of the header to be zero, so documents without a name index
+/// keep version 1.
+pub const INDEXED_VERSION: u32 = 2;
+/// Maximum number of buckets that a lookup in the name index probes.
+pub const MAX_INDEX_PROBE: usize = 32;
 
 #[cfg(not(target_endian = "little"))]
 compile_error!("Unsupported target endian");
@@ -25,7 +31,8 @@ compile_error!("Unsupported target endian");
 pub struct Header {
     /// Magic bytes. Must be "zdoc\0\0\0\0" (8 bytes).
     pub magic: [u8; 8],
-    /// Document format version, must be 1.
+    /// Document format version. Must be [`VERSION`], or [`INDEXED_VERSION`]
+    /// when the document has a name index.
     pub version: u32,
     /// Index of the root node. Must be zero or less than `nodes_len`.
     pub root_node_index: u32,
@@ -52,10 +59,15 @@ pub struct Header {
     pub binary_offset: u32,
     /// Length in bytes of the binary data block.
     pub binary_len: u32,
+    /// Start of the optional name index (byte offset from the beginning of
+    /// file/buffer). Must be 4-byte aligned, and zero unless `version` is
+    /// [`INDEXED_VERSION`]. See [`IndexEntry`].
+    pub index_offset: u32,
+    /// Number of buckets in the name index. Must be zero (no index) or a power
+    /// of two.
+    pub index_len: u32,
     /// Reserved.
-    pub reserved1: u32,
-    pub reserved2: u32,
-    pub reserved3: u32,
+    pub reserved: u32,
 }
 
 // Static checks to ensure that the `#[repr(C)]` representation of the struct
@@ -83,9 +95,9 @@ const _: () = {
     assert!(offset_of!(Header, strings_len) == 40, "unexpected offset");
     assert!(offset_of!(Header, binary_offset) == 44, "unexpected offset");
     assert!(offset_of!(Header, binary_len) == 48, "unexpected offset");
-    assert!(offset_of!(Header, reserved1) == 52, "unexpected offset");
-    assert!(offset_of!(Header, reserved2) == 56, "unexpected offset");
-    assert!(offset_of!(Header, reserved3) == 60, "unexpected offset");
+    assert!(offset_of!(Header, index_offset) == 52, "unexpected offset");
+    assert!(offset_of!(Header, index_len) == 56, "unexpected offset");
+    assert!(offset_of!(Header, reserved) == 60, "unexpected offset");
 };
 
 pub static DEFAULT_HEADER: Header = Header {
@@ -101,9 +113,9 @@ pub static DEFAULT_HEADER: Header = Header {
     strings_len: 0,
     binary_offset: 0,
     binary_len: 0,
-    reserved1: 0,
-    reserved2: 0,
-    reserved3: 0,
+    index_offset: 0,
+    index_len: 0,
+    reserved: 0,
 };
 
 impl Default for Header {
@@ -289,6 +301,76 @@ impl From<core::ops::Range<u32>> for BinaryRange {
     }
 }
 
+/// Bucket in the name index of a document.
+///
+/// The name index is an optional hash table that maps a node and a name to the
+/// last argument and the last child of the node with that name, so that lookups
+/// by name do not need to scan all entries of the node.
+///
+/// The table uses open addressing with linear probing: the search for a name
+/// starts at bucket `index_hash(node, name) & (index_len - 1)`, and continues
+/// with the next bucket (wrapping around) until a matching bucket or an empty
+/// bucket is found, or [`MAX_INDEX_PROBE`] buckets have been searched. At most
+/// half of the buckets may be occupied, so that validating the index of an
+/// untrusted document takes linear time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C, align(4))]
+pub struct IndexEntry {
+    /// Index of the node in the `nodes` section, or `u32::MAX` if the bucket
+    /// is empty.
+    pub node: u32,
+    /// Index of the entry within the node, counting arguments before children.
+    pub entry: u32,
+}
+
+// Static checks to ensure that the `#[repr(C)]` representation of the struct
+// for this platform matches expectations.
+const _: () = {
+    assert!(
+        size_of::<IndexEntry>() == 8,
+        "Incompatible C ABI for this platform"
+    );
+    assert!(offset_of!(IndexEntry, node) == 0, "unexpected offset");
+    assert!(offset_of!(IndexEntry, entry) == 4, "unexpected offset");
+};
+
+impl IndexEntry {
+    pub const EMPTY: IndexEntry = IndexEntry {
+        node: u32::MAX,
+        entry: u32::MAX,
+    };
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.node == u32::MAX
+    }
+}
+
+impl Default for IndexEntry {
+    #[inline]
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// Hash function of the name index.
+///
+/// This is 32-bit FNV-1a over the little-endian bytes of the node index,
+/// followed by the UTF-8 bytes of the name.
+#[inline]
+#[must_use]
+pub fn index_hash(node: u32, name: &[u8]) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c_9dc5;
+    const PRIME: u32 = 0x0100_0193;
+    node.to_le_bytes()
+        .iter()
+        .chain(name)
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 #[repr(u8)]
 pub enum RawValue {
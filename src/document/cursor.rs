@@ -0,0 +1,248 @@
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::path::{Path, Segment};
+
+use super::{Document, Node, NodeId};
+
+/// Cursor for navigating a [`Document`], including upwards to the parents of
+/// the current node.
+///
+/// Nodes in a document only know their children, so the cursor keeps track of
+/// the ancestors of the current node as it moves through the document.
+#[derive(Clone)]
+pub struct Cursor<'a> {
+    node: Node<'a>,
+    /// Ancestors of `node`, starting with the root node.
+    ancestors: Vec<Node<'a>>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Create a cursor pointing at `root`, which is treated as the root of the
+    /// document.
+    #[inline]
+    #[must_use]
+    pub fn new(root: Node<'a>) -> Self {
+        Self {
+            node: root,
+            ancestors: Vec::new(),
+        }
+    }
+
+    /// The current node.
+    #[inline]
+    #[must_use]
+    pub fn node(&self) -> Node<'a> {
+        self.node
+    }
+
+    /// The ID of the current node.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        self.node.id()
+    }
+
+    /// The number of ancestors of the current node. The root node has depth 0.
+    #[inline]
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.ancestors.len()
+    }
+
+    /// The parent of the current node, or `None` at the root node.
+    #[inline]
+    #[must_use]
+    pub fn parent(&self) -> Option<Node<'a>> {
+        self.ancestors.last().copied()
+    }
+
+    /// Iterate over the ancestors of the current node, starting with the
+    /// parent and ending with the root node.
+    #[inline]
+    #[must_use]
+    pub fn ancestors(&self) -> impl DoubleEndedIterator<Item = Node<'a>> + ExactSizeIterator {
+        self.ancestors.iter().rev().copied()
+    }
+
+    /// The index of the current node among the children of its parent, or
+    /// `None` at the root node.
+    #[inline]
+    #[must_use]
+    pub fn index(&self) -> Option<usize> {
+        let parent = self.parent()?;
+        Some(self.node.raw_index() - parent.encoded().children.start as usize)
+    }
+
+    /// The next sibling of the current node, if any.
+    #[inline]
+    #[must_use]
+    pub fn next_sibling(&self) -> Option<Node<'a>> {
+        let index = self.index()?;
+        self.parent()?.children().get(index + 1)
+    }
+
+    /// The previous sibling of the current node, if any.
+    #[inline]
+    #[must_use]
+    pub fn prev_sibling(&self) -> Option<Node<'a>> {
+        let index = self.index()?.checked_sub(1)?;
+        self.parent()?.children().get(index)
+    }
+
+    /// Move to the parent of the current node.
+    ///
+    /// Returns `false` if the current node is the root node.
+    #[inline]
+    pub fn goto_parent(&mut self) -> bool {
+        match self.ancestors.pop() {
+            Some(parent) => {
+                self.node = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the child at `index` of the current node.
+    ///
+    /// Returns `false` if the child does not exist.
+    #[inline]
+    pub fn goto_child(&mut self, index: usize) -> bool {
+        match self.node.children().get(index) {
+            Some(child) => {
+                self.ancestors.push(self.node);
+                self.node = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the first child of the current node.
+    ///
+    /// Returns `false` if the current node has no children.
+    #[inline]
+    pub fn goto_first_child(&mut self) -> bool {
+        self.goto_child(0)
+    }
+
+    /// Move to the next sibling of the current node.
+    ///
+    /// Returns `false` if the current node is the last child of its parent, or
+    /// the root node.
+    #[inline]
+    pub fn goto_next_sibling(&mut self) -> bool {
+        match self.next_sibling() {
+            Some(sibling) => {
+                self.node = sibling;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the previous sibling of the current node.
+    ///
+    /// Returns `false` if the current node is the first child of its parent, or
+    /// the root node.
+    #[inline]
+    pub fn goto_prev_sibling(&mut self) -> bool {
+        match self.prev_sibling() {
+            Some(sibling) => {
+                self.node = sibling;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move back to the root node.
+    #[inline]
+    pub fn reset(&mut self) {
+        if let Some(root) = self.ancestors.first() {
+            self.node = *root;
+            self.ancestors.clear();
+        }
+    }
+
+    /// The path from the root node to the current node.
+    ///
+    /// Children are referred to by name when the name selects them (i.e., the
+    /// child is the last child of its parent with that name, and no argument
+    /// has that name), and by index otherwise. See [`path`](crate::path) for the path syntax.
+    #[must_use]
+    pub fn path(&self) -> Path<'a> {
+        self.ancestors
+            .iter()
+            .zip(self.ancestors.iter().skip(1).chain([&self.node]))
+            .map(|(parent, child)| segment_of(*parent, *child))
+            .collect()
+    }
+}
+
+impl core::fmt::Debug for Cursor<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Cursor")
+            .field("id", &self.id())
+            .field("path", &format_args!("{}", self.path()))
+            .finish()
+    }
+}
+
+fn segment_of<'a>(parent: Node<'a>, child: Node<'a>) -> Segment<'a> {
+    let index = child.raw_index() - parent.encoded().children.start as usize;
+    if let Some(name) = child.name() {
+        let shadowed = parent.args().into_iter().any(|arg| arg.name == Some(name))
+            || parent
+                .children()
+                .into_iter()
+                .skip(index + 1)
+                .any(|sibling| sibling.name() == Some(name));
+        if !shadowed {
+            return Segment::Name(Cow::Borrowed(name));
+        }
+    }
+    Segment::Index(parent.args().len() + index)
+}
+
+impl Document {
+    /// Create a [`Cursor`] pointing at the root node.
+    #[inline]
+    #[must_use]
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor::new(self.root())
+    }
+
+    /// Create a [`Cursor`] pointing at the node with the given ID.
+    ///
+    /// This finds the ancestors of the node by scanning the nodes that precede
+    /// it, which takes time proportional to the index of the node. If a node
+    /// appears as the child of multiple nodes, the closest preceding parent is
+    /// used.
+    ///
+    /// Returns `None` if the ID is out of bounds, or if the node is not
+    /// reachable from the root node.
+    #[must_use]
+    pub fn cursor_at(&self, id: NodeId) -> Option<Cursor<'_>> {
+        let node = self.node(id)?;
+        let root = self.root().id();
+        let nodes = self.nodes();
+
+        let mut ancestors = Vec::new();
+        let mut current = id.0;
+        while current != root.0 {
+            // Children always come after their parent, so the parent must be
+            // before the current node.
+            let parent = (0..current).rev().find(|&index| {
+                let children = nodes[index as usize].children;
+                children.start <= current && current - children.start < children.len
+            })?;
+            ancestors.push(self.node(NodeId(parent))?);
+            current = parent;
+        }
+        ancestors.reverse();
+
+        Some(Cursor { node, ancestors })
+    }
+}
@@ -1,9 +1,40 @@
 use core::iter::FusedIterator;
 
-use crate::{access, internal};
+use crate::{Document, ValueError, access, internal};
 
 use super::{ValueRef, codec, raw};
 
+/// Stable identifier of a node within a [`Document`](crate::Document).
+///
+/// This is the position of the node in the document's block of nodes (see
+/// [`Node::raw_index()`]), so it can be used as a key in side tables that
+/// annotate the nodes of a document. Use
+/// [`Document::node()`](crate::Document::node) to get the node back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub u32);
+
+impl NodeId {
+    #[inline]
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<NodeId> for usize {
+    #[inline]
+    fn from(id: NodeId) -> Self {
+        id.index()
+    }
+}
+
+impl core::fmt::Display for NodeId {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
 /// Node in a [`Document`](crate::Document).
 #[derive(Clone, Copy)]
 pub struct Node<'a> {
@@ -42,6 +73,23 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// The ID of the node, which is stable for the lifetime of the document.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        NodeId(self.raw_index() as u32)
+    }
+
+    /// The document containing the node.
+    #[inline]
+    #[must_use]
+    pub fn document(&self) -> &'a Document {
+        unsafe {
+            // SAFETY: This node comes from a valid document.
+            Document::from_raw_unchecked(self.raw.document())
+        }
+    }
+
     /// If the node has a name, return the name of the node.
     ///
     /// Nodes with unnamed children may be considered "array-like", and named
@@ -149,6 +197,108 @@ impl<'a> Node<'a> {
     pub fn value(&self) -> Option<ValueRef<'a>> {
         self.args().into_iter().next().map(|arg| arg.value)
     }
+
+    /// Iterate over all children with the given name.
+    #[inline]
+    #[must_use]
+    pub fn children_named<'b>(
+        &self,
+        name: &'b str,
+    ) -> impl DoubleEndedIterator<Item = Node<'a>> + Clone + use<'a, 'b> {
+        self.children()
+            .into_iter()
+            .filter(move |child| child.name() == Some(name))
+    }
+
+    /// Iterate over all arguments with the given name.
+    #[inline]
+    #[must_use]
+    pub fn args_named<'b>(
+        &self,
+        name: &'b str,
+    ) -> impl DoubleEndedIterator<Item = Arg<'a>> + Clone + use<'a, 'b> {
+        self.args()
+            .into_iter()
+            .filter(move |arg| arg.name == Some(name))
+    }
+
+    /// Get the value of the entry named `key`, converted to `T`.
+    ///
+    /// The value is looked up as in [`get()`](Self::get) and
+    /// [`Entry::value()`]. Returns `Ok(None)` if there is no such entry, or if
+    /// the entry is a node without arguments.
+    ///
+    /// # Errors
+    ///
+    /// If the value cannot be converted to `T` without loss, this returns an
+    /// error.
+    #[inline]
+    pub fn get_as<T>(&self, key: &str) -> Result<Option<T>, ValueError>
+    where
+        T: TryFrom<ValueRef<'a>, Error = ValueError>,
+    {
+        self.get(key).map_or(Ok(None), |entry| entry.value_as())
+    }
+
+    /// Get the value of the entry named `key` as a bool.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or is not a bool.
+    #[inline]
+    #[must_use]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_as(key).ok().flatten()
+    }
+
+    /// Get the value of the entry named `key` as an `i64`.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or cannot be converted without loss.
+    #[inline]
+    #[must_use]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get_as(key).ok().flatten()
+    }
+
+    /// Get the value of the entry named `key` as a `u64`.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or cannot be converted without loss.
+    #[inline]
+    #[must_use]
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get_as(key).ok().flatten()
+    }
+
+    /// Get the value of the entry named `key` as an `f64`.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or cannot be converted without loss.
+    #[inline]
+    #[must_use]
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get_as(key).ok().flatten()
+    }
+
+    /// Get the value of the entry named `key` as a string.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or is not a string.
+    #[inline]
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&'a str> {
+        self.get_as(key).ok().flatten()
+    }
+
+    /// Get the value of the entry named `key` as bytes.
+    ///
+    /// See [`get_as()`](Self::get_as). Returns `None` if the entry does not
+    /// exist or is not binary data or a string.
+    #[inline]
+    #[must_use]
+    pub fn get_bytes(&self, key: &str) -> Option<&'a [u8]> {
+        self.get_as(key).ok().flatten()
+    }
 }
 
 /// Argument or child of a [`Node`].
@@ -185,6 +335,38 @@ impl<'a> Entry<'a> {
             Entry::Child(node) => node.value(),
         }
     }
+
+    /// Get the [`value()`](Self::value) of this entry, converted to `T`.
+    ///
+    /// # Errors
+    ///
+    /// If the value cannot be converted to `T` without loss, this returns an
+    /// error.
+    #[inline]
+    pub fn value_as<T>(&self) -> Result<Option<T>, ValueError>
+    where
+        T: TryFrom<ValueRef<'a>, Error = ValueError>,
+    {
+        self.value().map(T::try_from).transpose()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_arg(&self) -> Option<Arg<'a>> {
+        match self {
+            Entry::Arg(arg) => Some(*arg),
+            Entry::Child(_) => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_node(&self) -> Option<Node<'a>> {
+        match self {
+            Entry::Arg(_) => None,
+            Entry::Child(node) => Some(*node),
+        }
+    }
 }
 
 impl<'a> From<ValueRef<'a>> for Entry<'a> {
@@ -318,10 +500,13 @@ impl<'a> Children<'a> {
     /// Get a child by name.
     ///
     /// If multiple children have the same name, this returns the *last* child
-    /// with that name.
+    /// with that name. This uses the name index of the document if it has one.
     #[inline]
     fn get_by_name(&self, name: &str) -> Option<Node<'a>> {
-        self.into_iter().rfind(|child| child.name() == Some(name))
+        match self.raw.find_by_name(name) {
+            Some(found) => found.and_then(|index| self.get_by_index(index)),
+            None => self.into_iter().rfind(|child| child.name() == Some(name)),
+        }
     }
 }
 
@@ -444,10 +629,18 @@ impl<'a> Args<'a> {
         }
     }
 
+    /// Get an argument by name.
+    ///
+    /// If multiple arguments have the same name, this returns the *last*
+    /// argument with that name. This uses the name index of the document if it
+    /// has one.
     #[inline]
     #[must_use]
     pub fn get_by_name(&self, name: &str) -> Option<Arg<'a>> {
-        self.into_iter().rfind(|arg| arg.name == Some(name))
+        match self.raw.find_by_name(name) {
+            Some(found) => found.and_then(|index| self.get_by_index(index)),
+            None => self.into_iter().rfind(|arg| arg.name == Some(name)),
+        }
     }
 }
 
@@ -533,6 +726,20 @@ impl<'a> Arg<'a> {
             Self { name, value }
         }
     }
+
+    /// Get the value of this argument, converted to `T`.
+    ///
+    /// # Errors
+    ///
+    /// If the value cannot be converted to `T` without loss, this returns an
+    /// error.
+    #[inline]
+    pub fn value_as<T>(&self) -> Result<T, ValueError>
+    where
+        T: TryFrom<ValueRef<'a>, Error = ValueError>,
+    {
+        T::try_from(self.value)
+    }
 }
 
 impl<'a> From<ValueRef<'a>> for Arg<'a> {
@@ -607,6 +814,11 @@ impl<'a> access::NodeRef<'a> for Node<'a> {
     fn args(&self) -> Self::ArgsIter<'a> {
         Node::args(self).into_iter()
     }
+
+    #[inline]
+    fn concrete(&self) -> access::ConcreteNode<'a> {
+        access::ConcreteNode::Document(*self)
+    }
 }
 
 impl<'a> access::ArgRef<'a> for Arg<'a> {
@@ -167,7 +167,8 @@ impl RawDocument {
     /// This function is safe to call if:
     ///
     /// - The slice is 4-byte aligned.
-    /// - The slice is either empty or at least 64 bytes long.
+    /// - The slice is either empty or at least 64 bytes long, or
+    ///   [`check()`](Self::check) is called before any other method.
     #[inline]
     #[must_use]
     pub unsafe fn from_slice_unchecked(aligned_bytes: &[u8]) -> &Self {
@@ -180,7 +181,6 @@ impl RawDocument {
             unaligned_prefix.is_empty(),
             "document must be 4-byte aligned"
         );
-        debug_assert!(aligned_bytes.len() >= size_of::<Header>());
         unsafe { &*(core::ptr::from_ref(aligned_bytes) as *const Self) }
     }
 
@@ -301,6 +301,29 @@ impl RawDocument {
         }
     }
 
+    /// Get the buckets of the name index of the document.
+    ///
+    /// If the document does not have a name index, this is empty.
+    ///
+    /// # Panics
+    ///
+    /// - This function panics if the header's `index_offset` or `index_len` is
+    ///   out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn name_index(&self) -> &[codec::IndexEntry] {
+        let header = self.header();
+        let start = header.index_offset as usize;
+        let end = start + size_of::<codec::IndexEntry>() * header.index_len as usize;
+        cast_slice(&self.bytes[start..end])
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_name_index(&self) -> bool {
+        self.header().index_len != 0
+    }
+
     /// Get the root node.
     ///
     /// # Safety
@@ -308,7 +331,7 @@ impl RawDocument {
     /// This function is safe to call when `check_header()` has returned `Ok(())`.
     #[inline]
     #[must_use]
-    pub unsafe fn root_unchecked(&self) -> RawNodeRef {
+    pub unsafe fn root_unchecked(&self) -> RawNodeRef<'_> {
         let header = self.header();
         let root_offset = header.root_node_index;
 
@@ -335,7 +358,7 @@ impl RawDocument {
     /// [`check_header()`] and [`check_nodes()`] must have returned `Ok(())`.
     #[inline]
     #[must_use]
-    pub unsafe fn get_node_unchecked(&self, index: u32) -> RawNodeRef {
+    pub unsafe fn get_node_unchecked(&self, index: u32) -> RawNodeRef<'_> {
         unsafe {
             // SAFETY: Invariants of this function.
             let header = self.header();
@@ -373,7 +396,7 @@ impl RawDocument {
     /// [`check_header()`] and [`check_args()`] must have returned `Ok(())`.
     #[inline]
     #[must_use]
-    pub unsafe fn get_arg_unchecked(&self, index: u32) -> RawArgRef {
+    pub unsafe fn get_arg_unchecked(&self, index: u32) -> RawArgRef<'_> {
         unsafe {
             // SAFETY: Invariants of this function.
             let values = self.args_unchecked();
@@ -439,6 +462,7 @@ impl RawDocument {
         self.check_nodes()?;
         self.check_args()?;
         self.check_strings()?;
+        self.check_index()?;
         Ok(())
     }
 
@@ -454,11 +478,14 @@ impl RawDocument {
     pub fn check_header(&self) -> Result<(), ValidationError> {
         #[inline]
         const fn is_overlapping(a: core::ops::Range<usize>, b: core::ops::Range<usize>) -> bool {
-            (a.start < b.end) & (b.start < a.end)
+            (a.start < b.end) && (b.start < a.end)
         }
 
-        // Check the safety invariants.
-        debug_assert!(self.bytes.len() >= size_of::<Header>());
+        // Check the safety invariants. The length is checked unconditionally,
+        // because `from_slice()` does not check it.
+        if self.bytes.len() < size_of::<Header>() {
+            return Err(ValidationErrorKind::HeaderSize.at_offset(0usize));
+        }
         #[cfg(debug_assertions)]
         {
             let (unaligned, _, _) = pod_align_to::<_, Header>(&self.bytes);
@@ -478,19 +505,28 @@ impl RawDocument {
             strings_len,
             binary_offset,
             binary_len,
-            reserved1,
-            reserved2,
-            reserved3,
+            index_offset,
+            index_len,
+            reserved,
         } = *self.header();
 
         if magic != codec::MAGIC {
             return Err(ValidationErrorKind::HeaderMagic.at_offset(0usize));
         }
-        if version != codec::VERSION {
+        if version != codec::VERSION && version != codec::INDEXED_VERSION {
             return Err(
                 ValidationErrorKind::HeaderVersion(version).at_offset(offset_of!(Header, version))
             );
         }
+        // The index fields are reserved in documents without a name index.
+        if version == codec::VERSION && (index_offset != 0 || index_len != 0) {
+            let field = if index_offset == 0 {
+                offset_of!(Header, index_len)
+            } else {
+                offset_of!(Header, index_offset)
+            };
+            return Err(ValidationErrorKind::HeaderReservedFieldsMustBeZero.at_offset(field));
+        }
         if size as usize != self.bytes.len() {
             return Err(ValidationErrorKind::HeaderSize.at_offset(offset_of!(Header, size)));
         }
@@ -578,6 +614,27 @@ impl RawDocument {
             );
         };
 
+        if index_offset % 4 != 0
+            || index_offset as usize > self.bytes.len()
+            || (index_offset != 0 && index_offset < size_of::<Header>() as u32)
+            || (index_offset == 0 && index_len != 0)
+        {
+            return Err(
+                ValidationErrorKind::HeaderIndexOffset.at_offset(offset_of!(Header, index_offset))
+            );
+        }
+        let Some(index_end) = (index_len as usize)
+            .checked_mul(size_of::<codec::IndexEntry>())
+            .and_then(|len| len.checked_add(index_offset as usize))
+            .filter(|end| {
+                *end <= self.bytes.len() && (index_len == 0 || index_len.is_power_of_two())
+            })
+        else {
+            return Err(
+                ValidationErrorKind::HeaderIndexLen.at_offset(offset_of!(Header, index_len))
+            );
+        };
+
         if root_node_index != 0 && root_node_index >= nodes_len {
             return Err(ValidationErrorKind::HeaderRootNodeOutOfBounds
                 .at_offset(offset_of!(Header, root_node_index)));
@@ -631,17 +688,33 @@ impl RawDocument {
                 .at_offset(offset_of!(Header, binary_offset)));
         }
 
-        if reserved1 != 0 {
-            return Err(ValidationErrorKind::HeaderReservedFieldsMustBeZero
-                .at_offset(offset_of!(Header, reserved1)));
-        }
-        if reserved2 != 0 {
-            return Err(ValidationErrorKind::HeaderReservedFieldsMustBeZero
-                .at_offset(offset_of!(Header, reserved2)));
+        let index = index_offset as usize..index_end;
+        for (other, field) in [
+            (
+                nodes_offset as usize..nodes_end,
+                offset_of!(Header, nodes_offset),
+            ),
+            (
+                args_offset as usize..args_end,
+                offset_of!(Header, args_offset),
+            ),
+            (
+                strings_offset as usize..strings_end,
+                offset_of!(Header, strings_offset),
+            ),
+            (
+                binary_offset as usize..binary_end,
+                offset_of!(Header, binary_offset),
+            ),
+        ] {
+            if is_overlapping(index.clone(), other) {
+                return Err(ValidationErrorKind::HeaderSectionsOverlap.at_offset(field));
+            }
         }
-        if reserved3 != 0 {
+
+        if reserved != 0 {
             return Err(ValidationErrorKind::HeaderReservedFieldsMustBeZero
-                .at_offset(offset_of!(Header, reserved3)));
+                .at_offset(offset_of!(Header, reserved)));
         }
 
         Ok(())
@@ -846,6 +919,155 @@ impl RawDocument {
         Err(ValidationErrorKind::BinaryOutOfBounds.at_offset(offset))
     }
 
+    /// Check the name index of the document.
+    ///
+    /// This checks that all buckets of the index refer to named entries of
+    /// nodes, and that looking up the name of any argument or child of a node
+    /// finds the last argument or child of the node with that name.
+    ///
+    /// # Errors
+    ///
+    /// If the name index is not valid, this returns an error.
+    pub fn check_index(&self) -> Result<(), ValidationError> {
+        let header = self.header();
+        let index = self.name_index();
+        if index.is_empty() {
+            return Ok(());
+        }
+
+        let occupied = index.iter().filter(|bucket| !bucket.is_empty()).count();
+        if occupied * 2 > index.len() {
+            return Err(ValidationErrorKind::IndexFull.at_offset(offset_of!(Header, index_len)));
+        }
+
+        let nodes = self.nodes();
+        for (bucket_index, bucket) in index.iter().enumerate() {
+            if bucket.is_empty() {
+                continue;
+            }
+            let named = nodes.get(bucket.node as usize).and_then(|node| {
+                self.entry_name(node, bucket.entry)
+                    .filter(|(_, name)| !name.is_empty())
+            });
+            if named.is_none() {
+                let offset =
+                    header.index_offset as usize + bucket_index * size_of::<codec::IndexEntry>();
+                return Err(ValidationErrorKind::IndexEntryOutOfBounds.at_offset(offset));
+            }
+        }
+
+        for (node_index, node) in nodes.iter().enumerate() {
+            let entries = node.args.len as usize + node.children.len as usize;
+            for entry in 0..entries as u32 {
+                let Some((kind, name)) = self.entry_name(node, entry) else {
+                    // Out of bounds entries are reported by `check_nodes()`.
+                    continue;
+                };
+                if name.is_empty() {
+                    continue;
+                }
+                // Note: The index must find an entry after this one if there
+                // are multiple entries with the same name, which also means
+                // that it finds the last entry for every name.
+                let found = self.lookup_in_index(index, node_index as u32, node, kind, name);
+                if found.is_none_or(|found| found < entry) {
+                    let offset =
+                        header.nodes_offset as usize + node_index * size_of::<codec::Node>();
+                    return Err(ValidationErrorKind::IndexMismatch.at_offset(offset));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the last argument or child of a node with the given name using the
+    /// name index.
+    ///
+    /// Returns `None` if the document does not have a name index, and
+    /// `Some(None)` if the node does not have an argument or child with that
+    /// name. Otherwise, the position of the entry within the arguments or
+    /// children of the node is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the header is not valid.
+    #[must_use]
+    pub fn find_by_name(
+        &self,
+        node: &codec::Node,
+        kind: EntryKind,
+        name: &str,
+    ) -> Option<Option<usize>> {
+        let index = self.name_index();
+        if index.is_empty() {
+            return None;
+        }
+        let Some(node_index) = self.node_index_of(node) else {
+            // The node is not in this document, so it must be the empty root.
+            return Some(None);
+        };
+        let entry = self.lookup_in_index(index, node_index, node, kind, name.as_bytes());
+        Some(entry.map(|entry| match kind {
+            EntryKind::Arg => entry as usize,
+            EntryKind::Child => (entry - node.args.len) as usize,
+        }))
+    }
+
+    fn lookup_in_index(
+        &self,
+        index: &[codec::IndexEntry],
+        node_index: u32,
+        node: &codec::Node,
+        kind: EntryKind,
+        name: &[u8],
+    ) -> Option<u32> {
+        let mask = index.len() - 1;
+        let mut bucket_index = codec::index_hash(node_index, name) as usize & mask;
+        for _ in 0..index.len().min(codec::MAX_INDEX_PROBE) {
+            let bucket = index[bucket_index];
+            if bucket.is_empty() {
+                return None;
+            }
+            if bucket.node == node_index
+                && self.entry_name(node, bucket.entry) == Some((kind, name))
+            {
+                return Some(bucket.entry);
+            }
+            bucket_index = (bucket_index + 1) & mask;
+        }
+        None
+    }
+
+    /// Get the kind and the bytes of the name of an entry in a node, where
+    /// arguments come before children.
+    fn entry_name(&self, node: &codec::Node, entry: u32) -> Option<(EntryKind, &[u8])> {
+        let (kind, range) = if entry < node.args.len {
+            let arg = self.args().get(node.args.start as usize + entry as usize)?;
+            (EntryKind::Arg, arg.name)
+        } else if entry - node.args.len < node.children.len {
+            let index = node.children.start as usize + (entry - node.args.len) as usize;
+            (EntryKind::Child, self.nodes().get(index)?.name)
+        } else {
+            return None;
+        };
+        let header = self.header();
+        let start = header.strings_offset as usize + range.start as usize;
+        let end = start + range.len as usize;
+        let strings_end = header.strings_offset as usize + header.strings_len as usize;
+        if end > strings_end {
+            return None;
+        }
+        Some((kind, self.bytes.get(start..end)?))
+    }
+
+    fn node_index_of(&self, node: &codec::Node) -> Option<u32> {
+        let nodes = self.nodes();
+        let offset = (core::ptr::from_ref(node) as usize).checked_sub(nodes.as_ptr() as usize)?;
+        let index = offset / size_of::<codec::Node>();
+        (index < nodes.len()).then_some(index as u32)
+    }
+
     #[inline]
     #[must_use]
     pub fn as_bytes(&self) -> &[u8] {
@@ -853,6 +1075,13 @@ impl RawDocument {
     }
 }
 
+/// Whether an entry of a node is an argument or a child.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    Arg,
+    Child,
+}
+
 #[derive(Clone, Copy)]
 pub struct RawNodeRef<'a> {
     doc: &'a RawDocument,
@@ -868,6 +1097,11 @@ impl<'a> RawNodeRef<'a> {
     /// This `RawNodeRef` must come from a valid document.
     #[must_use]
     pub unsafe fn raw_index(&self) -> usize {
+        if self.header.nodes_len == 0 {
+            // The root node of the empty document is not part of the document.
+            return 0;
+        }
+
         let node_ptr = core::ptr::from_ref(self.node);
         let start_ptr = unsafe {
             // SAFETY: Invariants of this function.
@@ -886,6 +1120,13 @@ impl<'a> RawNodeRef<'a> {
         self.node
     }
 
+    /// The document containing this node.
+    #[inline]
+    #[must_use]
+    pub fn document(&self) -> &'a RawDocument {
+        self.doc
+    }
+
     /// Get the name of the node.
     ///
     /// If the node is unnamed, this returns the empty string.
@@ -983,6 +1224,16 @@ impl<'a> RawNodeChildren<'a> {
                 .get_node_unchecked_with_header(self.header, child_offset)
         }
     }
+
+    /// Find the last child with the given name using the name index of the
+    /// document.
+    ///
+    /// Returns `None` if the document does not have a name index.
+    #[inline]
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<Option<usize>> {
+        self.doc.find_by_name(self.node, EntryKind::Child, name)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -1020,6 +1271,16 @@ impl<'a> RawNodeArgs<'a> {
             self.doc.get_arg_unchecked(arg_offset)
         }
     }
+
+    /// Find the last argument with the given name using the name index of the
+    /// document.
+    ///
+    /// Returns `None` if the document does not have a name index.
+    #[inline]
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<Option<usize>> {
+        self.doc.find_by_name(self.node, EntryKind::Arg, name)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,335 @@
+use crate::ValueError;
+
+use super::ValueRef;
+
+/// The kind of a [`ValueRef`], without its contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    Null,
+    Bool,
+    Int,
+    Uint,
+    Float,
+    String,
+    Binary,
+}
+
+impl core::fmt::Display for ValueKind {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            ValueKind::Null => "null",
+            ValueKind::Bool => "bool",
+            ValueKind::Int => "int",
+            ValueKind::Uint => "uint",
+            ValueKind::Float => "float",
+            ValueKind::String => "string",
+            ValueKind::Binary => "binary",
+        })
+    }
+}
+
+/// 2^63, the smallest `f64` that is out of range for `i64`.
+const I64_END: f64 = 9_223_372_036_854_775_808.0;
+/// 2^64, the smallest `f64` that is out of range for `u64`.
+const U64_END: f64 = 18_446_744_073_709_551_616.0;
+
+impl<'a> ValueRef<'a> {
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            ValueRef::Null => ValueKind::Null,
+            ValueRef::Bool(_) => ValueKind::Bool,
+            ValueRef::Int(_) => ValueKind::Int,
+            ValueRef::Uint(_) => ValueKind::Uint,
+            ValueRef::Float(_) => ValueKind::Float,
+            ValueRef::String(_) => ValueKind::String,
+            ValueRef::Binary(_) => ValueKind::Binary,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, ValueRef::Null)
+    }
+
+    /// Get the value if it is a bool.
+    #[inline]
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            ValueRef::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value if it is a signed integer.
+    ///
+    /// This does not convert unsigned integers. See [`to_i64()`](Self::to_i64)
+    /// for a lossless conversion.
+    #[inline]
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            ValueRef::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value if it is an unsigned integer.
+    ///
+    /// This does not convert signed integers. See [`to_u64()`](Self::to_u64)
+    /// for a lossless conversion.
+    #[inline]
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ValueRef::Uint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value if it is a float.
+    ///
+    /// This does not convert integers. See [`to_f64()`](Self::to_f64) for a
+    /// lossless conversion.
+    #[inline]
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            ValueRef::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value if it is a string.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            ValueRef::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value if it is binary data.
+    ///
+    /// This does not convert strings. See [`to_bytes()`](Self::to_bytes).
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match *self {
+            ValueRef::Binary(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a bool.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a bool, this returns an error.
+    #[inline]
+    pub fn to_bool(&self) -> Result<bool, ValueError> {
+        self.as_bool().ok_or(self.mismatch("bool"))
+    }
+
+    /// Convert the value to an `i64` without loss of precision.
+    ///
+    /// Unsigned integers are converted when in range, and floats are converted
+    /// when they have no fractional part and are in range.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a number, or cannot be represented exactly as an
+    /// `i64`, this returns an error.
+    pub fn to_i64(&self) -> Result<i64, ValueError> {
+        match *self {
+            ValueRef::Int(value) => Ok(value),
+            ValueRef::Uint(value) => i64::try_from(value).map_err(|_| self.out_of_range("i64")),
+            #[expect(clippy::cast_possible_truncation)]
+            ValueRef::Float(value)
+                if value.fract() == 0.0 && (-I64_END..I64_END).contains(&value) =>
+            {
+                Ok(value as i64)
+            }
+            ValueRef::Float(_) => Err(self.out_of_range("i64")),
+            _ => Err(self.mismatch("i64")),
+        }
+    }
+
+    /// Convert the value to a `u64` without loss of precision.
+    ///
+    /// Signed integers are converted when in range, and floats are converted
+    /// when they have no fractional part and are in range.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a number, or cannot be represented exactly as a
+    /// `u64`, this returns an error.
+    pub fn to_u64(&self) -> Result<u64, ValueError> {
+        match *self {
+            ValueRef::Int(value) => u64::try_from(value).map_err(|_| self.out_of_range("u64")),
+            ValueRef::Uint(value) => Ok(value),
+            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            ValueRef::Float(value) if value.fract() == 0.0 && (0.0..U64_END).contains(&value) => {
+                Ok(value as u64)
+            }
+            ValueRef::Float(_) => Err(self.out_of_range("u64")),
+            _ => Err(self.mismatch("u64")),
+        }
+    }
+
+    /// Convert the value to an `f64` without loss of precision.
+    ///
+    /// Integers are converted when they can be represented exactly as an
+    /// `f64`.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a number, or cannot be represented exactly as an
+    /// `f64`, this returns an error.
+    #[expect(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn to_f64(&self) -> Result<f64, ValueError> {
+        match *self {
+            ValueRef::Int(value) => {
+                let float = value as f64;
+                // Note: Casting back saturates, so 2^63 must be excluded.
+                if float < I64_END && float as i64 == value {
+                    Ok(float)
+                } else {
+                    Err(self.out_of_range("f64"))
+                }
+            }
+            ValueRef::Uint(value) => {
+                let float = value as f64;
+                if float < U64_END && float as u64 == value {
+                    Ok(float)
+                } else {
+                    Err(self.out_of_range("f64"))
+                }
+            }
+            ValueRef::Float(value) => Ok(value),
+            _ => Err(self.mismatch("f64")),
+        }
+    }
+
+    /// Get the value as a string.
+    ///
+    /// Binary data is converted when it is valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a string or valid UTF-8 binary data, this returns
+    /// an error.
+    pub fn to_str(&self) -> Result<&'a str, ValueError> {
+        match *self {
+            ValueRef::String(value) => Ok(value),
+            ValueRef::Binary(value) => {
+                core::str::from_utf8(value).map_err(|_| self.out_of_range("str"))
+            }
+            _ => Err(self.mismatch("str")),
+        }
+    }
+
+    /// Get the value as bytes.
+    ///
+    /// Strings are converted to their UTF-8 bytes.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not binary data or a string, this returns an error.
+    pub fn to_bytes(&self) -> Result<&'a [u8], ValueError> {
+        match *self {
+            ValueRef::String(value) => Ok(value.as_bytes()),
+            ValueRef::Binary(value) => Ok(value),
+            _ => Err(self.mismatch("bytes")),
+        }
+    }
+
+    #[inline]
+    fn mismatch(&self, expected: &'static str) -> ValueError {
+        ValueError::TypeMismatch {
+            expected,
+            found: self.kind(),
+        }
+    }
+
+    #[inline]
+    fn out_of_range(&self, expected: &'static str) -> ValueError {
+        ValueError::OutOfRange {
+            expected,
+            found: self.kind(),
+        }
+    }
+}
+
+macro_rules! impl_try_from_value {
+    ($($ty:ty => |$value:ident| $convert:expr;)*) => {
+        $(
+            impl<'a> TryFrom<ValueRef<'a>> for $ty {
+                type Error = ValueError;
+
+                #[inline]
+                fn try_from($value: ValueRef<'a>) -> Result<Self, Self::Error> {
+                    $convert
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_try_from_int {
+    ($via:ident => $($ty:ident),*) => {
+        impl_try_from_value! {
+            $(
+                $ty => |value| {
+                    let int = value.$via()?;
+                    $ty::try_from(int).map_err(|_| value.out_of_range(stringify!($ty)))
+                };
+            )*
+        }
+    };
+}
+
+impl_try_from_int!(to_i64 => i8, i16, i32, isize);
+impl_try_from_int!(to_u64 => u8, u16, u32, usize);
+
+impl_try_from_value! {
+    bool => |value| value.to_bool();
+    i64 => |value| value.to_i64();
+    u64 => |value| value.to_u64();
+    i128 => |value| match value {
+        ValueRef::Uint(uint) => Ok(uint.into()),
+        _ => value.to_i64().map(Into::into),
+    };
+    u128 => |value| value.to_u64().map(Into::into);
+    f64 => |value| value.to_f64();
+    f32 => |value| {
+        let float = value.to_f64()?;
+        #[expect(clippy::cast_possible_truncation)]
+        let narrow = float as f32;
+        #[expect(clippy::float_cmp)]
+        let exact = f64::from(narrow) == float;
+        if exact || float.is_nan() {
+            Ok(narrow)
+        } else {
+            Err(value.out_of_range("f32"))
+        }
+    };
+    char => |value| {
+        let mut chars = value.to_str().map_err(|_| value.mismatch("char"))?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(value.out_of_range("char")),
+        }
+    };
+    &'a str => |value| value.to_str();
+    &'a [u8] => |value| value.to_bytes();
+}
@@ -7,6 +7,8 @@ pub enum Error {
     UnrepresentableUint(u64),
     #[error("the target format cannot represent this value: {0}")]
     UnrepresentableFloat(f64),
+    #[error("the target format cannot represent null values")]
+    UnrepresentableNull,
     #[error("the target format cannot represent binary data")]
     UnrepresentableBinary,
     #[error("the string is not valid UTF-8")]
@@ -36,6 +38,21 @@ impl Error {
     }
 }
 
+/// Error when converting a [`ValueRef`](crate::ValueRef) to a specific type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ValueError {
+    #[error("expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: crate::ValueKind,
+    },
+    #[error("{found} value cannot be represented as {expected}")]
+    OutOfRange {
+        expected: &'static str,
+        found: crate::ValueKind,
+    },
+}
+
 /// Validation error when checking the integrity of the binary encoding of a
 /// document.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
@@ -71,6 +88,10 @@ pub enum ValidationErrorKind {
     HeaderBinaryOffset,
     #[error("header binary length field is invalid")]
     HeaderBinaryLen,
+    #[error("header index offset field is invalid")]
+    HeaderIndexOffset,
+    #[error("header index length field is invalid")]
+    HeaderIndexLen,
     #[error("header root node index is out of bounds")]
     HeaderRootNodeOutOfBounds,
     #[error("header reserved fields must be zero")]
@@ -96,6 +117,12 @@ pub enum ValidationErrorKind {
         "children of node come before the node; all children of a node must come after the node itself"
     )]
     ChildrenBeforeParent,
+    #[error("name index entry out of bounds")]
+    IndexEntryOutOfBounds,
+    #[error("name index does not match the names of the entries of a node")]
+    IndexMismatch,
+    #[error("more than half of the buckets of the name index are occupied")]
+    IndexFull,
 }
 
 impl ValidationErrorKind {
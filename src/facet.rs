@@ -1,11 +1,42 @@
-use facet_core::{Facet, FieldError, Shape};
+//! Serialization support via `facet`.
+//!
+//! Values are represented with the same conventions as with the `serde`
+//! feature, so a type can switch between the two without changing the shape
+//! of its documents.
+//!
+//! These field attributes are supported:
+//!
+//! - `#[facet(rename = "name")]`: The entry has a different name than the
+//!   field.
+//! - `#[facet(default)]` and `#[facet(default = "function")]`: The field is
+//!   optional. `#[facet(default)]` on a struct makes all of its fields
+//!   optional. Fields of type `Option<T>` are always optional.
+//! - `#[facet(skip)]`: The field is neither serialized nor deserialized, and
+//!   is initialized with its default value.
+//! - `#[facet(flatten)]`: The entries of a struct field are entries of the
+//!   containing node.
+//! - `#[facet(sensitive)]`: No effect, because errors never contain values.
+//!
+//! Unknown entries are ignored, unless the struct has
+//! `#[facet(deny_unknown_fields)]`.
+//!
+//! Borrowed byte slices (`&[u8]`) are binary values, while `Vec<u8>` is a list
+//! of integers, like with serde. Sets are not supported, because `facet` does
+//! not describe them. Arrays and smart pointers like `Arc<T>` can be
+//! serialized, but not deserialized, because `facet-reflect` cannot build them
+//! in place.
+
+use facet_core::{Facet, Field, FieldAttribute, FieldError, Shape};
 use facet_reflect::ReflectError;
 
 use crate::Document;
 
 mod de;
+mod registry;
 mod ser;
 
+pub use registry::Registry;
+
 #[derive(thiserror::Error)]
 #[non_exhaustive]
 pub enum Error {
@@ -29,6 +60,19 @@ pub enum Error {
     Reflect(#[from] ReflectError),
     #[error("unexpected shape: {0}")]
     UnexpectedShape(&'static Shape),
+    #[error("unknown field `{0}` in {1}")]
+    UnknownField(alloc::string::String, &'static Shape),
+    #[error("integer out of range: {0}")]
+    IntegerOverflow(&'static Shape),
+    #[error("unknown type `{ty}`, expected one of: {}", expected.join(", "))]
+    UnknownType {
+        ty: alloc::string::String,
+        expected: alloc::vec::Vec<&'static str>,
+    },
+    #[error("cannot serialize a value of an unregistered type")]
+    UnregisteredType,
+    #[error("`{0}` serializes as a typed node, which cannot have the type of the implementation")]
+    TypedImplementation(&'static str),
 }
 
 impl core::fmt::Debug for Error {
@@ -137,6 +181,31 @@ pub fn to_document<'a, T: Facet<'a>>(value: &'a T) -> Result<crate::DocumentBuff
     Ok(builder.build())
 }
 
+/// Name of the entry holding `field`.
+fn field_name(field: &'static Field) -> &'static str {
+    field.get_rename_attr().unwrap_or(field.name)
+}
+
+/// Whether `field` has the attribute `#[facet(name)]`, which facet passes
+/// through as an arbitrary attribute.
+fn has_attr(field: &Field, name: &str) -> bool {
+    field
+        .attributes
+        .iter()
+        .any(|attr| matches!(attr, FieldAttribute::Arbitrary(attr) if *attr == name))
+}
+
+/// Whether the entries of `field` are entries of the containing node
+/// (`#[facet(flatten)]`).
+fn is_flattened(field: &Field) -> bool {
+    has_attr(field, "flatten")
+}
+
+/// Whether `field` is never serialized or deserialized (`#[facet(skip)]`).
+fn is_skipped(field: &Field) -> bool {
+    has_attr(field, "skip")
+}
+
 #[cfg(all(test, feature = "alloc"))]
 mod tests {
     use crate::builder::{self, Arg, Value};
@@ -346,4 +415,206 @@ mod tests {
         };
         assert_eq!(s.as_ptr(), hello.as_ptr());
     }
+
+    #[test]
+    fn registry() {
+        use alloc::{borrow::ToOwned as _, boxed::Box, string::ToString as _};
+
+        trait Shape: core::any::Any {
+            fn area(&self) -> f64;
+        }
+
+        #[derive(Facet)]
+        struct Square {
+            side: f64,
+        }
+        impl Shape for Square {
+            fn area(&self) -> f64 {
+                self.side * self.side
+            }
+        }
+
+        #[derive(Facet)]
+        struct Rect(f64, f64);
+        impl Shape for Rect {
+            fn area(&self) -> f64 {
+                self.0 * self.1
+            }
+        }
+
+        let mut shapes: Registry<dyn Shape> = Registry::new();
+        shapes
+            .register("Square", |shape: Square| Box::new(shape))
+            .register("Rect", |shape: Rect| Box::new(shape));
+
+        let values: Vec<Box<dyn Shape>> =
+            vec![Box::new(Square { side: 2.0 }), Box::new(Rect(2.0, 3.0))];
+        let mut root = builder::Node::empty();
+        for value in &values {
+            let any: &dyn core::any::Any = &**value;
+            root.push(shapes.to_builder_node(any).unwrap());
+        }
+        let mut builder = crate::Builder::new();
+        builder.set_root(root);
+        let doc = builder.build();
+        let children = doc.root().children();
+        assert_eq!(children.get(0).unwrap().ty(), Some("Square"));
+        assert_eq!(children.get(0).unwrap().get_f64("side"), Some(2.0));
+        assert_eq!(children.get(1).unwrap().ty(), Some("Rect"));
+
+        let areas: Vec<f64> = doc
+            .root()
+            .children()
+            .into_iter()
+            .map(|node| shapes.from_document_node(node).unwrap().area())
+            .collect();
+        assert_eq!(areas, [4.0, 6.0]);
+
+        let circle = builder::Node::from_values([1.0]).with_ty("Circle");
+        let err = shapes.from_builder_node(&circle).map(|_| ()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown type `Circle`, expected one of: Rect, Square"
+        );
+        let err = shapes.to_builder_node(&"circle".to_owned()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot serialize a value of an unregistered type"
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        use alloc::string::ToString as _;
+
+        fn default_port() -> u16 {
+            8080
+        }
+
+        #[derive(Facet, Debug, PartialEq)]
+        struct Listen {
+            host: String,
+            #[facet(default = "default_port")]
+            port: u16,
+        }
+
+        #[derive(Facet, Debug, PartialEq)]
+        #[facet(deny_unknown_fields)]
+        struct Server {
+            #[facet(rename = "server-name")]
+            name: String,
+            #[facet(flatten)]
+            listen: Listen,
+            #[facet(default)]
+            workers: u32,
+            #[facet(skip)]
+            cache: Vec<i32>,
+            user: Option<String>,
+        }
+
+        let server = Server {
+            name: String::from("example"),
+            listen: Listen {
+                host: String::from("localhost"),
+                port: 80,
+            },
+            workers: 4,
+            cache: vec![1, 2, 3],
+            user: None,
+        };
+        let node = to_builder_node(&server).unwrap();
+        let names: Vec<_> = node
+            .args()
+            .iter()
+            .map(|arg| arg.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["server-name", "host", "port", "workers", "user"]);
+        let de: Server = from_builder_node(&node).unwrap();
+        assert_eq!(
+            de,
+            Server {
+                cache: Vec::new(),
+                ..server
+            }
+        );
+
+        let node = builder::Node::from_args([
+            Arg {
+                name: Some("server-name".into()),
+                value: Value::String("example".into()),
+            },
+            Arg {
+                name: Some("host".into()),
+                value: Value::String("localhost".into()),
+            },
+        ]);
+        let de: Server = from_builder_node(&node).unwrap();
+        assert_eq!(de.listen.port, 8080);
+        assert_eq!(de.workers, 0);
+        assert_eq!(de.user, None);
+
+        let mut node = node;
+        node.push(Arg {
+            name: Some("name".into()),
+            value: Value::String("example".into()),
+        });
+        let err = from_builder_node::<Server>(&node).unwrap_err();
+        assert!(err.to_string().starts_with("unknown field `name`"));
+    }
+
+    #[test]
+    fn scalars() {
+        #[derive(Facet, Debug, PartialEq)]
+        struct Scalars<'a> {
+            big: i128,
+            huge: u128,
+            size: usize,
+            letter: char,
+            bytes: &'a [u8],
+        }
+
+        let bytes = vec![1u8, 2, 3];
+        let scalars = Scalars {
+            big: -1,
+            huge: 1 << 40,
+            size: 3,
+            letter: 'z',
+            bytes: &bytes,
+        };
+        let node = to_builder_node(&scalars).unwrap();
+        assert!(matches!(&node.args()[3].value, Value::String(s) if s == "z"));
+        assert!(matches!(&node.args()[4].value, Value::Binary(b) if **b == *bytes));
+        let de: Scalars<'_> = from_builder_node(&node).unwrap();
+        assert_eq!(de, scalars);
+
+        let err = to_builder_node(&u128::MAX).unwrap_err();
+        assert!(matches!(err, Error::IntegerOverflow(_)));
+    }
+
+    #[test]
+    fn arrays_and_smart_pointers() {
+        use alloc::sync::Arc;
+
+        #[derive(Facet, Debug)]
+        struct Serialized {
+            array: [u8; 3],
+            shared: Arc<String>,
+        }
+
+        let value = Serialized {
+            array: [4, 5, 6],
+            shared: Arc::new(String::from("shared")),
+        };
+        let node = to_builder_node(&value).unwrap();
+        assert!(matches!(&node.args()[0].value, Value::String(s) if s == "shared"));
+        assert_eq!(node.children()[0].name(), "array");
+        assert_eq!(node.children()[0].args().len(), 3);
+
+        // These cannot be deserialized.
+        let err = from_builder_node::<Serialized>(&node).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedValue(_)));
+        let node = builder::Node::from_values(["a"]);
+        let err = from_builder_node::<Arc<String>>(&node).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedValue(_)));
+    }
 }
@@ -1,8 +1,11 @@
-use alloc::borrow::{Cow, ToOwned as _};
-use facet_core::{Def, Facet, Struct, StructKind};
+use alloc::{
+    borrow::{Cow, ToOwned as _},
+    vec::Vec,
+};
+use facet_core::{Def, Facet, Field, Shape, Struct, StructKind};
 use facet_reflect::{ReflectError, Wip};
 
-use super::Error;
+use super::{Error, field_name, is_flattened, is_skipped};
 use crate::{
     ValueRef,
     access::{self, ArgRef as _},
@@ -33,13 +36,34 @@ pub fn deserialize_node<'mem, N: access::NodeRef<'mem>>(
         Def::Struct(s) => deserialize_node_as_struct(wip, node, s),
         Def::Enum(_) => deserialize_node_as_enum(wip, node),
         Def::Map(_) => deserialize_node_as_map(wip, node),
-        Def::List(_) | Def::Array(_) | Def::Slice(_) => deserialize_node_as_list(wip, node),
+        Def::List(_) | Def::Slice(_) if is_bytes(wip.shape()) && is_binary(node) => {
+            deserialize_value_as_scalar(wip, node.args().next().unwrap().value())
+                .map_err(Into::into)
+        }
+        Def::List(_) => deserialize_node_as_list(wip, node),
         Def::Option(_) => deserialize_node_as_option(wip, node),
-        // TODO: Can probably support Box.
+        // `facet-reflect` cannot build these in place.
+        Def::Array(_) | Def::SmartPointer(_) => Err(Error::UnsupportedValue(wip.shape())),
         _ => Err(Error::UnexpectedShape(wip.shape())),
     }
 }
 
+/// Whether values of `shape` may be deserialized from binary values.
+fn is_bytes(shape: &'static Shape) -> bool {
+    shape.is_type::<&[u8]>() || shape.is_type::<alloc::vec::Vec<u8>>()
+}
+
+/// Whether the node is a single binary value, rather than a list of bytes.
+fn is_binary<'mem, N: access::NodeRef<'mem>>(node: &N) -> bool {
+    let mut args = node.args();
+    node.children().next().is_none()
+        && matches!(
+            args.next().map(|arg| arg.value()),
+            Some(ValueRef::Binary(_))
+        )
+        && args.next().is_none()
+}
+
 fn deserialize_node_as_list<'mem, N: access::NodeRef<'mem>>(
     list: Wip<'mem>,
     node: &N,
@@ -86,24 +110,92 @@ fn deserialize_node_as_struct<'mem, N: access::NodeRef<'mem>>(
         }
         StructKind::Struct => {
             for entry in node.entries() {
-                wip = deserialize_entry(wip.field_named(entry.name())?, &entry)?.pop()?;
+                let Some(path) = find_field(def.fields, entry.name()) else {
+                    if shape.has_deny_unknown_fields_attr() {
+                        return Err(Error::UnknownField(entry.name().into(), shape));
+                    }
+                    continue;
+                };
+                for &index in &path {
+                    wip = wip.field(index)?;
+                }
+                wip = deserialize_entry(wip, &entry)?;
+                for _ in &path {
+                    wip = wip.pop()?;
+                }
             }
-            Ok(wip)
+            fill_missing_fields(wip, def.fields, shape)
         }
         _ => Err(Error::UnexpectedShape(shape)),
     }
 }
 
+/// Find the field that an entry named `name` is deserialized into, as the
+/// indices of the fields leading to it through flattened structs.
+fn find_field(fields: &'static [Field], name: &str) -> Option<Vec<usize>> {
+    for (index, field) in fields.iter().enumerate() {
+        if is_skipped(field) {
+            continue;
+        }
+        if is_flattened(field) {
+            if let Def::Struct(def) = field.shape().def
+                && let Some(mut path) = find_field(def.fields, name)
+            {
+                path.insert(0, index);
+                return Some(path);
+            }
+        } else if field_name(field) == name {
+            return Some(alloc::vec![index]);
+        }
+    }
+    None
+}
+
+/// Initialize the fields that had no entry, with their default values where
+/// that is allowed: for fields with `#[facet(default)]` or `#[facet(skip)]`,
+/// for all fields of structs with `#[facet(default)]`, and for options.
+fn fill_missing_fields<'mem>(
+    mut wip: Wip<'mem>,
+    fields: &'static [Field],
+    shape: &'static Shape,
+) -> Result<Wip<'mem>, Error> {
+    for (index, field) in fields.iter().enumerate() {
+        if wip.is_field_set(index)? {
+            continue;
+        }
+        wip = wip.field(index)?;
+        if is_flattened(field)
+            && let Def::Struct(def) = field.shape().def
+        {
+            wip = fill_missing_fields(wip, def.fields, field.shape())?;
+        } else if let Some(Some(default)) = field.get_default_attr() {
+            wip = wip.put_from_fn(default)?;
+        } else if field.get_default_attr().is_some()
+            || is_skipped(field)
+            || shape.has_default_attr()
+            || matches!(field.shape().def, Def::Option(_))
+        {
+            wip = wip.put_default()?;
+        } else {
+            // Leave it to `build` to report the missing field.
+            wip = wip.pop()?;
+            continue;
+        }
+        wip = wip.pop()?;
+    }
+    Ok(wip)
+}
+
 fn deserialize_node_as_enum<'mem, N: access::NodeRef<'mem>>(
     wip: Wip<'mem>,
     node: &N,
 ) -> Result<Wip<'mem>, Error> {
     if node.ty().is_empty() {
         // If the node is just a single string, assume this is a unit variant.
-        if let Some(first_arg) = node.args().next() {
-            if let ValueRef::String(s) = first_arg.value() {
-                return Ok(wip.variant_named(s)?);
-            }
+        if let Some(first_arg) = node.args().next()
+            && let ValueRef::String(s) = first_arg.value()
+        {
+            return Ok(wip.variant_named(s)?);
         }
 
         Err(Error::ExpectedEnum(wip.shape()))
@@ -132,8 +224,12 @@ fn deserialize_value<'mem>(wip: Wip<'mem>, value: ValueRef<'mem>) -> Result<Wip<
         Def::Scalar(_) => deserialize_value_as_scalar(wip, value).map_err(Into::into),
         Def::Struct(s) => deserialize_value_as_struct(wip, value, s),
         Def::Enum(_) => deserialize_value_as_enum(wip, value),
-        Def::List(_) | Def::Array(_) | Def::Slice(_) => deserialize_value_as_list(wip, value),
+        Def::List(_) | Def::Slice(_) if is_bytes(wip.shape()) => {
+            deserialize_value_as_scalar(wip, value).map_err(Into::into)
+        }
+        Def::List(_) => deserialize_value_as_list(wip, value),
         Def::Option(_) => deserialize_value_as_option(wip, value),
+        Def::Array(_) | Def::SmartPointer(_) => Err(Error::UnsupportedValue(wip.shape())),
         _ => Err(Error::UnexpectedShape(wip.shape())),
     }
 }
@@ -252,6 +348,9 @@ fn deserialize_value_as_scalar<'mem>(
             if shape.is_type::<i128>() {
                 return wip.put(value as i128);
             }
+            if shape.is_type::<isize>() {
+                return try_put_int::<isize>(wip, value);
+            }
             if value >= 0 {
                 if shape.is_type::<u8>() {
                     return try_put_int::<u8>(wip, value);
@@ -265,6 +364,9 @@ fn deserialize_value_as_scalar<'mem>(
                 if shape.is_type::<u64>() {
                     return try_put_int::<u64>(wip, value);
                 }
+                if shape.is_type::<usize>() {
+                    return try_put_int::<usize>(wip, value);
+                }
                 #[allow(clippy::cast_sign_loss)] // false positive: checked sign already
                 if shape.is_type::<u128>() {
                     return try_put_int::<u128>(wip, value);
@@ -291,6 +393,9 @@ fn deserialize_value_as_scalar<'mem>(
             if shape.is_type::<u128>() {
                 return wip.put(value as u128);
             }
+            if shape.is_type::<usize>() {
+                return try_put_uint::<usize>(wip, value);
+            }
             if shape.is_type::<i8>() {
                 return try_put_uint::<i8>(wip, value);
             }
@@ -306,6 +411,9 @@ fn deserialize_value_as_scalar<'mem>(
             if shape.is_type::<i128>() {
                 return wip.put(value as i128);
             }
+            if shape.is_type::<isize>() {
+                return try_put_uint::<isize>(wip, value);
+            }
             Err(ReflectError::WrongShape {
                 expected: <u64 as Facet>::SHAPE,
                 actual: shape,
@@ -333,6 +441,16 @@ fn deserialize_value_as_scalar<'mem>(
             if shape.is_type::<Cow<'mem, str>>() {
                 return wip.put(Cow::Borrowed(value));
             }
+            if shape.is_type::<char>() {
+                let mut chars = value.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c), None) => wip.put(c),
+                    _ => Err(ReflectError::OperationFailed {
+                        shape,
+                        operation: "expected a single character",
+                    }),
+                };
+            }
             Err(ReflectError::WrongShape {
                 expected: alloc::string::String::SHAPE,
                 actual: shape,
@@ -0,0 +1,174 @@
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, string::String};
+use core::{
+    any::{Any, TypeId},
+    fmt,
+};
+
+use facet_core::Facet;
+
+use crate::{access::ConcreteNode, builder};
+
+use super::Error;
+
+type DeserializeFn<T> = dyn Fn(ConcreteNode<'_>) -> Result<Box<T>, Error> + Send + Sync;
+type SerializeFn = for<'a> fn(&'a dyn Any) -> Result<builder::Node<'a>, Error>;
+
+/// Registry of the implementations of a trait, for converting trait objects to
+/// and from nodes, where the type of the node selects the implementation.
+///
+/// This is the facet counterpart of `zdoc::serde::Registry`. The trait must
+/// have [`Any`] as a supertrait, so the implementation behind a trait object
+/// can be found.
+///
+/// ```ignore
+/// let mut filters: Registry<dyn Filter> = Registry::new();
+/// filters
+///     .register("Upper", |filter: Upper| Box::new(filter))
+///     .register("Prefix", |filter: Prefix| Box::new(filter));
+///
+/// // (Prefix) { prefix = "> " }
+/// let node = filters.to_builder_node(&*filter)?;
+/// let filter: Box<dyn Filter> = filters.from_builder_node(&node)?;
+/// ```
+pub struct Registry<T: ?Sized> {
+    types: BTreeMap<&'static str, Registration<T>>,
+    names: BTreeMap<TypeId, &'static str>,
+}
+
+struct Registration<T: ?Sized> {
+    deserialize: Box<DeserializeFn<T>>,
+    serialize: SerializeFn,
+}
+
+impl<T: ?Sized + 'static> Registry<T> {
+    /// Create an empty registry.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            types: BTreeMap::new(),
+            names: BTreeMap::new(),
+        }
+    }
+
+    /// Register the implementation `C` under the type name `ty`. `into`
+    /// converts a deserialized value into a trait object, and is usually
+    /// `|value: C| Box::new(value)`.
+    ///
+    /// Values of `C` must not serialize as typed nodes, like enums, because
+    /// the type of the node is the name of the implementation.
+    ///
+    /// # Panics
+    ///
+    /// If `ty` is empty, or if `ty` or `C` is already registered, this
+    /// panics.
+    pub fn register<C>(&mut self, ty: &'static str, into: fn(C) -> Box<T>) -> &mut Self
+    where
+        C: for<'a> Facet<'a> + 'static,
+    {
+        assert!(!ty.is_empty(), "type name must not be empty");
+        assert!(
+            self.names.insert(TypeId::of::<C>(), ty).is_none(),
+            "`{}` is already registered",
+            core::any::type_name::<C>()
+        );
+        let registration = Registration {
+            deserialize: Box::new(move |node| deserialize_any::<C>(node).map(into)),
+            serialize: serialize_any::<C>,
+        };
+        assert!(
+            self.types.insert(ty, registration).is_none(),
+            "type `{ty}` is already registered"
+        );
+        self
+    }
+
+    /// The registered type names, in order.
+    pub fn types(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types.keys().copied()
+    }
+
+    /// Deserialize a trait object from a [`Node`](crate::Node), choosing the
+    /// implementation by the type of the node.
+    ///
+    /// # Errors
+    ///
+    /// If the type of the node is not registered, or the node does not match
+    /// the shape of the implementation, this returns an error.
+    pub fn from_document_node(&self, node: crate::Node<'_>) -> Result<Box<T>, Error> {
+        self.dispatch(node.ty().unwrap_or_default(), ConcreteNode::Document(node))
+    }
+
+    /// Deserialize a trait object from a [`builder::Node`], choosing the
+    /// implementation by the type of the node.
+    ///
+    /// # Errors
+    ///
+    /// If the type of the node is not registered, or the node does not match
+    /// the shape of the implementation, this returns an error.
+    pub fn from_builder_node(&self, node: &builder::Node<'_>) -> Result<Box<T>, Error> {
+        self.dispatch(node.ty(), ConcreteNode::Builder(node))
+    }
+
+    /// Serialize the value behind a trait object into a node, with the name
+    /// of its implementation as the type.
+    ///
+    /// Strings and binary buffers are borrowed from `value` rather than
+    /// copied.
+    ///
+    /// # Errors
+    ///
+    /// If the implementation is not registered, or the value cannot be
+    /// serialized, this returns an error.
+    pub fn to_builder_node<'a>(&self, value: &'a dyn Any) -> Result<builder::Node<'a>, Error> {
+        let Some(&ty) = self.names.get(&value.type_id()) else {
+            return Err(Error::UnregisteredType);
+        };
+        let mut node = (self.types[ty].serialize)(value)?;
+        if !node.ty().is_empty() {
+            return Err(Error::TypedImplementation(ty));
+        }
+        node.set_ty(ty);
+        Ok(node)
+    }
+
+    fn dispatch(&self, ty: &str, node: ConcreteNode<'_>) -> Result<Box<T>, Error> {
+        let Some(registration) = self.types.get(ty) else {
+            return Err(Error::UnknownType {
+                ty: String::from(ty),
+                expected: self.types().collect(),
+            });
+        };
+        (registration.deserialize)(node)
+    }
+}
+
+impl<T: ?Sized + 'static> Default for Registry<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Registry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("types", &self.types.keys())
+            .finish_non_exhaustive()
+    }
+}
+
+fn deserialize_any<C: for<'a> Facet<'a>>(node: ConcreteNode<'_>) -> Result<C, Error> {
+    match node {
+        ConcreteNode::Document(node) => super::from_document_node(node),
+        ConcreteNode::Builder(node) => super::from_builder_node(node),
+    }
+}
+
+fn serialize_any<'a, C: for<'b> Facet<'b> + 'static>(
+    value: &'a dyn Any,
+) -> Result<builder::Node<'a>, Error> {
+    let value = value
+        .downcast_ref::<C>()
+        .expect("registered under the type ID of the value");
+    super::to_builder_node(value)
+}
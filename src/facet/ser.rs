@@ -1,24 +1,29 @@
 use alloc::{borrow::Cow, string::String, vec::Vec};
-use facet_core::{Def, Facet, Field, StructKind};
-use facet_reflect::{Peek, PeekList, PeekMap, PeekOption};
+use core::mem;
+use facet_core::{Def, Facet, Field, PtrConst, SmartPointerDef, StructKind};
+use facet_reflect::{Peek, PeekMap, PeekOption};
 
 use crate::{
     ValueRef,
     builder::{Arg, Entry, Node, Value},
 };
 
-use super::Error;
+use super::{Error, field_name, is_flattened, is_skipped};
 
 /// Serialize anything into a node. This is used when the result should always
 /// be a node, such as when serializing the root.
 pub fn serialize_as_node(peek: Peek) -> Result<Node, Error> {
     match peek.shape().def {
         Def::Scalar(_) => serialize_value_as_value(peek).map(|value| Node::from_args([value])),
+        Def::Slice(_) if is_bytes(peek) => {
+            serialize_value_as_value(peek).map(|value| Node::from_args([value]))
+        }
         Def::Struct(_) => serialize_struct_as_node(peek),
         Def::Enum(_) => serialize_enum_as_node(peek),
         Def::Map(_) => serialize_map_as_node(peek.into_map()?),
-        Def::List(_) | Def::Array(_) | Def::Slice(_) => serialize_list_as_node(peek.into_list()?),
+        Def::List(_) | Def::Array(_) | Def::Slice(_) => serialize_list_as_node(items(peek)?),
         Def::Option(_) => serialize_option_as_node(peek.into_option()?),
+        Def::SmartPointer(def) => serialize_as_node(pointee(peek, def)?),
         _ => Err(Error::UnexpectedShape(peek.shape())),
     }
 }
@@ -27,14 +32,15 @@ pub fn serialize_as_node(peek: Peek) -> Result<Node, Error> {
 pub fn serialize_as_entry(peek: Peek) -> Result<Entry, Error> {
     match peek.shape().def {
         Def::Scalar(_) => serialize_value_as_entry(peek),
+        Def::Slice(_) if is_bytes(peek) => serialize_value_as_entry(peek),
         Def::Struct(_) => serialize_struct_as_entry(peek),
         Def::Enum(_) => serialize_enum_as_entry(peek),
         Def::Map(_) => serialize_map_as_node(peek.into_map()?).map(Entry::Child),
-        Def::List(_) => serialize_list_as_node(peek.into_list()?).map(Entry::Child),
-        Def::Array(_) | Def::Slice(_) => {
-            serialize_list_as_node(peek.into_list()?).map(Entry::Child)
+        Def::List(_) | Def::Array(_) | Def::Slice(_) => {
+            serialize_list_as_node(items(peek)?).map(Entry::Child)
         }
         Def::Option(_) => serialize_option_as_entry(peek.into_option()?),
+        Def::SmartPointer(def) => serialize_as_entry(pointee(peek, def)?),
         _ => Err(Error::UnexpectedShape(peek.shape())),
     }
 }
@@ -45,9 +51,55 @@ fn serialize_value_as_entry(peek: Peek<'_>) -> Result<Entry<'_>, Error> {
         .map(Entry::Arg)
 }
 
-fn serialize_list_as_node(peek: PeekList<'_>) -> Result<Node<'_>, Error> {
+/// Borrowed byte slices are binary values, like with serde. (`Vec<u8>` is a
+/// list, also like with serde.)
+fn is_bytes(peek: Peek<'_>) -> bool {
+    peek.shape().is_type::<&[u8]>()
+}
+
+/// The items of a list, an array, or a slice.
+fn items(peek: Peek<'_>) -> Result<Vec<Peek<'_>>, Error> {
+    let data = peek.data();
+    match peek.shape().def {
+        Def::List(_) => Ok(peek.into_list()?.iter().collect()),
+        Def::Array(def) => {
+            // `get_item_ptr` of arrays always points at the first item, so
+            // index the array by hand.
+            let item = def.t();
+            Ok((0..def.n)
+                .map(|index| unsafe {
+                    // SAFETY: `data` is an array of `def.n` items of shape `item`.
+                    let ptr = data.as_byte_ptr().add(index * item.layout.size());
+                    Peek::unchecked_new(PtrConst::new(ptr), item)
+                })
+                .collect())
+        }
+        Def::Slice(def) => {
+            // SAFETY: `data` is a slice of items of shape `def.t()`.
+            let len = unsafe { (def.vtable.len)(data) };
+            Ok((0..len)
+                .map(|index| unsafe {
+                    Peek::unchecked_new((def.vtable.get_item_ptr)(data, index), def.t())
+                })
+                .collect())
+        }
+        _ => Err(Error::ExpectedList),
+    }
+}
+
+/// The value behind a smart pointer, like `Arc<T>`.
+fn pointee(peek: Peek<'_>, def: SmartPointerDef) -> Result<Peek<'_>, Error> {
+    let (Some(borrow), Some(pointee)) = (def.vtable.borrow_fn, def.pointee) else {
+        // Weak pointers cannot be borrowed.
+        return Err(Error::UnsupportedValue(peek.shape()));
+    };
+    // SAFETY: `peek` is a smart pointer to a value of shape `pointee`.
+    Ok(unsafe { Peek::unchecked_new(borrow(peek.data()), pointee) })
+}
+
+fn serialize_list_as_node(items: Vec<Peek<'_>>) -> Result<Node<'_>, Error> {
     let mut node = Node::empty();
-    for item in peek.iter() {
+    for item in items {
         let item = serialize_as_entry(item)?;
         node.push_ordered(item);
     }
@@ -113,8 +165,21 @@ fn serialize_named_struct_fields_as_node<'mem>(
 ) -> Result<Node<'mem>, Error> {
     let mut node = Node::empty();
     for (field, value) in fields {
+        if is_skipped(field) {
+            continue;
+        }
+        if is_flattened(field) {
+            let mut flattened = serialize_as_node(value)?;
+            for arg in mem::take(flattened.args_mut()) {
+                node.push(arg);
+            }
+            for child in mem::take(flattened.children_mut()) {
+                node.push(child);
+            }
+            continue;
+        }
         let mut serialized_value = serialize_as_entry(value)?;
-        serialized_value.set_name(field.name);
+        serialized_value.set_name(field_name(field));
         node.push(serialized_value);
     }
     Ok(node)
@@ -205,6 +270,22 @@ fn serialize_value_as_value<'mem>(peek: Peek<'mem>) -> Result<Value<'mem>, Error
     if let Some(value) = try_get::<u64>(&peek) {
         return Ok(Value::Uint(*value as _));
     }
+    if let Some(value) = try_get::<i128>(&peek) {
+        return i64::try_from(*value)
+            .map(Value::Int)
+            .map_err(|_| Error::IntegerOverflow(shape));
+    }
+    if let Some(value) = try_get::<u128>(&peek) {
+        return u64::try_from(*value)
+            .map(Value::Uint)
+            .map_err(|_| Error::IntegerOverflow(shape));
+    }
+    if let Some(value) = try_get::<isize>(&peek) {
+        return Ok(Value::Int(*value as _));
+    }
+    if let Some(value) = try_get::<usize>(&peek) {
+        return Ok(Value::Uint(*value as _));
+    }
     if let Some(value) = try_get::<f32>(&peek) {
         return Ok(Value::Float(*value as _));
     }
@@ -215,6 +296,9 @@ fn serialize_value_as_value<'mem>(peek: Peek<'mem>) -> Result<Value<'mem>, Error
         return Ok(Value::Bool(*value as _));
     }
 
+    if let Some(value) = try_get::<char>(&peek) {
+        return Ok(Value::String(Cow::Owned(String::from(*value))));
+    }
     if let Some(value) = try_get::<String>(&peek) {
         return Ok(Value::String(Cow::Borrowed(value)));
     }
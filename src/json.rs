@@ -52,6 +52,10 @@ pub struct JsonSettings<'a> {
     /// indistinguishable from single-value nodes, so single-element sequences
     /// will not have an `$items` field, but instead a `$value` field.
     pub value_tag: &'a str,
+    /// Number of spaces to indent nested objects and arrays with when
+    /// producing a JSON string. When zero, the output is compact and contains
+    /// no whitespace. Default is `0`.
+    pub indent: usize,
 }
 
 impl Default for JsonSettings<'_> {
@@ -61,6 +65,7 @@ impl Default for JsonSettings<'_> {
             type_tag: "$type",
             items_tag: "$items",
             value_tag: "$value",
+            indent: 0,
         }
     }
 }
@@ -84,8 +89,7 @@ pub fn document_to_json_value(doc: &Document) -> Result<serde_json::Value> {
 /// document conflict with the default JSON settings, this returns an error.
 #[inline]
 pub fn document_to_json(doc: &Document) -> Result<alloc::string::String> {
-    let value = document_to_json_value(doc)?;
-    Ok(value.to_string())
+    document_to_json_with_settings(doc, &JsonSettings::default())
 }
 
 /// Convert [`Document`] to JSON.
@@ -113,9 +117,8 @@ pub fn document_to_json_with_settings(
     doc: &Document,
     settings: &JsonSettings,
 ) -> Result<alloc::string::String> {
-    settings
-        .node_to_json(&doc.root())
-        .map(|value| value.to_string())
+    let value = settings.node_to_json(&doc.root())?;
+    settings.write_json(&value)
 }
 
 /// Convert JSON to [`Document`].
@@ -215,6 +218,19 @@ impl JsonSettings<'_> {
         builder
     }
 
+    fn write_json(&self, value: &serde_json::Value) -> Result<String> {
+        if self.indent == 0 {
+            return Ok(value.to_string());
+        }
+
+        let indent = " ".repeat(self.indent);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut out = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        serde::Serialize::serialize(value, &mut serializer).map_err(Error::custom)?;
+        String::from_utf8(out).map_err(Error::custom)
+    }
+
     fn node_to_json(&self, node: &crate::Node) -> Result<serde_json::Value> {
         let classification = node.classify();
 
@@ -423,6 +439,15 @@ mod tests {
             r#"{"$items":["child",123],"$type":"Root","key":"value"}"#
         );
 
+        let settings = JsonSettings {
+            indent: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            document_to_json_with_settings(&doc, &settings).unwrap(),
+            "{\n  \"$items\": [\n    \"child\",\n    123\n  ],\n  \"$type\": \"Root\",\n  \"key\": \"value\"\n}"
+        );
+
         let doc = document_from_json_value(&json_value);
         let root = doc.root();
         assert_eq!(root.ty(), Some("Root"));
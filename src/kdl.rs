@@ -11,6 +11,20 @@ use crate::{
     builder::{Arg, Node, Value},
 };
 
+/// Settings for emitting KDL.
+pub struct KdlSettings {
+    /// Number of spaces to indent children with. When zero, children are not
+    /// indented. Default is `4`.
+    pub indent: usize,
+}
+
+impl Default for KdlSettings {
+    #[inline]
+    fn default() -> Self {
+        Self { indent: 4 }
+    }
+}
+
 /// Convert a document to a KDL document.
 ///
 /// # Errors
@@ -30,7 +44,48 @@ pub fn document_to_kdl_document(doc: &Document) -> Result<KdlDocument> {
 /// error.
 #[inline]
 pub fn document_to_kdl(doc: &Document) -> Result<String> {
-    KdlDocument::try_from(doc).map(|doc| doc.to_string())
+    document_to_kdl_with_settings(doc, &KdlSettings::default())
+}
+
+/// Convert a document to a formatted KDL string.
+///
+/// # Errors
+///
+/// If `doc` contains binary data (unrepresentable in KDL), this returns an
+/// error.
+pub fn document_to_kdl_with_settings(doc: &Document, settings: &KdlSettings) -> Result<String> {
+    fn prepare_format(doc: &mut KdlDocument) {
+        // The `kdl` crate ignores the configured indentation for closing
+        // braces of child documents that do not have a format yet.
+        for node in doc.nodes_mut() {
+            // The `kdl` crate also writes strings that are spelled like
+            // keywords bare, which does not parse again.
+            for entry in node.entries_mut() {
+                if let KdlValue::String(s) = entry.value()
+                    && matches!(&**s, "true" | "false" | "null" | "inf" | "-inf" | "nan")
+                {
+                    entry.set_format(kdl::KdlEntryFormat {
+                        value_repr: alloc::format!("\"{s}\""),
+                        leading: " ".into(),
+                        autoformat_keep: true,
+                        ..Default::default()
+                    });
+                }
+            }
+            if let Some(children) = node.children_mut() {
+                if children.format().is_none() {
+                    children.set_format(kdl::KdlDocumentFormat::default());
+                }
+                prepare_format(children);
+            }
+        }
+    }
+
+    let mut kdl = KdlDocument::try_from(doc)?;
+    prepare_format(&mut kdl);
+    let indent = " ".repeat(settings.indent);
+    kdl.autoformat_config(&kdl::FormatConfig::builder().indent(&indent).build());
+    Ok(kdl.to_string())
 }
 
 #[inline]
@@ -123,13 +178,13 @@ impl<'a> From<&'a KdlNode> for Node<'a> {
         let mut node = Node::empty();
 
         // Handle conventionally nameless nodes.
-        let mut name = value.name().repr().unwrap_or("-");
+        let mut name = value.name().value();
         if name == "-" {
             name = "";
         }
 
         node.set_name(name);
-        node.set_ty(value.ty().and_then(|ty| ty.repr()).unwrap_or(""));
+        node.set_ty(value.ty().map_or("", |ty| ty.value()));
 
         for entry in value.entries() {
             node.push_arg(entry);
@@ -210,7 +265,7 @@ impl<'a> From<&'a KdlEntry> for Arg<'a> {
     #[inline]
     fn from(value: &'a KdlEntry) -> Self {
         Arg {
-            name: value.name().and_then(|name| name.repr()).map(Cow::Borrowed),
+            name: value.name().map(|name| Cow::Borrowed(name.value())),
             value: value.value().into(),
         }
     }
@@ -307,3 +362,39 @@ impl TryFrom<Value<'_>> for KdlValue {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indent() {
+        let doc = document_from_kdl("a 1 x=2 {\nb s {\n      c\n}\n}").unwrap();
+        assert_eq!(
+            document_to_kdl(&doc).unwrap(),
+            "a 1 x=2 {\n    b s {\n        c\n    }\n}\n"
+        );
+        assert_eq!(
+            document_to_kdl_with_settings(&doc, &KdlSettings { indent: 2 }).unwrap(),
+            "a 1 x=2 {\n  b s {\n    c\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn quoted_names() {
+        let builder = builder_from_kdl("(\"a b\")\">=\" \"x y\"=1\n- 2").unwrap();
+        let node = &builder.root().children[0];
+        assert_eq!(node.name(), ">=");
+        assert_eq!(node.ty(), "a b");
+        assert_eq!(node.args[0].name.as_deref(), Some("x y"));
+        assert_eq!(builder.root().children[1].name(), "");
+    }
+
+    #[test]
+    fn keyword_strings() {
+        let doc = document_from_kdl("a \"null\" x=\"true\" #false \"-inf\"").unwrap();
+        let kdl = document_to_kdl(&doc).unwrap();
+        assert_eq!(kdl, "a \"null\" x=\"true\" #false \"-inf\"\n");
+        assert!(document_from_kdl(&kdl).is_ok());
+    }
+}
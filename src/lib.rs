@@ -7,11 +7,15 @@
 extern crate alloc;
 
 pub(crate) mod access;
+#[cfg(feature = "bincode")]
+pub mod bincode;
 #[cfg(feature = "alloc")]
 pub mod builder;
 pub(crate) mod classify;
 mod compare;
 pub(crate) mod debug;
+#[cfg(feature = "builder")]
+pub mod diff;
 mod document;
 mod error;
 #[cfg(feature = "facet")]
@@ -20,10 +24,20 @@ pub mod facet;
 pub mod json;
 #[cfg(feature = "kdl")]
 pub mod kdl;
+#[cfg(feature = "alloc")]
+pub mod path;
+#[cfg(feature = "builder")]
+pub mod query;
 #[cfg(feature = "rkyv")]
 pub mod rkyv;
+#[cfg(feature = "builder")]
+pub mod schema;
 #[cfg(feature = "serde")]
 pub mod serde;
+pub mod text;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod view;
 #[cfg(feature = "xml")]
 pub mod xml;
 #[cfg(feature = "yaml")]
@@ -32,6 +46,8 @@ pub mod yaml;
 #[cfg(feature = "alloc")]
 pub use builder::Builder;
 pub use classify::ClassifyNode;
+#[cfg(feature = "builder")]
+pub use diff::diff;
 pub use document::*;
 pub use error::*;
 
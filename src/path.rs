@@ -0,0 +1,319 @@
+//! Paths addressing entries within a document.
+//!
+//! A path is a sequence of segments, each selecting an entry of the current
+//! node:
+//!
+//! - `name` selects the last argument or child with that name, preferring
+//!   arguments, so the entry that readers see is the one selected. Names that
+//!   are empty, contain `.`, `[`, `]`, or `"`, or start with `@` or `#` must be
+//!   quoted: `"a.b"`. Quoted names support the escapes `\"` and `\\`.
+//! - `@name` selects the last argument with that name, like an XML attribute.
+//! - `[n]` selects the entry at index `n`, counting arguments before children.
+//! - `#type` selects the type of the node, and can only be the last segment.
+//!
+//! Segments other than indices are separated by `.`, for example
+//! `servers[2].port`, `servers[0].@host`, or `servers[0].#type`. The empty path
+//! refers to the node itself.
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt::{self, Write as _};
+
+/// Parsed path to an entry in a document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Path<'p> {
+    segments: Vec<Segment<'p>>,
+}
+
+/// Segment of a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment<'p> {
+    /// Argument or child with the given name.
+    Name(Cow<'p, str>),
+    /// Argument with the given name.
+    Arg(Cow<'p, str>),
+    /// Entry at the given index.
+    Index(usize),
+    /// Type of the node.
+    Type,
+}
+
+/// Error when parsing or applying a [`Path`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PathError {
+    #[error("invalid path syntax, offset {0}")]
+    Syntax(usize),
+    #[error("path is empty")]
+    Empty,
+    #[error("path goes through an argument, which cannot have children")]
+    NotANode,
+    #[error("index out of bounds: {0}")]
+    IndexOutOfBounds(usize),
+    #[error("the value cannot be stored at this path")]
+    InvalidValue,
+}
+
+impl<'p> Path<'p> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    /// Parse a path.
+    ///
+    /// # Errors
+    ///
+    /// If the path is not valid path syntax, this returns an error.
+    pub fn parse(path: &'p str) -> Result<Self, PathError> {
+        let bytes = path.as_bytes();
+        let mut segments = Vec::new();
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            if segments.last() == Some(&Segment::Type) {
+                return Err(PathError::Syntax(pos));
+            }
+
+            if bytes[pos] == b'[' {
+                let len = bytes[pos + 1..]
+                    .iter()
+                    .position(|&b| b == b']')
+                    .ok_or(PathError::Syntax(path.len()))?;
+                let index = path[pos + 1..pos + 1 + len]
+                    .parse()
+                    .map_err(|_| PathError::Syntax(pos + 1))?;
+                segments.push(Segment::Index(index));
+                pos += len + 2;
+                continue;
+            }
+
+            // Names must be separated by a dot, which is optional at the
+            // start of the path.
+            if bytes[pos] == b'.' {
+                pos += 1;
+            } else if pos != 0 {
+                return Err(PathError::Syntax(pos));
+            }
+
+            let segment = match bytes.get(pos) {
+                Some(b'@') => {
+                    pos += 1;
+                    Segment::Arg(parse_name(path, &mut pos)?)
+                }
+                Some(b'#') if path[pos..].starts_with("#type") => {
+                    pos += "#type".len();
+                    Segment::Type
+                }
+                _ => Segment::Name(parse_name(path, &mut pos)?),
+            };
+            segments.push(segment);
+        }
+
+        Ok(Self { segments })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn segments(&self) -> &[Segment<'p>] {
+        &self.segments
+    }
+
+    #[inline]
+    pub fn push(&mut self, segment: Segment<'p>) -> &mut Self {
+        self.segments.push(segment);
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub(crate) fn push_front(&mut self, segment: Segment<'p>) -> &mut Self {
+        self.segments.insert(0, segment);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_static(self) -> Path<'static> {
+        Path {
+            segments: self
+                .segments
+                .into_iter()
+                .map(Segment::into_static)
+                .collect(),
+        }
+    }
+}
+
+impl Segment<'_> {
+    #[inline]
+    #[must_use]
+    pub fn into_static(self) -> Segment<'static> {
+        match self {
+            Segment::Name(name) => Segment::Name(Cow::Owned(name.into_owned())),
+            Segment::Arg(name) => Segment::Arg(Cow::Owned(name.into_owned())),
+            Segment::Index(index) => Segment::Index(index),
+            Segment::Type => Segment::Type,
+        }
+    }
+}
+
+impl<'p> TryFrom<&'p str> for Path<'p> {
+    type Error = PathError;
+
+    #[inline]
+    fn try_from(value: &'p str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl<'p> FromIterator<Segment<'p>> for Path<'p> {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = Segment<'p>>>(iter: T) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            if index != 0 && !matches!(segment, Segment::Index(_)) {
+                f.write_char('.')?;
+            }
+            segment.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Segment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Name(name) => write_name(f, name),
+            Segment::Arg(name) => {
+                f.write_char('@')?;
+                write_name(f, name)
+            }
+            Segment::Index(index) => write!(f, "[{index}]"),
+            Segment::Type => f.write_str("#type"),
+        }
+    }
+}
+
+fn is_special(c: char) -> bool {
+    matches!(c, '.' | '[' | ']' | '"')
+}
+
+fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let needs_quotes = name.is_empty() || name.starts_with(['@', '#']) || name.contains(is_special);
+    if !needs_quotes {
+        return f.write_str(name);
+    }
+    f.write_char('"')?;
+    for c in name.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+fn parse_name<'p>(path: &'p str, pos: &mut usize) -> Result<Cow<'p, str>, PathError> {
+    let rest = &path[*pos..];
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut owned: Option<String> = None;
+        let mut chars = quoted.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    *pos += index + 2;
+                    return Ok(match owned {
+                        Some(owned) => Cow::Owned(owned),
+                        None => Cow::Borrowed(&quoted[..index]),
+                    });
+                }
+                '\\' => {
+                    let owned = owned.get_or_insert_with(|| String::from(&quoted[..index]));
+                    match chars.next() {
+                        Some((_, c @ ('"' | '\\'))) => owned.push(c),
+                        _ => return Err(PathError::Syntax(*pos + 1 + index)),
+                    }
+                }
+                c => {
+                    if let Some(owned) = &mut owned {
+                        owned.push(c);
+                    }
+                }
+            }
+        }
+        return Err(PathError::Syntax(path.len()));
+    }
+
+    let len = rest.find(is_special).unwrap_or(rest.len());
+    if len == 0 {
+        return Err(PathError::Syntax(*pos));
+    }
+    *pos += len;
+    Ok(Cow::Borrowed(&rest[..len]))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString as _;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let path = Path::parse("servers[2].port").unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                Segment::Name("servers".into()),
+                Segment::Index(2),
+                Segment::Name("port".into()),
+            ]
+        );
+
+        let path = Path::parse(r#"[0].@host."a.\"b\"".#type"#).unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                Segment::Index(0),
+                Segment::Arg("host".into()),
+                Segment::Name(r#"a."b""#.into()),
+                Segment::Type,
+            ]
+        );
+
+        assert_eq!(Path::parse("").unwrap(), Path::new());
+        assert_eq!(
+            Path::parse(".a").unwrap().segments(),
+            [Segment::Name("a".into())]
+        );
+
+        assert_eq!(Path::parse("a..b"), Err(PathError::Syntax(2)));
+        assert_eq!(Path::parse("a[x]"), Err(PathError::Syntax(2)));
+        assert_eq!(Path::parse("a[1"), Err(PathError::Syntax(3)));
+        assert_eq!(Path::parse("a[1]b"), Err(PathError::Syntax(4)));
+        assert_eq!(Path::parse("#type.a"), Err(PathError::Syntax(5)));
+        assert_eq!(Path::parse(r#""a"#), Err(PathError::Syntax(2)));
+    }
+
+    #[test]
+    fn display() {
+        for path in [
+            "servers[2].port",
+            "[0][1].@host",
+            r#""a.\"b\""[0].#type"#,
+            r##""@a".@"#b""##,
+            r#""""#,
+        ] {
+            assert_eq!(Path::parse(path).unwrap().to_string(), path);
+        }
+    }
+}
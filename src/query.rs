@@ -0,0 +1,825 @@
+//! KDL Query Language (KQL) for selecting entries of a document.
+//!
+//! A query is one or more selectors separated by `||`. Each selector is a
+//! sequence of filters separated by combinators, and matches entries whose
+//! surroundings match the filters from right to left, like CSS selectors:
+//!
+//! ```text
+//! top() > server[port > 8000] || (Http)route[val() ^= "/api"]
+//! ```
+//!
+//! # Filters
+//!
+//! - `name` matches entries with that name. Names that are not bare
+//!   identifiers are written as quoted strings.
+//! - `(type)` matches nodes with that type, and `()` matches nodes with any
+//!   type.
+//! - `[]` matches any entry.
+//! - `[accessor]` matches entries where the accessor has a value, and
+//!   `[accessor op value]` matches entries where the value of the accessor
+//!   compares with `value`. A missing value never matches.
+//! - `top()` matches the node that the query is run on, and can only be the
+//!   first filter of a selector.
+//!
+//! Filters can be combined, like `(Http)server[port][tls = true]`.
+//!
+//! # Accessors
+//!
+//! - `val()` or `val(n)`: The first (or `n`-th) unnamed argument.
+//! - `prop(name)` or `name`: The value of the argument with that name, or the
+//!   first argument of the child with that name (see
+//!   [`Node::get()`](crate::Node::get)).
+//! - `name()`: The name of the entry.
+//! - `tag()`: The type of the node.
+//!
+//! # Operators
+//!
+//! `=` and `!=` compare any values, where numbers are compared by value
+//! regardless of their representation. `>`, `>=`, `<`, and `<=` compare
+//! numbers, and `^=`, `$=`, and `*=` check if a string starts with, ends with,
+//! or contains another string. Values are strings (`"a"`, or bare
+//! identifiers), numbers, `true`, `false`, and `null` (optionally prefixed with
+//! `#`, as in KDL v2).
+//!
+//! # Combinators
+//!
+//! - `a > b`: `b` is a child of `a`.
+//! - `a b` or `a >> b`: `b` is a descendant of `a`.
+//! - `a + b`: `b` immediately follows its sibling `a`.
+//! - `a ~ b`: `b` follows its sibling `a`.
+//!
+//! # Entries
+//!
+//! Named arguments are treated like key-value nodes (with the value as their
+//! only unnamed argument), so that queries work the same regardless of whether
+//! a document stores a value as an argument or as a child node, which depends
+//! on the format it was converted from. Unnamed arguments are values of their
+//! node, and are never matched by themselves.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::cmp::Ordering;
+
+use crate::{
+    ValueRef,
+    access::{ArgRef, EntryRef, NodeRef},
+    builder::{self, PathRef},
+};
+
+/// Select the entries of `node` (including itself) that match `query`, in
+/// document order.
+///
+/// # Errors
+///
+/// If `query` is not a valid query, this returns an error.
+#[inline]
+pub fn select<'a>(node: crate::Node<'a>, query: &str) -> Result<Select<'static, 'a>, QueryError> {
+    let query = Query::parse(query)?;
+    Ok(Select {
+        query: QueryRef::Owned(query),
+        walker: Walker::new(node),
+    })
+}
+
+/// Select the entries of a builder node (including itself) that match
+/// `query`, in document order.
+///
+/// # Errors
+///
+/// If `query` is not a valid query, this returns an error.
+#[inline]
+pub fn select_builder<'a>(
+    node: &'a builder::Node,
+    query: &str,
+) -> Result<SelectBuilder<'static, 'a>, QueryError> {
+    let query = Query::parse(query)?;
+    Ok(SelectBuilder {
+        query: QueryRef::Owned(query),
+        walker: Walker::new(node),
+    })
+}
+
+/// Parsed query.
+///
+/// See the [module documentation](self) for the syntax.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    selectors: Vec<Selector>,
+}
+
+/// Error when parsing a [`Query`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, offset {offset}")]
+pub struct QueryError {
+    /// Byte offset in the query where the error occurred.
+    pub offset: usize,
+    /// Kind of error that occurred.
+    pub error: QueryErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum QueryErrorKind {
+    #[error("unexpected end of query")]
+    UnexpectedEof,
+    #[error("unexpected character {0:?}")]
+    UnexpectedChar(char),
+    #[error("expected a filter")]
+    ExpectedFilter,
+    #[error("unknown function")]
+    UnknownFunction,
+    #[error("invalid number")]
+    InvalidNumber,
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+    #[error("top() must be the first filter of a selector")]
+    MisplacedTop,
+}
+
+impl Query {
+    /// Parse a query.
+    ///
+    /// # Errors
+    ///
+    /// If `query` is not a valid query, this returns an error.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            input: query,
+            pos: 0,
+        };
+        let mut selectors = vec![parser.parse_selector()?];
+        while parser.eat("||") {
+            selectors.push(parser.parse_selector()?);
+        }
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(QueryErrorKind::UnexpectedChar(c)));
+        }
+        Ok(Self { selectors })
+    }
+
+    /// Select the entries of `node` (including itself) that match the query,
+    /// in document order.
+    #[inline]
+    #[must_use]
+    pub fn select<'a>(&self, node: crate::Node<'a>) -> Select<'_, 'a> {
+        Select {
+            query: QueryRef::Borrowed(self),
+            walker: Walker::new(node),
+        }
+    }
+
+    /// Select the entries of a builder node (including itself) that match the
+    /// query, in document order.
+    #[inline]
+    #[must_use]
+    pub fn select_builder<'a>(&self, node: &'a builder::Node) -> SelectBuilder<'_, 'a> {
+        SelectBuilder {
+            query: QueryRef::Borrowed(self),
+            walker: Walker::new(node),
+        }
+    }
+}
+
+impl core::str::FromStr for Query {
+    type Err = QueryError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Iterator over the entries of a [`Node`](crate::Node) that match a
+/// [`Query`].
+pub struct Select<'q, 'a> {
+    query: QueryRef<'q>,
+    walker: Walker<crate::Arg<'a>, crate::Node<'a>>,
+}
+
+impl<'a> Iterator for Select<'_, 'a> {
+    type Item = crate::Entry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walker.next(&self.query).map(|entry| match entry {
+            EntryRef::Arg(arg) => crate::Entry::Arg(arg),
+            EntryRef::Child(node) => crate::Entry::Child(node),
+        })
+    }
+}
+
+/// Iterator over the entries of a [`builder::Node`] that match a [`Query`].
+pub struct SelectBuilder<'q, 'a> {
+    query: QueryRef<'q>,
+    walker: Walker<&'a builder::Arg<'a>, &'a builder::Node<'a>>,
+}
+
+impl<'a> Iterator for SelectBuilder<'_, 'a> {
+    type Item = PathRef<'a, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walker.next(&self.query).map(|entry| match entry {
+            EntryRef::Arg(arg) => PathRef::Arg(arg),
+            EntryRef::Child(node) => PathRef::Node(node),
+        })
+    }
+}
+
+enum QueryRef<'q> {
+    Borrowed(&'q Query),
+    Owned(Query),
+}
+
+impl core::ops::Deref for QueryRef<'_> {
+    type Target = Query;
+
+    #[inline]
+    fn deref(&self) -> &Query {
+        match self {
+            QueryRef::Borrowed(query) => query,
+            QueryRef::Owned(query) => query,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Selector {
+    /// Filters and the combinators that relate them to the previous filter.
+    /// The combinator of the first filter is ignored.
+    steps: Vec<(Combinator, Filter)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Combinator {
+    Child,
+    Descendant,
+    NextSibling,
+    Sibling,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Filter {
+    top: bool,
+    ty: Option<TypeFilter>,
+    name: Option<String>,
+    matchers: Vec<Matcher>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TypeFilter {
+    Any,
+    Exact(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Matcher {
+    accessor: Accessor,
+    comparison: Option<(Op, Literal)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Accessor {
+    Val(usize),
+    Prop(String),
+    Name,
+    Tag,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+}
+
+impl Selector {
+    /// Check if the entry at `index` of `frames[depth]` matches the selector
+    /// up to and including `step`.
+    fn matches_at<'a, A, N>(
+        &self,
+        step: usize,
+        frames: &[Frame<A, N>],
+        depth: usize,
+        index: usize,
+    ) -> bool
+    where
+        A: ArgRef<'a> + Copy,
+        N: NodeRef<'a> + Copy,
+    {
+        let (combinator, filter) = &self.steps[step];
+        let matches = if filter.top {
+            depth == 0
+        } else {
+            depth != 0 && filter.matches(&frames[depth].entries[index])
+        };
+        if !matches {
+            return false;
+        }
+        if step == 0 {
+            return true;
+        }
+
+        let step = step - 1;
+        match combinator {
+            Combinator::Child => {
+                depth != 0 && self.matches_at(step, frames, depth - 1, frames[depth - 1].index)
+            }
+            Combinator::Descendant => {
+                (0..depth).any(|depth| self.matches_at(step, frames, depth, frames[depth].index))
+            }
+            Combinator::NextSibling => {
+                index != 0 && self.matches_at(step, frames, depth, index - 1)
+            }
+            Combinator::Sibling => {
+                (0..index).any(|index| self.matches_at(step, frames, depth, index))
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn matches<'a, A, N>(&self, entry: &EntryRef<A, N>) -> bool
+    where
+        A: ArgRef<'a>,
+        N: NodeRef<'a>,
+    {
+        if let Some(name) = &self.name
+            && entry.name() != name
+        {
+            return false;
+        }
+        match &self.ty {
+            Some(TypeFilter::Any) if entry.ty().is_empty() => return false,
+            Some(TypeFilter::Exact(ty)) if entry.ty() != ty => return false,
+            _ => {}
+        }
+        self.matchers.iter().all(|matcher| matcher.matches(entry))
+    }
+}
+
+impl Matcher {
+    fn matches<'a, A, N>(&self, entry: &EntryRef<A, N>) -> bool
+    where
+        A: ArgRef<'a>,
+        N: NodeRef<'a>,
+    {
+        let Some(value) = self.accessor.get(entry) else {
+            return false;
+        };
+        let Some((op, literal)) = &self.comparison else {
+            return true;
+        };
+        match op {
+            Op::Eq => literal.equals(value),
+            Op::Ne => !literal.equals(value),
+            Op::Gt => literal.compare(value) == Some(Ordering::Greater),
+            Op::Ge => matches!(
+                literal.compare(value),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            Op::Lt => literal.compare(value) == Some(Ordering::Less),
+            Op::Le => matches!(
+                literal.compare(value),
+                Some(Ordering::Less | Ordering::Equal)
+            ),
+            Op::StartsWith | Op::EndsWith | Op::Contains => {
+                let (ValueRef::String(value), Literal::String(pattern)) = (value, literal) else {
+                    return false;
+                };
+                match op {
+                    Op::StartsWith => value.starts_with(pattern.as_str()),
+                    Op::EndsWith => value.ends_with(pattern.as_str()),
+                    _ => value.contains(pattern.as_str()),
+                }
+            }
+        }
+    }
+}
+
+impl Accessor {
+    fn get<'a, A, N>(&self, entry: &EntryRef<A, N>) -> Option<ValueRef<'a>>
+    where
+        A: ArgRef<'a>,
+        N: NodeRef<'a>,
+    {
+        match (self, entry) {
+            (Accessor::Name, _) => Some(entry.name())
+                .filter(|name| !name.is_empty())
+                .map(ValueRef::String),
+            (Accessor::Tag, _) => Some(entry.ty())
+                .filter(|ty| !ty.is_empty())
+                .map(ValueRef::String),
+            (Accessor::Val(0), EntryRef::Arg(arg)) => Some(arg.value()),
+            (Accessor::Val(index), EntryRef::Child(node)) => node
+                .args()
+                .filter(|arg| arg.name().is_empty())
+                .nth(*index)
+                .map(|arg| arg.value()),
+            (Accessor::Prop(name), EntryRef::Child(node)) => node
+                .args()
+                .filter(|arg| arg.name() == name)
+                .last()
+                .map(|arg| arg.value())
+                .or_else(|| {
+                    node.children()
+                        .filter(|child| child.name() == name)
+                        .last()
+                        .and_then(|child| child.args().next())
+                        .map(|arg| arg.value())
+                }),
+            _ => None,
+        }
+    }
+}
+
+impl Literal {
+    fn equals(&self, value: ValueRef) -> bool {
+        match (self, value) {
+            (Literal::Null, ValueRef::Null) => true,
+            (Literal::Bool(lhs), ValueRef::Bool(rhs)) => *lhs == rhs,
+            (Literal::String(lhs), ValueRef::String(rhs)) => lhs == rhs,
+            _ => self.compare(value) == Some(Ordering::Equal),
+        }
+    }
+
+    /// Compare `value` to this literal, if both are numbers.
+    #[expect(clippy::cast_precision_loss)]
+    fn compare(&self, value: ValueRef) -> Option<Ordering> {
+        let int = match value {
+            ValueRef::Int(value) => i128::from(value),
+            ValueRef::Uint(value) => i128::from(value),
+            ValueRef::Float(value) => {
+                return match *self {
+                    Literal::Int(literal) => value.partial_cmp(&(literal as f64)),
+                    Literal::Float(literal) => value.partial_cmp(&literal),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        match *self {
+            Literal::Int(literal) => Some(int.cmp(&literal)),
+            Literal::Float(literal) => (int as f64).partial_cmp(&literal),
+            _ => None,
+        }
+    }
+}
+
+/// Entries of a node that is being visited, and the position of the entry
+/// that is currently visited.
+struct Frame<A, N> {
+    entries: Vec<EntryRef<A, N>>,
+    index: usize,
+}
+
+/// Depth-first traversal of a node, keeping track of the ancestors and
+/// siblings of the current entry.
+struct Walker<A, N> {
+    frames: Vec<Frame<A, N>>,
+}
+
+impl<A: Copy, N: Copy> Walker<A, N> {
+    fn new(root: N) -> Self {
+        Self {
+            frames: vec![Frame {
+                entries: vec![EntryRef::Child(root)],
+                index: 0,
+            }],
+        }
+    }
+
+    fn next<'a>(&mut self, query: &Query) -> Option<EntryRef<A, N>>
+    where
+        A: ArgRef<'a>,
+        N: NodeRef<'a>,
+        N::ArgsIter<'a>: Iterator<Item = A>,
+        N::ChildrenIter<'a>: Iterator<Item = N>,
+    {
+        loop {
+            let depth = self.frames.len().checked_sub(1)?;
+            let frame = &self.frames[depth];
+            let index = frame.index;
+            let Some(entry) = frame.entries.get(index).copied() else {
+                self.frames.pop();
+                if let Some(parent) = self.frames.last_mut() {
+                    parent.index += 1;
+                }
+                continue;
+            };
+
+            let matches = query.selectors.iter().any(|selector| {
+                selector.matches_at(selector.steps.len() - 1, &self.frames, depth, index)
+            });
+
+            match entry {
+                EntryRef::Child(node) => {
+                    let entries = node
+                        .args()
+                        .filter(|arg| !arg.name().is_empty())
+                        .map(EntryRef::Arg)
+                        .chain(node.children().map(EntryRef::Child))
+                        .collect();
+                    self.frames.push(Frame { entries, index: 0 });
+                }
+                EntryRef::Arg(_) => self.frames[depth].index += 1,
+            }
+
+            if matches {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    !c.is_whitespace() && !"()[]{}<>=!^$*~+|\",;/\\#".contains(c)
+}
+
+struct Parser<'q> {
+    input: &'q str,
+    pos: usize,
+}
+
+impl<'q> Parser<'q> {
+    fn error(&self, error: QueryErrorKind) -> QueryError {
+        QueryError {
+            offset: self.pos,
+            error,
+        }
+    }
+
+    fn unexpected(&self) -> QueryError {
+        match self.peek() {
+            Some(c) => self.error(QueryErrorKind::UnexpectedChar(c)),
+            None => self.error(QueryErrorKind::UnexpectedEof),
+        }
+    }
+
+    fn rest(&self) -> &'q str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), QueryError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Skip whitespace, returning `true` if there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        self.pos != start
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, QueryError> {
+        self.skip_whitespace();
+        let mut steps = vec![(Combinator::Descendant, self.parse_filter()?)];
+        loop {
+            let whitespace = self.skip_whitespace();
+            if self.rest().is_empty() || self.rest().starts_with("||") {
+                break;
+            }
+            let combinator = if self.eat(">>") {
+                Combinator::Descendant
+            } else if self.eat(">") {
+                Combinator::Child
+            } else if self.eat("+") {
+                Combinator::NextSibling
+            } else if self.eat("~") {
+                Combinator::Sibling
+            } else if whitespace {
+                Combinator::Descendant
+            } else {
+                return Err(self.unexpected());
+            };
+            self.skip_whitespace();
+            let start = self.pos;
+            let filter = self.parse_filter()?;
+            if filter.top {
+                return Err(QueryError {
+                    offset: start,
+                    error: QueryErrorKind::MisplacedTop,
+                });
+            }
+            steps.push((combinator, filter));
+        }
+        Ok(Selector { steps })
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter, QueryError> {
+        if self.eat("top()") {
+            return Ok(Filter {
+                top: true,
+                ..Filter::default()
+            });
+        }
+
+        let start = self.pos;
+        let mut filter = Filter::default();
+        if self.eat("(") {
+            self.skip_whitespace();
+            filter.ty = Some(if self.eat(")") {
+                TypeFilter::Any
+            } else {
+                let ty = self.parse_name()?;
+                self.skip_whitespace();
+                self.expect(")")?;
+                TypeFilter::Exact(ty)
+            });
+        }
+        if self.peek().is_some_and(|c| c == '"' || is_ident_char(c)) {
+            filter.name = Some(self.parse_name()?);
+        }
+        while self.eat("[") {
+            self.skip_whitespace();
+            if !self.eat("]") {
+                filter.matchers.push(self.parse_matcher()?);
+                self.skip_whitespace();
+                self.expect("]")?;
+            }
+        }
+
+        if self.pos == start {
+            return Err(match self.peek() {
+                Some(_) => self.error(QueryErrorKind::ExpectedFilter),
+                None => self.error(QueryErrorKind::UnexpectedEof),
+            });
+        }
+        Ok(filter)
+    }
+
+    fn parse_matcher(&mut self) -> Result<Matcher, QueryError> {
+        let start = self.pos;
+        let quoted = self.peek() == Some('"');
+        let name = self.parse_name()?;
+        let accessor = if !quoted && self.eat("(") {
+            self.skip_whitespace();
+            let accessor = match name.as_str() {
+                "val" if self.peek() == Some(')') => Accessor::Val(0),
+                "val" => {
+                    let digits = self.rest().len()
+                        - self
+                            .rest()
+                            .trim_start_matches(|c: char| c.is_ascii_digit())
+                            .len();
+                    let index = self.rest()[..digits]
+                        .parse()
+                        .map_err(|_| self.error(QueryErrorKind::InvalidNumber))?;
+                    self.pos += digits;
+                    Accessor::Val(index)
+                }
+                "prop" => Accessor::Prop(self.parse_name()?),
+                "name" => Accessor::Name,
+                "tag" => Accessor::Tag,
+                _ => {
+                    return Err(QueryError {
+                        offset: start,
+                        error: QueryErrorKind::UnknownFunction,
+                    });
+                }
+            };
+            self.skip_whitespace();
+            self.expect(")")?;
+            accessor
+        } else {
+            Accessor::Prop(name)
+        };
+
+        self.skip_whitespace();
+        let ops = [
+            ("!=", Op::Ne),
+            (">=", Op::Ge),
+            ("<=", Op::Le),
+            ("^=", Op::StartsWith),
+            ("$=", Op::EndsWith),
+            ("*=", Op::Contains),
+            ("=", Op::Eq),
+            (">", Op::Gt),
+            ("<", Op::Lt),
+        ];
+        let comparison = match ops.into_iter().find(|(s, _)| self.eat(s)) {
+            Some((_, op)) => {
+                self.skip_whitespace();
+                Some((op, self.parse_literal()?))
+            }
+            None => None,
+        };
+        Ok(Matcher {
+            accessor,
+            comparison,
+        })
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, QueryError> {
+        let rest = self.rest();
+        let keyword = rest.strip_prefix('#').unwrap_or(rest);
+        let keyword_len = keyword.len() - keyword.trim_start_matches(is_ident_char).len();
+        let skip = rest.len() - keyword.len() + keyword_len;
+        let literal = match &keyword[..keyword_len] {
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            "null" => Literal::Null,
+            _ if rest.starts_with('#') => return Err(self.unexpected()),
+            _ => {
+                let mut chars = rest.chars();
+                let first = chars.next();
+                let number = first.is_some_and(|c| c.is_ascii_digit())
+                    || (matches!(first, Some('-' | '+'))
+                        && chars.next().is_some_and(|c| c.is_ascii_digit()));
+                if number {
+                    return self.parse_number();
+                }
+                return self.parse_name().map(Literal::String);
+            }
+        };
+        self.pos += skip;
+        Ok(literal)
+    }
+
+    fn parse_number(&mut self) -> Result<Literal, QueryError> {
+        let rest = self.rest();
+        let len = rest.len()
+            - rest
+                .trim_start_matches(|c: char| {
+                    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-')
+                })
+                .len();
+        let text = rest[..len].replace('_', "");
+        let literal = if let Ok(int) = text.parse() {
+            Literal::Int(int)
+        } else if text.contains(['.', 'e', 'E'])
+            && let Ok(float) = text.parse()
+        {
+            Literal::Float(float)
+        } else {
+            return Err(self.error(QueryErrorKind::InvalidNumber));
+        };
+        self.pos += len;
+        Ok(literal)
+    }
+
+    /// Parse a bare identifier or a quoted string.
+    fn parse_name(&mut self) -> Result<String, QueryError> {
+        if !self.eat("\"") {
+            let rest = self.rest();
+            let len = rest.len() - rest.trim_start_matches(is_ident_char).len();
+            if len == 0 {
+                return Err(self.unexpected());
+            }
+            self.pos += len;
+            return Ok(String::from(&rest[..len]));
+        }
+
+        let mut out = String::new();
+        loop {
+            let c = self.peek().ok_or_else(|| self.unexpected())?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self.peek().ok_or_else(|| self.unexpected())?;
+                    out.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        _ => return Err(self.error(QueryErrorKind::InvalidEscape)),
+                    });
+                    self.pos += 1;
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
@@ -0,0 +1,921 @@
+//! Validation of documents against a schema in the [KDL Schema
+//! Language](https://github.com/kdl-org/kdl/blob/main/SCHEMA-SPEC.md).
+//!
+//! A schema is itself a document, usually written in KDL:
+//!
+//! ```kdl
+//! document {
+//!     node "server" {
+//!         min 1
+//!         tag { enum "Http" "Grpc" }
+//!         prop "port" { required #true; type "u16" }
+//!         children {
+//!             node "tls" { max 1; value { min 1; max 1; type "boolean" } }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Values are the unnamed arguments of a node, properties are its named
+//! arguments, and the tag is its type. [`Schema::validate()`] checks a node
+//! and all its descendants, and reports all errors at once instead of stopping
+//! at the first one.
+//!
+//! # Supported schema nodes
+//!
+//! - `document`: Rules for the root node. Contains `node`, `tag`, `prop`,
+//!   `value`, `other-nodes-allowed`, and `other-props-allowed`, like a `node`
+//!   that contains its `children` directly. `info` and `definitions` are
+//!   ignored.
+//! - `node "name"`: Rules for children with that name, or any child without a
+//!   name rule if the name is omitted. Contains `min` and `max` (the number of
+//!   such children), `tag`, `prop`, `value`, `children`, and
+//!   `other-props-allowed`.
+//! - `children`: Rules for the children of a node. Contains `node` and
+//!   `other-nodes-allowed`. A node without `children` cannot have children.
+//! - `prop "name"`: Rules for a property, or any property without a rule if
+//!   the name is omitted. Contains `required` and validations.
+//! - `value`: Rules for the values of a node. Contains `min` and `max` (the
+//!   number of values) and validations. A node without `value` can have any
+//!   values.
+//! - `tag`: Validations for the tag of a node, which is `null` when the node
+//!   has no tag.
+//!
+//! `other-nodes-allowed` and `other-props-allowed` default to `#false`.
+//!
+//! Validations are `type`, `enum`, `min-length`, `max-length`, `%`, `>`,
+//! `>=`, `<`, and `<=`. Types are `string`, `boolean`, `number`, `null`,
+//! `binary`, and the integer types `i8` through `i64` and `u8` through `u64`,
+//! which also check the range of the value.
+//!
+//! Any schema node can have a `ref` property instead of its own rules, which is
+//! a [query](crate::query) selecting the schema node to use instead, like
+//! `ref=#"[id="dir"]"#`. References may be recursive.
+//!
+//! Schema nodes that are not supported (`pattern`, `format`, and the
+//! `*-names` and `*-tags` rules) are rejected when loading the schema.
+//!
+//! Schemas can also be inferred from example documents with [`infer()`].
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString as _},
+    vec::Vec,
+};
+use core::cmp::Ordering;
+
+use hashbrown::HashMap;
+
+use crate::{
+    ValueKind, ValueRef,
+    access::{ArgRef, NodeRef},
+    builder::{self, PathRef},
+    path::{Path, Segment},
+};
+
+mod infer;
+
+pub use infer::*;
+
+/// Schema that documents can be validated against.
+///
+/// See the [module documentation](self) for the supported rules.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    root: NodeRule,
+    /// Rules of `node` elements, referenced by index.
+    nodes: Vec<NodeRule>,
+}
+
+/// Error when loading a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, path {path}")]
+pub struct LoadError {
+    /// Path of the invalid element in the schema document.
+    pub path: Path<'static>,
+    /// Kind of error that occurred.
+    pub error: LoadErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum LoadErrorKind {
+    #[error("schema has no document node")]
+    MissingDocument,
+    #[error("unknown schema node")]
+    UnknownNode,
+    #[error("unsupported schema node")]
+    Unsupported,
+    #[error("invalid value")]
+    InvalidValue,
+    #[error("unknown value type")]
+    UnknownType,
+    #[error("reference does not select a schema node")]
+    InvalidRef,
+}
+
+/// Error when a document does not match a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, path {path}")]
+pub struct SchemaError {
+    /// Path of the invalid entry in the document.
+    pub path: Path<'static>,
+    /// Kind of error that occurred.
+    pub error: SchemaErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SchemaErrorKind {
+    #[error("expected at least {min} {name:?} nodes, found {found}")]
+    TooFewNodes {
+        name: String,
+        min: usize,
+        found: usize,
+    },
+    #[error("expected at most {max} {name:?} nodes, found {found}")]
+    TooManyNodes {
+        name: String,
+        max: usize,
+        found: usize,
+    },
+    #[error("unexpected node")]
+    UnexpectedNode,
+    #[error("missing required property {0:?}")]
+    MissingProp(String),
+    #[error("unexpected property")]
+    UnexpectedProp,
+    #[error("expected at least {min} values, found {found}")]
+    TooFewValues { min: usize, found: usize },
+    #[error("expected at most {max} values, found {found}")]
+    TooManyValues { max: usize, found: usize },
+    #[error("expected {expected}, found {found}")]
+    InvalidType { expected: String, found: ValueKind },
+    #[error("value is not one of the allowed values")]
+    NotInEnum,
+    #[error("expected a length of at least {min}, found {len}")]
+    TooShort { min: usize, len: usize },
+    #[error("expected a length of at most {max}, found {len}")]
+    TooLong { max: usize, len: usize },
+    #[error("value is out of range")]
+    OutOfRange,
+    #[error("value is not a multiple of the required divisor")]
+    NotMultiple,
+}
+
+#[derive(Clone, Debug, Default)]
+struct NodeRule {
+    name: Option<String>,
+    min: usize,
+    max: Option<usize>,
+    tag: Validations,
+    values: Option<ValuesRule>,
+    props: Vec<PropRule>,
+    other_props: bool,
+    children: ChildrenRule,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ChildrenRule {
+    /// Indices of the node rules.
+    nodes: Vec<usize>,
+    other_nodes: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct PropRule {
+    name: Option<String>,
+    required: bool,
+    validations: Validations,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ValuesRule {
+    min: usize,
+    max: Option<usize>,
+    validations: Validations,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Validations {
+    types: Vec<Type>,
+    /// Names of the types, for error messages.
+    type_names: String,
+    enumeration: Vec<builder::Value<'static>>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    bounds: Vec<(Ordering, bool, Number)>,
+    multiple_of: Option<Number>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Type {
+    String,
+    Bool,
+    Number,
+    Integer { min: i128, max: i128 },
+    Null,
+    Binary,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Schema {
+    /// Load a schema from a document in the KDL Schema Language, where the
+    /// `document` node is a child of `root`.
+    ///
+    /// References are resolved by querying `root`.
+    ///
+    /// # Errors
+    ///
+    /// If the schema is invalid or uses unsupported rules, this returns an
+    /// error.
+    pub fn from_node(root: &builder::Node) -> Result<Self, LoadError> {
+        let mut loader = Loader {
+            root,
+            nodes: Vec::new(),
+            loaded: Vec::new(),
+            path: Vec::new(),
+        };
+        let Some(index) = root
+            .children
+            .iter()
+            .position(|child| child.name() == "document")
+        else {
+            return Err(loader.error(LoadErrorKind::MissingDocument));
+        };
+        loader.path.push(child_segment(root, index));
+        let document = loader.resolve(&root.children[index])?;
+        let root = loader.load_body(document, true)?;
+        Ok(Self {
+            root,
+            nodes: loader.nodes,
+        })
+    }
+
+    /// Load a schema from KDL Schema Language source.
+    ///
+    /// # Errors
+    ///
+    /// If `kdl` is not valid KDL syntax, or the schema is invalid, this returns
+    /// an error.
+    #[cfg(feature = "kdl")]
+    pub fn from_kdl(kdl: &str) -> crate::Result<Self> {
+        let builder = crate::kdl::builder_from_kdl(kdl)?;
+        Self::from_node(builder.root()).map_err(crate::Error::custom)
+    }
+
+    /// Validate a node and all its descendants against the schema.
+    ///
+    /// # Errors
+    ///
+    /// If the node does not match the schema, this returns all errors, in
+    /// document order.
+    pub fn validate(&self, node: crate::Node) -> Result<(), Vec<SchemaError>> {
+        Validator::new(self).run(&self.root, node)
+    }
+
+    /// Validate a builder node and all its descendants against the schema.
+    ///
+    /// # Errors
+    ///
+    /// If the node does not match the schema, this returns all errors, in
+    /// document order.
+    pub fn validate_builder(&self, node: &builder::Node) -> Result<(), Vec<SchemaError>> {
+        Validator::new(self).run(&self.root, node)
+    }
+}
+
+struct Loader<'s> {
+    root: &'s builder::Node<'s>,
+    nodes: Vec<NodeRule>,
+    /// Node rules that have been loaded, so references to them can be
+    /// recursive.
+    loaded: Vec<(&'s builder::Node<'s>, usize)>,
+    path: Vec<Segment<'static>>,
+}
+
+impl<'s> Loader<'s> {
+    fn error(&self, error: LoadErrorKind) -> LoadError {
+        LoadError {
+            path: self.path.iter().cloned().collect(),
+            error,
+        }
+    }
+
+    /// Follow the `ref` properties of `node`, if any.
+    fn resolve(&self, mut node: &'s builder::Node<'s>) -> Result<&'s builder::Node<'s>, LoadError> {
+        let mut visited = Vec::new();
+        while let Some(query) = named_arg(node, "ref") {
+            let Some(query) = ValueRef::from(query).as_str() else {
+                return Err(self.error(LoadErrorKind::InvalidValue));
+            };
+            let target = crate::query::select_builder(self.root, query)
+                .ok()
+                .and_then(|mut matches| {
+                    matches.find_map(|entry| match entry {
+                        PathRef::Node(node) => Some(node),
+                        _ => None,
+                    })
+                });
+            match target {
+                Some(target) if !visited.iter().any(|n| core::ptr::eq(*n, target)) => {
+                    visited.push(target);
+                    node = target;
+                }
+                _ => return Err(self.error(LoadErrorKind::InvalidRef)),
+            }
+        }
+        Ok(node)
+    }
+
+    /// Call `f` for each child of `node`, with the path pointing to the child.
+    fn each_child(
+        &mut self,
+        node: &'s builder::Node<'s>,
+        mut f: impl FnMut(&mut Self, &'s builder::Node<'s>) -> Result<(), LoadError>,
+    ) -> Result<(), LoadError> {
+        for (index, child) in node.children.iter().enumerate() {
+            self.path.push(child_segment(node, index));
+            f(self, child)?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    fn load_node(&mut self, node: &'s builder::Node<'s>) -> Result<usize, LoadError> {
+        let node = self.resolve(node)?;
+        if let Some(&(_, index)) = self.loaded.iter().find(|(n, _)| core::ptr::eq(*n, node)) {
+            return Ok(index);
+        }
+        let index = self.nodes.len();
+        self.nodes.push(NodeRule::default());
+        self.loaded.push((node, index));
+        let mut rule = self.load_body(node, false)?;
+        rule.name = self.name_arg(node)?;
+        self.nodes[index] = rule;
+        Ok(index)
+    }
+
+    fn load_body(
+        &mut self,
+        node: &'s builder::Node<'s>,
+        document: bool,
+    ) -> Result<NodeRule, LoadError> {
+        let mut rule = NodeRule::default();
+        self.each_child(node, |this, child| {
+            match child.name() {
+                "min" if !document => rule.min = this.usize_arg(child)?,
+                "max" if !document => rule.max = Some(this.usize_arg(child)?),
+                "tag" => rule.tag = this.load_validations(child)?,
+                "children" if !document => {
+                    let child = this.resolve(child)?;
+                    this.load_children(child, &mut rule.children)?;
+                }
+                "node" if document => {
+                    let index = this.load_node(child)?;
+                    rule.children.nodes.push(index);
+                }
+                "other-nodes-allowed" if document => {
+                    rule.children.other_nodes = this.bool_arg(child)?;
+                }
+                "info" | "definitions" if document => {}
+                "value" => rule.values = Some(this.load_values(child)?),
+                "prop" => rule.props.push(this.load_prop(child)?),
+                "other-props-allowed" => rule.other_props = this.bool_arg(child)?,
+                "node-names" | "prop-names" | "tag-names" | "other-tags-allowed" => {
+                    return Err(this.error(LoadErrorKind::Unsupported));
+                }
+                _ => return Err(this.error(LoadErrorKind::UnknownNode)),
+            }
+            Ok(())
+        })?;
+        Ok(rule)
+    }
+
+    fn load_children(
+        &mut self,
+        node: &'s builder::Node<'s>,
+        rule: &mut ChildrenRule,
+    ) -> Result<(), LoadError> {
+        self.each_child(node, |this, child| {
+            match child.name() {
+                "node" => {
+                    let index = this.load_node(child)?;
+                    rule.nodes.push(index);
+                }
+                "other-nodes-allowed" => rule.other_nodes = this.bool_arg(child)?,
+                "node-names" | "tag" | "tag-names" | "other-tags-allowed" => {
+                    return Err(this.error(LoadErrorKind::Unsupported));
+                }
+                _ => return Err(this.error(LoadErrorKind::UnknownNode)),
+            }
+            Ok(())
+        })
+    }
+
+    fn load_prop(&mut self, node: &'s builder::Node<'s>) -> Result<PropRule, LoadError> {
+        let node = self.resolve(node)?;
+        let mut rule = PropRule {
+            name: self.name_arg(node)?,
+            ..PropRule::default()
+        };
+        self.each_child(node, |this, child| {
+            if child.name() == "required" {
+                rule.required = this.bool_arg(child)?;
+                Ok(())
+            } else {
+                this.load_validation(child, &mut rule.validations)
+            }
+        })?;
+        Ok(rule)
+    }
+
+    fn load_values(&mut self, node: &'s builder::Node<'s>) -> Result<ValuesRule, LoadError> {
+        let node = self.resolve(node)?;
+        let mut rule = ValuesRule::default();
+        self.each_child(node, |this, child| {
+            match child.name() {
+                "min" => rule.min = this.usize_arg(child)?,
+                "max" => rule.max = Some(this.usize_arg(child)?),
+                _ => this.load_validation(child, &mut rule.validations)?,
+            }
+            Ok(())
+        })?;
+        Ok(rule)
+    }
+
+    fn load_validations(&mut self, node: &'s builder::Node<'s>) -> Result<Validations, LoadError> {
+        let node = self.resolve(node)?;
+        let mut validations = Validations::default();
+        self.each_child(node, |this, child| {
+            this.load_validation(child, &mut validations)
+        })?;
+        Ok(validations)
+    }
+
+    fn load_validation(
+        &mut self,
+        node: &'s builder::Node<'s>,
+        validations: &mut Validations,
+    ) -> Result<(), LoadError> {
+        let bound = match node.name() {
+            "type" => {
+                for value in values(node) {
+                    let name = ValueRef::from(value)
+                        .as_str()
+                        .ok_or_else(|| self.error(LoadErrorKind::InvalidValue))?;
+                    let ty =
+                        parse_type(name).ok_or_else(|| self.error(LoadErrorKind::UnknownType))?;
+                    if !validations.type_names.is_empty() {
+                        validations.type_names.push_str(" or ");
+                    }
+                    validations.type_names.push_str(name);
+                    validations.types.push(ty);
+                }
+                return Ok(());
+            }
+            "enum" => {
+                validations
+                    .enumeration
+                    .extend(values(node).map(|value| value.clone().into_static()));
+                return Ok(());
+            }
+            "min-length" => {
+                validations.min_length = Some(self.usize_arg(node)?);
+                return Ok(());
+            }
+            "max-length" => {
+                validations.max_length = Some(self.usize_arg(node)?);
+                return Ok(());
+            }
+            "%" => {
+                validations.multiple_of = Some(self.number_arg(node)?);
+                return Ok(());
+            }
+            ">" => (Ordering::Greater, false),
+            ">=" => (Ordering::Greater, true),
+            "<" => (Ordering::Less, false),
+            "<=" => (Ordering::Less, true),
+            "pattern" | "format" | "tag" => return Err(self.error(LoadErrorKind::Unsupported)),
+            _ => return Err(self.error(LoadErrorKind::UnknownNode)),
+        };
+        let number = self.number_arg(node)?;
+        validations.bounds.push((bound.0, bound.1, number));
+        Ok(())
+    }
+
+    /// Optional name of a `node` or `prop` element.
+    fn name_arg(&self, node: &builder::Node) -> Result<Option<String>, LoadError> {
+        match values(node).next() {
+            Some(value) => match ValueRef::from(value).as_str() {
+                Some(name) => Ok(Some(name.into())),
+                None => Err(self.error(LoadErrorKind::InvalidValue)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn usize_arg(&self, node: &builder::Node) -> Result<usize, LoadError> {
+        values(node)
+            .next()
+            .and_then(|value| ValueRef::from(value).try_into().ok())
+            .ok_or_else(|| self.error(LoadErrorKind::InvalidValue))
+    }
+
+    fn bool_arg(&self, node: &builder::Node) -> Result<bool, LoadError> {
+        match values(node).next().map(ValueRef::from) {
+            Some(ValueRef::Bool(value)) => Ok(value),
+            _ => Err(self.error(LoadErrorKind::InvalidValue)),
+        }
+    }
+
+    fn number_arg(&self, node: &builder::Node) -> Result<Number, LoadError> {
+        values(node)
+            .next()
+            .and_then(|value| Number::from_value(value.into()))
+            .ok_or_else(|| self.error(LoadErrorKind::InvalidValue))
+    }
+}
+
+/// Values (unnamed arguments) of a schema node.
+fn values<'b, 'a>(node: &'b builder::Node<'a>) -> impl Iterator<Item = &'b builder::Value<'a>> {
+    node.args
+        .iter()
+        .filter(|arg| arg.name.is_none())
+        .map(|arg| &arg.value)
+}
+
+fn named_arg<'b, 'a>(node: &'b builder::Node<'a>, name: &str) -> Option<&'b builder::Value<'a>> {
+    node.args
+        .iter()
+        .find(|arg| arg.name.as_deref() == Some(name))
+        .map(|arg| &arg.value)
+}
+
+fn parse_type(name: &str) -> Option<Type> {
+    let integer = |min: i128, max: i128| Some(Type::Integer { min, max });
+    match name {
+        "string" => Some(Type::String),
+        "boolean" | "bool" => Some(Type::Bool),
+        "number" | "float" | "f32" | "f64" => Some(Type::Number),
+        "null" => Some(Type::Null),
+        "binary" => Some(Type::Binary),
+        "i8" => integer(i8::MIN.into(), i8::MAX.into()),
+        "i16" => integer(i16::MIN.into(), i16::MAX.into()),
+        "i32" => integer(i32::MIN.into(), i32::MAX.into()),
+        "i64" | "int" => integer(i64::MIN.into(), i64::MAX.into()),
+        "u8" => integer(0, u8::MAX.into()),
+        "u16" => integer(0, u16::MAX.into()),
+        "u32" => integer(0, u32::MAX.into()),
+        "u64" | "uint" => integer(0, u64::MAX.into()),
+        _ => None,
+    }
+}
+
+impl Type {
+    fn matches(self, value: ValueRef) -> bool {
+        match (self, value) {
+            (Type::String, ValueRef::String(_))
+            | (Type::Bool, ValueRef::Bool(_))
+            | (Type::Null, ValueRef::Null)
+            | (Type::Binary, ValueRef::Binary(_))
+            | (Type::Number, ValueRef::Int(_) | ValueRef::Uint(_) | ValueRef::Float(_)) => true,
+            (Type::Integer { min, max }, value) => match Number::from_value(value) {
+                Some(Number::Int(value)) => (min..=max).contains(&value),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Number {
+    fn from_value(value: ValueRef) -> Option<Self> {
+        match value {
+            ValueRef::Int(value) => Some(Number::Int(value.into())),
+            ValueRef::Uint(value) => Some(Number::Int(value.into())),
+            ValueRef::Float(value) => Some(Number::Float(value)),
+            _ => None,
+        }
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(value) => value as f64,
+            Number::Float(value) => value,
+        }
+    }
+
+    fn partial_cmp(self, other: Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(lhs), Number::Int(rhs)) => Some(lhs.cmp(&rhs)),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    fn is_multiple_of(self, divisor: Self) -> bool {
+        match (self, divisor) {
+            (Number::Int(_), Number::Int(0)) => false,
+            (Number::Int(lhs), Number::Int(rhs)) => lhs % rhs == 0,
+            _ => self.to_f64() % divisor.to_f64() == 0.0,
+        }
+    }
+}
+
+impl Validations {
+    fn is_empty(&self) -> bool {
+        self.types.is_empty()
+            && self.enumeration.is_empty()
+            && self.min_length.is_none()
+            && self.max_length.is_none()
+            && self.bounds.is_empty()
+            && self.multiple_of.is_none()
+    }
+
+    fn check(&self, value: ValueRef, mut error: impl FnMut(SchemaErrorKind)) {
+        if !self.types.is_empty() && !self.types.iter().any(|ty| ty.matches(value)) {
+            error(SchemaErrorKind::InvalidType {
+                expected: self.type_names.clone(),
+                found: value.kind(),
+            });
+        }
+
+        if !self.enumeration.is_empty()
+            && !self
+                .enumeration
+                .iter()
+                .any(|allowed| same_value(allowed.into(), value))
+        {
+            error(SchemaErrorKind::NotInEnum);
+        }
+
+        let len = match value {
+            ValueRef::String(value) => Some(value.chars().count()),
+            ValueRef::Binary(value) => Some(value.len()),
+            _ => None,
+        };
+        if let Some(len) = len {
+            if let Some(min) = self.min_length
+                && len < min
+            {
+                error(SchemaErrorKind::TooShort { min, len });
+            }
+            if let Some(max) = self.max_length
+                && len > max
+            {
+                error(SchemaErrorKind::TooLong { max, len });
+            }
+        }
+
+        if let Some(number) = Number::from_value(value) {
+            let in_range = self.bounds.iter().all(|&(ordering, inclusive, bound)| {
+                number
+                    .partial_cmp(bound)
+                    .is_some_and(|cmp| cmp == ordering || (inclusive && cmp == Ordering::Equal))
+            });
+            if !in_range {
+                error(SchemaErrorKind::OutOfRange);
+            }
+            if let Some(divisor) = self.multiple_of
+                && !number.is_multiple_of(divisor)
+            {
+                error(SchemaErrorKind::NotMultiple);
+            }
+        }
+    }
+}
+
+/// Compare values, where numbers are equal if they have the same value.
+fn same_value(lhs: ValueRef, rhs: ValueRef) -> bool {
+    match (Number::from_value(lhs), Number::from_value(rhs)) {
+        (Some(lhs), Some(rhs)) => lhs.partial_cmp(rhs) == Some(Ordering::Equal),
+        _ => lhs == rhs,
+    }
+}
+
+/// Segment selecting the child at `index` of a schema node, by name if
+/// possible.
+///
+/// A name selects the last child with that name, and is shadowed by arguments
+/// with the same name.
+fn child_segment(node: &builder::Node, index: usize) -> Segment<'static> {
+    let name = node.children[index].name();
+    let shadowed = node
+        .args
+        .iter()
+        .any(|arg| arg.name.as_deref() == Some(name))
+        || node.children[index + 1..]
+            .iter()
+            .any(|child| child.name() == name);
+    if name.is_empty() || shadowed {
+        Segment::Index(node.args.len() + index)
+    } else {
+        Segment::Name(Cow::Owned(name.into()))
+    }
+}
+
+struct Validator<'s> {
+    schema: &'s Schema,
+    path: Vec<Segment<'static>>,
+    errors: Vec<SchemaError>,
+}
+
+impl<'s> Validator<'s> {
+    fn new(schema: &'s Schema) -> Self {
+        Self {
+            schema,
+            path: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn run<'a, N, A>(mut self, rule: &NodeRule, node: N) -> Result<(), Vec<SchemaError>>
+    where
+        N: NodeRef<'a> + Copy,
+        N::ArgsIter<'a>: Iterator<Item = A>,
+        N::ChildrenIter<'a>: Iterator<Item = N>,
+        A: ArgRef<'a>,
+    {
+        self.validate_node(rule, node);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn error(&mut self, error: SchemaErrorKind) {
+        self.errors.push(SchemaError {
+            path: self.path.iter().cloned().collect(),
+            error,
+        });
+    }
+
+    /// Check `value` at the current path, with `segment` appended.
+    fn check(&mut self, validations: &Validations, segment: Segment<'static>, value: ValueRef) {
+        self.path.push(segment);
+        let mut errors = Vec::new();
+        validations.check(value, |error| errors.push(error));
+        for error in errors {
+            self.error(error);
+        }
+        self.path.pop();
+    }
+
+    fn validate_node<'a, N, A>(&mut self, rule: &NodeRule, node: N)
+    where
+        N: NodeRef<'a> + Copy,
+        N::ArgsIter<'a>: Iterator<Item = A>,
+        N::ChildrenIter<'a>: Iterator<Item = N>,
+        A: ArgRef<'a>,
+    {
+        if !rule.tag.is_empty() {
+            let ty = node.ty();
+            let value = if ty.is_empty() {
+                ValueRef::Null
+            } else {
+                ValueRef::String(ty)
+            };
+            self.check(&rule.tag, Segment::Type, value);
+        }
+
+        // Names select the last argument with that name.
+        let mut last = HashMap::new();
+        for (index, arg) in node.args().enumerate() {
+            let name = arg.name();
+            if !name.is_empty() {
+                last.insert(name, index);
+            }
+        }
+
+        let mut values = 0;
+        for (index, arg) in node.args().enumerate() {
+            let name = arg.name();
+            if name.is_empty() {
+                values += 1;
+                if let Some(rule) = &rule.values {
+                    self.check(&rule.validations, Segment::Index(index), arg.value());
+                }
+                continue;
+            }
+
+            let segment = if last[name] == index {
+                Segment::Arg(Cow::Owned(name.into()))
+            } else {
+                Segment::Index(index)
+            };
+            let prop = rule
+                .props
+                .iter()
+                .find(|prop| prop.name.as_deref() == Some(name))
+                .or_else(|| rule.props.iter().find(|prop| prop.name.is_none()));
+            match prop {
+                Some(prop) => self.check(&prop.validations, segment, arg.value()),
+                None if !rule.other_props => {
+                    self.path.push(segment);
+                    self.error(SchemaErrorKind::UnexpectedProp);
+                    self.path.pop();
+                }
+                None => {}
+            }
+        }
+
+        if let Some(rule) = &rule.values {
+            if values < rule.min {
+                self.error(SchemaErrorKind::TooFewValues {
+                    min: rule.min,
+                    found: values,
+                });
+            }
+            if let Some(max) = rule.max
+                && values > max
+            {
+                self.error(SchemaErrorKind::TooManyValues { max, found: values });
+            }
+        }
+
+        for prop in &rule.props {
+            if let Some(name) = &prop.name
+                && prop.required
+                && !last.contains_key(name.as_str())
+            {
+                self.error(SchemaErrorKind::MissingProp(name.clone()));
+            }
+        }
+
+        self.validate_children(&rule.children, node, &last);
+    }
+
+    fn validate_children<'a, N, A>(
+        &mut self,
+        rule: &ChildrenRule,
+        node: N,
+        args: &HashMap<&str, usize>,
+    ) where
+        N: NodeRef<'a> + Copy,
+        N::ArgsIter<'a>: Iterator<Item = A>,
+        N::ChildrenIter<'a>: Iterator<Item = N>,
+        A: ArgRef<'a>,
+    {
+        let schema = self.schema;
+        let args_len = node.args().len();
+        let mut counts = alloc::vec![0; rule.nodes.len()];
+        let mut last = HashMap::new();
+        for (index, child) in node.children().enumerate() {
+            last.insert(child.name(), index);
+        }
+        for (index, child) in node.children().enumerate() {
+            let name = child.name();
+            let segment = if name.is_empty() || args.contains_key(name) || last[name] != index {
+                Segment::Index(args_len + index)
+            } else {
+                Segment::Name(Cow::Owned(name.into()))
+            };
+
+            let matching = rule
+                .nodes
+                .iter()
+                .position(|&i| schema.nodes[i].name.as_deref() == Some(name))
+                .or_else(|| {
+                    rule.nodes
+                        .iter()
+                        .position(|&i| schema.nodes[i].name.is_none())
+                });
+            self.path.push(segment);
+            match matching {
+                Some(position) => {
+                    counts[position] += 1;
+                    self.validate_node(&schema.nodes[rule.nodes[position]], child);
+                }
+                None if !rule.other_nodes => self.error(SchemaErrorKind::UnexpectedNode),
+                None => {}
+            }
+            self.path.pop();
+        }
+
+        for (&index, found) in rule.nodes.iter().zip(counts) {
+            let node = &schema.nodes[index];
+            let name = || node.name.as_deref().unwrap_or_default().to_string();
+            if found < node.min {
+                self.error(SchemaErrorKind::TooFewNodes {
+                    name: name(),
+                    min: node.min,
+                    found,
+                });
+            }
+            if let Some(max) = node.max
+                && found > max
+            {
+                self.error(SchemaErrorKind::TooManyNodes {
+                    name: name(),
+                    max,
+                    found,
+                });
+            }
+        }
+    }
+}
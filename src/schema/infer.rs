@@ -0,0 +1,518 @@
+use alloc::{borrow::ToOwned as _, boxed::Box, string::String, vec::Vec};
+
+use hashbrown::HashMap;
+
+use crate::{Builder, ClassifyNode, ValueRef, builder};
+
+/// Infer a schema from example documents.
+///
+/// See [`InferredSchema`].
+#[must_use]
+pub fn infer<'a>(docs: impl IntoIterator<Item = crate::Node<'a>>) -> InferredSchema {
+    let mut schema = InferredSchema::new();
+    for doc in docs {
+        schema.add(doc);
+    }
+    schema
+}
+
+/// Schema inferred from the shape of example documents.
+///
+/// Nodes are unified by their position and name: the children named `server`
+/// of all root nodes share a shape, and so do their children, and so on.
+/// Unnamed children of a node share one shape. For each shape, this records:
+///
+/// - The names of the observed types.
+/// - The kinds of values of properties (named arguments) and unnamed
+///   arguments, where integers widen to `u64`, `i64`, or `number`, depending
+///   on their range, and floats widen to `number`.
+/// - Whether properties and children appear in all nodes (required) or not
+///   (optional).
+/// - Whether a child appears more than once in a node (repeated) or not.
+///
+/// The schema can be emitted as a schema document in the KDL Schema Language
+/// that all the example documents are valid against, or as a JSON Schema for
+/// the JSON representation of the documents.
+#[derive(Clone, Debug, Default)]
+pub struct InferredSchema {
+    root: Shape,
+}
+
+/// Shape of nodes with the same name at the same position.
+#[derive(Clone, Debug, Default)]
+struct Shape {
+    /// Number of observed nodes.
+    count: usize,
+    types: Vec<String>,
+    untyped: bool,
+    values: Kinds,
+    min_values: usize,
+    max_values: usize,
+    props: Fields<Prop>,
+    /// Children, where unnamed children have an empty name.
+    children: Fields<Child>,
+    forms: JsonForms,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Prop {
+    /// Number of nodes that had the property.
+    count: usize,
+    kinds: Kinds,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Child {
+    /// Number of parents that had the child.
+    count: usize,
+    /// Minimum number of occurrences in parents that had the child.
+    min: usize,
+    /// Maximum number of occurrences in a parent.
+    max: usize,
+    shape: Box<Shape>,
+}
+
+/// Observed kinds of values.
+#[derive(Clone, Copy, Debug, Default)]
+#[expect(clippy::struct_excessive_bools)]
+struct Kinds {
+    null: bool,
+    bool: bool,
+    string: bool,
+    binary: bool,
+    float: bool,
+    /// Range of the observed integers.
+    ints: Option<(i128, i128)>,
+}
+
+/// JSON representations of the observed nodes, which depend on their
+/// classification.
+#[derive(Clone, Copy, Debug, Default)]
+#[expect(clippy::struct_excessive_bools)]
+struct JsonForms {
+    object: bool,
+    array: bool,
+    value: bool,
+    null: bool,
+}
+
+/// Named fields in order of appearance.
+#[derive(Clone, Debug)]
+struct Fields<T> {
+    index: HashMap<String, usize>,
+    entries: Vec<(String, T)>,
+}
+
+impl<T> Default for Fields<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            index: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Default> Fields<T> {
+    fn get_or_default(&mut self, name: &str) -> &mut T {
+        let index = if let Some(&index) = self.index.get(name) {
+            index
+        } else {
+            self.index.insert(name.to_owned(), self.entries.len());
+            self.entries.push((name.to_owned(), T::default()));
+            self.entries.len() - 1
+        };
+        &mut self.entries[index].1
+    }
+}
+
+impl<T> Fields<T> {
+    #[cfg(feature = "json")]
+    fn get(&self, name: &str) -> Option<&T> {
+        self.index.get(name).map(|&index| &self.entries[index].1)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl InferredSchema {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an example document to the schema.
+    pub fn add(&mut self, node: crate::Node) {
+        self.root.add(node);
+    }
+
+    /// Number of example documents.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    /// Emit the schema as a document in the KDL Schema Language, which can be
+    /// loaded with [`Schema::from_node()`](super::Schema::from_node).
+    #[must_use]
+    pub fn to_builder(&self) -> Builder<'static> {
+        let mut builder = Builder::new();
+        builder.root_mut().push_named_with("document", |document| {
+            self.root.write(document, true);
+        });
+        builder
+    }
+
+    /// Emit the schema as a [`Schema`](super::Schema).
+    ///
+    /// # Panics
+    ///
+    /// The emitted schema document is always valid, so this does not panic.
+    #[must_use]
+    pub fn to_schema(&self) -> super::Schema {
+        super::Schema::from_node(self.to_builder().root()).expect("inferred schema should be valid")
+    }
+
+    /// Emit the schema as a [JSON Schema](https://json-schema.org/) for the
+    /// JSON representation of documents, as produced by
+    /// [`json::document_to_json_value_with_settings()`](crate::json::document_to_json_value_with_settings).
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn to_json_schema(&self, settings: &crate::json::JsonSettings) -> serde_json::Value {
+        let mut schema = self.root.json_schema(settings);
+        if let serde_json::Value::Object(object) = &mut schema {
+            object.insert(
+                "$schema".into(),
+                "https://json-schema.org/draft/2020-12/schema".into(),
+            );
+        }
+        schema
+    }
+}
+
+impl Shape {
+    fn add(&mut self, node: crate::Node) {
+        let first = self.count == 0;
+        self.count += 1;
+
+        match node.ty() {
+            Some(ty) => {
+                if !self.types.iter().any(|t| t == ty) {
+                    self.types.push(ty.into());
+                }
+            }
+            None => self.untyped = true,
+        }
+
+        match node.classify() {
+            ClassifyNode::Seq => self.forms.array = true,
+            ClassifyNode::Value => self.forms.value = true,
+            ClassifyNode::Unit => self.forms.null = true,
+            _ => self.forms.object = true,
+        }
+
+        let mut values = 0;
+        let mut props = Vec::new();
+        for arg in node.args() {
+            if let Some(name) = arg.name {
+                let prop = self.props.get_or_default(name);
+                prop.kinds.add(arg.value);
+                if !props.contains(&name) {
+                    props.push(name);
+                    prop.count += 1;
+                }
+            } else {
+                values += 1;
+                self.values.add(arg.value);
+            }
+        }
+        if first {
+            self.min_values = values;
+        }
+        self.min_values = self.min_values.min(values);
+        self.max_values = self.max_values.max(values);
+
+        let mut counts = HashMap::<&str, usize>::new();
+        for child in node.children() {
+            let name = child.name().unwrap_or_default();
+            *counts.entry(name).or_default() += 1;
+            self.children.get_or_default(name).shape.add(child);
+        }
+        for (name, count) in counts {
+            let child = self.children.get_or_default(name);
+            child.min = if child.count == 0 {
+                count
+            } else {
+                child.min.min(count)
+            };
+            child.max = child.max.max(count);
+            child.count += 1;
+        }
+    }
+
+    /// Write the rules of the shape to a `node` or `document` element.
+    fn write(&self, out: &mut builder::Node<'static>, document: bool) {
+        if !self.types.is_empty() {
+            out.push_named_with("tag", |tag| {
+                tag.push_named_with("enum", |values| {
+                    for ty in &self.types {
+                        values.push_unnamed_arg(ty.clone());
+                    }
+                    if self.untyped {
+                        values.push_unnamed_arg(builder::Value::Null);
+                    }
+                });
+            });
+        }
+
+        for (name, prop) in self.props.iter() {
+            out.push_named_with("prop", |rule| {
+                rule.push_unnamed_arg(name.to_owned());
+                if prop.count == self.count {
+                    rule.push_named_with("required", |required| {
+                        required.push_unnamed_arg(true);
+                    });
+                }
+                prop.kinds.write(rule);
+            });
+        }
+
+        if self.max_values != 0 {
+            out.push_named_with("value", |rule| {
+                write_cardinality(rule, self.min_values, self.max_values);
+                self.values.write(rule);
+            });
+        }
+
+        if self.children.is_empty() {
+            return;
+        }
+        let mut children = builder::Node::empty();
+        for (name, child) in self.children.iter() {
+            children.push_named_with("node", |rule| {
+                if !name.is_empty() {
+                    rule.push_unnamed_arg(name.to_owned());
+                }
+                let min = if child.count == self.count {
+                    child.min
+                } else {
+                    0
+                };
+                write_cardinality(rule, min, child.max);
+                child.shape.write(rule, false);
+            });
+        }
+        if document {
+            out.children.append(&mut children.children);
+        } else {
+            children.set_name("children");
+            out.push(children);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn json_schema(&self, settings: &crate::json::JsonSettings) -> serde_json::Value {
+        use serde_json::{Map, Value, json};
+
+        let mut alternatives = Vec::new();
+        if self.forms.null {
+            alternatives.push(json!({ "type": "null" }));
+        }
+        if self.forms.value {
+            alternatives.push(self.json_item_schema(settings));
+        }
+        if self.forms.array {
+            alternatives.push(json!({
+                "type": "array",
+                "items": self.json_item_schema(settings),
+            }));
+        }
+        if self.forms.object {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for (name, prop) in self.props.iter() {
+                properties.insert(name.into(), prop.kinds.json_schema());
+                if prop.count == self.count {
+                    required.push(Value::from(name));
+                }
+            }
+            for (name, child) in self.children.iter() {
+                if name.is_empty() {
+                    continue;
+                }
+                let schema = child.shape.json_schema(settings);
+                let schema = match properties.remove(name) {
+                    Some(prop) => any_of([prop, schema]),
+                    None => schema,
+                };
+                properties.insert(name.into(), schema);
+                if child.count == self.count && !required.contains(&Value::from(name)) {
+                    required.push(name.into());
+                }
+            }
+            if !self.types.is_empty() && !settings.type_tag.is_empty() {
+                properties.insert(settings.type_tag.into(), json!({ "enum": self.types }));
+                if !self.untyped {
+                    required.push(settings.type_tag.into());
+                }
+            }
+            if self.max_values != 0 || self.children.get("").is_some() {
+                let item = self.json_item_schema(settings);
+                properties.insert(
+                    settings.items_tag.into(),
+                    json!({ "type": "array", "items": item }),
+                );
+                properties.insert(settings.value_tag.into(), item);
+            }
+            alternatives.push(json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            }));
+        }
+        any_of(alternatives)
+    }
+
+    /// JSON Schema of the unnamed entries of nodes.
+    #[cfg(feature = "json")]
+    fn json_item_schema(&self, settings: &crate::json::JsonSettings) -> serde_json::Value {
+        let mut alternatives = Vec::new();
+        if self.max_values != 0 {
+            alternatives.push(self.values.json_schema());
+        }
+        if let Some(child) = self.children.get("") {
+            alternatives.push(child.shape.json_schema(settings));
+        }
+        any_of(alternatives)
+    }
+}
+
+/// Write `min` and `max` rules, where `max` is only written for entries that
+/// are never repeated.
+fn write_cardinality(rule: &mut builder::Node<'static>, min: usize, max: usize) {
+    if min != 0 {
+        rule.push_named_with("min", |node| {
+            node.push_unnamed_arg(min as u64);
+        });
+    }
+    if max == 1 {
+        rule.push_named_with("max", |node| {
+            node.push_unnamed_arg(1u64);
+        });
+    }
+}
+
+impl Kinds {
+    fn add(&mut self, value: ValueRef) {
+        match value {
+            ValueRef::Null => self.null = true,
+            ValueRef::Bool(_) => self.bool = true,
+            ValueRef::String(_) => self.string = true,
+            ValueRef::Binary(_) => self.binary = true,
+            ValueRef::Float(_) => self.float = true,
+            ValueRef::Int(value) => self.add_int(value.into()),
+            ValueRef::Uint(value) => self.add_int(value.into()),
+        }
+    }
+
+    fn add_int(&mut self, value: i128) {
+        self.ints = Some(match self.ints {
+            Some((min, max)) => (min.min(value), max.max(value)),
+            None => (value, value),
+        });
+    }
+
+    /// Name of the numeric type, if any numbers were observed.
+    fn number_type(&self) -> Option<&'static str> {
+        if self.float {
+            return Some("number");
+        }
+        let (min, max) = self.ints?;
+        Some(if min >= 0 {
+            "u64"
+        } else if max <= i64::MAX.into() {
+            "i64"
+        } else {
+            "number"
+        })
+    }
+
+    fn write(&self, rule: &mut builder::Node<'static>) {
+        let mut types = Vec::new();
+        if self.string {
+            types.push("string");
+        }
+        if self.bool {
+            types.push("boolean");
+        }
+        types.extend(self.number_type());
+        if self.binary {
+            types.push("binary");
+        }
+        if self.null {
+            types.push("null");
+        }
+        if types.is_empty() {
+            return;
+        }
+        rule.push_named_with("type", |node| {
+            for ty in types {
+                node.push_unnamed_arg(ty);
+            }
+        });
+    }
+
+    #[cfg(feature = "json")]
+    fn json_schema(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        let mut types = Vec::new();
+        if self.string {
+            types.push("string");
+        }
+        if self.bool {
+            types.push("boolean");
+        }
+        if self.float {
+            types.push("number");
+        } else if self.ints.is_some() {
+            types.push("integer");
+        }
+        if self.null {
+            types.push("null");
+        }
+        match types.as_slice() {
+            [] => json!({}),
+            [ty] => json!({ "type": ty }),
+            _ => json!({ "type": types }),
+        }
+    }
+}
+
+/// Combine JSON Schemas, where an empty list accepts nothing.
+#[cfg(feature = "json")]
+fn any_of(alternatives: impl IntoIterator<Item = serde_json::Value>) -> serde_json::Value {
+    let mut alternatives = alternatives.into_iter().collect::<Vec<_>>();
+    match alternatives.len() {
+        0 => serde_json::Value::Bool(false),
+        1 => alternatives.remove(0),
+        _ => serde_json::json!({ "anyOf": alternatives }),
+    }
+}
@@ -64,13 +64,108 @@
 //!    to the name of the variant.
 //! 6. Struct enum variants are serialized as structs, but the `type` field is
 //!    set to the name of the variant.
+//!
+//! ## Options
+//!
+//! The conventions for unit variants and for the layout of structs, lists, and
+//! maps can be changed with [`SerializerOptions`], which is accepted by the
+//! `*_with()` functions, like [`to_builder_with()`] and
+//! [`from_document_with()`]. For example, [`SerializerOptions::children()`]
+//! represents every entry as a child node, which suits XML.
+//!
+//! Internally and adjacently tagged enums (`#[serde(tag = "...")]`) are
+//! supported by setting [`SerializerOptions::tag`], so that the variant is the
+//! type of the node, like for externally tagged enums.
+//!
+//! ## Serializing many documents
+//!
+//! For serializing many documents, like responses to requests,
+//! [`to_document_with_cache()`] writes values directly into the buffers of a
+//! reused [`BuildCache`](crate::builder::BuildCache).
+//!
+//! ## Trait objects
+//!
+//! Trait objects can be serialized and deserialized with a [`Registry`] of the
+//! implementations of the trait, where the type of the node is the name under
+//! which the implementation is registered.
+//!
+//! ## Documents and nodes
+//!
+//! [`Document`](crate::Document), [`DocumentBuffer`](crate::DocumentBuffer),
+//! and nodes can themselves be serialized with other serde formats. In
+//! human-readable formats (like JSON), they are serialized as the structured
+//! value that they would deserialize as, while other formats get the validated
+//! bytes of the document.
+//!
+//! ## Errors
+//!
+//! Errors from deserialization carry the [path](crate::path::Path) to the
+//! entry where they occurred (like `servers[3].tls.cert`), and the
+//! classification of the node. [`from_text()`] also finds the line and column
+//! of the entry in the source text.
 
 mod de;
 mod error;
+mod impls;
+mod options;
+mod raw;
+#[cfg(feature = "alloc")]
+mod registry;
 #[cfg(feature = "alloc")]
 mod ser;
+#[cfg(feature = "alloc")]
+mod tree;
 
 pub use error::*;
+pub use options::{Layout, SerializerOptions, UnitVariants};
+pub use raw::RawNode;
+#[cfg(feature = "alloc")]
+pub use registry::Registry;
+#[cfg(feature = "alloc")]
+pub(crate) use tree::Tree;
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+pub mod __private {
+    pub use alloc::boxed::Box;
+    pub use core::any::Any;
+    pub use serde::{Deserialize, Deserializer, Serialize, Serializer};
+}
+
+/// Implement `Serialize` and `Deserialize` for `Box<dyn Trait>` with a
+/// [`Registry`], which must be a `static` (or an expression dereferencing to
+/// a `&'static Registry<dyn Trait>`).
+///
+/// ```ignore
+/// static FILTERS: LazyLock<Registry<dyn Filter>> = LazyLock::new(|| { ... });
+/// zdoc::serde_registry!(dyn Filter => FILTERS);
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! serde_registry {
+    ($ty:ty => $registry:expr) => {
+        impl $crate::serde::__private::Serialize for $crate::serde::__private::Box<$ty> {
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::__private::Serializer,
+            {
+                let value: &dyn $crate::serde::__private::Any = &**self;
+                $registry.serialize(value, serializer)
+            }
+        }
+
+        impl<'de> $crate::serde::__private::Deserialize<'de>
+            for $crate::serde::__private::Box<$ty>
+        {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: $crate::serde::__private::Deserializer<'de>,
+            {
+                $registry.deserialize(deserializer)
+            }
+        }
+    };
+}
 
 /// Serialize into a [`Builder`](crate::Builder), which can be modified further.
 ///
@@ -79,8 +174,24 @@ pub use error::*;
 /// If the value cannot be serialized, this returns an error.
 #[cfg(feature = "alloc")]
 pub fn to_builder<'a, T: serde::Serialize>(value: &T) -> Result<crate::Builder<'a>, Error> {
+    to_builder_with(value, &SerializerOptions::new())
+}
+
+/// Serialize into a [`Builder`](crate::Builder) with the given conventions.
+///
+/// # Errors
+///
+/// If the value cannot be serialized, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn to_builder_with<'a, T: serde::Serialize>(
+    value: &T,
+    options: &SerializerOptions,
+) -> Result<crate::Builder<'a>, Error> {
     let mut builder = crate::Builder::new();
-    value.serialize(builder.root_mut())?;
+    value.serialize(ser::NodeSerializer {
+        node: builder.root_mut(),
+        options: *options,
+    })?;
     Ok(builder)
 }
 
@@ -93,9 +204,26 @@ pub fn to_builder<'a, T: serde::Serialize>(value: &T) -> Result<crate::Builder<'
 #[cfg(feature = "alloc")]
 pub fn to_builder_node<'a, T: serde::Serialize>(
     value: &T,
+) -> Result<crate::builder::Node<'a>, Error> {
+    to_builder_node_with(value, &SerializerOptions::new())
+}
+
+/// Serialize into a builder [`Node`](crate::builder::Node) with the given
+/// conventions.
+///
+/// # Errors
+///
+/// If the value cannot be serialized, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn to_builder_node_with<'a, T: serde::Serialize>(
+    value: &T,
+    options: &SerializerOptions,
 ) -> Result<crate::builder::Node<'a>, Error> {
     let mut root = crate::builder::Node::empty();
-    value.serialize(&mut root)?;
+    value.serialize(ser::NodeSerializer {
+        node: &mut root,
+        options: *options,
+    })?;
     Ok(root)
 }
 
@@ -110,6 +238,46 @@ pub fn to_document<T: serde::Serialize>(value: &T) -> Result<crate::DocumentBuff
     to_builder(value).map(|builder| builder.build())
 }
 
+/// Serialize a value to a [`DocumentBuffer`](crate::DocumentBuffer) with the
+/// given conventions.
+///
+/// # Errors
+///
+/// If the value cannot be serialized, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn to_document_with<T: serde::Serialize>(
+    value: &T,
+    options: &SerializerOptions,
+) -> Result<crate::DocumentBuffer, Error> {
+    to_builder_with(value, options).map(|builder| builder.build())
+}
+
+/// Serialize a value to a [`DocumentBuffer`](crate::DocumentBuffer) with the
+/// given conventions, reusing the allocations in `cache`.
+///
+/// Unlike [`to_document_with()`], this does not build a tree of
+/// [`builder::Node`](crate::builder::Node)s first, so serializing many values
+/// with the same cache allocates little more than the resulting documents.
+/// The documents are identical to those from [`to_document_with()`].
+///
+/// # Errors
+///
+/// If the value cannot be serialized, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn to_document_with_cache<T: serde::Serialize>(
+    value: &T,
+    options: &SerializerOptions,
+    cache: &mut crate::builder::BuildCache,
+) -> Result<crate::DocumentBuffer, Error> {
+    value.serialize(ser::NodeSerializer {
+        node: cache.serde.reset(),
+        options: *options,
+    })?;
+    cache.set_auto_intern_limit(crate::builder::DEFAULT_AUTO_INTERN_LIMIT);
+    cache.raw.set_build_index(false);
+    Ok(cache.serde.build(&mut cache.raw))
+}
+
 /// Deserialize a [`Builder`](crate::Builder) into a value of type `T`,
 /// borrowing strings and binary data from the builder.
 ///
@@ -126,6 +294,20 @@ pub fn from_builder<'a, T: serde::Deserialize<'a>>(
     from_builder_node(builder.root())
 }
 
+/// Deserialize a [`Builder`](crate::Builder) into a value of type `T` with the
+/// given conventions.
+///
+/// # Errors
+///
+/// If the document cannot be deserialized into `T`, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn from_builder_with<'a, T: serde::Deserialize<'a>>(
+    builder: &'a crate::Builder<'_>,
+    options: &SerializerOptions,
+) -> Result<T, Error> {
+    from_builder_node_with(builder.root(), options)
+}
+
 /// Deserialize a builder [`Node`](crate::builder::Node) into a value of type
 /// `T`, borrowing strings and binary data from the document.
 ///
@@ -139,7 +321,21 @@ pub fn from_builder<'a, T: serde::Deserialize<'a>>(
 pub fn from_builder_node<'a, T: serde::Deserialize<'a>>(
     node: &'a crate::builder::Node<'_>,
 ) -> Result<T, Error> {
-    serde::de::Deserialize::deserialize(de::DeNode(node))
+    from_builder_node_with(node, &SerializerOptions::new())
+}
+
+/// Deserialize a builder [`Node`](crate::builder::Node) into a value of type
+/// `T` with the given conventions.
+///
+/// # Errors
+///
+/// If the document cannot be deserialized into `T`, this returns an error.
+#[cfg(feature = "alloc")]
+pub fn from_builder_node_with<'a, T: serde::Deserialize<'a>>(
+    node: &'a crate::builder::Node<'_>,
+    options: &SerializerOptions,
+) -> Result<T, Error> {
+    de::from_node(node, *options)
 }
 
 /// Deserialize a [`Document`](crate::Document) into a value of type `T`,
@@ -157,6 +353,19 @@ pub fn from_document<'a, T: serde::Deserialize<'a>>(
     from_document_node(document.root())
 }
 
+/// Deserialize a [`Document`](crate::Document) into a value of type `T` with
+/// the given conventions.
+///
+/// # Errors
+///
+/// If the document cannot be deserialized into `T`, this returns an error.
+pub fn from_document_with<'a, T: serde::Deserialize<'a>>(
+    document: &'a crate::Document,
+    options: &SerializerOptions,
+) -> Result<T, Error> {
+    from_document_node_with(document.root(), options)
+}
+
 /// Deserialize a [`Node`](crate::Node) into a value of type `T`, borrowing
 /// strings and binary data from the node.
 ///
@@ -166,7 +375,43 @@ pub fn from_document<'a, T: serde::Deserialize<'a>>(
 pub fn from_document_node<'a, T: serde::Deserialize<'a>>(
     node: crate::Node<'a>,
 ) -> Result<T, Error> {
-    serde::de::Deserialize::deserialize(de::DeNode(node))
+    from_document_node_with(node, &SerializerOptions::new())
+}
+
+/// Deserialize a [`Node`](crate::Node) into a value of type `T` with the given
+/// conventions.
+///
+/// # Errors
+///
+/// If the document cannot be deserialized into `T`, this returns an error.
+pub fn from_document_node_with<'a, T: serde::Deserialize<'a>>(
+    node: crate::Node<'a>,
+    options: &SerializerOptions,
+) -> Result<T, Error> {
+    de::from_node(node, *options)
+}
+
+/// Parse a document in the [text format](crate::text), and deserialize it
+/// into a value of type `T`.
+///
+/// Unlike the other functions in this module, errors carry the line and
+/// column in `text` where they occurred. This is only possible for the zdoc
+/// text format; there is no equivalent for JSON, YAML, or other formats.
+///
+/// # Errors
+///
+/// If `text` is not a valid document, or the document cannot be deserialized
+/// into `T`, this returns an error.
+#[cfg(feature = "builder")]
+pub fn from_text<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, Error> {
+    let builder = crate::text::builder_from_text(text).map_err(|err| Error {
+        location: Some((err.line, err.column)),
+        ..ErrorKind::Parse(err.error).into()
+    })?;
+    from_builder(&builder).map_err(|mut err| {
+        err.location = crate::text::locate(text, &err.path);
+        err
+    })
 }
 
 impl<'de> serde::de::IntoDeserializer<'de, Error> for crate::Node<'de> {
@@ -174,7 +419,7 @@ impl<'de> serde::de::IntoDeserializer<'de, Error> for crate::Node<'de> {
 
     #[inline]
     fn into_deserializer(self) -> Self::Deserializer {
-        de::DeNode(self)
+        de::DeNode(self, SerializerOptions::new())
     }
 }
 
@@ -184,7 +429,7 @@ impl<'de> serde::de::IntoDeserializer<'de, Error> for &'de crate::builder::Node<
 
     #[inline]
     fn into_deserializer(self) -> Self::Deserializer {
-        de::DeNode(self)
+        de::DeNode(self, SerializerOptions::new())
     }
 }
 
@@ -866,4 +1111,542 @@ vec 1 2
             }
         );
     }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    struct Plugin<'a> {
+        name: String,
+        #[serde(borrow)]
+        settings: RawNode<'a>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    enum Server {
+        Http { host: String, port: u16 },
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Settings {
+        server: Server,
+        #[serde(default)]
+        ports: Vec<u16>,
+    }
+
+    #[test]
+    fn raw_node() {
+        let mut root = crate::zdoc! {
+            settings {
+                (Http)server host="a" port=80;
+                - 1 2;
+            }
+        };
+        root.push(("name", builder::Value::from("plugin")));
+        let mut builder = crate::Builder::new();
+        builder.set_root(root);
+        let doc = builder.build();
+        let settings = doc.root().children().get("settings").unwrap();
+
+        // Borrowed from the document.
+        let plugin = from_document::<Plugin>(&doc).unwrap();
+        assert_eq!(plugin.name, "plugin");
+        let RawNode::Document(node) = plugin.settings else {
+            panic!("expected a document node")
+        };
+        assert_eq!(node.id(), settings.id());
+        assert_eq!(node.document().as_bytes().as_ptr(), doc.as_bytes().as_ptr());
+        assert_eq!(
+            plugin.settings.deserialize_into::<Settings>().unwrap(),
+            Settings {
+                server: Server::Http {
+                    host: "a".to_owned(),
+                    port: 80
+                },
+                ports: vec![],
+            }
+        );
+
+        // Serialized back losslessly.
+        let builder = to_builder(&plugin).unwrap();
+        assert_eq!(builder.root().children()[0], settings);
+
+        // Captured from a builder.
+        let plugin = from_builder::<Plugin>(&builder).unwrap();
+        let RawNode::Builder(node) = &plugin.settings else {
+            panic!("expected a builder node")
+        };
+        assert_eq!(*node, settings);
+        assert_eq!(to_builder(&plugin).unwrap().root().children()[0], settings);
+
+        // Captured from an argument.
+        let doc = to_document(&("plugin", 1)).unwrap();
+        let (_, raw) = from_document::<(&str, RawNode)>(&doc).unwrap();
+        assert_eq!(raw.to_builder_node(), builder::Node::from_values([1]));
+    }
+
+    #[test]
+    fn raw_nodes_many() {
+        // Each raw node borrows from the same document, which must not be
+        // validated again for every node.
+        let count = if cfg!(miri) { 100u32 } else { 20_000 };
+        let mut root = builder::Node::empty();
+        for i in 0..count {
+            root.push(builder::Node::from_values([i, i + 1]));
+        }
+        let mut builder = crate::Builder::new();
+        builder.set_root(root);
+        let doc = builder.build();
+
+        let nodes = from_document::<Vec<RawNode>>(&doc).unwrap();
+        assert_eq!(nodes.len(), usize::try_from(count).unwrap());
+        for (node, expected) in nodes.iter().zip(doc.root().children()) {
+            let RawNode::Document(node) = node else {
+                panic!("expected a document node")
+            };
+            assert_eq!(node.id(), expected.id());
+        }
+        assert_eq!(nodes[42].deserialize_into::<[u32; 2]>().unwrap(), [42, 43]);
+    }
+
+    #[test]
+    #[cfg(feature = "builder")]
+    fn error_location() {
+        let text = r#"{
+    server = (Http) {
+        host = "a"
+        port = "80"
+    }
+    ports = { 1, 2, -3 }
+}"#;
+        let err = from_text::<Settings>(text).unwrap_err();
+        assert_eq!(err.path.to_string(), "server.port");
+        assert_eq!(err.node, Some(crate::ClassifyNode::StructVariant));
+        assert_eq!(err.location, Some((4, 9)));
+        assert_eq!(
+            err.to_string(),
+            "invalid type: string \"80\", expected u16, at `server.port`, line 4, column 9"
+        );
+
+        // Without the source text, only the path is known.
+        let builder = crate::text::builder_from_text(text).unwrap();
+        let err = from_builder::<Settings>(&builder).unwrap_err();
+        assert_eq!(err.path.to_string(), "server.port");
+        assert_eq!(err.location, None);
+
+        let text = text.replace("\"80\"", "80");
+        let err = from_text::<Settings>(&text).unwrap_err();
+        assert_eq!(err.path.to_string(), "ports[2]");
+        assert_eq!(err.node, Some(crate::ClassifyNode::Seq));
+        assert_eq!(err.location, Some((6, 21)));
+
+        let text = text.replace("(Http)", "(Ftp)");
+        let err = from_text::<Settings>(&text).unwrap_err();
+        assert_eq!(err.path.to_string(), "server.#type");
+        assert_eq!(err.location, Some((2, 5)));
+
+        // Like the deserializer, names resolve to the last entry.
+        let err = from_text::<BTreeMap<String, u16>>("{ a = 1, a = \"2\" }").unwrap_err();
+        assert_eq!(err.path.to_string(), "a");
+        assert_eq!(err.location, Some((1, 10)));
+
+        let err = from_text::<Settings>("{ server = ").unwrap_err();
+        assert!(matches!(err.error, ErrorKind::Parse(_)));
+        assert_eq!(err.location, Some((1, 12)));
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Job {
+        name: String,
+        mode: Mode,
+        steps: Vec<Vec<i32>>,
+        retries: u8,
+    }
+
+    #[test]
+    fn options() {
+        let job = Job {
+            name: "build".to_owned(),
+            mode: Mode::Slow,
+            steps: vec![vec![1, 2], vec![3]],
+            retries: 3,
+        };
+        let names = |node: &builder::Node| -> Vec<String> {
+            node.children()
+                .iter()
+                .map(|child| child.name().to_owned())
+                .collect()
+        };
+
+        let builder = to_builder(&job).unwrap();
+        assert_eq!(builder.root().args().len(), 3);
+        assert_eq!(names(builder.root()), ["steps"]);
+
+        let options = SerializerOptions {
+            structs: Layout::Ordered,
+            ..SerializerOptions::default()
+        };
+        let builder = to_builder_with(&job, &options).unwrap();
+        assert!(builder.root().args().is_empty());
+        assert_eq!(names(builder.root()), ["name", "mode", "steps", "retries"]);
+        assert_eq!(from_builder_with::<Job>(&builder, &options).unwrap(), job);
+
+        let options = SerializerOptions::children();
+        let doc = to_document_with(&job, &options).unwrap();
+        let root = doc.root();
+        assert!(root.args().is_empty());
+        let mode = root.children().get("mode").unwrap();
+        assert_eq!(mode.ty(), Some("Slow"));
+        assert!(mode.is_empty());
+        let steps = root.children().get("steps").unwrap();
+        assert!(
+            steps
+                .children()
+                .into_iter()
+                .all(|step| step.args().is_empty())
+        );
+        assert_eq!(from_document_with::<Job>(&doc, &options).unwrap(), job);
+
+        let options = SerializerOptions::args();
+        let value = (1, vec![2], 3);
+        let builder = to_builder_with(&value, &options).unwrap();
+        assert_eq!(builder.root().args().len(), 2);
+        assert_eq!(builder.root().children().len(), 1);
+    }
+
+    #[test]
+    fn unit_variant_any() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Untagged {
+            Mode(Mode),
+            Int(i32),
+        }
+
+        let doc = to_document(&Mode::Fast).unwrap();
+        assert_eq!(doc.root().ty(), Some("Fast"));
+        assert_eq!(
+            from_document::<Untagged>(&doc).unwrap(),
+            Untagged::Mode(Mode::Fast)
+        );
+        let options = SerializerOptions::children();
+        assert!(from_document_with::<Untagged>(&doc, &options).is_err());
+        assert_eq!(
+            from_document_with::<Mode>(&doc, &options).unwrap(),
+            Mode::Fast
+        );
+
+        // A list with a single compound element.
+        let doc = to_document(&vec![vec![1, 2]]).unwrap();
+        assert_eq!(
+            from_document::<Vec<Vec<i32>>>(&doc).unwrap(),
+            vec![vec![1, 2]]
+        );
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Tls {
+        cert: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    #[serde(tag = "kind")]
+    enum Internal {
+        Http { host: String, port: u16 },
+        Https(Tls),
+        Off,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    #[serde(tag = "kind", content = "value")]
+    enum Adjacent {
+        Http { host: String, port: u16 },
+        Port(u16),
+        Pair(i32, i32),
+        Off,
+    }
+
+    #[test]
+    fn internally_tagged() {
+        let options = SerializerOptions::new().with_tag("kind", None);
+        let servers = vec![
+            Internal::Http {
+                host: "a".to_owned(),
+                port: 80,
+            },
+            Internal::Https(Tls {
+                cert: "b".to_owned(),
+            }),
+            Internal::Off,
+        ];
+        let builder = to_builder_with(&servers, &options).unwrap();
+        let children = builder.root().children();
+        assert_eq!(
+            children[0],
+            crate::zdoc! { (Http)- host="a" port=80u16; }.children()[0]
+        );
+        assert_eq!(children[1].ty(), "Https");
+        assert!(!children[1].contains_named_argument("kind"));
+        assert_eq!(children[2].ty(), "Off");
+        assert!(children[2].args().is_empty());
+        assert_eq!(
+            from_builder_with::<Vec<Internal>>(&builder, &options).unwrap(),
+            servers
+        );
+
+        // An explicit tag field is still accepted.
+        let root = builder::Node::from_entries([("kind", builder::Value::from("Off"))]);
+        assert_eq!(
+            from_builder_node_with::<Internal>(&root, &options).unwrap(),
+            Internal::Off
+        );
+    }
+
+    #[test]
+    fn adjacently_tagged() {
+        let options = SerializerOptions::new().with_tag("kind", Some("value"));
+        let values = vec![
+            Adjacent::Http {
+                host: "a".to_owned(),
+                port: 80,
+            },
+            Adjacent::Port(80),
+            Adjacent::Pair(1, 2),
+            Adjacent::Off,
+        ];
+        let doc = to_document_with(&values, &options).unwrap();
+        let children = doc.root().children();
+        assert_eq!(children.get(0).unwrap().ty(), Some("Http"));
+        assert_eq!(children.get(0).unwrap().get_u64("port"), Some(80));
+        assert_eq!(children.get(1).unwrap().ty(), Some("Port"));
+        assert_eq!(children.get(1).unwrap().args().len(), 1);
+        assert_eq!(children.get(2).unwrap().args().len(), 2);
+        assert_eq!(children.get(3).unwrap().ty(), Some("Off"));
+        assert_eq!(
+            from_document_with::<Vec<Adjacent>>(&doc, &options).unwrap(),
+            values
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "json", expect(clippy::too_many_lines))]
+    fn registry() {
+        extern crate std;
+        use std::sync::LazyLock;
+
+        trait Filter: core::any::Any + core::fmt::Debug {
+            fn apply(&self, input: &str) -> String;
+        }
+
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Upper;
+        impl Filter for Upper {
+            fn apply(&self, input: &str) -> String {
+                input.to_uppercase()
+            }
+        }
+
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Prefix {
+            prefix: String,
+        }
+        impl Filter for Prefix {
+            fn apply(&self, input: &str) -> String {
+                self.prefix.clone() + input
+            }
+        }
+
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Repeat(usize);
+        impl Filter for Repeat {
+            fn apply(&self, input: &str) -> String {
+                input.repeat(self.0)
+            }
+        }
+
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Unregistered;
+        impl Filter for Unregistered {
+            fn apply(&self, input: &str) -> String {
+                input.to_owned()
+            }
+        }
+
+        static FILTERS: LazyLock<Registry<dyn Filter + Send + Sync>> = LazyLock::new(|| {
+            let mut registry: Registry<dyn Filter + Send + Sync> = Registry::new();
+            registry
+                .register("Upper", |filter: Upper| Box::new(filter))
+                .register("Prefix", |filter: Prefix| Box::new(filter))
+                .register("Repeat", |filter: Repeat| Box::new(filter));
+            registry
+        });
+        crate::serde_registry!(dyn Filter + Send + Sync => FILTERS);
+
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Pipeline {
+            filters: Vec<Box<dyn Filter + Send + Sync>>,
+        }
+
+        impl Pipeline {
+            fn apply(&self, input: &str) -> String {
+                self.filters
+                    .iter()
+                    .fold(input.to_owned(), |acc, filter| filter.apply(&acc))
+            }
+        }
+
+        let pipeline = Pipeline {
+            filters: vec![
+                Box::new(Upper),
+                Box::new(Prefix {
+                    prefix: "> ".to_owned(),
+                }),
+                Box::new(Repeat(2)),
+            ],
+        };
+        let doc = to_document(&pipeline).unwrap();
+        let filters = doc.root().children().get("filters").unwrap().children();
+        assert_eq!(filters.get(0).unwrap().ty(), Some("Upper"));
+        assert!(filters.get(0).unwrap().is_empty());
+        assert_eq!(filters.get(1).unwrap().ty(), Some("Prefix"));
+        assert_eq!(filters.get(1).unwrap().get_str("prefix"), Some("> "));
+        assert_eq!(filters.get(2).unwrap().ty(), Some("Repeat"));
+
+        let deserialized: Pipeline = from_document(&doc).unwrap();
+        assert_eq!(deserialized.apply("ab"), "> AB> AB");
+        let mut builder = to_builder(&pipeline).unwrap();
+        let deserialized: Pipeline = from_builder(&builder).unwrap();
+        assert_eq!(deserialized.apply("ab"), "> AB> AB");
+
+        // Trait objects are borrowed from the document as raw nodes, which
+        // does not validate the document again for each of them.
+        let count = if cfg!(miri) { 100 } else { 10_000 };
+        let many = Pipeline {
+            filters: (0..count).map(|_| Box::new(Repeat(1)) as _).collect(),
+        };
+        let deserialized: Pipeline = from_document(&to_document(&many).unwrap()).unwrap();
+        assert_eq!(deserialized.filters.len(), count);
+
+        #[cfg(feature = "json")]
+        {
+            // Formats without types represent trait objects like externally
+            // tagged enums, and unit structs like unit variants.
+            let json = serde_json::to_string(&pipeline).unwrap();
+            assert_eq!(
+                json,
+                r#"{"filters":["Upper",{"Prefix":{"prefix":"> "}},{"Repeat":2}]}"#
+            );
+            let deserialized: Pipeline = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized.apply("ab"), "> AB> AB");
+        }
+
+        builder.root_mut().children_mut()[0].children_mut()[1].set_ty("Suffix");
+        let err = from_builder::<Pipeline>(&builder).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown type `Suffix`, expected one of `Prefix`, `Repeat`, `Upper`, at `filters[1]`"
+        );
+
+        let err = to_document(&Pipeline {
+            filters: vec![Box::new(Unregistered)],
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot serialize a value of an unregistered type"
+        );
+    }
+
+    #[test]
+    fn document_with_cache() {
+        fn check<T: serde::Serialize>(
+            value: &T,
+            options: &SerializerOptions,
+            cache: &mut builder::BuildCache,
+        ) {
+            let expected = to_document_with(value, options).unwrap();
+            let doc = to_document_with_cache(value, options, cache).unwrap();
+            assert_eq!(doc.as_bytes(), expected.as_bytes());
+        }
+
+        let job = Job {
+            name: "build".to_owned(),
+            mode: Mode::Slow,
+            steps: vec![vec![1, 2], vec![3]],
+            retries: 3,
+        };
+        let settings = Settings {
+            server: Server::Http {
+                host: "a".to_owned(),
+                port: 80,
+            },
+            ports: vec![1, 2],
+        };
+        let map = BTreeMap::from([('a', vec![1]), ('b', vec![])]);
+        let mixed = (1, vec![2], "3", Some(4.5f32), None::<u8>, Bytes(vec![5]));
+        let nested = vec![vec![vec![(); 2]], vec![]];
+
+        let mut cache = builder::BuildCache::default();
+        for options in [
+            SerializerOptions::new(),
+            SerializerOptions::children(),
+            SerializerOptions::args(),
+            SerializerOptions {
+                structs: Layout::Ordered,
+                maps: Layout::Split,
+                ..SerializerOptions::default()
+            },
+        ] {
+            check(&job, &options, &mut cache);
+            check(&settings, &options, &mut cache);
+            check(&map, &options, &mut cache);
+            check(&mixed, &options, &mut cache);
+            check(&nested, &options, &mut cache);
+            check(&Mode::Fast, &options, &mut cache);
+            check(&'x', &options, &mut cache);
+            check(&(), &options, &mut cache);
+        }
+
+        let options = SerializerOptions::new().with_tag("kind", None);
+        check(
+            &vec![
+                Internal::Https(Tls {
+                    cert: "b".to_owned(),
+                }),
+                Internal::Off,
+            ],
+            &options,
+            &mut cache,
+        );
+        let options = SerializerOptions::new().with_tag("kind", Some("value"));
+        check(
+            &vec![Adjacent::Port(80), Adjacent::Pair(1, 2), Adjacent::Off],
+            &options,
+            &mut cache,
+        );
+
+        // Raw nodes are copied into the cache.
+        let doc = to_document(&settings).unwrap();
+        let plugin = Plugin {
+            name: "plugin".to_owned(),
+            settings: RawNode::Document(doc.root()),
+        };
+        check(&plugin, &SerializerOptions::new(), &mut cache);
+        let plugin = Plugin {
+            settings: RawNode::Builder(to_builder_node(&settings).unwrap()),
+            ..plugin
+        };
+        check(&plugin, &SerializerOptions::new(), &mut cache);
+
+        let err = to_document_with_cache(
+            &BTreeMap::from([(1, 2)]),
+            &SerializerOptions::new(),
+            &mut cache,
+        )
+        .unwrap_err();
+        assert!(matches!(err.error, ErrorKind::NonStringMapKey));
+    }
 }
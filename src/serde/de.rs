@@ -8,12 +8,91 @@ use serde::{
 
 use crate::{
     ClassifyNode, ValueRef,
-    access::{self, ArgRef as _},
+    access::{self, ArgRef as _, ConcreteNode},
 };
 
-use super::Error;
+use super::{Error, SerializerOptions, UnitVariants, raw};
 
-pub struct DeNode<N>(pub N);
+pub struct DeNode<N>(pub N, pub SerializerOptions);
+
+/// Deserialize `T` from `node`, attaching the node to errors that occur
+/// outside of its entries.
+pub(crate) fn from_node<'de, T, N>(node: N, options: SerializerOptions) -> Result<T, Error>
+where
+    T: serde::Deserialize<'de>,
+    N: access::NodeRef<'de>,
+{
+    let concrete = node.concrete();
+    T::deserialize(DeNode(node, options)).map_err(|err| err.in_node(concrete))
+}
+
+/// Deserialize `T` from the entries of `node`, disregarding its type.
+#[cfg(feature = "alloc")]
+pub(crate) fn from_untyped<'de, T>(
+    node: ConcreteNode<'de>,
+    options: SerializerOptions,
+) -> Result<T, Error>
+where
+    T: serde::Deserialize<'de>,
+{
+    match node {
+        ConcreteNode::Document(node) => from_node(Untyped(node), options),
+        ConcreteNode::Builder(node) => from_node(Untyped(node), options),
+    }
+}
+
+/// Node without its type, which has been consumed as an enum variant.
+struct Untyped<N>(N);
+
+impl<'a, N: access::NodeRef<'a>> access::NodeRef<'a> for Untyped<N> {
+    type ChildrenIter<'b>
+        = N::ChildrenIter<'b>
+    where
+        Self: 'b;
+
+    type ArgsIter<'b>
+        = N::ArgsIter<'b>
+    where
+        Self: 'b;
+
+    #[inline]
+    fn name(&self) -> &'a str {
+        self.0.name()
+    }
+
+    #[inline]
+    fn ty(&self) -> &'a str {
+        ""
+    }
+
+    #[inline]
+    fn children(&self) -> Self::ChildrenIter<'a> {
+        self.0.children()
+    }
+
+    #[inline]
+    fn args(&self) -> Self::ArgsIter<'a> {
+        self.0.args()
+    }
+
+    #[inline]
+    fn concrete(&self) -> ConcreteNode<'a> {
+        self.0.concrete()
+    }
+}
+
+/// The tag field to synthesize from the type of `node`, if any.
+fn tag_field<'de>(
+    node: &impl access::NodeRef<'de>,
+    options: SerializerOptions,
+) -> Option<(&'static str, &'de str)> {
+    let tag = options.tag?;
+    let ty = node.ty();
+    if ty.is_empty() || node.entries().any(|entry| entry.name() == tag) {
+        return None;
+    }
+    Some((tag, ty))
+}
 
 impl<'de, N: access::NodeRef<'de>> serde::de::IntoDeserializer<'de, Error> for DeNode<N> {
     type Deserializer = Self;
@@ -31,20 +110,37 @@ impl<'de, N: access::NodeRef<'de>> serde::Deserializer<'de> for DeNode<N> {
         V: serde::de::Visitor<'de>,
     {
         match self.0.classify() {
-            ClassifyNode::Struct | ClassifyNode::Mixed => visitor.visit_map(MapAccess::new(self.0)),
-            ClassifyNode::Seq => visitor.visit_seq(SeqAccess::new(self.0)),
+            ClassifyNode::Struct | ClassifyNode::Mixed => {
+                visitor.visit_map(MapAccess::new(self.0, self.1))
+            }
+            ClassifyNode::Seq => visitor.visit_seq(SeqAccess::new(self.0, self.1)),
             ClassifyNode::Value => {
                 let Some(arg) = self.0.args().next() else {
-                    unreachable!()
+                    // A list with a single compound element.
+                    return visitor.visit_seq(SeqAccess::new(self.0, self.1));
                 };
                 arg.value().deserialize_any(visitor)
             }
             ClassifyNode::Unit => visitor.visit_unit(),
             ClassifyNode::StructVariant
+            | ClassifyNode::UnitVariant
+            | ClassifyNode::MixedVariant
+                if tag_field(&self.0, self.1).is_some() =>
+            {
+                // Internally tagged enums.
+                visitor.visit_map(MapAccess::new(self.0, self.1))
+            }
+            ClassifyNode::UnitVariant if self.1.unit_variants == UnitVariants::String => {
+                visitor.visit_borrowed_str(self.0.ty())
+            }
+            ClassifyNode::StructVariant
             | ClassifyNode::SeqVariant
             | ClassifyNode::ValueVariant
             | ClassifyNode::UnitVariant
-            | ClassifyNode::MixedVariant => visitor.visit_enum(EnumAccess { node: self.0 }),
+            | ClassifyNode::MixedVariant => visitor.visit_enum(EnumAccess {
+                node: self.0,
+                options: self.1,
+            }),
         }
     }
 
@@ -71,14 +167,14 @@ impl<'de, N: access::NodeRef<'de>> serde::Deserializer<'de> for DeNode<N> {
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccess::new(self.0))
+        visitor.visit_seq(SeqAccess::new(self.0, self.1))
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccess::new(self.0))
+        visitor.visit_seq(SeqAccess::new(self.0, self.1))
     }
 
     fn deserialize_tuple_struct<V>(
@@ -90,36 +186,52 @@ impl<'de, N: access::NodeRef<'de>> serde::Deserializer<'de> for DeNode<N> {
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccess::new(self.0))
+        visitor.visit_seq(SeqAccess::new(self.0, self.1))
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_map(MapAccess::new(self.0))
+        visitor.visit_map(MapAccess::new(self.0, self.1))
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_map(MapAccess::new(self.0))
+        if let Some((tag, ty)) = tag_field(&self.0, self.1)
+            && let Some(content) = self.1.content
+            && fields == [tag, content]
+        {
+            // Adjacently tagged enums.
+            return visitor.visit_map(AdjacentAccess {
+                tag,
+                content,
+                ty: Some(ty),
+                node: Some(self.0),
+                options: self.1,
+            });
+        }
+        visitor.visit_map(MapAccess::new(self.0, self.1))
     }
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
+        if name == raw::TOKEN {
+            return raw::visit_raw(self.0.concrete(), visitor);
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -143,29 +255,42 @@ impl<'de, N: access::NodeRef<'de>> serde::Deserializer<'de> for DeNode<N> {
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_enum(EnumAccess { node: self.0 })
+        visitor.visit_enum(EnumAccess {
+            node: self.0,
+            options: self.1,
+        })
     }
 }
 
-struct MapAccess<Args: Iterator, Children: Iterator> {
+struct MapAccess<'de, Args: Iterator, Children: Iterator> {
+    node: ConcreteNode<'de>,
+    options: SerializerOptions,
+    /// Tag field synthesized from the type of the node.
+    tag: Option<(&'static str, &'de str)>,
+    tag_value: Option<&'de str>,
     entries: access::EntryRefIter<Args, Children>,
     current: Option<<access::EntryRefIter<Args, Children> as Iterator>::Item>,
 }
 
-impl<'de, Args: Iterator, Children: Iterator> MapAccess<Args, Children> {
+impl<'de, Args: Iterator, Children: Iterator> MapAccess<'de, Args, Children> {
     #[inline]
     #[allow(clippy::needless_pass_by_value)]
     pub fn new<N: access::NodeRef<'de, ArgsIter<'de> = Args, ChildrenIter<'de> = Children>>(
         node: N,
+        options: SerializerOptions,
     ) -> Self {
         Self {
+            node: node.concrete(),
+            options,
+            tag: tag_field(&node, options),
+            tag_value: None,
             entries: node.entries(),
             current: None,
         }
     }
 }
 
-impl<'de, Args, Children> serde::de::MapAccess<'de> for MapAccess<Args, Children>
+impl<'de, Args, Children> serde::de::MapAccess<'de> for MapAccess<'de, Args, Children>
 where
     Args: Iterator<Item: access::ArgRef<'de>>,
     Children: Iterator<Item: access::NodeRef<'de>>,
@@ -176,12 +301,19 @@ where
     where
         K: serde::de::DeserializeSeed<'de>,
     {
+        if let Some((tag, ty)) = self.tag.take() {
+            self.tag_value = Some(ty);
+            return seed.deserialize(MapKeyDeserializer(tag)).map(Some);
+        }
         loop {
             if let Some(next) = self.entries.next() {
                 let name = next.name();
                 if !name.is_empty() {
                     self.current = Some(next);
-                    return seed.deserialize(MapKeyDeserializer(name)).map(Some);
+                    return seed
+                        .deserialize(MapKeyDeserializer(name))
+                        .map(Some)
+                        .map_err(|err| err.at_name(name).in_node(self.node));
                 }
             } else {
                 return Ok(None);
@@ -193,13 +325,85 @@ where
     where
         V: serde::de::DeserializeSeed<'de>,
     {
+        if let Some(ty) = self.tag_value.take() {
+            return seed
+                .deserialize(BorrowedStrDeserializer::<Error>::new(ty))
+                .map_err(|err| err.at_type().in_node(self.node));
+        }
         let Some(entry) = self.current.take() else {
             panic!("unbalanced map access; call next_key() first")
         };
-        match entry {
-            access::EntryRef::Arg(arg) => seed.deserialize(arg.value()),
-            access::EntryRef::Child(node) => seed.deserialize(DeNode(node)),
+        let name = entry.name();
+        deserialize_entry(self.node, entry, self.options, seed).map_err(|err| err.at_name(name))
+    }
+}
+
+/// Deserialize an entry of `parent`, attaching the node where an error occurred.
+fn deserialize_entry<'de, A, C, T>(
+    parent: ConcreteNode<'de>,
+    entry: access::EntryRef<A, C>,
+    options: SerializerOptions,
+    seed: T,
+) -> Result<T::Value, Error>
+where
+    A: access::ArgRef<'de>,
+    C: access::NodeRef<'de>,
+    T: serde::de::DeserializeSeed<'de>,
+{
+    match entry {
+        access::EntryRef::Arg(arg) => seed
+            .deserialize(arg.value())
+            .map_err(|err| err.in_node(parent)),
+        access::EntryRef::Child(node) => {
+            let concrete = node.concrete();
+            seed.deserialize(DeNode(node, options))
+                .map_err(|err| err.in_node(concrete))
+        }
+    }
+}
+
+/// Map of the tag and content of an adjacently tagged enum, where the tag is
+/// the type of the node, and the content is the node itself.
+struct AdjacentAccess<'de, N> {
+    tag: &'static str,
+    content: &'static str,
+    ty: Option<&'de str>,
+    node: Option<N>,
+    options: SerializerOptions,
+}
+
+impl<'de, N: access::NodeRef<'de>> serde::de::MapAccess<'de> for AdjacentAccess<'de, N> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        let key = if self.ty.is_some() {
+            self.tag
+        } else if self.node.is_some() {
+            self.content
+        } else {
+            return Ok(None);
+        };
+        seed.deserialize(MapKeyDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        if let Some(ty) = self.ty.take() {
+            return seed
+                .deserialize(BorrowedStrDeserializer::<Error>::new(ty))
+                .map_err(Error::at_type);
         }
+        let Some(node) = self.node.take() else {
+            panic!("unbalanced map access; call next_key() first")
+        };
+        let concrete = node.concrete();
+        seed.deserialize(DeNode(Untyped(node), self.options))
+            .map_err(|err| err.in_node(concrete))
     }
 }
 
@@ -280,23 +484,30 @@ impl<'de> serde::de::Deserializer<'de> for MapKeyDeserializer<'de> {
     }
 }
 
-struct SeqAccess<Args, Children> {
+struct SeqAccess<'de, Args, Children> {
+    node: ConcreteNode<'de>,
+    options: SerializerOptions,
     entries: access::EntryRefIter<Args, Children>,
+    index: usize,
 }
 
-impl<'de, Args, Children> SeqAccess<Args, Children> {
+impl<'de, Args, Children> SeqAccess<'de, Args, Children> {
     #[inline]
     #[allow(clippy::needless_pass_by_value)]
     pub fn new<N: access::NodeRef<'de, ArgsIter<'de> = Args, ChildrenIter<'de> = Children>>(
         node: N,
+        options: SerializerOptions,
     ) -> Self {
         Self {
+            node: node.concrete(),
+            options,
             entries: node.entries(),
+            index: 0,
         }
     }
 }
 
-impl<'de, Args, Children> serde::de::SeqAccess<'de> for SeqAccess<Args, Children>
+impl<'de, Args, Children> serde::de::SeqAccess<'de> for SeqAccess<'de, Args, Children>
 where
     Args: Iterator<Item: access::ArgRef<'de>>,
     Children: Iterator<Item: access::NodeRef<'de>>,
@@ -308,10 +519,11 @@ where
         T: serde::de::DeserializeSeed<'de>,
     {
         if let Some(entry) = self.entries.next() {
-            match entry {
-                access::EntryRef::Arg(arg) => seed.deserialize(arg.value()).map(Some),
-                access::EntryRef::Child(node) => seed.deserialize(DeNode(node)).map(Some),
-            }
+            let index = self.index;
+            self.index += 1;
+            deserialize_entry(self.node, entry, self.options, seed)
+                .map(Some)
+                .map_err(|err| err.at_index(index))
         } else {
             Ok(None)
         }
@@ -320,6 +532,7 @@ where
 
 struct EnumAccess<N> {
     node: N,
+    options: SerializerOptions,
 }
 
 impl<'de, N: access::NodeRef<'de>> serde::de::EnumAccess<'de> for EnumAccess<N> {
@@ -331,23 +544,24 @@ impl<'de, N: access::NodeRef<'de>> serde::de::EnumAccess<'de> for EnumAccess<N>
         V: serde::de::DeserializeSeed<'de>,
     {
         let ty = self.node.ty();
-        if ty.is_empty() {
-            if let Some(first_arg) = self.node.args().next() {
-                if let ValueRef::String(variant) = first_arg.value() {
-                    let variant = seed.deserialize(BorrowedStrDeserializer::new(variant))?;
-                    return Ok((variant, VariantAccess::Unit));
-                }
-            }
+        if ty.is_empty()
+            && let Some(first_arg) = self.node.args().next()
+            && let ValueRef::String(variant) = first_arg.value()
+        {
+            let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(variant))?;
+            return Ok((variant, VariantAccess::Unit));
         }
 
-        let variant = seed.deserialize(BorrowedStrDeserializer::new(ty))?;
-        Ok((variant, VariantAccess::TypedNode(self.node)))
+        let variant = seed
+            .deserialize(BorrowedStrDeserializer::<Error>::new(ty))
+            .map_err(|err| err.at_type().in_node(self.node.concrete()))?;
+        Ok((variant, VariantAccess::TypedNode(self.node, self.options)))
     }
 }
 
 enum VariantAccess<N> {
     Unit,
-    TypedNode(N),
+    TypedNode(N, SerializerOptions),
 }
 
 impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAccess<N> {
@@ -357,7 +571,7 @@ impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAcce
     fn unit_variant(self) -> Result<(), Self::Error> {
         match self {
             VariantAccess::Unit => Ok(()),
-            VariantAccess::TypedNode(node) => {
+            VariantAccess::TypedNode(node, _) => {
                 if !node.is_empty() {
                     // We're returning an error - might as well perform an
                     // "expensive" calculation to provide the most accurate
@@ -394,7 +608,7 @@ impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAcce
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        let Self::TypedNode(node) = self else {
+        let Self::TypedNode(node, options) = self else {
             return Err(serde::de::Error::invalid_type(
                 Unexpected::UnitVariant,
                 &"newtype variant",
@@ -405,10 +619,7 @@ impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAcce
             if entries.next().is_some() {
                 return Err(serde::de::Error::invalid_length(node.entries().len(), &"1"));
             }
-            match first {
-                access::EntryRef::Arg(arg) => seed.deserialize(arg.value()),
-                access::EntryRef::Child(node) => seed.deserialize(DeNode(node)),
-            }
+            deserialize_entry(node.concrete(), first, options, seed).map_err(|err| err.at_index(0))
         } else {
             Err(serde::de::Error::invalid_type(
                 Unexpected::UnitVariant,
@@ -421,13 +632,13 @@ impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAcce
     where
         V: serde::de::Visitor<'de>,
     {
-        let Self::TypedNode(node) = self else {
+        let Self::TypedNode(node, options) = self else {
             // Deserialize the empty sequence.
             return serde::de::value::SeqDeserializer::new(iter::empty::<DeNode<N>>())
                 .deserialize_seq(visitor);
         };
 
-        DeNode(node).deserialize_seq(visitor)
+        DeNode(node, options).deserialize_seq(visitor)
     }
 
     fn struct_variant<V>(
@@ -438,12 +649,12 @@ impl<'de, N: access::NodeRef<'de>> serde::de::VariantAccess<'de> for VariantAcce
     where
         V: serde::de::Visitor<'de>,
     {
-        let Self::TypedNode(node) = self else {
+        let Self::TypedNode(node, options) = self else {
             // Deserialize the empty map.
             return serde::de::value::MapDeserializer::new(iter::empty::<(&str, DeNode<N>)>())
                 .deserialize_map(visitor);
         };
-        DeNode(node).deserialize_map(visitor)
+        visitor.visit_map(MapAccess::new(Untyped(node), options))
     }
 }
 
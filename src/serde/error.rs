@@ -1,10 +1,50 @@
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use crate::path::{Path, Segment};
+use crate::{ClassifyNode, access::ConcreteNode};
+
+/// Error when serializing or deserializing with serde.
+///
+/// Errors from deserialization carry the location in the document where they
+/// occurred, as far as it is known.
+///
+/// The kind of error is in [`error`](Self::error), so code that matched on
+/// the variants of this type matches on `err.error` instead.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Error {
+    /// Kind of error that occurred.
+    pub error: ErrorKind,
+    /// Path to the entry where the error occurred, relative to the node that
+    /// was deserialized.
+    #[cfg(feature = "alloc")]
+    pub path: Path<'static>,
+    /// Classification of the node at [`path`](Self::path), or of the node
+    /// containing the argument at `path`.
+    pub node: Option<ClassifyNode>,
+    /// Line and column (in characters, both starting at 1) of the entry where
+    /// the error occurred.
+    ///
+    /// This is only known when deserializing the [text format](crate::text)
+    /// with [`from_text()`](super::from_text). Documents parsed from other
+    /// formats, like JSON or YAML, do not keep the locations of entries.
+    pub location: Option<(usize, usize)>,
+}
+
+/// Kind of [`Error`].
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
-pub enum Error {
+pub enum ErrorKind {
     #[error("keys in maps must serialize as strings")]
     NonStringMapKey,
     #[error("cannot serialize a compound type as a plain value")]
     CompoundValue,
+    #[error("document would be too large (> 4 GiB)")]
+    TooLarge,
+    #[cfg(feature = "builder")]
+    #[error(transparent)]
+    Parse(crate::text::ParseErrorKind),
     #[cfg(feature = "alloc")]
     #[error("{0}")]
     Custom(alloc::string::String),
@@ -13,6 +53,76 @@ pub enum Error {
     Custom,
 }
 
+impl Error {
+    /// Prepend a named entry to the path of the error.
+    #[cfg_attr(not(feature = "alloc"), allow(unused_mut, unused_variables))]
+    pub(crate) fn at_name(mut self, name: &str) -> Self {
+        #[cfg(feature = "alloc")]
+        self.path
+            .push_front(Segment::Name(alloc::string::String::from(name).into()));
+        self
+    }
+
+    /// Prepend an index to the path of the error.
+    #[cfg_attr(not(feature = "alloc"), allow(unused_mut, unused_variables))]
+    pub(crate) fn at_index(mut self, index: usize) -> Self {
+        #[cfg(feature = "alloc")]
+        self.path.push_front(Segment::Index(index));
+        self
+    }
+
+    /// Prepend the type of the node to the path of the error.
+    #[cfg_attr(not(feature = "alloc"), allow(unused_mut))]
+    pub(crate) fn at_type(mut self) -> Self {
+        #[cfg(feature = "alloc")]
+        self.path.push_front(Segment::Type);
+        self
+    }
+
+    /// Set the classification of the node where the error occurred, unless it
+    /// is already known.
+    pub(crate) fn in_node(mut self, node: ConcreteNode<'_>) -> Self {
+        if self.node.is_none() {
+            self.node = Some(node.classify());
+        }
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    #[inline]
+    fn from(error: ErrorKind) -> Self {
+        Self {
+            error,
+            #[cfg(feature = "alloc")]
+            path: Path::new(),
+            node: None,
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)?;
+        #[cfg(feature = "alloc")]
+        if !self.path.segments().is_empty() {
+            write!(f, ", at `{}`", self.path)?;
+        }
+        if let Some((line, column)) = self.location {
+            write!(f, ", line {line}, column {column}")?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
 #[cfg(feature = "alloc")]
 impl serde::ser::Error for Error {
     fn custom<T>(msg: T) -> Self
@@ -20,7 +130,7 @@ impl serde::ser::Error for Error {
         T: core::fmt::Display,
     {
         use alloc::string::ToString as _;
-        Error::Custom(msg.to_string())
+        ErrorKind::Custom(msg.to_string()).into()
     }
 }
 
@@ -31,7 +141,7 @@ impl serde::de::Error for Error {
         T: core::fmt::Display,
     {
         use alloc::string::ToString as _;
-        Error::Custom(msg.to_string())
+        ErrorKind::Custom(msg.to_string()).into()
     }
 }
 
@@ -41,6 +151,6 @@ impl serde::de::Error for Error {
     where
         T: core::fmt::Display,
     {
-        Error::Custom
+        ErrorKind::Custom.into()
     }
 }
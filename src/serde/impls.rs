@@ -0,0 +1,431 @@
+//! `Serialize` and `Deserialize` for documents, nodes, and values, so they can
+//! be embedded in other serde formats.
+//!
+//! Human-readable formats (like JSON) get a structured value that mirrors
+//! `deserialize_any()` of a document, while other formats get the bytes of the
+//! document, which are validated when deserializing.
+
+use serde::{
+    Serialize, Serializer,
+    ser::{SerializeMap as _, SerializeSeq as _},
+};
+
+use crate::{
+    Document, ValueRef,
+    access::{self, EntryRef},
+};
+
+impl Serialize for ValueRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            ValueRef::Null => serializer.serialize_none(),
+            ValueRef::Bool(value) => serializer.serialize_bool(value),
+            ValueRef::Int(value) => serializer.serialize_i64(value),
+            ValueRef::Uint(value) => serializer.serialize_u64(value),
+            ValueRef::Float(value) => serializer.serialize_f64(value),
+            ValueRef::String(value) => serializer.serialize_str(value),
+            ValueRef::Binary(value) => serializer.serialize_bytes(value),
+        }
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            SerNode(&self.root()).serialize(serializer)
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for crate::DocumentBuffer {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+/// In non-human-readable formats, the node is serialized as a document with the
+/// node as its root.
+#[cfg(feature = "alloc")]
+impl Serialize for crate::Node<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            SerNode(self).serialize(serializer)
+        } else {
+            build_root(*self).serialize(serializer)
+        }
+    }
+}
+
+/// In non-human-readable formats, the node is serialized as a document with the
+/// node as its root.
+#[cfg(feature = "alloc")]
+impl Serialize for crate::builder::Node<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            SerNode(&self).serialize(serializer)
+        } else {
+            build_root(self).serialize(serializer)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn build_root(root: impl crate::builder::BuildRawNode) -> crate::DocumentBuffer {
+    let mut raw = crate::builder::RawBuilder::default();
+    raw.set_root(root);
+    raw.build()
+}
+
+/// Structured representation of a node.
+///
+/// Typed nodes are serialized like externally tagged enums: as a map with a
+/// single entry, where the key is the type, except for empty typed nodes,
+/// which are serialized as the type itself (like unit variants). Untyped nodes
+/// are serialized as:
+///
+/// - `()` if the node is empty.
+/// - The single entry of the node, if it is unnamed.
+/// - A sequence, if all entries are unnamed.
+/// - A map, if all entries are named.
+/// - Otherwise, a map where the unnamed entries are collected in a sequence
+///   under the key `""`, in the place of the first unnamed entry.
+struct SerNode<'n, N>(&'n N);
+
+impl<'a, N: access::NodeRef<'a>> Serialize for SerNode<'_, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ty = self.0.ty();
+        if ty.is_empty() {
+            return SerContent(self.0).serialize(serializer);
+        }
+        if self.0.is_empty() {
+            return serializer.serialize_str(ty);
+        }
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(ty, &SerContent(self.0))?;
+        map.end()
+    }
+}
+
+/// Entries of a node, disregarding its type.
+struct SerContent<'n, N>(&'n N);
+
+impl<'a, N: access::NodeRef<'a>> Serialize for SerContent<'_, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let entries = self.0.entries();
+        let named = entries.clone().filter(|entry| !entry.name().is_empty());
+        let len = entries.len();
+        let named_len = named.count();
+
+        if len == 0 {
+            serializer.serialize_unit()
+        } else if named_len == 0 {
+            if len == 1 {
+                SerEntry(entries.clone().next().unwrap()).serialize(serializer)
+            } else {
+                serializer.collect_seq(entries.map(SerEntry))
+            }
+        } else {
+            let mixed = named_len != len;
+            let mut map = serializer.serialize_map(Some(named_len + usize::from(mixed)))?;
+            let mut unnamed = mixed.then_some(entries.clone());
+            for entry in entries {
+                let name = entry.name();
+                if !name.is_empty() {
+                    map.serialize_entry(name, &SerEntry(entry))?;
+                } else if let Some(unnamed) = unnamed.take() {
+                    map.serialize_entry(
+                        "",
+                        &SerUnnamed(unnamed.filter(|entry| entry.name().is_empty())),
+                    )?;
+                }
+            }
+            map.end()
+        }
+    }
+}
+
+struct SerEntry<Arg, Child>(EntryRef<Arg, Child>);
+
+impl<'a, Arg: access::ArgRef<'a>, Child: access::NodeRef<'a>> Serialize for SerEntry<Arg, Child> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.0 {
+            EntryRef::Arg(arg) => arg.value().serialize(serializer),
+            EntryRef::Child(node) => SerNode(node).serialize(serializer),
+        }
+    }
+}
+
+struct SerUnnamed<I>(I);
+
+impl<'a, I, Arg, Child> Serialize for SerUnnamed<I>
+where
+    I: Iterator<Item = EntryRef<Arg, Child>> + Clone,
+    Arg: access::ArgRef<'a>,
+    Child: access::NodeRef<'a>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(None)?;
+        for entry in self.0.clone() {
+            seq.serialize_element(&SerEntry(entry))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod de {
+    use alloc::{borrow::Cow, string::String, vec::Vec};
+    use core::mem;
+
+    use serde::{
+        Deserialize, Deserializer,
+        de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor},
+    };
+
+    use crate::{
+        DocumentBuffer,
+        builder::{Arg, Builder, Entry, Node, Value},
+    };
+
+    impl<'de> Deserialize<'de> for DocumentBuffer {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                let mut builder = Builder::new();
+                builder.set_root(Node::deserialize(deserializer)?);
+                Ok(builder.build())
+            } else {
+                deserializer.deserialize_byte_buf(BytesVisitor)
+            }
+        }
+    }
+
+    /// In human-readable formats, maps become named entries, sequences become
+    /// unnamed entries, and other values become arguments, following the
+    /// conventions of [`crate::serde`]. Note that types are lost in the round
+    /// trip, because they cannot be distinguished from maps with a single
+    /// entry, except in formats that deserialize them as enums.
+    ///
+    /// Like `Cow`, the node always owns its strings and binary data.
+    impl<'de> Deserialize<'de> for Node<'_> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                match deserializer.deserialize_any(EntryVisitor)? {
+                    Entry::Arg(Arg {
+                        value: Value::Null, ..
+                    }) => Ok(Node::empty()),
+                    entry => Ok(entry.into()),
+                }
+            } else {
+                let doc = deserializer.deserialize_byte_buf(BytesVisitor)?;
+                Ok(Node::from(doc.root()).into_static())
+            }
+        }
+    }
+
+    struct BytesVisitor;
+
+    impl Visitor<'_> for BytesVisitor {
+        type Value = DocumentBuffer;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("zdoc document bytes")
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            self.visit_byte_buf(v.to_vec())
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            DocumentBuffer::from_buffer(v).map_err(E::custom)
+        }
+    }
+
+    struct EntrySeed;
+
+    impl<'de> DeserializeSeed<'de> for EntrySeed {
+        type Value = Entry<'static>;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(EntryVisitor)
+        }
+    }
+
+    struct EntryVisitor;
+
+    impl<'de> Visitor<'de> for EntryVisitor {
+        type Value = Entry<'static>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("any value")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+            Ok(Value::Bool(v).into())
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+            Ok(Value::Int(v).into())
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(Value::Uint(v).into())
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+            Ok(Value::Float(v).into())
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(Value::String(Cow::Owned(v.into())).into())
+        }
+
+        fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+            Ok(Value::String(Cow::Owned(v)).into())
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(Value::Binary(Cow::Owned(v.into())).into())
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(Value::Binary(Cow::Owned(v)).into())
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E> {
+            Ok(Entry::null())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(Entry::null())
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut node = Node::empty();
+            while let Some(entry) = seq.next_element_seed(EntrySeed)? {
+                node.push_ordered(entry);
+            }
+            Ok(node.into())
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut node = Node::empty();
+            while let Some(key) = map.next_key_seed(KeySeed)? {
+                let mut entry = map.next_value_seed(EntrySeed)?;
+                if !key.is_empty() {
+                    entry.set_name(key);
+                    node.push_ordered(entry);
+                    continue;
+                }
+                // Unnamed entries of a node with both named and unnamed
+                // entries.
+                match entry {
+                    Entry::Child(mut list) if list.ty().is_empty() && list.name().is_empty() => {
+                        for arg in mem::take(list.args_mut()) {
+                            node.push_ordered(arg);
+                        }
+                        for child in mem::take(list.children_mut()) {
+                            node.push_ordered(child);
+                        }
+                    }
+                    entry => {
+                        node.push_ordered(entry);
+                    }
+                }
+            }
+            Ok(node.into())
+        }
+
+        fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+        where
+            A: EnumAccess<'de>,
+        {
+            let (ty, variant) = data.variant_seed(KeySeed)?;
+            let mut node = match variant.newtype_variant_seed(EntrySeed)? {
+                Entry::Child(node) if node.ty().is_empty() => node,
+                entry => Node::unnamed(entry),
+            };
+            node.set_ty(ty);
+            Ok(node.into())
+        }
+    }
+
+    /// Map keys and enum variants.
+    struct KeySeed;
+
+    impl<'de> DeserializeSeed<'de> for KeySeed {
+        type Value = String;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            String::deserialize(deserializer)
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Options for the conventions used to map serde data to documents.
+///
+/// The defaults are the conventions described in [the module
+/// documentation](crate::serde). The same options should be used to serialize
+/// and deserialize a value, although the deserializer accepts the shapes
+/// produced by any of them where they are unambiguous.
+///
+/// ```ignore
+/// let options = SerializerOptions::children();
+/// let builder = zdoc::serde::to_builder_with(&config, &options)?;
+/// let config: Config = zdoc::serde::from_builder_with(&builder, &options)?;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SerializerOptions {
+    /// Representation of unit enum variants, except at the root, where they
+    /// are always typed nodes. Default is [`UnitVariants::String`].
+    pub unit_variants: UnitVariants,
+    /// Layout of the fields of structs and struct variants. Default is
+    /// [`Layout::Split`].
+    pub structs: Layout,
+    /// Layout of the elements of lists, tuples, and the values of newtype
+    /// variants. Default is [`Layout::Ordered`].
+    pub seqs: Layout,
+    /// Layout of the entries of maps. Default is [`Layout::Ordered`].
+    pub maps: Layout,
+    /// Name of the field holding the variant of internally and adjacently
+    /// tagged enums (`#[serde(tag = "...")]`). Instead of a field, the variant
+    /// is the type of the node, and when deserializing, the type of a node is
+    /// presented as this field. Default is `None`.
+    ///
+    /// This applies to every struct and map with a field of this name, which
+    /// should be used consistently for the enums in a value.
+    pub tag: Option<&'static str>,
+    /// Name of the field holding the content of adjacently tagged enums
+    /// (`#[serde(tag = "...", content = "...")]`). Instead of a field, the
+    /// content is the entries of the node typed by [`tag`](Self::tag).
+    /// Default is `None`.
+    pub content: Option<&'static str>,
+}
+
+/// Representation of unit enum variants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UnitVariants {
+    /// The name of the variant as a string value, like in JSON.
+    ///
+    /// When deserializing a value of unknown type (with `deserialize_any()`),
+    /// a typed node without entries is also seen as a string.
+    #[default]
+    String,
+    /// A node without entries, with the name of the variant as its type.
+    TypedNode,
+}
+
+/// Layout of the entries of a compound value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Simple values are arguments, and compound values are children. The
+    /// order between arguments and children is not preserved.
+    Split,
+    /// Simple values are arguments until the first compound value, after
+    /// which all entries are children, including the earlier arguments. The
+    /// order of entries is preserved.
+    Ordered,
+    /// All entries are children. Simple values are children with the value as
+    /// their only argument, like XML elements with text content.
+    Children,
+}
+
+impl SerializerOptions {
+    /// The default conventions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            unit_variants: UnitVariants::String,
+            structs: Layout::Split,
+            seqs: Layout::Ordered,
+            maps: Layout::Ordered,
+            tag: None,
+            content: None,
+        }
+    }
+
+    /// Conventions suited for XML, where every entry is a child node, and
+    /// unit variants are typed nodes.
+    #[must_use]
+    pub const fn children() -> Self {
+        Self {
+            unit_variants: UnitVariants::TypedNode,
+            structs: Layout::Children,
+            seqs: Layout::Children,
+            maps: Layout::Children,
+            tag: None,
+            content: None,
+        }
+    }
+
+    /// Conventions suited for KDL, where simple values are always arguments,
+    /// and compound values are children.
+    #[must_use]
+    pub const fn args() -> Self {
+        Self {
+            unit_variants: UnitVariants::String,
+            structs: Layout::Split,
+            seqs: Layout::Split,
+            maps: Layout::Split,
+            tag: None,
+            content: None,
+        }
+    }
+
+    /// Use `tag` as the name of the tag field of internally and adjacently
+    /// tagged enums, and `content` as the name of the content field of
+    /// adjacently tagged enums.
+    ///
+    /// ```ignore
+    /// #[derive(Serialize, Deserialize)]
+    /// #[serde(tag = "kind")]
+    /// enum Server {
+    ///     Http { host: String, port: u16 },
+    /// }
+    ///
+    /// // (Http) { host = "a", port = 80u }
+    /// let options = SerializerOptions::new().with_tag("kind", None);
+    /// let doc = zdoc::serde::to_document_with(&server, &options)?;
+    /// ```
+    #[must_use]
+    pub const fn with_tag(mut self, tag: &'static str, content: Option<&'static str>) -> Self {
+        self.tag = Some(tag);
+        self.content = content;
+        self
+    }
+}
+
+impl Default for SerializerOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,498 @@
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde::de::{SeqAccess, Visitor};
+
+use crate::{Document, NodeId, access::ConcreteNode};
+
+use super::Error;
+
+/// Name of the newtype struct that [`RawNode`] serializes and deserializes as,
+/// which the zdoc serializer and deserializer recognize.
+pub(crate) const TOKEN: &str = "$zdoc::private::RawNode";
+
+/// Node passed through serialization and deserialization without being
+/// interpreted, like `serde_json::value::RawValue`.
+///
+/// This can be used as a field in a `Deserialize` struct to defer
+/// deserialization of the contents of that field, or to carry a section of
+/// a document that should not be interpreted at all.
+///
+/// ```ignore
+/// #[derive(serde::Deserialize)]
+/// struct Plugin<'a> {
+///     name: String,
+///     #[serde(borrow)]
+///     settings: RawNode<'a>,
+/// }
+/// ```
+///
+/// When deserializing from a [`Document`], the node is borrowed from the
+/// document without copying or validating the document again. From all other
+/// sources, including [`Builder`](crate::Builder)s, the node is captured
+/// as a [`builder::Node`](crate::builder::Node).
+///
+/// When serializing to a [`Builder`](crate::Builder), the node is reproduced
+/// exactly. Other formats get the same representation as the underlying node.
+#[derive(Clone, Debug)]
+pub enum RawNode<'de> {
+    /// Node borrowed from a document.
+    Document(crate::Node<'de>),
+    /// Node captured from a builder or another format.
+    #[cfg(feature = "alloc")]
+    Builder(crate::builder::Node<'de>),
+}
+
+impl RawNode<'_> {
+    /// Deserialize the contents of the node into a value of type `T`.
+    ///
+    /// # Errors
+    ///
+    /// If the node cannot be deserialized into `T`, this returns an error.
+    pub fn deserialize_into<'a, T: serde::Deserialize<'a>>(&'a self) -> Result<T, Error> {
+        match self {
+            RawNode::Document(node) => super::from_document_node(*node),
+            #[cfg(feature = "alloc")]
+            RawNode::Builder(node) => super::from_builder_node(node),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> RawNode<'de> {
+    /// Copy the node into a builder node, which can be modified.
+    #[must_use]
+    pub fn to_builder_node(&self) -> crate::builder::Node<'de> {
+        match self {
+            RawNode::Document(node) => (*node).into(),
+            RawNode::Builder(node) => node.clone(),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> serde::Deserialize<'de> for RawNode<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, RawNodeVisitor)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl serde::Serialize for RawNode<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            RawNode::Document(node) => serializer.serialize_newtype_struct(TOKEN, node),
+            RawNode::Builder(node) => serializer.serialize_newtype_struct(TOKEN, node),
+        }
+    }
+}
+
+/// Pass `node` to the visitor of a [`RawNode`].
+///
+/// Document nodes are passed as the bytes of the document and the ID of the
+/// node. Builder nodes are passed as the bytes of a document with the node as
+/// its root.
+pub(crate) fn visit_raw<'de, V>(node: ConcreteNode<'de>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    match node {
+        ConcreteNode::Document(node) => {
+            let bytes = node.document().as_bytes();
+            let _registration = VALIDATED.register(bytes);
+            visitor.visit_seq(RawAccess::Document {
+                bytes: Some(bytes),
+                id: Some(node.id().0),
+            })
+        }
+        #[cfg(feature = "alloc")]
+        ConcreteNode::Builder(node) => {
+            let mut raw = crate::builder::RawBuilder::default();
+            raw.set_root(node);
+            visitor.visit_seq(RawAccess::Buffer(Some(raw.build())))
+        }
+    }
+}
+
+/// Documents whose bytes are being passed to a visitor by [`visit_raw`].
+///
+/// Serde cannot pass a [`Node`](crate::Node) through its data model, so the
+/// visitor of a [`RawNode`] receives the bytes of the document, and would have
+/// to validate the whole document again for every raw node. Instead,
+/// `visit_raw` registers the address and length of the bytes of its
+/// (validated) document while the visitor runs, and the visitor only validates
+/// bytes that are not registered. A slice with the same address and length is
+/// the same memory, which cannot change while both borrows are alive.
+///
+/// When all slots are taken, for example because many threads are
+/// deserializing at once, documents are not registered, and are validated
+/// again.
+static VALIDATED: Validated = Validated {
+    lock: AtomicBool::new(false),
+    slots: UnsafeCell::new([(0, 0); VALIDATED_SLOTS]),
+};
+
+const VALIDATED_SLOTS: usize = 16;
+
+struct Validated {
+    lock: AtomicBool,
+    /// Address and length of the registered slices. Free slots are `(0, 0)`.
+    slots: UnsafeCell<[(usize, usize); VALIDATED_SLOTS]>,
+}
+
+// SAFETY: The slots are only accessed while holding the lock.
+unsafe impl Sync for Validated {}
+
+impl Validated {
+    fn with_slots<R>(&self, f: impl FnOnce(&mut [(usize, usize); VALIDATED_SLOTS]) -> R) -> R {
+        while self
+            .lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let result = f(unsafe {
+            // SAFETY: The lock is held.
+            &mut *self.slots.get()
+        });
+        self.lock.store(false, Ordering::Release);
+        result
+    }
+
+    /// Register the bytes of a document until the returned registration is
+    /// dropped.
+    fn register<'a>(&'a self, document: &'a [u8]) -> Option<Registration<'a>> {
+        let key = (document.as_ptr().addr(), document.len());
+        let slot = self.with_slots(|slots| {
+            let slot = slots.iter().position(|slot| *slot == (0, 0))?;
+            slots[slot] = key;
+            Some(slot)
+        })?;
+        Some(Registration {
+            validated: self,
+            slot,
+            _document: document,
+        })
+    }
+
+    /// Whether `bytes` are the bytes of a registered document.
+    fn contains(&self, bytes: &[u8]) -> bool {
+        let key = (bytes.as_ptr().addr(), bytes.len());
+        key != (0, 0) && self.with_slots(|slots| slots.contains(&key))
+    }
+}
+
+struct Registration<'a> {
+    validated: &'a Validated,
+    slot: usize,
+    _document: &'a [u8],
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.validated.with_slots(|slots| slots[self.slot] = (0, 0));
+    }
+}
+
+enum RawAccess<'de> {
+    Document {
+        bytes: Option<&'de [u8]>,
+        id: Option<u32>,
+    },
+    #[cfg(feature = "alloc")]
+    Buffer(Option<crate::DocumentBuffer>),
+}
+
+impl<'de> SeqAccess<'de> for RawAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        use serde::de::IntoDeserializer as _;
+
+        match self {
+            RawAccess::Document { bytes, id } => {
+                if let Some(bytes) = bytes.take() {
+                    seed.deserialize(serde::de::value::BorrowedBytesDeserializer::new(bytes))
+                        .map(Some)
+                } else if let Some(id) = id.take() {
+                    seed.deserialize(id.into_deserializer()).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+            #[cfg(feature = "alloc")]
+            RawAccess::Buffer(buffer) => buffer
+                .take()
+                .map(|buffer| {
+                    seed.deserialize(serde::de::value::BytesDeserializer::new(buffer.as_bytes()))
+                })
+                .transpose(),
+        }
+    }
+}
+
+struct RawNodeVisitor;
+
+impl<'de> Visitor<'de> for RawNodeVisitor {
+    type Value = RawNode<'de>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a node")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        use serde::de::Error as _;
+
+        match seq.next_element::<RawBytes<'de>>()? {
+            Some(RawBytes::Borrowed(bytes)) => {
+                let id = seq
+                    .next_element::<u32>()?
+                    .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+                let doc = if VALIDATED.contains(bytes) {
+                    unsafe {
+                        // SAFETY: The bytes are registered, so they are the
+                        // bytes of a document that `visit_raw` is holding.
+                        Document::from_slice_unchecked(bytes)
+                    }
+                } else {
+                    Document::from_slice(bytes).map_err(A::Error::custom)?
+                };
+                match doc.node(NodeId(id)) {
+                    Some(node) => Ok(RawNode::Document(node)),
+                    None if doc.is_empty() => Ok(RawNode::Document(doc.root())),
+                    None => Err(A::Error::custom("invalid node ID")),
+                }
+            }
+            #[cfg(feature = "alloc")]
+            Some(RawBytes::Owned(bytes)) => {
+                let doc = crate::DocumentBuffer::from_buffer(bytes).map_err(A::Error::custom)?;
+                Ok(RawNode::Builder(
+                    crate::builder::Node::from(doc.root()).into_static(),
+                ))
+            }
+            None => Err(A::Error::invalid_length(0, &self)),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::Deserialize::deserialize(deserializer).map(RawNode::Builder)
+    }
+}
+
+/// Bytes of a document, borrowed if possible.
+enum RawBytes<'de> {
+    Borrowed(&'de [u8]),
+    #[cfg(feature = "alloc")]
+    Owned(alloc::vec::Vec<u8>),
+}
+
+impl<'de> serde::Deserialize<'de> for RawBytes<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = RawBytes<'de>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("document bytes")
+            }
+
+            fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+                Ok(RawBytes::Borrowed(v))
+            }
+
+            #[cfg(feature = "alloc")]
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(RawBytes::Owned(v.to_vec()))
+            }
+
+            #[cfg(feature = "alloc")]
+            fn visit_byte_buf<E>(self, v: alloc::vec::Vec<u8>) -> Result<Self::Value, E> {
+                Ok(RawBytes::Owned(v))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// Capture the node that a [`RawNode`] serialized as a newtype struct, by
+/// serializing it as the bytes of a document.
+#[cfg(feature = "alloc")]
+pub(crate) fn capture<T>(value: &T) -> Result<crate::builder::Node<'static>, Error>
+where
+    T: ?Sized + serde::Serialize,
+{
+    let bytes = value.serialize(CaptureSerializer)?;
+    let doc =
+        crate::DocumentBuffer::from_buffer(bytes).map_err(<Error as serde::ser::Error>::custom)?;
+    Ok(crate::builder::Node::from(doc.root()).into_static())
+}
+
+#[cfg(feature = "alloc")]
+struct CaptureSerializer;
+
+#[cfg(feature = "alloc")]
+macro_rules! expected_bytes {
+    ($($function:ident($($ty:ty),*);)*) => {
+        $(
+            #[inline]
+            fn $function(self $(, _: $ty)*) -> Result<Self::Ok, Self::Error> {
+                Err(serde::ser::Error::custom("expected document bytes"))
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl serde::Serializer for CaptureSerializer {
+    type Ok = alloc::vec::Vec<u8>;
+    type Error = Error;
+    type SerializeSeq = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = serde::ser::Impossible<Self::Ok, Self::Error>;
+
+    expected_bytes! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    #[inline]
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(v.to_vec())
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(serde::ser::Error::custom("expected document bytes"))
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,323 @@
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, format};
+use core::{
+    any::{Any, TypeId},
+    fmt,
+};
+
+use serde::{Deserialize as _, Serialize as _, de::DeserializeOwned};
+
+use crate::{
+    access::{self, ConcreteNode},
+    builder,
+};
+
+use super::{Error, RawNode, SerializerOptions, de};
+
+type DeserializeFn<T> =
+    dyn Fn(ConcreteNode<'_>, SerializerOptions) -> Result<Box<T>, Error> + Send + Sync;
+type SerializeFn = fn(&dyn Any, &SerializerOptions) -> Result<builder::Node<'static>, Error>;
+
+/// Registry of the implementations of a trait, for serializing and
+/// deserializing trait objects, like the `typetag` crate.
+///
+/// Each implementation is registered under a type name. A trait object is
+/// serialized as the value behind it, in a node whose type is the name of the
+/// implementation, and deserialized by looking up the type of the node in the
+/// registry.
+///
+/// The trait must have [`Any`] as a supertrait, so the implementation behind
+/// a trait object can be found. [`serde_registry!`](crate::serde_registry)
+/// implements `Serialize` and `Deserialize` for `Box<dyn Trait>` with a
+/// registry, so trait objects can be used in derived types:
+///
+/// ```ignore
+/// trait Filter: Any {
+///     fn apply(&self, input: &str) -> String;
+/// }
+///
+/// static FILTERS: LazyLock<Registry<dyn Filter>> = LazyLock::new(|| {
+///     let mut registry: Registry<dyn Filter> = Registry::new();
+///     registry
+///         .register("Upper", |filter: Upper| Box::new(filter))
+///         .register("Prefix", |filter: Prefix| Box::new(filter));
+///     registry
+/// });
+/// zdoc::serde_registry!(dyn Filter => FILTERS);
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Pipeline {
+///     // (Upper) {}, (Prefix) { prefix = "> " }
+///     filters: Vec<Box<dyn Filter>>,
+/// }
+/// ```
+///
+/// In formats without types, like JSON, trait objects are represented like
+/// externally tagged enums (`{"Prefix": {"prefix": "> "}}`, and `"Upper"` for
+/// unit structs).
+pub struct Registry<T: ?Sized> {
+    types: BTreeMap<&'static str, Registration<T>>,
+    names: BTreeMap<TypeId, &'static str>,
+    options: SerializerOptions,
+}
+
+struct Registration<T: ?Sized> {
+    deserialize: Box<DeserializeFn<T>>,
+    serialize: SerializeFn,
+}
+
+impl<T: ?Sized + 'static> Registry<T> {
+    /// Create an empty registry, which serializes values with the default
+    /// conventions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_options(SerializerOptions::new())
+    }
+
+    /// Create an empty registry, which serializes values with the given
+    /// conventions.
+    #[must_use]
+    pub const fn with_options(options: SerializerOptions) -> Self {
+        Self {
+            types: BTreeMap::new(),
+            names: BTreeMap::new(),
+            options,
+        }
+    }
+
+    /// Register the implementation `C` under the type name `ty`. `into`
+    /// converts a deserialized value into a trait object, and is usually
+    /// `|value: C| Box::new(value)`.
+    ///
+    /// Values of `C` must not serialize as typed nodes, like enums, because
+    /// the type of the node is the name of the implementation.
+    ///
+    /// # Panics
+    ///
+    /// If `ty` is empty, or if `ty` or `C` is already registered, this
+    /// panics.
+    pub fn register<C>(&mut self, ty: &'static str, into: fn(C) -> Box<T>) -> &mut Self
+    where
+        C: serde::Serialize + DeserializeOwned + 'static,
+    {
+        assert!(!ty.is_empty(), "type name must not be empty");
+        assert!(
+            self.names.insert(TypeId::of::<C>(), ty).is_none(),
+            "`{}` is already registered",
+            core::any::type_name::<C>()
+        );
+        let registration = Registration {
+            deserialize: Box::new(move |node, options| de::from_untyped(node, options).map(into)),
+            serialize: serialize_any::<C>,
+        };
+        assert!(
+            self.types.insert(ty, registration).is_none(),
+            "type `{ty}` is already registered"
+        );
+        self
+    }
+
+    /// The registered type names, in order.
+    pub fn types(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types.keys().copied()
+    }
+
+    /// The conventions that values are serialized and deserialized with.
+    #[inline]
+    #[must_use]
+    pub fn options(&self) -> &SerializerOptions {
+        &self.options
+    }
+
+    /// Deserialize a trait object from a [`Node`](crate::Node), choosing the
+    /// implementation by the type of the node.
+    ///
+    /// # Errors
+    ///
+    /// If the type of the node is not registered, or the node cannot be
+    /// deserialized into the implementation, this returns an error.
+    pub fn from_document_node(&self, node: crate::Node<'_>) -> Result<Box<T>, Error> {
+        self.dispatch(&node)
+    }
+
+    /// Deserialize a trait object from a [`builder::Node`], choosing the
+    /// implementation by the type of the node.
+    ///
+    /// # Errors
+    ///
+    /// If the type of the node is not registered, or the node cannot be
+    /// deserialized into the implementation, this returns an error.
+    pub fn from_builder_node(&self, node: &builder::Node<'_>) -> Result<Box<T>, Error> {
+        self.dispatch(&node)
+    }
+
+    /// Serialize the value behind a trait object into a node, with the name
+    /// of its implementation as the type.
+    ///
+    /// # Errors
+    ///
+    /// If the implementation is not registered, or the value cannot be
+    /// serialized, this returns an error.
+    pub fn to_builder_node(&self, value: &dyn Any) -> Result<builder::Node<'static>, Error> {
+        let Some(&ty) = self.names.get(&value.type_id()) else {
+            return Err(custom("cannot serialize a value of an unregistered type"));
+        };
+        let mut node = (self.types[ty].serialize)(value, &self.options)?;
+        if !node.ty().is_empty() {
+            return Err(custom(format!(
+                "`{ty}` serializes as a node of type `{}`, which would be replaced",
+                node.ty()
+            )));
+        }
+        node.set_ty(ty);
+        Ok(node)
+    }
+
+    /// Deserialize a trait object from any serde format. This is the
+    /// implementation of `Deserialize` for `Box<dyn Trait>` generated by
+    /// [`serde_registry!`](crate::serde_registry).
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a node of a registered type, or cannot be
+    /// deserialized into the implementation, this returns an error.
+    pub fn deserialize<'de, D>(&self, deserializer: D) -> Result<Box<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawNode::deserialize(deserializer)? {
+            RawNode::Document(node) => self.from_document_node(node),
+            RawNode::Builder(node) => self.from_builder_node(&self.retag(node)),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+
+    /// Serialize a trait object to any serde format. This is the
+    /// implementation of `Serialize` for `Box<dyn Trait>` generated by
+    /// [`serde_registry!`](crate::serde_registry).
+    ///
+    /// # Errors
+    ///
+    /// If the implementation is not registered, or the value cannot be
+    /// serialized, this returns an error.
+    pub fn serialize<S>(&self, value: &dyn Any, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let node = self
+            .to_builder_node(value)
+            .map_err(serde::ser::Error::custom)?;
+        RawNode::Builder(node).serialize(serializer)
+    }
+
+    fn dispatch<'a>(&self, node: &impl access::NodeRef<'a>) -> Result<Box<T>, Error> {
+        let ty = node.ty();
+        let Some(registration) = self.types.get(ty) else {
+            let expected = Expected(&self.types);
+            let error = if ty.is_empty() {
+                custom(format!("missing type, expected {expected}"))
+            } else {
+                custom(format!("unknown type `{ty}`, expected {expected}"))
+            };
+            return Err(error.in_node(node.concrete()));
+        };
+        (registration.deserialize)(node.concrete(), self.options)
+    }
+
+    /// Convert a node with a single entry named after a registered type, like
+    /// the externally tagged representation from formats without types, into
+    /// a node of that type. A single unnamed string argument that is a
+    /// registered type, like a unit variant, becomes an empty node of that
+    /// type.
+    fn retag<'a>(&self, mut node: builder::Node<'a>) -> builder::Node<'a> {
+        if !node.ty().is_empty() {
+            return node;
+        }
+        match (node.args(), node.children()) {
+            (
+                [
+                    builder::Arg {
+                        name: None,
+                        value: builder::Value::String(ty),
+                    },
+                ],
+                [],
+            ) if self.types.contains_key(&**ty) => builder::Node::empty().with_ty(ty.clone()),
+            ([arg], [])
+                if arg
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| self.types.contains_key(name)) =>
+            {
+                let arg = node.args_mut().pop().unwrap();
+                builder::Node::from_values([arg.value]).with_ty(arg.name.unwrap())
+            }
+            ([], [child]) if child.ty().is_empty() && self.types.contains_key(child.name()) => {
+                let mut child = node.children_mut().pop().unwrap();
+                let ty = alloc::string::String::from(child.name());
+                child.set_name("").set_ty(ty);
+                child
+            }
+            _ => node,
+        }
+    }
+}
+
+impl<T: ?Sized + 'static> Default for Registry<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Registry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("types", &self.types.keys())
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+fn serialize_any<C: serde::Serialize + 'static>(
+    value: &dyn Any,
+    options: &SerializerOptions,
+) -> Result<builder::Node<'static>, Error> {
+    let value = value
+        .downcast_ref::<C>()
+        .expect("registered under the type ID of the value");
+    super::to_builder_node_with(value, options)
+}
+
+fn custom(msg: impl fmt::Display) -> Error {
+    <Error as serde::de::Error>::custom(msg)
+}
+
+/// The registered type names, formatted like the expected variants in serde
+/// errors.
+struct Expected<'r, T: ?Sized>(&'r BTreeMap<&'static str, Registration<T>>);
+
+impl<T: ?Sized> fmt::Display for Expected<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut types = self.0.keys();
+        match self.0.len() {
+            0 => f.write_str("a registered type, but there are none"),
+            1 => write!(f, "`{}`", types.next().unwrap()),
+            2 => write!(
+                f,
+                "`{}` or `{}`",
+                types.next().unwrap(),
+                types.next().unwrap()
+            ),
+            _ => {
+                f.write_str("one of ")?;
+                for (i, ty) in types.enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "`{ty}`")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
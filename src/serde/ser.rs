@@ -1,59 +1,311 @@
-use alloc::{
-    borrow::{Cow, ToOwned},
-    string::{String, ToString as _},
-};
-use serde::{Serialize as _, ser::Impossible};
+use alloc::borrow::{Cow, ToOwned as _};
+use serde::ser::Impossible;
 
 use crate::{
+    ValueRef,
     builder::{Arg, Entry, Node, Value},
-    serde::Error,
+    serde::{Error, ErrorKind, Layout, SerializerOptions, UnitVariants, raw},
 };
 
-macro_rules! fwd_ser_entry {
-    ($function:ident, $value:ty) => {
+/// Entry that the serializers in this module write a value into.
+///
+/// The serializers implement the conventions of this crate on top of this
+/// trait and [`NodeSink`], which are implemented for the entries and nodes of
+/// a [`Builder`](crate::Builder), and for those of a
+/// [`Tree`](super::tree::Tree).
+pub trait EntrySink: Sized {
+    /// The entry as a node.
+    type Node: NodeSink;
+
+    /// Replace the entry with an unnamed value.
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error>;
+
+    /// Replace the entry with an unnamed, empty node with the type `ty`.
+    fn set_empty_node(self, ty: &'static str) -> Result<(), Error>;
+
+    /// Replace the entry with `node`, like a captured [`RawNode`](raw::RawNode).
+    fn set_builder_node(self, node: Node<'static>) -> Result<(), Error>;
+
+    /// Turn the entry into a node, unless it is one already.
+    fn into_node(self) -> Self::Node;
+}
+
+/// Node that the serializers in this module write a value into. See
+/// [`EntrySink`].
+pub trait NodeSink: Sized {
+    /// Entry that is not part of a node yet.
+    type Entry;
+    /// Name of an entry.
+    type Key;
+    /// Sink for an [`Entry`](Self::Entry) of this node.
+    type EntrySink<'s>: EntrySink
+    where
+        Self: 's;
+
+    /// Replace the node with an unnamed node with `value` as its only
+    /// argument.
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error>;
+
+    /// Replace the node with `node`, like a captured [`RawNode`](raw::RawNode).
+    fn set_builder_node(self, node: Node<'static>) -> Result<(), Error>;
+
+    fn set_type(&mut self, ty: &'static str) -> Result<(), Error>;
+
+    fn has_type(&self) -> bool;
+
+    /// Reserve room for `additional` more children.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Create a detached null entry.
+    fn new_entry(&mut self) -> Self::Entry;
+
+    /// Get a sink for a detached entry.
+    fn entry_sink<'s>(&'s mut self, entry: &'s mut Self::Entry) -> Self::EntrySink<'s>;
+
+    fn key(&mut self, key: &str) -> Result<Self::Key, Error>;
+
+    fn static_key(&mut self, key: &'static str) -> Result<Self::Key, Error>;
+
+    fn key_str<'k>(&'k self, key: &'k Self::Key) -> &'k str;
+
+    fn set_name(&mut self, entry: &mut Self::Entry, name: Self::Key);
+
+    fn classify(&self, entry: &Self::Entry) -> EntryKind;
+
+    /// Set the type of the node to the string value of `entry`, or to the
+    /// type of `entry` if it is a node.
+    fn set_type_from(&mut self, entry: Self::Entry);
+
+    /// Move the entries of `entry`, which must be a node, to the end of the
+    /// entries of the node.
+    fn append_entries(&mut self, entry: Self::Entry);
+
+    /// Add `entry` to the end of its arguments or children, like
+    /// [`Node::push()`].
+    fn push_entry(&mut self, entry: Self::Entry);
+
+    /// Add `entry` while preserving the order of entries, like
+    /// [`Node::push_ordered()`].
+    fn push_entry_ordered(&mut self, entry: Self::Entry);
+
+    /// Turn an argument into a node with its name and its value as the only
+    /// argument, like [`Arg::into_key_value_node()`].
+    fn key_value_node(&mut self, entry: Self::Entry) -> Self::Entry;
+}
+
+/// What [`push_field()`] needs to know about an entry.
+#[derive(Clone, Copy)]
+pub enum EntryKind {
+    /// A string value.
+    String,
+    /// Any other value.
+    Value,
+    /// A node.
+    Node { empty: bool, typed: bool },
+}
+
+/// Serializer for an entry, using the given options.
+pub struct EntrySerializer<E> {
+    pub entry: E,
+    pub options: SerializerOptions,
+}
+
+/// Serializer for a node, using the given options.
+pub struct NodeSerializer<N> {
+    pub node: N,
+    pub options: SerializerOptions,
+}
+
+/// Serialize `value` into a new, detached entry of `node`.
+fn serialize_entry<N, T>(
+    node: &mut N,
+    value: &T,
+    options: SerializerOptions,
+) -> Result<N::Entry, Error>
+where
+    N: NodeSink,
+    T: ?Sized + serde::Serialize,
+{
+    let mut entry = node.new_entry();
+    value.serialize(EntrySerializer {
+        entry: node.entry_sink(&mut entry),
+        options,
+    })?;
+    Ok(entry)
+}
+
+/// Push the field `name` of a struct or map to `node`, representing the tag
+/// and content fields of tagged enums by the node itself.
+fn push_field<N: NodeSink>(
+    node: &mut N,
+    name: N::Key,
+    mut entry: N::Entry,
+    options: SerializerOptions,
+    layout: Layout,
+) {
+    let kind = node.classify(&entry);
+    let name_str = node.key_str(&name);
+    let is_tag = options.tag == Some(name_str);
+    let is_content = options.content == Some(name_str);
+
+    if is_tag && !node.has_type() {
+        match kind {
+            // A unit variant with `UnitVariants::TypedNode` is an empty node.
+            EntryKind::String | EntryKind::Node { empty: true, .. } => {
+                node.set_type_from(entry);
+                return;
+            }
+            EntryKind::Value | EntryKind::Node { .. } => {}
+        }
+    }
+
+    if is_content && node.has_type() {
+        match kind {
+            EntryKind::String | EntryKind::Value => {
+                push(node, entry, options.seqs);
+                return;
+            }
+            EntryKind::Node { typed: false, .. } => {
+                node.append_entries(entry);
+                return;
+            }
+            EntryKind::Node { typed: true, .. } => {}
+        }
+    }
+
+    node.set_name(&mut entry, name);
+    push(node, entry, layout);
+}
+
+/// Push `entry` to `node` with the given layout.
+fn push<N: NodeSink>(node: &mut N, entry: N::Entry, layout: Layout) {
+    match layout {
+        Layout::Split => node.push_entry(entry),
+        Layout::Ordered => node.push_entry_ordered(entry),
+        Layout::Children => {
+            let entry = node.key_value_node(entry);
+            node.push_entry(entry);
+        }
+    }
+}
+
+impl<E: EntrySink> EntrySerializer<E> {
+    #[inline]
+    fn set(self, value: ValueRef<'_>) -> Result<(), Error> {
+        self.entry.set_value(value)
+    }
+
+    #[inline]
+    fn into_node(self) -> NodeSerializer<E::Node> {
+        NodeSerializer {
+            node: self.entry.into_node(),
+            options: self.options,
+        }
+    }
+}
+
+impl<N: NodeSink> NodeSerializer<N> {
+    #[inline]
+    fn set(self, value: ValueRef<'_>) -> Result<(), Error> {
+        self.node.set_value(value)
+    }
+}
+
+/// Implement the serializer methods for simple values by calling `set()`.
+macro_rules! ser_values {
+    () => {
+        #[inline]
+        fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Bool(v))
+        }
+
+        #[inline]
+        fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Int(v.into()))
+        }
+
+        #[inline]
+        fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Int(v.into()))
+        }
+
+        #[inline]
+        fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Int(v.into()))
+        }
+
+        #[inline]
+        fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Int(v))
+        }
+
+        #[inline]
+        fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Uint(v.into()))
+        }
+
+        #[inline]
+        fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Uint(v.into()))
+        }
+
+        #[inline]
+        fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Uint(v.into()))
+        }
+
+        #[inline]
+        fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Uint(v))
+        }
+
+        #[inline]
+        fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Float(v.into()))
+        }
+
         #[inline]
-        #[allow(unused_mut)]
-        fn $function(mut self, v: $value) -> Result<Self::Ok, Self::Error> {
-            *self = v.serialize(ValueSerializer)?.into();
-            Ok(())
+        fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Float(v))
+        }
+
+        #[inline]
+        fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::String(v.encode_utf8(&mut [0; 4])))
+        }
+
+        #[inline]
+        fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::String(v))
+        }
+
+        #[inline]
+        fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Binary(v))
+        }
+
+        #[inline]
+        fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+            self.set(ValueRef::Null)
         }
     };
 }
 
-impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
+impl<E: EntrySink> serde::Serializer for EntrySerializer<E> {
     type Ok = ();
     type Error = Error;
-    type SerializeSeq = SeqSerializer<'a, 'b>;
-    type SerializeTuple = SeqSerializer<'a, 'b>;
-    type SerializeTupleStruct = SeqSerializer<'a, 'b>;
-    type SerializeTupleVariant = SeqSerializer<'a, 'b>;
-    type SerializeMap = MapSerializer<'a, 'b>;
-    type SerializeStruct = &'a mut Node<'b>;
-    type SerializeStructVariant = &'a mut Node<'b>;
-
-    fwd_ser_entry!(serialize_bool, bool);
-    fwd_ser_entry!(serialize_i8, i8);
-    fwd_ser_entry!(serialize_i16, i16);
-    fwd_ser_entry!(serialize_i32, i32);
-    fwd_ser_entry!(serialize_i64, i64);
-    fwd_ser_entry!(serialize_u8, u8);
-    fwd_ser_entry!(serialize_u16, u16);
-    fwd_ser_entry!(serialize_u32, u32);
-    fwd_ser_entry!(serialize_u64, u64);
-    fwd_ser_entry!(serialize_f32, f32);
-    fwd_ser_entry!(serialize_f64, f64);
-    fwd_ser_entry!(serialize_char, char);
-    fwd_ser_entry!(serialize_str, &str);
-    fwd_ser_entry!(serialize_bytes, &[u8]);
+    type SerializeSeq = SeqSerializer<E::Node>;
+    type SerializeTuple = SeqSerializer<E::Node>;
+    type SerializeTupleStruct = SeqSerializer<E::Node>;
+    type SerializeTupleVariant = SeqSerializer<E::Node>;
+    type SerializeMap = MapSerializer<E::Node>;
+    type SerializeStruct = NodeSerializer<E::Node>;
+    type SerializeStructVariant = NodeSerializer<E::Node>;
 
-    #[inline]
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        *self = Entry::Arg(Arg {
-            name: None,
-            value: Value::Null,
-        });
-        Ok(())
-    }
+    ser_values!();
 
     #[inline]
     fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
@@ -65,8 +317,7 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
 
     #[inline]
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        *self = Entry::Child(Node::empty());
-        Ok(())
+        self.entry.set_empty_node("")
     }
 
     #[inline]
@@ -77,25 +328,28 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
     #[inline]
     fn serialize_unit_variant(
         self,
-        name: &'static str,
-        variant_index: u32,
+        _name: &'static str,
+        _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        *self = ValueSerializer
-            .serialize_unit_variant(name, variant_index, variant)?
-            .into();
-        Ok(())
+        match self.options.unit_variants {
+            UnitVariants::String => self.serialize_str(variant),
+            UnitVariants::TypedNode => self.entry.set_empty_node(variant),
+        }
     }
 
     #[inline]
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        if name == raw::TOKEN {
+            return self.entry.set_builder_node(raw::capture(value)?);
+        }
         value.serialize(self)
     }
 
@@ -110,18 +364,18 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
     where
         T: ?Sized + serde::Serialize,
     {
-        self.reset_as_node()
+        self.into_node()
             .serialize_newtype_variant(name, variant_index, variant, value)
     }
 
     #[inline]
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        self.reset_as_node().serialize_seq(len)
+        self.into_node().serialize_seq(len)
     }
 
     #[inline]
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        self.reset_as_node().serialize_tuple(len)
+        self.into_node().serialize_tuple(len)
     }
 
     #[inline]
@@ -130,7 +384,7 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        self.reset_as_node().serialize_tuple_struct(name, len)
+        self.into_node().serialize_tuple_struct(name, len)
     }
 
     #[inline]
@@ -141,13 +395,13 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.reset_as_node()
+        self.into_node()
             .serialize_tuple_variant(name, variant_index, variant, len)
     }
 
     #[inline]
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        self.reset_as_node().serialize_map(len)
+        self.into_node().serialize_map(len)
     }
 
     #[inline]
@@ -156,7 +410,7 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.reset_as_node().serialize_struct(name, len)
+        self.into_node().serialize_struct(name, len)
     }
 
     #[inline]
@@ -167,22 +421,11 @@ impl<'a, 'b> serde::Serializer for &'a mut Entry<'b> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.reset_as_node()
+        self.into_node()
             .serialize_struct_variant(name, variant_index, variant, len)
     }
 }
 
-macro_rules! fwd_ser_node {
-    ($function:ident, $value:ty) => {
-        #[inline]
-        #[allow(unused_mut)]
-        fn $function(mut self, v: $value) -> Result<Self::Ok, Self::Error> {
-            *self = Node::from_values(Some(ValueSerializer.$function(v)?));
-            Ok(())
-        }
-    };
-}
-
 /// Serialize as a document node.
 ///
 /// For plain values, this produces a single, unnamed node with the given value
@@ -190,37 +433,18 @@ macro_rules! fwd_ser_node {
 ///
 /// When serializing named types (structs and enums), this will only ever set
 /// the `type` of the node - not the name.
-impl<'a, 'b: 'a> serde::Serializer for &'a mut Node<'b> {
+impl<N: NodeSink> serde::Serializer for NodeSerializer<N> {
     type Ok = ();
     type Error = Error;
-    type SerializeSeq = SeqSerializer<'a, 'b>;
-    type SerializeTuple = SeqSerializer<'a, 'b>;
-    type SerializeTupleStruct = SeqSerializer<'a, 'b>;
-    type SerializeTupleVariant = SeqSerializer<'a, 'b>;
-    type SerializeMap = MapSerializer<'a, 'b>;
+    type SerializeSeq = SeqSerializer<N>;
+    type SerializeTuple = SeqSerializer<N>;
+    type SerializeTupleStruct = SeqSerializer<N>;
+    type SerializeTupleVariant = SeqSerializer<N>;
+    type SerializeMap = MapSerializer<N>;
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fwd_ser_node!(serialize_bool, bool);
-    fwd_ser_node!(serialize_i8, i8);
-    fwd_ser_node!(serialize_i16, i16);
-    fwd_ser_node!(serialize_i32, i32);
-    fwd_ser_node!(serialize_i64, i64);
-    fwd_ser_node!(serialize_u8, u8);
-    fwd_ser_node!(serialize_u16, u16);
-    fwd_ser_node!(serialize_u32, u32);
-    fwd_ser_node!(serialize_u64, u64);
-    fwd_ser_node!(serialize_f32, f32);
-    fwd_ser_node!(serialize_f64, f64);
-    fwd_ser_node!(serialize_char, char);
-    fwd_ser_node!(serialize_str, &str);
-    fwd_ser_node!(serialize_bytes, &[u8]);
-
-    #[inline]
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        *self = Node::from_values(Some(Value::Null));
-        Ok(())
-    }
+    ser_values!();
 
     #[inline]
     fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
@@ -242,30 +466,32 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut Node<'b> {
 
     #[inline]
     fn serialize_unit_variant(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        self.set_ty(variant);
-        Ok(())
+        self.node.set_type(variant)
     }
 
     #[inline]
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        if name == raw::TOKEN {
+            return self.node.set_builder_node(raw::capture(value)?);
+        }
         value.serialize(self)
     }
 
     #[inline]
     fn serialize_newtype_variant<T>(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
@@ -274,50 +500,53 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut Node<'b> {
     where
         T: ?Sized + serde::Serialize,
     {
-        let mut entry = Entry::null();
-        value.serialize(&mut entry)?;
-        self.set_ty(variant);
-        self.push(entry);
+        let entry = serialize_entry(&mut self.node, value, self.options)?;
+        self.node.set_type(variant)?;
+        push(&mut self.node, entry, self.options.seqs);
         Ok(())
     }
 
     #[inline]
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Ok(SeqSerializer { seq: self })
+        Ok(SeqSerializer {
+            seq: self.node,
+            options: self.options,
+        })
     }
 
     #[inline]
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Ok(SeqSerializer { seq: self })
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
     }
 
     #[inline]
     fn serialize_tuple_struct(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(SeqSerializer { seq: self })
+        self.serialize_seq(Some(len))
     }
 
     #[inline]
     fn serialize_tuple_variant(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.set_ty(variant);
-        Ok(SeqSerializer { seq: self })
+        self.node.set_type(variant)?;
+        self.serialize_seq(Some(len))
     }
 
     #[inline]
-    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        self.children_mut().reserve(len.unwrap_or(0));
+    fn serialize_map(mut self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.node.reserve(len.unwrap_or(0));
         Ok(MapSerializer {
-            node: self,
+            node: self.node,
             pending_key: None,
+            options: self.options,
         })
     }
 
@@ -334,7 +563,7 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut Node<'b> {
 
     #[inline]
     fn serialize_struct_variant(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
@@ -342,16 +571,17 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut Node<'b> {
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         // Not preallocating; we don't know if the fields will be serialized as
         // arguments or children.
-        self.set_ty(variant);
+        self.node.set_type(variant)?;
         Ok(self)
     }
 }
 
-pub struct SeqSerializer<'a, 'b> {
-    seq: &'a mut Node<'b>,
+pub struct SeqSerializer<N> {
+    seq: N,
+    options: SerializerOptions,
 }
 
-impl serde::ser::SerializeSeq for SeqSerializer<'_, '_> {
+impl<N: NodeSink> serde::ser::SerializeSeq for SeqSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -359,9 +589,8 @@ impl serde::ser::SerializeSeq for SeqSerializer<'_, '_> {
     where
         T: ?Sized + serde::Serialize,
     {
-        let mut child = Entry::null();
-        value.serialize(&mut child)?;
-        self.seq.push_ordered(child);
+        let child = serialize_entry(&mut self.seq, value, self.options)?;
+        push(&mut self.seq, child, self.options.seqs);
         Ok(())
     }
 
@@ -371,7 +600,7 @@ impl serde::ser::SerializeSeq for SeqSerializer<'_, '_> {
     }
 }
 
-impl serde::ser::SerializeTuple for SeqSerializer<'_, '_> {
+impl<N: NodeSink> serde::ser::SerializeTuple for SeqSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -388,7 +617,7 @@ impl serde::ser::SerializeTuple for SeqSerializer<'_, '_> {
     }
 }
 
-impl serde::ser::SerializeTupleStruct for SeqSerializer<'_, '_> {
+impl<N: NodeSink> serde::ser::SerializeTupleStruct for SeqSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -405,7 +634,7 @@ impl serde::ser::SerializeTupleStruct for SeqSerializer<'_, '_> {
     }
 }
 
-impl serde::ser::SerializeTupleVariant for SeqSerializer<'_, '_> {
+impl<N: NodeSink> serde::ser::SerializeTupleVariant for SeqSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -422,28 +651,13 @@ impl serde::ser::SerializeTupleVariant for SeqSerializer<'_, '_> {
     }
 }
 
-struct MapKey<'a>(Cow<'a, str>);
-
-impl From<String> for MapKey<'_> {
-    #[inline]
-    fn from(value: String) -> Self {
-        MapKey(value.into())
-    }
-}
-
-impl From<&'static str> for MapKey<'_> {
-    #[inline]
-    fn from(value: &'static str) -> Self {
-        MapKey(value.into())
-    }
-}
-
-pub struct MapSerializer<'a, 'b> {
-    node: &'a mut Node<'b>,
-    pending_key: Option<MapKey<'b>>,
+pub struct MapSerializer<N: NodeSink> {
+    node: N,
+    pending_key: Option<N::Key>,
+    options: SerializerOptions,
 }
 
-impl serde::ser::SerializeMap for MapSerializer<'_, '_> {
+impl<N: NodeSink> serde::ser::SerializeMap for MapSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -455,24 +669,22 @@ impl serde::ser::SerializeMap for MapSerializer<'_, '_> {
             self.pending_key.is_none(),
             "unbalanced calls to serialize_key()/serialize_value()"
         );
-        let mut pending_key = MapKey(Cow::Borrowed(""));
-        key.serialize(&mut pending_key)?;
-        self.pending_key = Some(pending_key);
+        self.pending_key = Some(key.serialize(KeySerializer {
+            node: &mut self.node,
+        })?);
         Ok(())
     }
 
-    fn serialize_value<T>(&mut self, serialize_value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
-        let mut value = Entry::null();
-        serialize_value.serialize(&mut value)?;
+        let entry = serialize_entry(&mut self.node, value, self.options)?;
         let key = self
             .pending_key
             .take()
             .expect("unbalanced calls to serialize_key()/serialize_value()");
-        value.set_name(key.0);
-        self.node.push_ordered(value);
+        push_field(&mut self.node, key, entry, self.options, self.options.maps);
         Ok(())
     }
 
@@ -482,7 +694,7 @@ impl serde::ser::SerializeMap for MapSerializer<'_, '_> {
     }
 }
 
-impl serde::ser::SerializeStruct for &mut Node<'_> {
+impl<N: NodeSink> serde::ser::SerializeStruct for NodeSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -490,11 +702,15 @@ impl serde::ser::SerializeStruct for &mut Node<'_> {
     where
         T: ?Sized + serde::Serialize,
     {
-        let mut entry = Entry::null();
-        value.serialize(&mut entry)?;
-        entry.set_name(key);
-        self.push(entry);
-
+        let entry = serialize_entry(&mut self.node, value, self.options)?;
+        let key = self.node.static_key(key)?;
+        push_field(
+            &mut self.node,
+            key,
+            entry,
+            self.options,
+            self.options.structs,
+        );
         Ok(())
     }
 
@@ -504,7 +720,7 @@ impl serde::ser::SerializeStruct for &mut Node<'_> {
     }
 }
 
-impl serde::ser::SerializeStructVariant for &mut Node<'_> {
+impl<N: NodeSink> serde::ser::SerializeStructVariant for NodeSerializer<N> {
     type Ok = ();
     type Error = Error;
 
@@ -521,52 +737,145 @@ impl serde::ser::SerializeStructVariant for &mut Node<'_> {
     }
 }
 
-macro_rules! must_be_string_key {
-    ($func:ident, $ty:ty) => {
-        #[inline]
-        fn $func(self, _key: $ty) -> Result<Self::Ok, Self::Error> {
-            Err(Error::NonStringMapKey)
+macro_rules! forward_serializer {
+    ($serializer:ident { $field:ident }; $($function:ident($($arg:ident: $ty:ty),*) -> $ret:ident;)*) => {
+        $(
+            #[inline]
+            fn $function(self $(, $arg: $ty)*) -> Result<Self::$ret, Self::Error> {
+                let serializer = $serializer {
+                    $field: self,
+                    options: SerializerOptions::new(),
+                };
+                serializer.$function($($arg),*)
+            }
+        )*
+    };
+    ($serializer:ident { $field:ident }; $($function:ident<T>($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            #[inline]
+            fn $function<T>(self $(, $arg: $ty)*) -> Result<Self::Ok, Self::Error>
+            where
+                T: ?Sized + serde::Serialize,
+            {
+                let serializer = $serializer {
+                    $field: self,
+                    options: SerializerOptions::new(),
+                };
+                serializer.$function($($arg),*)
+            }
+        )*
+    };
+}
+
+/// Serialize with the default [`SerializerOptions`].
+macro_rules! default_serializer {
+    ($target:ty, $serializer:ident, $field:ident) => {
+        impl<'a, 'b> serde::Serializer for $target {
+            type Ok = ();
+            type Error = Error;
+            type SerializeSeq = SeqSerializer<&'a mut Node<'b>>;
+            type SerializeTuple = SeqSerializer<&'a mut Node<'b>>;
+            type SerializeTupleStruct = SeqSerializer<&'a mut Node<'b>>;
+            type SerializeTupleVariant = SeqSerializer<&'a mut Node<'b>>;
+            type SerializeMap = MapSerializer<&'a mut Node<'b>>;
+            type SerializeStruct = NodeSerializer<&'a mut Node<'b>>;
+            type SerializeStructVariant = NodeSerializer<&'a mut Node<'b>>;
+
+            forward_serializer! {
+                $serializer { $field };
+                serialize_bool(v: bool) -> Ok;
+                serialize_i8(v: i8) -> Ok;
+                serialize_i16(v: i16) -> Ok;
+                serialize_i32(v: i32) -> Ok;
+                serialize_i64(v: i64) -> Ok;
+                serialize_u8(v: u8) -> Ok;
+                serialize_u16(v: u16) -> Ok;
+                serialize_u32(v: u32) -> Ok;
+                serialize_u64(v: u64) -> Ok;
+                serialize_f32(v: f32) -> Ok;
+                serialize_f64(v: f64) -> Ok;
+                serialize_char(v: char) -> Ok;
+                serialize_str(v: &str) -> Ok;
+                serialize_bytes(v: &[u8]) -> Ok;
+                serialize_none() -> Ok;
+                serialize_unit() -> Ok;
+                serialize_unit_struct(name: &'static str) -> Ok;
+                serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str) -> Ok;
+                serialize_seq(len: Option<usize>) -> SerializeSeq;
+                serialize_tuple(len: usize) -> SerializeTuple;
+                serialize_tuple_struct(name: &'static str, len: usize) -> SerializeTupleStruct;
+                serialize_tuple_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> SerializeTupleVariant;
+                serialize_map(len: Option<usize>) -> SerializeMap;
+                serialize_struct(name: &'static str, len: usize) -> SerializeStruct;
+                serialize_struct_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> SerializeStructVariant;
+            }
+
+            forward_serializer! {
+                $serializer { $field };
+                serialize_some<T>(value: &T);
+                serialize_newtype_struct<T>(name: &'static str, value: &T);
+                serialize_newtype_variant<T>(name: &'static str, variant_index: u32, variant: &'static str, value: &T);
+            }
         }
     };
 }
 
-impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
-    type Ok = ();
+default_serializer!(&'a mut Entry<'b>, EntrySerializer, entry);
+default_serializer!(&'a mut Node<'b>, NodeSerializer, node);
+
+/// Serializer for the keys of maps, which must be strings.
+struct KeySerializer<'a, N> {
+    node: &'a mut N,
+}
+
+macro_rules! must_be_string_key {
+    ($($func:ident($ty:ty);)*) => {
+        $(
+            #[inline]
+            fn $func(self, _key: $ty) -> Result<Self::Ok, Self::Error> {
+                Err(ErrorKind::NonStringMapKey.into())
+            }
+        )*
+    };
+}
+
+impl<N: NodeSink> serde::Serializer for KeySerializer<'_, N> {
+    type Ok = N::Key;
     type Error = Error;
-    type SerializeSeq = SeqSerializer<'a, 'b>;
-    type SerializeTuple = SeqSerializer<'a, 'b>;
-    type SerializeTupleStruct = SeqSerializer<'a, 'b>;
-    type SerializeTupleVariant = SeqSerializer<'a, 'b>;
-    type SerializeMap = MapSerializer<'a, 'b>;
-    type SerializeStruct = &'a mut Node<'b>;
-    type SerializeStructVariant = &'a mut Node<'b>;
-
-    must_be_string_key!(serialize_bool, bool);
-    must_be_string_key!(serialize_i8, i8);
-    must_be_string_key!(serialize_i16, i16);
-    must_be_string_key!(serialize_i32, i32);
-    must_be_string_key!(serialize_i64, i64);
-    must_be_string_key!(serialize_u8, u8);
-    must_be_string_key!(serialize_u16, u16);
-    must_be_string_key!(serialize_u32, u32);
-    must_be_string_key!(serialize_u64, u64);
-    must_be_string_key!(serialize_f32, f32);
-    must_be_string_key!(serialize_f64, f64);
-    must_be_string_key!(serialize_bytes, &[u8]);
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    must_be_string_key! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_bytes(&[u8]);
+    }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        *self = MapKey(Cow::Owned(v.to_string()));
-        Ok(())
+        self.node.key(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        *self = MapKey(Cow::Owned(v.to_owned()));
-        Ok(())
+        self.node.key(v)
     }
 
     #[inline]
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -579,12 +888,12 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
 
     #[inline]
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        self.serialize_none()
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -594,8 +903,7 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        *self = MapKey(Cow::Borrowed(variant));
-        Ok(())
+        self.node.static_key(variant)
     }
 
     #[inline]
@@ -621,17 +929,17 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
     where
         T: ?Sized + serde::Serialize,
     {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -640,7 +948,7 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -651,12 +959,12 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -665,7 +973,7 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 
     #[inline]
@@ -676,203 +984,154 @@ impl<'a, 'b: 'a> serde::Serializer for &'a mut MapKey<'b> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(Error::NonStringMapKey)
+        Err(ErrorKind::NonStringMapKey.into())
     }
 }
 
-struct ValueSerializer;
-impl serde::Serializer for ValueSerializer {
-    type Ok = Value<'static>;
-    type Error = Error;
-    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
-    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
-    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
-    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
-    type SerializeMap = Impossible<Self::Ok, Self::Error>;
-    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
-    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
-
-    #[inline]
-    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Bool(v))
-    }
-
-    #[inline]
-    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Int(v.into()))
-    }
-
-    #[inline]
-    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Int(v.into()))
-    }
-
-    #[inline]
-    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Int(v.into()))
-    }
-
-    #[inline]
-    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Int(v))
-    }
-
-    #[inline]
-    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Uint(v.into()))
-    }
+impl<'a, 'b> EntrySink for &'a mut Entry<'b> {
+    type Node = &'a mut Node<'b>;
 
     #[inline]
-    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Uint(v.into()))
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error> {
+        *self = Value::from(value).into_static().into();
+        Ok(())
     }
 
     #[inline]
-    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Uint(v.into()))
+    fn set_empty_node(self, ty: &'static str) -> Result<(), Error> {
+        *self = Entry::Child(Node::empty().with_ty(ty));
+        Ok(())
     }
 
     #[inline]
-    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Uint(v))
+    fn set_builder_node(self, node: Node<'static>) -> Result<(), Error> {
+        *self = Entry::Child(node);
+        Ok(())
     }
 
     #[inline]
-    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Float(v.into()))
+    fn into_node(self) -> Self::Node {
+        self.reset_as_node()
     }
+}
 
-    #[inline]
-    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Float(v))
-    }
+impl<'b> NodeSink for &mut Node<'b> {
+    type Entry = Entry<'b>;
+    type Key = Cow<'b, str>;
+    type EntrySink<'s>
+        = &'s mut Entry<'b>
+    where
+        Self: 's;
 
     #[inline]
-    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_string().into()))
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error> {
+        *self = Node::from_values(Some(Value::from(value).into_static()));
+        Ok(())
     }
 
     #[inline]
-    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_owned().into()))
+    fn set_builder_node(self, node: Node<'static>) -> Result<(), Error> {
+        *self = node;
+        Ok(())
     }
 
     #[inline]
-    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Binary(v.to_owned().into()))
+    fn set_type(&mut self, ty: &'static str) -> Result<(), Error> {
+        Node::set_ty(self, ty);
+        Ok(())
     }
 
     #[inline]
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Null)
+    fn has_type(&self) -> bool {
+        !self.ty().is_empty()
     }
 
     #[inline]
-    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
-    where
-        T: ?Sized + serde::Serialize,
-    {
-        value.serialize(self)
+    fn reserve(&mut self, additional: usize) {
+        self.children_mut().reserve(additional);
     }
 
     #[inline]
-    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Null)
+    fn new_entry(&mut self) -> Self::Entry {
+        Entry::null()
     }
 
     #[inline]
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        self.serialize_unit()
+    fn entry_sink<'s>(&'s mut self, entry: &'s mut Self::Entry) -> Self::EntrySink<'s> {
+        entry
     }
 
     #[inline]
-    fn serialize_unit_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        variant: &'static str,
-    ) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(Cow::Borrowed(variant)))
+    fn key(&mut self, key: &str) -> Result<Self::Key, Error> {
+        Ok(Cow::Owned(key.to_owned()))
     }
 
     #[inline]
-    fn serialize_newtype_struct<T>(
-        self,
-        _name: &'static str,
-        value: &T,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        T: ?Sized + serde::Serialize,
-    {
-        value.serialize(self)
+    fn static_key(&mut self, key: &'static str) -> Result<Self::Key, Error> {
+        Ok(Cow::Borrowed(key))
     }
 
     #[inline]
-    fn serialize_newtype_variant<T>(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        T: ?Sized + serde::Serialize,
-    {
-        Err(Error::CompoundValue)
+    fn key_str<'k>(&'k self, key: &'k Self::Key) -> &'k str {
+        key
     }
 
     #[inline]
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(Error::CompoundValue)
+    fn set_name(&mut self, entry: &mut Self::Entry, name: Self::Key) {
+        entry.set_name(name);
     }
 
-    #[inline]
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(Error::CompoundValue)
+    fn classify(&self, entry: &Self::Entry) -> EntryKind {
+        match entry {
+            Entry::Arg(Arg {
+                value: Value::String(_),
+                ..
+            }) => EntryKind::String,
+            Entry::Arg(_) => EntryKind::Value,
+            Entry::Child(child) => EntryKind::Node {
+                empty: child.args.is_empty() && child.children.is_empty(),
+                typed: !child.ty().is_empty(),
+            },
+        }
     }
 
-    #[inline]
-    fn serialize_tuple_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(Error::CompoundValue)
+    fn set_type_from(&mut self, entry: Self::Entry) {
+        match entry {
+            Entry::Arg(Arg {
+                value: Value::String(ty),
+                ..
+            }) => {
+                Node::set_ty(self, ty);
+            }
+            Entry::Child(child) => {
+                Node::set_ty(self, child.ty);
+            }
+            Entry::Arg(_) => {}
+        }
     }
 
-    #[inline]
-    fn serialize_tuple_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(Error::CompoundValue)
+    fn append_entries(&mut self, entry: Self::Entry) {
+        if let Entry::Child(child) = entry {
+            self.args.extend(child.args);
+            self.children.extend(child.children);
+        }
     }
 
     #[inline]
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Err(Error::CompoundValue)
+    fn push_entry(&mut self, entry: Self::Entry) {
+        Node::push(self, entry);
     }
 
     #[inline]
-    fn serialize_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStruct, Self::Error> {
-        Err(Error::CompoundValue)
+    fn push_entry_ordered(&mut self, entry: Self::Entry) {
+        Node::push_ordered(self, entry);
     }
 
     #[inline]
-    fn serialize_struct_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(Error::CompoundValue)
+    fn key_value_node(&mut self, entry: Self::Entry) -> Self::Entry {
+        match entry {
+            Entry::Arg(arg) => Entry::Child(arg.into_key_value_node()),
+            child @ Entry::Child(_) => child,
+        }
     }
 }
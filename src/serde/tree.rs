@@ -0,0 +1,628 @@
+use alloc::{string::String, vec::Vec};
+
+use super::ser::{EntryKind, EntrySink, NodeSink};
+use crate::{
+    ValueRef,
+    builder::{self, BuildRawNode, RawBuilder, RawNode},
+    serde::{Error, ErrorKind},
+};
+
+/// Index of an entry in a [`Tree`].
+type Index = u32;
+
+const NONE: Index = Index::MAX;
+
+/// Flat tree of entries, which the serializers write into instead of a tree of
+/// [`builder::Node`]s, and which is then written into a [`RawBuilder`].
+///
+/// The entries of a node are a linked list, so that entries can be added to
+/// a node after its children, and moved between nodes without allocating.
+/// Strings and binary data are copied into shared buffers. All buffers are
+/// kept between documents, so serializing documents of similar shapes does
+/// not allocate, apart from interned strings in the [`RawBuilder`].
+///
+/// The nodes, arguments, and strings are written into the [`RawBuilder`] in
+/// the same order as from a [`builder::Node`], so the documents are
+/// byte-identical to those built by [`Builder`](crate::Builder).
+#[derive(Clone, Default)]
+pub(crate) struct Tree {
+    entries: Vec<Entry>,
+    text: String,
+    binary: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    name: Range,
+    next: Index,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Arg(Value),
+    Node(Node),
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    ty: Range,
+    first: Index,
+    last: Index,
+    args: u32,
+    children: u32,
+}
+
+#[derive(Clone, Copy)]
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    String(Range),
+    Binary(Range),
+}
+
+/// Range of [`Tree::text`] or [`Tree::binary`].
+#[derive(Clone, Copy)]
+pub(crate) struct Range {
+    start: u32,
+    len: u32,
+}
+
+impl Range {
+    const EMPTY: Self = Self { start: 0, len: 0 };
+
+    fn new(start: usize, len: usize) -> Result<Self, Error> {
+        let too_large = |_| Error::from(ErrorKind::TooLarge);
+        Ok(Self {
+            start: u32::try_from(start).map_err(too_large)?,
+            len: u32::try_from(len).map_err(too_large)?,
+        })
+    }
+}
+
+impl Node {
+    const EMPTY: Self = Self {
+        ty: Range::EMPTY,
+        first: NONE,
+        last: NONE,
+        args: 0,
+        children: 0,
+    };
+}
+
+impl Tree {
+    /// Clear the tree, keeping the allocations.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.text.clear();
+        self.binary.clear();
+    }
+
+    /// Clear the tree, and add an empty root node.
+    pub fn reset(&mut self) -> NodeMut<'_> {
+        self.clear();
+        let index = self.add(Kind::Node(Node::EMPTY));
+        NodeMut { tree: self, index }
+    }
+
+    /// Build the document with the root node added by
+    /// [`reset()`](Self::reset), like [`Builder::build()`](crate::Builder::build).
+    pub fn build(&self, raw: &mut RawBuilder) -> crate::DocumentBuffer {
+        let root = self.node(0);
+        if root.args == 0 && root.children == 0 && root.ty.len == 0 && self.entries[0].name.len == 0
+        {
+            return crate::DocumentBuffer::default();
+        }
+        raw.set_root(NodeRef {
+            tree: self,
+            index: 0,
+        });
+        raw.build()
+    }
+
+    fn add(&mut self, kind: Kind) -> Index {
+        let index = self.entries.len() as Index;
+        assert!(index != NONE, "too many entries");
+        self.entries.push(Entry {
+            name: Range::EMPTY,
+            next: NONE,
+            kind,
+        });
+        index
+    }
+
+    fn add_str(&mut self, s: &str) -> Result<Range, Error> {
+        let range = Range::new(self.text.len(), s.len())?;
+        self.text.push_str(s);
+        Ok(range)
+    }
+
+    fn add_binary(&mut self, data: &[u8]) -> Result<Range, Error> {
+        let range = Range::new(self.binary.len(), data.len())?;
+        self.binary.extend_from_slice(data);
+        Ok(range)
+    }
+
+    fn add_value(&mut self, value: ValueRef<'_>) -> Result<Value, Error> {
+        Ok(match value {
+            ValueRef::Null => Value::Null,
+            ValueRef::Bool(value) => Value::Bool(value),
+            ValueRef::Int(value) => Value::Int(value),
+            ValueRef::Uint(value) => Value::Uint(value),
+            ValueRef::Float(value) => Value::Float(value),
+            ValueRef::String(value) => Value::String(self.add_str(value)?),
+            ValueRef::Binary(value) => Value::Binary(self.add_binary(value)?),
+        })
+    }
+
+    fn str(&self, range: Range) -> &str {
+        &self.text[range.start as usize..][..range.len as usize]
+    }
+
+    fn value(&self, value: Value) -> ValueRef<'_> {
+        match value {
+            Value::Null => ValueRef::Null,
+            Value::Bool(value) => ValueRef::Bool(value),
+            Value::Int(value) => ValueRef::Int(value),
+            Value::Uint(value) => ValueRef::Uint(value),
+            Value::Float(value) => ValueRef::Float(value),
+            Value::String(range) => ValueRef::String(self.str(range)),
+            Value::Binary(range) => {
+                ValueRef::Binary(&self.binary[range.start as usize..][..range.len as usize])
+            }
+        }
+    }
+
+    fn node(&self, index: Index) -> &Node {
+        match &self.entries[index as usize].kind {
+            Kind::Node(node) => node,
+            Kind::Arg(_) => unreachable!("entry is not a node"),
+        }
+    }
+
+    fn node_mut(&mut self, index: Index) -> &mut Node {
+        match &mut self.entries[index as usize].kind {
+            Kind::Node(node) => node,
+            Kind::Arg(_) => unreachable!("entry is not a node"),
+        }
+    }
+
+    /// Add a detached entry with a null value.
+    fn null(&mut self) -> Index {
+        self.add(Kind::Arg(Value::Null))
+    }
+
+    /// Replace the entry with an unnamed value (`*entry = value.into()`).
+    fn set_value(&mut self, index: Index, value: Value) {
+        let entry = &mut self.entries[index as usize];
+        entry.name = Range::EMPTY;
+        entry.kind = Kind::Arg(value);
+    }
+
+    /// Replace the node with an unnamed node with `value` as its only
+    /// argument (`*node = Node::from_values([value])`).
+    fn set_node_value(&mut self, index: Index, value: Value) {
+        let arg = self.add(Kind::Arg(value));
+        let entry = &mut self.entries[index as usize];
+        entry.name = Range::EMPTY;
+        entry.kind = Kind::Node(Node {
+            first: arg,
+            last: arg,
+            args: 1,
+            ..Node::EMPTY
+        });
+    }
+
+    /// Replace the entry with an unnamed node with the type `ty`.
+    fn set_empty_node(&mut self, index: Index, ty: &str) -> Result<(), Error> {
+        let ty = self.add_str(ty)?;
+        let entry = &mut self.entries[index as usize];
+        entry.name = Range::EMPTY;
+        entry.kind = Kind::Node(Node { ty, ..Node::EMPTY });
+        Ok(())
+    }
+
+    /// Turn the entry into a node, unless it is one already
+    /// (`Entry::reset_as_node()`).
+    fn reset_as_node(&mut self, index: Index) -> Index {
+        let entry = &mut self.entries[index as usize];
+        if let Kind::Arg(_) = entry.kind {
+            entry.name = Range::EMPTY;
+            entry.kind = Kind::Node(Node::EMPTY);
+        }
+        index
+    }
+
+    fn set_ty(&mut self, index: Index, ty: &str) -> Result<(), Error> {
+        let ty = self.add_str(ty)?;
+        self.node_mut(index).ty = ty;
+        Ok(())
+    }
+
+    /// Replace the entry with a copy of `node`, keeping its place in the
+    /// entries of its parent.
+    fn set_builder_node(&mut self, index: Index, node: &builder::Node<'_>) -> Result<(), Error> {
+        let copy = self.add_builder_node(node)?;
+        let next = self.entries[index as usize].next;
+        self.entries[index as usize] = Entry {
+            next,
+            ..self.entries[copy as usize]
+        };
+        Ok(())
+    }
+
+    fn add_builder_node(&mut self, node: &builder::Node<'_>) -> Result<Index, Error> {
+        let ty = self.add_str(node.ty())?;
+        let name = self.add_str(node.name())?;
+        let index = self.add(Kind::Node(Node { ty, ..Node::EMPTY }));
+        self.entries[index as usize].name = name;
+        for arg in node.args() {
+            let value = self.add_value((&arg.value).into())?;
+            let entry = self.add(Kind::Arg(value));
+            self.entries[entry as usize].name = self.add_str(arg.name.as_deref().unwrap_or(""))?;
+            self.append(index, entry);
+        }
+        for child in node.children() {
+            let child = self.add_builder_node(child)?;
+            self.append(index, child);
+        }
+        Ok(index)
+    }
+
+    /// Add a detached entry to the end of the entries of `node`
+    /// (`Node::push()`).
+    fn append(&mut self, node: Index, entry: Index) {
+        let is_arg = matches!(self.entries[entry as usize].kind, Kind::Arg(_));
+        let last = self.node(node).last;
+        if last == NONE {
+            self.node_mut(node).first = entry;
+        } else {
+            self.entries[last as usize].next = entry;
+        }
+        let node = self.node_mut(node);
+        node.last = entry;
+        if is_arg {
+            node.args += 1;
+        } else {
+            node.children += 1;
+        }
+    }
+
+    /// Add a detached entry to `node`, preserving the order of entries
+    /// (`Node::push_ordered()`).
+    fn append_ordered(&mut self, node: Index, entry: Index) {
+        let is_arg = matches!(self.entries[entry as usize].kind, Kind::Arg(_));
+        if is_arg && self.node(node).children == 0 {
+            self.append(node, entry);
+            return;
+        }
+
+        // Move the arguments to the end as children, after any existing
+        // children, in order to maintain their order.
+        if self.node(node).args != 0 {
+            let end = self.node(node).last;
+            let mut prev = NONE;
+            let mut current = self.node(node).first;
+            loop {
+                let next = self.entries[current as usize].next;
+                if let Kind::Arg(_) = self.entries[current as usize].kind {
+                    if prev == NONE {
+                        self.node_mut(node).first = next;
+                    } else {
+                        self.entries[prev as usize].next = next;
+                    }
+                    if self.node(node).last == current {
+                        self.node_mut(node).last = prev;
+                    }
+                    self.node_mut(node).args -= 1;
+                    self.entries[current as usize].next = NONE;
+                    self.make_key_value_node(current);
+                    self.append(node, current);
+                } else {
+                    prev = current;
+                }
+                if current == end {
+                    break;
+                }
+                current = next;
+            }
+        }
+
+        if is_arg {
+            self.make_key_value_node(entry);
+        }
+        self.append(node, entry);
+    }
+
+    /// Turn an argument into a node with its name and its value as the only
+    /// argument (`Arg::into_key_value_node()`).
+    fn make_key_value_node(&mut self, index: Index) {
+        let Kind::Arg(value) = self.entries[index as usize].kind else {
+            return;
+        };
+        let arg = self.add(Kind::Arg(value));
+        self.entries[index as usize].kind = Kind::Node(Node {
+            first: arg,
+            last: arg,
+            args: 1,
+            ..Node::EMPTY
+        });
+    }
+
+    /// Move all entries of `child` to the end of the entries of `node`.
+    fn append_entries(&mut self, node: Index, child: Index) {
+        let child = *self.node(child);
+        if child.first == NONE {
+            return;
+        }
+        let last = self.node(node).last;
+        if last == NONE {
+            self.node_mut(node).first = child.first;
+        } else {
+            self.entries[last as usize].next = child.first;
+        }
+        let node = self.node_mut(node);
+        node.last = child.last;
+        node.args += child.args;
+        node.children += child.children;
+    }
+}
+
+/// A node of a [`Tree`], written into a [`RawBuilder`].
+#[derive(Clone, Copy)]
+struct NodeRef<'a> {
+    tree: &'a Tree,
+    index: Index,
+}
+
+impl BuildRawNode for NodeRef<'_> {
+    fn build(self, builder: &mut RawBuilder, index: u32) {
+        let entry = &self.tree.entries[self.index as usize];
+        let node = self.tree.node(self.index);
+        RawNode {
+            ty: Some(self.tree.str(node.ty)),
+            name: Some(self.tree.str(entry.name)),
+            children: Children {
+                tree: self.tree,
+                next: node.first,
+                len: node.children,
+            },
+            args: Args {
+                tree: self.tree,
+                next: node.first,
+                len: node.args,
+            },
+        }
+        .build(builder, index);
+    }
+}
+
+struct Children<'a> {
+    tree: &'a Tree,
+    next: Index,
+    len: u32,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.len != 0 {
+            let index = self.next;
+            let entry = &self.tree.entries[index as usize];
+            self.next = entry.next;
+            if let Kind::Node(_) = entry.kind {
+                self.len -= 1;
+                return Some(NodeRef {
+                    tree: self.tree,
+                    index,
+                });
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len as usize, Some(self.len as usize))
+    }
+}
+
+impl ExactSizeIterator for Children<'_> {}
+
+struct Args<'a> {
+    tree: &'a Tree,
+    next: Index,
+    len: u32,
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = crate::Arg<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.len != 0 {
+            let entry = &self.tree.entries[self.next as usize];
+            self.next = entry.next;
+            if let Kind::Arg(value) = entry.kind {
+                self.len -= 1;
+                return Some(crate::Arg {
+                    name: Some(self.tree.str(entry.name)),
+                    value: self.tree.value(value),
+                });
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len as usize, Some(self.len as usize))
+    }
+}
+
+impl ExactSizeIterator for Args<'_> {}
+
+/// Entry of a [`Tree`], which values are serialized into.
+pub(crate) struct EntryMut<'a> {
+    tree: &'a mut Tree,
+    index: Index,
+}
+
+/// Node of a [`Tree`], which values are serialized into.
+pub(crate) struct NodeMut<'a> {
+    tree: &'a mut Tree,
+    index: Index,
+}
+
+impl<'a> EntrySink for EntryMut<'a> {
+    type Node = NodeMut<'a>;
+
+    #[inline]
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error> {
+        let value = self.tree.add_value(value)?;
+        self.tree.set_value(self.index, value);
+        Ok(())
+    }
+
+    #[inline]
+    fn set_empty_node(self, ty: &'static str) -> Result<(), Error> {
+        self.tree.set_empty_node(self.index, ty)
+    }
+
+    #[inline]
+    fn set_builder_node(self, node: builder::Node<'static>) -> Result<(), Error> {
+        self.tree.set_builder_node(self.index, &node)
+    }
+
+    #[inline]
+    fn into_node(self) -> Self::Node {
+        NodeMut {
+            index: self.tree.reset_as_node(self.index),
+            tree: self.tree,
+        }
+    }
+}
+
+impl NodeSink for NodeMut<'_> {
+    type Entry = Index;
+    type Key = Range;
+    type EntrySink<'s>
+        = EntryMut<'s>
+    where
+        Self: 's;
+
+    #[inline]
+    fn set_value(self, value: ValueRef<'_>) -> Result<(), Error> {
+        let value = self.tree.add_value(value)?;
+        self.tree.set_node_value(self.index, value);
+        Ok(())
+    }
+
+    #[inline]
+    fn set_builder_node(self, node: builder::Node<'static>) -> Result<(), Error> {
+        self.tree.set_builder_node(self.index, &node)
+    }
+
+    #[inline]
+    fn set_type(&mut self, ty: &'static str) -> Result<(), Error> {
+        self.tree.set_ty(self.index, ty)
+    }
+
+    #[inline]
+    fn has_type(&self) -> bool {
+        self.tree.node(self.index).ty.len != 0
+    }
+
+    #[inline]
+    fn new_entry(&mut self) -> Self::Entry {
+        self.tree.null()
+    }
+
+    #[inline]
+    fn entry_sink<'s>(&'s mut self, entry: &'s mut Self::Entry) -> Self::EntrySink<'s> {
+        EntryMut {
+            tree: self.tree,
+            index: *entry,
+        }
+    }
+
+    #[inline]
+    fn key(&mut self, key: &str) -> Result<Self::Key, Error> {
+        self.tree.add_str(key)
+    }
+
+    #[inline]
+    fn static_key(&mut self, key: &'static str) -> Result<Self::Key, Error> {
+        self.tree.add_str(key)
+    }
+
+    #[inline]
+    fn key_str<'k>(&'k self, key: &'k Self::Key) -> &'k str {
+        self.tree.str(*key)
+    }
+
+    #[inline]
+    fn set_name(&mut self, entry: &mut Self::Entry, name: Self::Key) {
+        self.tree.entries[*entry as usize].name = name;
+    }
+
+    fn classify(&self, entry: &Self::Entry) -> EntryKind {
+        match self.tree.entries[*entry as usize].kind {
+            Kind::Arg(Value::String(_)) => EntryKind::String,
+            Kind::Arg(_) => EntryKind::Value,
+            Kind::Node(child) => EntryKind::Node {
+                empty: child.args == 0 && child.children == 0,
+                typed: child.ty.len != 0,
+            },
+        }
+    }
+
+    fn set_type_from(&mut self, entry: Self::Entry) {
+        let ty = match self.tree.entries[entry as usize].kind {
+            Kind::Arg(Value::String(ty)) => ty,
+            Kind::Node(child) => child.ty,
+            Kind::Arg(_) => return,
+        };
+        self.tree.node_mut(self.index).ty = ty;
+    }
+
+    #[inline]
+    fn append_entries(&mut self, entry: Self::Entry) {
+        self.tree.append_entries(self.index, entry);
+    }
+
+    #[inline]
+    fn push_entry(&mut self, entry: Self::Entry) {
+        self.tree.append(self.index, entry);
+    }
+
+    #[inline]
+    fn push_entry_ordered(&mut self, entry: Self::Entry) {
+        self.tree.append_ordered(self.index, entry);
+    }
+
+    #[inline]
+    fn key_value_node(&mut self, entry: Self::Entry) -> Self::Entry {
+        self.tree.make_key_value_node(entry);
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn range_overflow() {
+        let max = u32::MAX as usize;
+        assert!(Range::new(max, max).is_ok());
+        for (start, len) in [(max + 1, 0), (0, max + 1)] {
+            let result = Range::new(start, len);
+            assert!(matches!(result, Err(err) if matches!(err.error, ErrorKind::TooLarge)));
+        }
+    }
+}
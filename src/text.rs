@@ -0,0 +1,1060 @@
+//! Lossless human-readable text representation of documents.
+//!
+//! The text format preserves everything that is stored in a binary document:
+//! node types and names, named and unnamed arguments, and the exact type of
+//! each value. It is intended for inspecting, hand-editing, and diffing
+//! documents.
+//!
+//! ```text
+//! // Comments run until the end of the line.
+//! (Root) {
+//!     "unnamed argument"
+//!     count = 3u
+//!     offset = -1
+//!     ratio = 0.5
+//!     data = x"68656c6c6f"
+//!     enabled = true
+//!     missing = null
+//!     point = (Point) {
+//!         x = 1
+//!         y = 2
+//!     }
+//!     {}
+//! }
+//! ```
+//!
+//! # Syntax
+//!
+//! - A node is an optional type in parentheses, followed by the entries of the
+//!   node in braces: `(Type) { ... }`. The root node is the only top-level
+//!   entry. An empty document is written as `{}`.
+//! - An entry is either an argument (a value) or a child node, optionally
+//!   preceded by a name: `name = value`. Entries are separated by whitespace or
+//!   commas.
+//! - Names and types are bare identifiers when they consist of alphanumeric
+//!   characters, `_`, `$`, `-`, `.`, and `:` (and do not start with a digit,
+//!   `-`, `.`, or `:`). Otherwise, they are quoted strings.
+//! - `null`, `true`, and `false` are the null and boolean values.
+//! - Integers without a suffix are signed (`-1`), and integers with a `u`
+//!   suffix are unsigned (`1u`). The `i` suffix is also accepted for signed
+//!   integers.
+//! - Floats always contain a decimal point or an exponent (`1.0`, `1e100`), or
+//!   have an `f` suffix (`1f`). Non-finite floats are `inf`, `-inf`, and `nan`.
+//! - Strings are double-quoted, and support the escapes `\\`, `\"`, `\n`, `\r`,
+//!   `\t`, `\0`, and `\u{...}`.
+//! - Binary data is hex encoded (`x"00ff"`) or base64 encoded (`b64"AP8="`).
+//!   Whitespace inside binary literals is ignored.
+//!
+//! Arguments of a node always come before its children in a document, so when
+//! parsing, arguments written after a child node are still stored before the
+//! children. The payload of NaN floats is not preserved.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "builder")]
+use alloc::{borrow::Cow, vec::Vec};
+use core::fmt;
+
+#[cfg(feature = "builder")]
+use crate::{
+    Builder, DocumentBuffer,
+    builder::{Arg, Entry, Node, Value},
+};
+use crate::{
+    Document, ValueRef,
+    access::{self, ArgRef as _, NodeRef},
+};
+
+/// Encoding of binary data in the text format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BinaryEncoding {
+    /// Hex encoding, like `x"68656c6c6f"`.
+    #[default]
+    Hex,
+    /// Standard base64 encoding with padding, like `b64"aGVsbG8="`.
+    Base64,
+}
+
+/// Settings for emitting the text format.
+#[derive(Clone, Debug)]
+pub struct TextSettings {
+    /// Number of spaces to indent entries with. When zero, the document is
+    /// written on a single line. Default is `4`.
+    pub indent: usize,
+    /// Encoding of binary data. Default is hex.
+    pub binary: BinaryEncoding,
+}
+
+impl Default for TextSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            binary: BinaryEncoding::Hex,
+        }
+    }
+}
+
+/// Write a document in the text format.
+///
+/// This does not allocate, so it is available without the `alloc` feature.
+///
+/// # Errors
+///
+/// This only returns an error if writing to `out` fails.
+pub fn write_document<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    doc: &Document,
+    settings: &TextSettings,
+) -> fmt::Result {
+    Writer { out, settings }.write_root(&doc.root())
+}
+
+/// Write the contents of a builder in the text format.
+///
+/// # Errors
+///
+/// This only returns an error if writing to `out` fails.
+#[cfg(feature = "builder")]
+pub fn write_builder<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    builder: &Builder,
+    settings: &TextSettings,
+) -> fmt::Result {
+    Writer { out, settings }.write_root(&builder.root())
+}
+
+/// Write a single entry in the text format, without its name.
+///
+/// This is the inverse of [`entry_from_text()`].
+///
+/// # Errors
+///
+/// This only returns an error if writing to `out` fails.
+#[cfg(feature = "builder")]
+pub fn write_entry<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    entry: &Entry,
+    settings: &TextSettings,
+) -> fmt::Result {
+    let mut writer = Writer { out, settings };
+    match entry {
+        Entry::Arg(arg) => writer.write_value((&arg.value).into()),
+        Entry::Child(node) => writer.write_node(&node, 0),
+    }
+}
+
+/// Convert a document to the text format.
+#[cfg(feature = "alloc")]
+#[inline]
+#[must_use]
+pub fn document_to_text(doc: &Document) -> String {
+    document_to_text_with_settings(doc, &TextSettings::default())
+}
+
+/// Convert a document to the text format.
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn document_to_text_with_settings(doc: &Document, settings: &TextSettings) -> String {
+    let mut out = String::new();
+    // Writing to a `String` cannot fail.
+    _ = write_document(&mut out, doc, settings);
+    out
+}
+
+/// Parse a document in the text format.
+///
+/// # Errors
+///
+/// If `text` is not a valid document, this returns an error.
+#[cfg(feature = "builder")]
+#[inline]
+pub fn document_from_text(text: &str) -> Result<DocumentBuffer, ParseError> {
+    builder_from_text(text).map(|builder| builder.build())
+}
+
+/// Parse a document in the text format into a [`Builder`].
+///
+/// Strings without escape sequences are borrowed from `text`.
+///
+/// # Errors
+///
+/// If `text` is not a valid document, this returns an error.
+#[cfg(feature = "builder")]
+pub fn builder_from_text(text: &str) -> Result<Builder<'_>, ParseError> {
+    let mut parser = Parser {
+        input: text,
+        pos: 0,
+    };
+    let root = parser.parse_document()?;
+    let mut builder = Builder::new();
+    builder.set_root(root);
+    Ok(builder)
+}
+
+/// Parse a single unnamed entry in the text format, which is either a value or
+/// a node.
+///
+/// # Errors
+///
+/// If `text` is not a valid value or node, this returns an error.
+#[cfg(feature = "builder")]
+pub fn entry_from_text(text: &str) -> Result<Entry<'_>, ParseError> {
+    let mut parser = Parser {
+        input: text,
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let entry = match parser.parse_value()? {
+        Parsed::Value(value) => Entry::Arg(Arg { name: None, value }),
+        Parsed::Node(node) => Entry::Child(node),
+    };
+    parser.skip_whitespace();
+    if !parser.rest().is_empty() {
+        return Err(parser.error(ParseErrorKind::TrailingInput));
+    }
+    Ok(entry)
+}
+
+/// Find the line and column of the entry at `path` in `text`, which is a valid
+/// document.
+///
+/// Names in the path select the last argument with that name, or else the
+/// last child with that name, and indices count arguments before children,
+/// like when applying the path to the parsed document. If the path goes
+/// through an argument, this returns the location of the argument.
+///
+/// Only the text format of this module is supported. Other text formats, like
+/// JSON or YAML, are converted to documents without keeping the locations of
+/// entries.
+#[cfg(all(feature = "builder", feature = "serde"))]
+pub(crate) fn locate(text: &str, path: &crate::path::Path<'_>) -> Option<(usize, usize)> {
+    use crate::path::Segment;
+
+    fn last<'s, 'a>(entries: &'s [Scanned<'a>], name: &str) -> Option<&'s Scanned<'a>> {
+        entries
+            .iter()
+            .rfind(|entry| entry.name.as_deref() == Some(name))
+    }
+
+    let mut parser = Parser {
+        input: text,
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let mut location = parser.pos;
+    parser.parse_name().ok()?;
+
+    for segment in path.segments() {
+        let Ok((args, children)) = parser.scan_node() else {
+            break;
+        };
+        let found = match segment {
+            Segment::Name(name) => last(&args, name).or_else(|| last(&children, name)),
+            Segment::Arg(name) => last(&args, name),
+            Segment::Index(index) => args.iter().chain(&children).nth(*index),
+            Segment::Type => break,
+        }?;
+        location = found.start;
+        parser.pos = found.value;
+    }
+
+    Some(parser.line_column(location))
+}
+
+/// Error when parsing the text format.
+#[cfg(feature = "builder")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, line {line}, column {column}")]
+pub struct ParseError {
+    /// Line number where the error occurred, starting at 1.
+    pub line: usize,
+    /// Column (in characters) where the error occurred, starting at 1.
+    pub column: usize,
+    /// Kind of error that occurred.
+    pub error: ParseErrorKind,
+}
+
+#[cfg(feature = "builder")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    #[error("unexpected character {0:?}")]
+    UnexpectedChar(char),
+    #[error("expected a node")]
+    ExpectedNode,
+    #[error("unknown keyword")]
+    UnknownKeyword,
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+    #[error("invalid number")]
+    InvalidNumber,
+    #[error("integer out of range")]
+    IntegerOutOfRange,
+    #[error("invalid hex data")]
+    InvalidHex,
+    #[error("invalid base64 data")]
+    InvalidBase64,
+    #[error("unexpected trailing input")]
+    TrailingInput,
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn is_key_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '-' | '.' | ':')
+}
+
+fn is_bare_key(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(is_key_start) && chars.all(is_key_char)
+}
+
+struct Writer<'w, W: ?Sized> {
+    out: &'w mut W,
+    settings: &'w TextSettings,
+}
+
+impl<W: fmt::Write + ?Sized> Writer<'_, W> {
+    fn write_root<'a>(&mut self, root: &impl NodeRef<'a>) -> fmt::Result {
+        self.write_name(root.name())?;
+        self.write_node(root, 0)?;
+        if self.settings.indent != 0 {
+            self.out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn write_node<'a>(&mut self, node: &impl NodeRef<'a>, depth: usize) -> fmt::Result {
+        let ty = node.ty();
+        if !ty.is_empty() {
+            self.out.write_char('(')?;
+            self.write_key(ty)?;
+            self.out.write_str(") ")?;
+        }
+
+        if node.is_empty() {
+            return self.out.write_str("{}");
+        }
+
+        let indent = self.settings.indent;
+        self.out.write_char('{')?;
+        for (index, entry) in node.entries().enumerate() {
+            if indent == 0 {
+                if index != 0 {
+                    self.out.write_str(", ")?;
+                }
+            } else {
+                self.out.write_char('\n')?;
+                self.write_indent(depth + 1)?;
+            }
+
+            self.write_name(entry.name())?;
+            match entry {
+                access::EntryRef::Arg(arg) => self.write_value(arg.value())?,
+                access::EntryRef::Child(child) => self.write_node(&child, depth + 1)?,
+            }
+        }
+        if indent != 0 {
+            self.out.write_char('\n')?;
+            self.write_indent(depth)?;
+        }
+        self.out.write_char('}')
+    }
+
+    fn write_indent(&mut self, depth: usize) -> fmt::Result {
+        for _ in 0..depth * self.settings.indent {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn write_name(&mut self, name: &str) -> fmt::Result {
+        if name.is_empty() {
+            return Ok(());
+        }
+        self.write_key(name)?;
+        self.out.write_str(" = ")
+    }
+
+    fn write_key(&mut self, key: &str) -> fmt::Result {
+        if is_bare_key(key) {
+            self.out.write_str(key)
+        } else {
+            self.write_string(key)
+        }
+    }
+
+    fn write_string(&mut self, s: &str) -> fmt::Result {
+        self.out.write_char('"')?;
+        for c in s.chars() {
+            match c {
+                '"' => self.out.write_str("\\\"")?,
+                '\\' => self.out.write_str("\\\\")?,
+                '\n' => self.out.write_str("\\n")?,
+                '\r' => self.out.write_str("\\r")?,
+                '\t' => self.out.write_str("\\t")?,
+                c if c.is_control() => write!(self.out, "\\u{{{:x}}}", c as u32)?,
+                c => self.out.write_char(c)?,
+            }
+        }
+        self.out.write_char('"')
+    }
+
+    fn write_value(&mut self, value: ValueRef<'_>) -> fmt::Result {
+        match value {
+            ValueRef::Null => self.out.write_str("null"),
+            ValueRef::Bool(value) => write!(self.out, "{value}"),
+            ValueRef::Int(value) => write!(self.out, "{value}"),
+            ValueRef::Uint(value) => write!(self.out, "{value}u"),
+            ValueRef::Float(value) if value.is_nan() => self.out.write_str("nan"),
+            ValueRef::Float(value) if value.is_infinite() => {
+                self.out.write_str(if value < 0.0 { "-inf" } else { "inf" })
+            }
+            // The `Debug` representation always contains a decimal point or an
+            // exponent, and round-trips exactly.
+            ValueRef::Float(value) => write!(self.out, "{value:?}"),
+            ValueRef::String(value) => self.write_string(value),
+            ValueRef::Binary(value) => match self.settings.binary {
+                BinaryEncoding::Hex => {
+                    self.out.write_str("x\"")?;
+                    for byte in value {
+                        write!(self.out, "{byte:02x}")?;
+                    }
+                    self.out.write_char('"')
+                }
+                BinaryEncoding::Base64 => {
+                    self.out.write_str("b64\"")?;
+                    self.write_base64(value)?;
+                    self.out.write_char('"')
+                }
+            },
+        }
+    }
+
+    fn write_base64(&mut self, data: &[u8]) -> fmt::Result {
+        for chunk in data.chunks(3) {
+            let bits = (u32::from(chunk[0]) << 16)
+                | (u32::from(chunk.get(1).copied().unwrap_or(0)) << 8)
+                | u32::from(chunk.get(2).copied().unwrap_or(0));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    let index = (bits >> (18 - 6 * i)) & 0x3f;
+                    self.out.write_char(BASE64[index as usize] as char)?;
+                } else {
+                    self.out.write_char('=')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "builder")]
+enum Parsed<'a> {
+    Value(Value<'a>),
+    Node(Node<'a>),
+}
+
+/// Position of an entry in the text, recorded by [`Parser::scan_node()`].
+#[cfg(all(feature = "builder", feature = "serde"))]
+struct Scanned<'a> {
+    name: Option<Cow<'a, str>>,
+    /// Start of the entry, including its name.
+    start: usize,
+    /// Start of the value of the entry.
+    value: usize,
+}
+
+#[cfg(feature = "builder")]
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+#[cfg(feature = "builder")]
+impl<'a> Parser<'a> {
+    fn error_at(&self, pos: usize, error: ParseErrorKind) -> ParseError {
+        let (line, column) = self.line_column(pos);
+        ParseError {
+            line,
+            column,
+            error,
+        }
+    }
+
+    fn line_column(&self, pos: usize) -> (usize, usize) {
+        let before = &self.input[..pos];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    fn error(&self, error: ParseErrorKind) -> ParseError {
+        self.error_at(self.pos, error)
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(c) => self.error(ParseErrorKind::UnexpectedChar(c)),
+            None => self.error(ParseErrorKind::UnexpectedEof),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skip whitespace, comments, and commas between entries.
+    fn skip_separators(&mut self) {
+        loop {
+            self.skip_whitespace();
+            if !self.eat(',') {
+                break;
+            }
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Node<'a>, ParseError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            return Ok(Node::empty());
+        }
+
+        let start = self.pos;
+        let (name, Parsed::Node(mut root)) = self.parse_entry()? else {
+            return Err(self.error_at(start, ParseErrorKind::ExpectedNode));
+        };
+        if let Some(name) = name {
+            root.name = name;
+        }
+
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(self.error(ParseErrorKind::TrailingInput));
+        }
+        Ok(root)
+    }
+
+    fn parse_entry(&mut self) -> Result<(Option<Cow<'a, str>>, Parsed<'a>), ParseError> {
+        let name = self.parse_name()?;
+        let parsed = self.parse_value()?;
+        Ok((name, parsed))
+    }
+
+    /// Parse `name =` if present at the current position.
+    fn parse_name(&mut self) -> Result<Option<Cow<'a, str>>, ParseError> {
+        let start = self.pos;
+        let name = match self.peek() {
+            Some('"') => self.parse_string()?,
+            Some(c) if is_key_start(c) => {
+                let ident = self.parse_ident();
+                if self.peek() == Some('"') {
+                    // Binary literal.
+                    self.pos = start;
+                    return Ok(None);
+                }
+                Cow::Borrowed(ident)
+            }
+            _ => return Ok(None),
+        };
+
+        self.skip_whitespace();
+        if self.eat('=') {
+            self.skip_whitespace();
+            Ok(Some(name))
+        } else {
+            self.pos = start;
+            Ok(None)
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<Cow<'a, str>, ParseError> {
+        match self.peek() {
+            Some('"') => self.parse_string(),
+            Some(c) if is_key_start(c) => Ok(Cow::Borrowed(self.parse_ident())),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_ident(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_key_char(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn parse_value(&mut self) -> Result<Parsed<'a>, ParseError> {
+        let start = self.pos;
+        let value = match self.peek() {
+            Some('(' | '{') => return self.parse_node().map(Parsed::Node),
+            Some('"') => Value::String(self.parse_string()?),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.parse_number()?,
+            Some(c) if is_key_start(c) => match self.parse_ident() {
+                "null" => Value::Null,
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "inf" => Value::Float(f64::INFINITY),
+                "nan" => Value::Float(f64::NAN),
+                "x" if self.peek() == Some('"') => {
+                    let data = self.parse_raw_string()?;
+                    let bytes = decode_hex(data)
+                        .ok_or_else(|| self.error_at(start, ParseErrorKind::InvalidHex))?;
+                    Value::Binary(Cow::Owned(bytes))
+                }
+                "b64" if self.peek() == Some('"') => {
+                    let data = self.parse_raw_string()?;
+                    let bytes = decode_base64(data)
+                        .ok_or_else(|| self.error_at(start, ParseErrorKind::InvalidBase64))?;
+                    Value::Binary(Cow::Owned(bytes))
+                }
+                _ => return Err(self.error_at(start, ParseErrorKind::UnknownKeyword)),
+            },
+            _ => return Err(self.unexpected()),
+        };
+        Ok(Parsed::Value(value))
+    }
+
+    fn parse_node(&mut self) -> Result<Node<'a>, ParseError> {
+        let mut node = Node::empty();
+        if self.eat('(') {
+            self.skip_whitespace();
+            node.ty = self.parse_key()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            self.skip_whitespace();
+        }
+
+        self.expect('{')?;
+        loop {
+            self.skip_separators();
+            if self.eat('}') {
+                return Ok(node);
+            }
+            match self.parse_entry()? {
+                (name, Parsed::Value(value)) => node.args.push(Arg { name, value }),
+                (name, Parsed::Node(mut child)) => {
+                    if let Some(name) = name {
+                        child.name = name;
+                    }
+                    node.children.push(child);
+                }
+            }
+        }
+    }
+
+    /// Parse a node like [`parse_node()`](Self::parse_node), but only record
+    /// the positions of its arguments and children.
+    #[cfg(feature = "serde")]
+    #[allow(clippy::type_complexity)]
+    fn scan_node(&mut self) -> Result<(Vec<Scanned<'a>>, Vec<Scanned<'a>>), ParseError> {
+        let mut args = Vec::new();
+        let mut children = Vec::new();
+        if self.eat('(') {
+            self.skip_whitespace();
+            self.parse_key()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            self.skip_whitespace();
+        }
+
+        self.expect('{')?;
+        loop {
+            self.skip_separators();
+            if self.eat('}') {
+                return Ok((args, children));
+            }
+            let start = self.pos;
+            let name = self.parse_name()?;
+            let value = self.pos;
+            let scanned = Scanned { name, start, value };
+            match self.parse_value()? {
+                Parsed::Value(_) => args.push(scanned),
+                Parsed::Node(_) => children.push(scanned),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<Cow<'a, str>, ParseError> {
+        self.expect('"')?;
+
+        let mut owned: Option<String> = None;
+        loop {
+            let rest = self.rest();
+            let Some(end) = rest.find(['"', '\\']) else {
+                self.pos = self.input.len();
+                return Err(self.error(ParseErrorKind::UnexpectedEof));
+            };
+            self.pos += end;
+
+            if self.eat('"') {
+                return Ok(match owned {
+                    Some(mut owned) => {
+                        owned.push_str(&rest[..end]);
+                        Cow::Owned(owned)
+                    }
+                    None => Cow::Borrowed(&rest[..end]),
+                });
+            }
+
+            let owned = owned.get_or_insert_default();
+            owned.push_str(&rest[..end]);
+            owned.push(self.parse_escape()?);
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, ParseError> {
+        let start = self.pos;
+        let invalid = |this: &Self| this.error_at(start, ParseErrorKind::InvalidEscape);
+
+        self.expect('\\')?;
+        let Some(c) = self.peek() else {
+            return Err(self.error(ParseErrorKind::UnexpectedEof));
+        };
+        self.pos += c.len_utf8();
+        Ok(match c {
+            '\\' => '\\',
+            '"' => '"',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                if !self.eat('{') {
+                    return Err(invalid(self));
+                }
+                let rest = self.rest();
+                let end = rest.find('}').ok_or_else(|| invalid(self))?;
+                self.pos += end + 1;
+                u32::from_str_radix(&rest[..end], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(self))?
+            }
+            _ => return Err(invalid(self)),
+        })
+    }
+
+    /// Parse the contents of a binary literal, which does not support escapes.
+    fn parse_raw_string(&mut self) -> Result<&'a str, ParseError> {
+        self.expect('"')?;
+        let rest = self.rest();
+        let Some(end) = rest.find('"') else {
+            self.pos = self.input.len();
+            return Err(self.error(ParseErrorKind::UnexpectedEof));
+        };
+        self.pos += end + 1;
+        Ok(&rest[..end])
+    }
+
+    fn parse_number(&mut self) -> Result<Value<'a>, ParseError> {
+        let start = self.pos;
+        let bytes = self.rest().as_bytes();
+        let mut len = usize::from(matches!(bytes.first(), Some(b'-' | b'+')));
+        while let Some(&b) = bytes.get(len) {
+            let is_exponent_sign =
+                matches!(b, b'-' | b'+') && matches!(bytes[len - 1], b'e' | b'E');
+            if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_') || is_exponent_sign {
+                len += 1;
+            } else {
+                break;
+            }
+        }
+        let token = &self.rest()[..len];
+        self.pos += len;
+
+        let invalid = |this: &Self, error| this.error_at(start, error);
+        let (negative, unsigned) = match token.as_bytes()[0] {
+            b'-' => (true, &token[1..]),
+            b'+' => (false, &token[1..]),
+            _ => (false, token),
+        };
+        if unsigned == "inf" {
+            return Ok(Value::Float(if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }));
+        }
+
+        let token: Cow<str> = if token.contains('_') {
+            Cow::Owned(token.replace('_', ""))
+        } else {
+            Cow::Borrowed(token)
+        };
+        let (body, suffix) = match token.as_bytes().last() {
+            Some(&suffix @ (b'i' | b'u' | b'f')) => (&token[..token.len() - 1], Some(suffix)),
+            _ => (&*token, None),
+        };
+        let is_float = body.contains(['.', 'e', 'E']);
+        let int_error = |this: &Self, err: core::num::ParseIntError| match err.kind() {
+            core::num::IntErrorKind::PosOverflow | core::num::IntErrorKind::NegOverflow => {
+                invalid(this, ParseErrorKind::IntegerOutOfRange)
+            }
+            _ => invalid(this, ParseErrorKind::InvalidNumber),
+        };
+
+        match suffix {
+            Some(b'u') if !negative && !is_float => body
+                .parse()
+                .map(Value::Uint)
+                .map_err(|err| int_error(self, err)),
+            None | Some(b'i') if !is_float => body
+                .parse()
+                .map(Value::Int)
+                .map_err(|err| int_error(self, err)),
+            None | Some(b'f') if body.bytes().any(|b| b.is_ascii_digit()) => body
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| invalid(self, ParseErrorKind::InvalidNumber)),
+            _ => Err(invalid(self, ParseErrorKind::InvalidNumber)),
+        }
+    }
+}
+
+#[cfg(feature = "builder")]
+fn decode_hex(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 2);
+    let mut digits = data
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16));
+    while let Some(high) = digits.next() {
+        let low = digits.next()?;
+        out.push((high? << 4 | low?) as u8);
+    }
+    Some(out)
+}
+
+#[cfg(feature = "builder")]
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for b in data.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        if b == b'=' {
+            padding += 1;
+            continue;
+        }
+        if padding != 0 {
+            return None;
+        }
+        let index = BASE64.iter().position(|&c| c == b)?;
+        acc = (acc << 6) | index as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // A single trailing character cannot encode a full byte.
+    if bits >= 6 || padding > 2 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::builder;
+
+    fn example() -> DocumentBuffer {
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.set_ty("Root");
+            root.push_unnamed_arg("unnamed \"argument\"\n");
+            root.push(builder::Arg::new("int", -1i64));
+            root.push(builder::Arg::new("uint", 1u64));
+            root.push(builder::Arg::new("float", 1.0f64));
+            root.push(builder::Arg::new("big", 1e100f64));
+            root.push(builder::Arg::new("ninf", f64::NEG_INFINITY));
+            root.push(builder::Arg::new("null", builder::Value::Null));
+            root.push(builder::Arg::new("bool", true));
+            root.push(builder::Arg::new(
+                "binary",
+                builder::Value::Binary(b"hello"[..].into()),
+            ));
+            root.push(builder::Arg::new("needs quoting", "\u{1b}"));
+            root.push_named_with("child", |child| {
+                child.set_ty("a::Type");
+                child.push_unnamed_arg(0u64);
+            });
+            root.push_named_with("empty", |_| {});
+            root.push(|child: &mut builder::Node| {
+                child.set_ty("Unit");
+            });
+        });
+        builder.build()
+    }
+
+    #[test]
+    fn basic_roundtrip() {
+        let doc = example();
+        let text = document_to_text(&doc);
+        assert_eq!(
+            text,
+            r#"(Root) {
+    "unnamed \"argument\"\n"
+    int = -1
+    uint = 1u
+    float = 1.0
+    big = 1e100
+    ninf = -inf
+    null = null
+    bool = true
+    binary = x"68656c6c6f"
+    "needs quoting" = "\u{1b}"
+    child = (a::Type) {
+        0u
+    }
+    empty = {}
+    (Unit) {}
+}
+"#
+        );
+        let parsed = document_from_text(&text).unwrap();
+        assert_eq!(parsed.as_bytes(), doc.as_bytes());
+
+        let settings = TextSettings {
+            indent: 0,
+            binary: BinaryEncoding::Base64,
+        };
+        let text = document_to_text_with_settings(&doc, &settings);
+        assert!(text.contains(r#"binary = b64"aGVsbG8=", "#));
+        assert!(!text.contains('\n'));
+        let parsed = document_from_text(&text).unwrap();
+        assert_eq!(parsed.as_bytes(), doc.as_bytes());
+
+        assert_eq!(document_to_text(Document::empty()), "{}\n");
+        assert!(document_from_text(" // empty\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse() {
+        let builder = builder_from_text(
+            r#"
+            // Comment
+            root = {
+                (Item) { a = 1 }, b = 2i, x"00 ff"
+                c = "\u{e9}", 1_000u, .5, 2f, +inf, nan, b64"AP8"
+            }
+            "#,
+        )
+        .unwrap();
+        let root = builder.root();
+        assert_eq!(root.name(), "root");
+        assert_eq!(root.children().len(), 1);
+        assert_eq!(root.children()[0].ty(), "Item");
+        // Arguments are stored before children.
+        assert_eq!(root.args().len(), 9);
+        assert!(matches!(root.args()[0].value, Value::Int(2)));
+        assert!(matches!(root.args()[1].value, Value::Binary(ref b) if **b == [0, 0xff]));
+        assert!(matches!(root.args()[2].value, Value::String(Cow::Owned(ref s)) if s == "é"));
+        assert!(matches!(root.args()[3].value, Value::Uint(1000)));
+        assert!(matches!(root.args()[4].value, Value::Float(0.5)));
+        assert!(matches!(root.args()[5].value, Value::Float(2.0)));
+        assert!(matches!(root.args()[6].value, Value::Float(f) if f == f64::INFINITY));
+        assert!(matches!(root.args()[7].value, Value::Float(f) if f.is_nan()));
+        assert!(matches!(root.args()[8].value, Value::Binary(ref b) if **b == [0, 0xff]));
+
+        assert!(matches!(
+            entry_from_text(" 1u ").unwrap(),
+            Entry::Arg(Arg {
+                name: None,
+                value: Value::Uint(1)
+            })
+        ));
+        assert!(
+            matches!(entry_from_text("(Ty) {}").unwrap(), Entry::Child(node) if node.ty() == "Ty")
+        );
+        assert_eq!(
+            entry_from_text("1 2").unwrap_err().error,
+            ParseErrorKind::TrailingInput
+        );
+
+        // Strings without escapes are borrowed.
+        let builder = builder_from_text(r#"{ "borrowed" }"#).unwrap();
+        assert!(matches!(
+            builder.root().args()[0].value,
+            Value::String(Cow::Borrowed("borrowed"))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| builder_from_text(text).unwrap_err();
+
+        assert_eq!(
+            error("{\n  a = 1\n  b = ?\n}"),
+            ParseError {
+                line: 3,
+                column: 7,
+                error: ParseErrorKind::UnexpectedChar('?'),
+            }
+        );
+        assert_eq!(error("{ a = 1").error, ParseErrorKind::UnexpectedEof);
+        assert_eq!(error("1").error, ParseErrorKind::ExpectedNode);
+        assert_eq!(error("{} {}").error, ParseErrorKind::TrailingInput);
+        assert_eq!(error("{ a = nil }").error, ParseErrorKind::UnknownKeyword);
+        assert_eq!(error(r#"{ "\q" }"#).error, ParseErrorKind::InvalidEscape);
+        assert_eq!(error("{ -1u }").error, ParseErrorKind::InvalidNumber);
+        assert_eq!(error("{ 1.5i }").error, ParseErrorKind::InvalidNumber);
+        assert_eq!(
+            error("{ 9223372036854775808 }").error,
+            ParseErrorKind::IntegerOutOfRange
+        );
+        assert_eq!(error(r#"{ x"0" }"#).error, ParseErrorKind::InvalidHex);
+        assert_eq!(error(r#"{ b64"A" }"#).error, ParseErrorKind::InvalidBase64);
+        assert_eq!(
+            error("{\n  a = nil }").to_string(),
+            "unknown keyword, line 2, column 7"
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Convert between [`Document`] and TOML.
+//!
+//! Key-value nodes are represented as TOML tables, and sequence-like nodes are
+//! represented as TOML arrays. The top level of a TOML document is always a
+//! table, so the root node is always converted to a table.
+//!
+//! Nodes that have a "type" will gain a special `"$type"` field in the TOML
+//! representation. Nodes that have a mix of named and unnamed
+//! children/arguments will gain an `"$items"` field, which contains a TOML
+//! array of the unnamed items. Nodes that have a single unnamed child will gain
+//! a special `"$value"` field containing the argument.
+//!
+//! Arrays of tables (`[[name]]`) are represented as repeated children with the
+//! same name, and vice versa: when a node has multiple entries with the same
+//! name, they are collected into a TOML array under that name.
+//!
+//! TOML datetimes are represented as nodes with the type `"datetime"`
+//! containing the datetime as a single string argument. Such nodes are
+//! converted back into TOML datetimes.
+//!
+//! **Note:** Conversion to and from TOML is lossy, because TOML does not
+//! distinguish between attributes and children, and TOML cannot represent null
+//! values. Named null values are omitted, and unnamed null values (i.e., in
+//! arrays) produce an error.
+//!
+//! Similarly, [`Document`] cannot distinguish between single-value lists and
+//! single-value nodes. For example, an array of tables with a single element
+//! will be represented as just the table, so a roundtrip through [`Document`]
+//! is not lossless.
+
+use alloc::{
+    borrow::{Cow, ToOwned as _},
+    string::{String, ToString as _},
+    vec::Vec,
+};
+
+use crate::{Builder, ClassifyNode, Document, DocumentBuffer, Error, Result, builder};
+
+/// Settings for converting between TOML and [`Document`].
+pub struct TomlSettings<'a> {
+    /// For nodes that have a type, using this key will add a field to the TOML
+    /// table with the type name. Default is `"$type"`. When empty, type
+    /// information is omitted.
+    pub type_tag: &'a str,
+    /// When the children of a node are mixed named and unnamed, or the node has
+    /// a type (and `type_tag` is not empty), overflow unnamed children into an
+    /// array with this name. Default is `"$items"`.
+    ///
+    /// Note that sequence-like nodes with a single element are
+    /// indistinguishable from single-value nodes, so single-element sequences
+    /// will not have an `$items` field, but instead a `$value` field.
+    pub items_tag: &'a str,
+    /// In tables representing a single value (e.g. a node that contains a
+    /// single unnamed element, but the node has a type), put the value in a
+    /// field with this name. Default is `"$value"`.
+    ///
+    /// Note that sequence-like nodes with a single element are
+    /// indistinguishable from single-value nodes, so single-element sequences
+    /// will not have an `$items` field, but instead a `$value` field.
+    pub value_tag: &'a str,
+    /// Node type used to represent TOML datetimes. Default is `"datetime"`.
+    ///
+    /// When converting from TOML, datetimes become nodes with this type and a
+    /// single unnamed string argument. When converting to TOML, nodes with this
+    /// type and a single string argument that is a valid TOML datetime become
+    /// datetimes. When empty, datetimes are converted to plain strings.
+    pub datetime_type: &'a str,
+}
+
+impl Default for TomlSettings<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            type_tag: "$type",
+            items_tag: "$items",
+            value_tag: "$value",
+            datetime_type: "datetime",
+        }
+    }
+}
+
+/// Convert [`Document`] to a TOML table.
+///
+/// # Errors
+///
+/// If the document cannot be represented as TOML, or if any fields in the
+/// document conflict with the default TOML settings, this returns an error.
+#[inline]
+pub fn document_to_toml_table(doc: &Document) -> Result<toml::Table> {
+    document_to_toml_table_with_settings(doc, &TomlSettings::default())
+}
+
+/// Convert [`Document`] to TOML.
+///
+/// # Errors
+///
+/// If the document cannot be represented as TOML, or if any fields in the
+/// document conflict with the default TOML settings, this returns an error.
+#[inline]
+pub fn document_to_toml(doc: &Document) -> Result<String> {
+    document_to_toml_with_settings(doc, &TomlSettings::default())
+}
+
+/// Convert [`Document`] to a TOML table.
+///
+/// # Errors
+///
+/// If the document cannot be represented as TOML, or if any fields in the
+/// document conflict with the TOML settings, this returns an error.
+#[inline]
+pub fn document_to_toml_table_with_settings(
+    doc: &Document,
+    settings: &TomlSettings,
+) -> Result<toml::Table> {
+    settings.node_to_toml_table(&doc.root())
+}
+
+/// Convert [`Document`] to TOML.
+///
+/// # Errors
+///
+/// If the document cannot be represented as TOML, or if any fields in the
+/// document conflict with the TOML settings, this returns an error.
+#[inline]
+pub fn document_to_toml_with_settings(doc: &Document, settings: &TomlSettings) -> Result<String> {
+    let table = document_to_toml_table_with_settings(doc, settings)?;
+    toml::to_string(&table).map_err(Error::custom)
+}
+
+/// Convert a TOML table to [`Document`].
+///
+/// This is an infallible conversion.
+#[inline]
+#[must_use]
+pub fn document_from_toml_table(table: &toml::Table) -> DocumentBuffer {
+    document_from_toml_table_with_settings(table, &TomlSettings::default())
+}
+
+/// Convert TOML to [`Document`].
+///
+/// # Errors
+///
+/// If the string is not a valid TOML document, this returns an error.
+#[inline]
+pub fn document_from_toml(toml: &str) -> Result<DocumentBuffer> {
+    document_from_toml_with_settings(toml, &TomlSettings::default())
+}
+
+/// Convert a TOML table to [`Document`].
+///
+/// This is an infallible conversion.
+#[inline]
+#[must_use]
+pub fn document_from_toml_table_with_settings(
+    table: &toml::Table,
+    settings: &TomlSettings,
+) -> DocumentBuffer {
+    builder_from_toml_table_with_settings(table, settings).build()
+}
+
+/// Convert TOML to [`Document`].
+///
+/// # Errors
+///
+/// If the string is not a valid TOML document, this returns an error.
+#[inline]
+pub fn document_from_toml_with_settings(
+    toml: &str,
+    settings: &TomlSettings,
+) -> Result<DocumentBuffer> {
+    let table = toml::from_str(toml).map_err(Error::custom)?;
+    Ok(builder_from_toml_table_with_settings(&table, settings).build())
+}
+
+/// Convert TOML to [`Builder`], which can be modified further.
+///
+/// # Errors
+///
+/// If the string is not a valid TOML document, this returns an error.
+pub fn builder_from_toml(toml: &str) -> Result<Builder<'static>> {
+    builder_from_toml_with_settings(toml, &TomlSettings::default())
+}
+
+/// Convert TOML to [`Builder`], which can be modified further.
+///
+/// # Errors
+///
+/// If the string is not a valid TOML document, this returns an error.
+pub fn builder_from_toml_with_settings(
+    toml: &str,
+    settings: &TomlSettings,
+) -> Result<Builder<'static>> {
+    let table = toml::from_str(toml).map_err(Error::custom)?;
+    let builder = builder_from_toml_table_with_settings(&table, settings);
+    Ok(builder.into_static())
+}
+
+/// Convert a TOML table to [`Builder`], which can be modified further.
+///
+/// Strings from the TOML table are borrowed, not cloned, so the TOML table must
+/// outlive the returned builder.
+///
+/// This is an infallible conversion.
+#[must_use]
+#[inline]
+pub fn builder_from_toml_table(table: &toml::Table) -> Builder<'_> {
+    builder_from_toml_table_with_settings(table, &TomlSettings::default())
+}
+
+/// Convert a TOML table to [`Builder`], which can be modified further.
+///
+/// Strings from the TOML table are borrowed, not cloned, so the TOML table must
+/// outlive the returned builder.
+///
+/// This is an infallible conversion.
+#[must_use]
+#[inline]
+pub fn builder_from_toml_table_with_settings<'a>(
+    table: &'a toml::Table,
+    settings: &TomlSettings,
+) -> Builder<'a> {
+    let mut builder = Builder::new();
+    builder.set_root(settings.toml_table_to_node(table));
+    builder
+}
+
+impl TomlSettings<'_> {
+    fn node_to_toml(&self, node: &crate::Node) -> Result<toml::Value> {
+        if let Some(datetime) = self.node_to_toml_datetime(node) {
+            return Ok(toml::Value::Datetime(datetime));
+        }
+
+        let classification = node.classify();
+
+        if let ClassifyNode::Struct
+        | ClassifyNode::StructVariant
+        | ClassifyNode::SeqVariant
+        | ClassifyNode::ValueVariant
+        | ClassifyNode::UnitVariant
+        | ClassifyNode::Mixed
+        | ClassifyNode::MixedVariant
+        | ClassifyNode::Unit = classification
+        {
+            // The node must be converted to a TOML table. TOML has no null
+            // value, so empty nodes also become (empty) tables.
+            return self.node_to_toml_table(node).map(Into::into);
+        }
+
+        match classification {
+            ClassifyNode::Seq => {
+                let mut items = Vec::with_capacity(node.children().len() + node.args().len());
+                for arg in node.args() {
+                    items.push(self.value_to_toml(&arg.value)?);
+                }
+                for child in node.children() {
+                    items.push(self.node_to_toml(&child)?);
+                }
+                Ok(toml::Value::Array(items))
+            }
+            ClassifyNode::Value => {
+                if let Some(first_arg) = node.args().get(0) {
+                    self.value_to_toml(&first_arg.value)
+                } else if let Some(first_child) = node.children().get(0) {
+                    self.node_to_toml(&first_child)
+                } else {
+                    unreachable!() // Value nodes have exactly one entry
+                }
+            }
+            _ => unreachable!(), // Handled above
+        }
+    }
+
+    fn node_to_toml_datetime(&self, node: &crate::Node) -> Option<toml::value::Datetime> {
+        if self.datetime_type.is_empty()
+            || node.ty() != Some(self.datetime_type)
+            || !node.children().is_empty()
+            || node.args().len() != 1
+        {
+            return None;
+        }
+
+        match node.args().get(0)? {
+            crate::Arg {
+                name: None,
+                value: crate::ValueRef::String(s),
+            } => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn node_to_toml_table(&self, node: &crate::Node) -> Result<toml::Table> {
+        // Entries with the same name are collected into arrays, in the order of
+        // first appearance.
+        let mut fields: Vec<(&str, Vec<toml::Value>)> = Vec::new();
+        let mut items = Vec::new();
+
+        let mut push_field = |name, value| {
+            if let Some((_, values)) = fields.iter_mut().find(|(existing, _)| *existing == name) {
+                values.push(value);
+            } else {
+                fields.push((name, alloc::vec![value]));
+            }
+        };
+
+        for arg in node.args() {
+            if let Some(name) = arg.name {
+                // TOML cannot represent null, so named null values are omitted.
+                if arg.value == crate::ValueRef::Null {
+                    continue;
+                }
+                push_field(name, self.value_to_toml(&arg.value)?);
+            } else {
+                items.push(self.value_to_toml(&arg.value)?);
+            }
+        }
+
+        for child in node.children() {
+            if let Some(name) = child.name() {
+                push_field(name, self.node_to_toml(&child)?);
+            } else {
+                items.push(self.node_to_toml(&child)?);
+            }
+        }
+
+        let mut table = toml::Table::new();
+        for (name, mut values) in fields {
+            let value = if values.len() == 1 {
+                values.remove(0)
+            } else {
+                toml::Value::Array(values)
+            };
+            table.insert(name.to_owned(), value);
+        }
+
+        if let Some(ty) = node.ty()
+            && !self.type_tag.is_empty()
+            && table
+                .insert(self.type_tag.to_owned(), ty.to_owned().into())
+                .is_some()
+        {
+            return Err(Error::ClobberedField);
+        }
+
+        if !items.is_empty() {
+            let (tag, value) = if items.len() == 1 {
+                (self.value_tag, items.remove(0))
+            } else {
+                (self.items_tag, items.into())
+            };
+            if table.insert(tag.to_owned(), value).is_some() {
+                return Err(Error::ClobberedField);
+            }
+        }
+
+        Ok(table)
+    }
+
+    #[expect(clippy::unused_self)]
+    fn value_to_toml(&self, value: &crate::ValueRef) -> Result<toml::Value> {
+        Ok(match value {
+            crate::ValueRef::Null => return Err(Error::UnrepresentableNull),
+            crate::ValueRef::Bool(value) => toml::Value::Boolean(*value),
+            crate::ValueRef::Int(value) => toml::Value::Integer(*value),
+            crate::ValueRef::Uint(value) => toml::Value::Integer(
+                i64::try_from(*value).map_err(|_| Error::UnrepresentableUint(*value))?,
+            ),
+            crate::ValueRef::Float(value) => toml::Value::Float(*value),
+            crate::ValueRef::String(value) => toml::Value::String((*value).to_owned()),
+            crate::ValueRef::Binary(_) => return Err(Error::UnrepresentableBinary),
+        })
+    }
+
+    fn toml_to_entry<'a>(&self, value: &'a toml::Value) -> builder::Entry<'a> {
+        match value {
+            toml::Value::Boolean(value) => builder::Value::Bool(*value).into(),
+            toml::Value::Integer(int) => {
+                if let Ok(uint) = u64::try_from(*int) {
+                    builder::Value::Uint(uint).into()
+                } else {
+                    builder::Value::Int(*int).into()
+                }
+            }
+            toml::Value::Float(float) => builder::Value::Float(*float).into(),
+            toml::Value::String(s) => builder::Value::String(Cow::Borrowed(s)).into(),
+            toml::Value::Datetime(datetime) => {
+                let datetime = builder::Value::String(Cow::Owned(datetime.to_string()));
+                if self.datetime_type.is_empty() {
+                    datetime.into()
+                } else {
+                    let mut node = builder::Node::empty();
+                    node.set_ty(self.datetime_type.to_owned());
+                    node.push_unnamed_arg(datetime);
+                    builder::Entry::Child(node)
+                }
+            }
+            toml::Value::Array(values) => {
+                let mut node = builder::Node::empty();
+                for value in values {
+                    node.push_ordered(self.toml_to_entry(value));
+                }
+                builder::Entry::Child(node)
+            }
+            toml::Value::Table(table) => self.toml_table_to_node(table).into(),
+        }
+    }
+
+    fn toml_table_to_node<'a>(&self, table: &'a toml::Table) -> builder::Node<'a> {
+        let mut node = builder::Node::empty();
+        for (k, v) in table {
+            if k == self.type_tag {
+                if let Some(ty) = v.as_str() {
+                    node.set_ty(ty);
+                }
+            } else if k == self.items_tag {
+                if let Some(list) = v.as_array() {
+                    for item in list {
+                        node.push_ordered(self.toml_to_entry(item));
+                    }
+                }
+            } else if k == self.value_tag {
+                let value = self.toml_to_entry(v);
+                node.push_ordered(value);
+            } else if let Some(tables) = as_array_of_tables(v) {
+                // Arrays of tables become repeated children with the same name.
+                for table in tables {
+                    let mut child = self.toml_table_to_node(table);
+                    child.set_name(k);
+                    node.push(child);
+                }
+            } else {
+                let mut value = self.toml_to_entry(v);
+                value.set_name(k);
+                node.push(value);
+            }
+        }
+
+        node
+    }
+}
+
+fn as_array_of_tables(value: &toml::Value) -> Option<impl Iterator<Item = &toml::Table>> {
+    let array = value.as_array()?;
+    if array.is_empty() || !array.iter().all(toml::Value::is_table) {
+        return None;
+    }
+    Some(array.iter().filter_map(toml::Value::as_table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_roundtrip() {
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.set_ty("Root");
+            root.push(builder::Arg::new(
+                "key",
+                builder::Value::String("value".into()),
+            ));
+            // Unnamed children should end up in an `$items` field.
+            root.push(builder::Value::String("child".into()));
+            root.push(builder::Value::Int(123));
+        });
+        let doc = builder.build();
+
+        let toml = document_to_toml(&doc).unwrap();
+        assert_eq!(
+            toml,
+            "\"$items\" = [\"child\", 123]\n\"$type\" = \"Root\"\nkey = \"value\"\n"
+        );
+
+        let doc = document_from_toml(&toml).unwrap();
+        let root = doc.root();
+        assert_eq!(root.ty(), Some("Root"));
+        assert_eq!(root.args().len(), 3);
+        assert_eq!(root.args().get(0).unwrap().name, None);
+        assert_eq!(
+            root.args().get(0).unwrap().value,
+            crate::ValueRef::String("child")
+        );
+        assert_eq!(root.args().get(1).unwrap().name, None);
+        assert_eq!(
+            root.args().get(1).unwrap().value,
+            // Non-negative integers are converted to `u64`.
+            crate::ValueRef::Uint(123)
+        );
+        assert_eq!(root.args().get(2).unwrap().name, Some("key"));
+        assert_eq!(
+            root.args().get(2).unwrap().value,
+            crate::ValueRef::String("value")
+        );
+    }
+
+    #[test]
+    fn array_of_tables() {
+        let toml = r#"
+[[servers]]
+host = "alpha"
+port = 8080
+
+[[servers]]
+host = "beta"
+port = 8081
+"#;
+        let doc = document_from_toml(toml).unwrap();
+        let root = doc.root();
+        assert_eq!(root.children().len(), 2);
+        for (child, host) in root.children().into_iter().zip(["alpha", "beta"]) {
+            assert_eq!(child.name(), Some("servers"));
+            assert_eq!(
+                child.get("host").unwrap().value(),
+                Some(crate::ValueRef::String(host))
+            );
+        }
+
+        let table = document_to_toml_table(&doc).unwrap();
+        let servers = table["servers"].as_array().unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0]["host"].as_str(), Some("alpha"));
+        assert_eq!(servers[1]["port"].as_integer(), Some(8081));
+    }
+
+    #[test]
+    fn datetime() {
+        let toml = "created = 1979-05-27T07:32:00Z\n";
+        let doc = document_from_toml(toml).unwrap();
+        let created = doc.root().children().get("created").unwrap();
+        assert_eq!(created.ty(), Some("datetime"));
+        assert_eq!(
+            created.args().get(0).unwrap().value,
+            crate::ValueRef::String("1979-05-27T07:32:00Z")
+        );
+
+        assert_eq!(document_to_toml(&doc).unwrap(), toml);
+
+        let settings = TomlSettings {
+            datetime_type: "",
+            ..Default::default()
+        };
+        let doc = document_from_toml_with_settings(toml, &settings).unwrap();
+        assert_eq!(
+            doc.root().args().get("created").unwrap().value,
+            crate::ValueRef::String("1979-05-27T07:32:00Z")
+        );
+    }
+
+    #[test]
+    fn unrepresentable() {
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.push(builder::Arg::new("skipped", builder::Value::Null));
+            root.push(builder::Arg::new("big", builder::Value::Uint(u64::MAX)));
+        });
+        assert_eq!(
+            document_to_toml(&builder.build()),
+            Err(Error::UnrepresentableUint(u64::MAX))
+        );
+
+        builder.root_mut().args_mut().pop();
+        assert_eq!(document_to_toml(&builder.build()).unwrap(), "");
+
+        builder.root_mut().push(builder::Value::Null);
+        assert_eq!(
+            document_to_toml(&builder.build()),
+            Err(Error::UnrepresentableNull)
+        );
+    }
+}
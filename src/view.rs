@@ -0,0 +1,384 @@
+//! Typed zero-copy views over nodes.
+//!
+//! Deserializing with `serde` or `facet` copies data into owned values. For
+//! hot read paths, `#[derive(ZdocView)]` (with the `derive` feature) generates
+//! a borrowed accessor struct for a struct with named fields, which wraps a
+//! [`Node`] and has a typed getter for each field. The getters follow the
+//! [conventions](crate::serde) of the `serde` support, so a view can read any
+//! document produced by serializing the original struct.
+//!
+//! ```ignore
+//! #[derive(ZdocView)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! #[derive(ZdocView)]
+//! struct Config {
+//!     name: String,
+//!     servers: Vec<Server>,
+//! }
+//!
+//! let config = ConfigView::new(doc.root());
+//! config.validate()?;
+//! for server in config.servers() {
+//!     println!("{}:{}", server.host()?, server.port()?);
+//! }
+//! ```
+//!
+//! The getter of a field of type `T` returns
+//! [`<T as Viewable>::Field<'a>`](Viewable::Field):
+//!
+//! | Field type            | Getter                                   |
+//! | --------------------- | ---------------------------------------- |
+//! | Primitives, `char`    | `Result<T, ViewError>`                   |
+//! | `String`              | `Result<&'a str, ViewError>`             |
+//! | `#[derive(ZdocView)]` | `Result<TView<'a>, ViewError>`           |
+//! | `Vec<T>`              | [`Items<'a, T>`], iterating `T`'s items  |
+//! | `Option<T>`           | `Option<_>` of the getter for `T`        |
+//!
+//! Views of structs only wrap the node, so the items of a `Vec` of structs are
+//! views, not results. Lists of primitives yield a `Result` per item.
+//!
+//! Accessing a field looks up the entry every time, and errors are only
+//! reported for the accessed field. [`validate()`](View::validate) checks all
+//! fields up front, recursively, so getters of a validated view do not fail.
+
+use core::{fmt, iter::FusedIterator, marker::PhantomData};
+
+use crate::{EntriesIter, Entry, Node, NodeId, ValueError, ValueRef};
+
+#[cfg(feature = "derive")]
+pub use zdoc_derive::ZdocView;
+
+/// Typed view over a [`Node`], generated by `#[derive(ZdocView)]`.
+pub trait View<'a>: Copy {
+    /// Wrap `node` without checking its contents.
+    fn from_node(node: Node<'a>) -> Self;
+
+    /// The viewed node.
+    fn node(&self) -> Node<'a>;
+
+    /// Check that the getters of all fields succeed, recursively.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first field that cannot be accessed.
+    fn validate(&self) -> Result<(), ViewError>;
+}
+
+/// Owned type that can be viewed as a field of a [`View`].
+///
+/// This is implemented for primitives, `String`, `Vec<T>`, `Option<T>`, and
+/// the types deriving `ZdocView`.
+pub trait Viewable {
+    /// Return type of the getter of a field of this type.
+    type Field<'a>;
+    /// Item type when iterating over a `Vec` of this type.
+    type Item<'a>;
+
+    /// Get the field at `at` of `parent`, where `entry` is the entry that was
+    /// found for it, if any.
+    fn field<'a>(parent: Node<'a>, at: ViewEntry, entry: Option<Entry<'a>>) -> Self::Field<'a>;
+
+    /// Get item number `index` of `list`, or `None` if the item should be
+    /// skipped.
+    fn item<'a>(list: Node<'a>, index: usize, entry: Entry<'a>) -> Option<Self::Item<'a>>;
+
+    /// Check that [`field()`](Self::field) or [`item()`](Self::item) succeed
+    /// for `entry`, recursively.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first entry that cannot be accessed.
+    fn validate(parent: Node<'_>, at: ViewEntry, entry: Option<Entry<'_>>)
+    -> Result<(), ViewError>;
+}
+
+/// Error when accessing a field of a [`View`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{error}, {entry} of node {node}")]
+pub struct ViewError {
+    /// The node containing the entry. Use
+    /// [`Document::cursor_at()`](crate::Document::cursor_at) to get its path.
+    pub node: NodeId,
+    /// The entry that could not be accessed.
+    pub entry: ViewEntry,
+    /// Kind of error that occurred.
+    pub error: ViewErrorKind,
+}
+
+/// Location of an entry within a node, for [`ViewError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewEntry {
+    /// Field of a struct, which is the argument or child with this name.
+    Field(&'static str),
+    /// Item of a list.
+    Item(usize),
+}
+
+impl fmt::Display for ViewEntry {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewEntry::Field(name) => write!(f, "field `{name}`"),
+            ViewEntry::Item(index) => write!(f, "item {index}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ViewErrorKind {
+    #[error("missing entry")]
+    Missing,
+    #[error("expected a node, found an argument")]
+    ExpectedNode,
+    #[error("expected a value, found a node without arguments")]
+    ExpectedValue,
+    #[error(transparent)]
+    Value(#[from] ValueError),
+}
+
+impl ViewError {
+    #[inline]
+    #[must_use]
+    pub fn new(node: Node, entry: ViewEntry, error: impl Into<ViewErrorKind>) -> Self {
+        Self {
+            node: node.id(),
+            entry,
+            error: error.into(),
+        }
+    }
+}
+
+/// Get the node of a field or item holding a struct or a list.
+///
+/// # Errors
+///
+/// If `entry` is missing or is an argument, this returns an error.
+#[inline]
+pub fn expect_node<'a>(
+    parent: Node<'a>,
+    at: ViewEntry,
+    entry: Option<Entry<'a>>,
+) -> Result<Node<'a>, ViewError> {
+    match entry {
+        Some(Entry::Child(node)) => Ok(node),
+        Some(Entry::Arg(_)) => Err(ViewError::new(parent, at, ViewErrorKind::ExpectedNode)),
+        None => Err(ViewError::new(parent, at, ViewErrorKind::Missing)),
+    }
+}
+
+/// Get the value of a field or item holding a primitive.
+fn value<'a, T>(parent: Node<'a>, at: ViewEntry, entry: Option<Entry<'a>>) -> Result<T, ViewError>
+where
+    T: TryFrom<ValueRef<'a>, Error = ValueError>,
+{
+    let Some(entry) = entry else {
+        return Err(ViewError::new(parent, at, ViewErrorKind::Missing));
+    };
+    match entry.value_as() {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => Err(ViewError::new(parent, at, ViewErrorKind::ExpectedValue)),
+        Err(error) => Err(ViewError::new(parent, at, error)),
+    }
+}
+
+macro_rules! impl_viewable_value {
+    ($($ty:ty => $view:ty),* $(,)?) => {
+        $(
+            impl Viewable for $ty {
+                type Field<'a> = Result<$view, ViewError>;
+                type Item<'a> = Result<$view, ViewError>;
+
+                #[inline]
+                fn field<'a>(
+                    parent: Node<'a>,
+                    at: ViewEntry,
+                    entry: Option<Entry<'a>>,
+                ) -> Self::Field<'a> {
+                    value(parent, at, entry)
+                }
+
+                #[inline]
+                fn item<'a>(list: Node<'a>, index: usize, entry: Entry<'a>) -> Option<Self::Item<'a>> {
+                    Some(value(list, ViewEntry::Item(index), Some(entry)))
+                }
+
+                #[inline]
+                fn validate<'a>(
+                    parent: Node<'a>,
+                    at: ViewEntry,
+                    entry: Option<Entry<'a>>,
+                ) -> Result<(), ViewError> {
+                    value::<$view>(parent, at, entry).map(|_| ())
+                }
+            }
+        )*
+    };
+}
+
+impl_viewable_value! {
+    bool => bool,
+    i8 => i8,
+    i16 => i16,
+    i32 => i32,
+    i64 => i64,
+    i128 => i128,
+    isize => isize,
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    u128 => u128,
+    usize => usize,
+    f32 => f32,
+    f64 => f64,
+    char => char,
+}
+
+#[cfg(feature = "alloc")]
+impl_viewable_value! {
+    alloc::string::String => &'a str,
+}
+
+/// Optional fields are `None` when the entry is missing, null, or an empty
+/// node.
+impl<T: Viewable> Viewable for Option<T> {
+    type Field<'a> = Option<T::Field<'a>>;
+    type Item<'a> = Option<T::Item<'a>>;
+
+    #[inline]
+    fn field<'a>(parent: Node<'a>, at: ViewEntry, entry: Option<Entry<'a>>) -> Self::Field<'a> {
+        entry
+            .filter(|entry| !is_none(*entry))
+            .map(|entry| T::field(parent, at, Some(entry)))
+    }
+
+    #[inline]
+    fn item<'a>(list: Node<'a>, index: usize, entry: Entry<'a>) -> Option<Self::Item<'a>> {
+        if is_none(entry) {
+            Some(None)
+        } else {
+            T::item(list, index, entry).map(Some)
+        }
+    }
+
+    #[inline]
+    fn validate(
+        parent: Node<'_>,
+        at: ViewEntry,
+        entry: Option<Entry<'_>>,
+    ) -> Result<(), ViewError> {
+        match entry {
+            Some(entry) if !is_none(entry) => T::validate(parent, at, Some(entry)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_none(entry: Entry) -> bool {
+    match entry {
+        Entry::Arg(arg) => arg.value.is_null(),
+        Entry::Child(node) => node.is_empty() || node.value().is_some_and(|value| value.is_null()),
+    }
+}
+
+/// Lists are nodes, where each argument or child is an item.
+///
+/// When the field is missing or is not a node, the iterator is empty.
+#[cfg(feature = "alloc")]
+impl<T: Viewable> Viewable for alloc::vec::Vec<T> {
+    type Field<'a> = Items<'a, T>;
+    type Item<'a> = Items<'a, T>;
+
+    #[inline]
+    fn field<'a>(parent: Node<'a>, at: ViewEntry, entry: Option<Entry<'a>>) -> Self::Field<'a> {
+        Items::new(expect_node(parent, at, entry).ok())
+    }
+
+    #[inline]
+    fn item<'a>(_list: Node<'a>, _index: usize, entry: Entry<'a>) -> Option<Self::Item<'a>> {
+        entry.as_node().map(|node| Items::new(Some(node)))
+    }
+
+    fn validate(
+        parent: Node<'_>,
+        at: ViewEntry,
+        entry: Option<Entry<'_>>,
+    ) -> Result<(), ViewError> {
+        let list = expect_node(parent, at, entry)?;
+        list.entries()
+            .into_iter()
+            .enumerate()
+            .try_for_each(|(index, entry)| T::validate(list, ViewEntry::Item(index), Some(entry)))
+    }
+}
+
+/// Iterator over the items of a list, returned by the getters of fields of
+/// type `Vec<T>`.
+pub struct Items<'a, T: ?Sized> {
+    list: Option<(Node<'a>, EntriesIter<'a>)>,
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: ?Sized> Items<'a, T> {
+    /// Iterate over the entries of `list`.
+    #[inline]
+    #[must_use]
+    pub fn new(list: Option<Node<'a>>) -> Self {
+        Self {
+            list: list.map(|node| (node, node.entries().into_iter())),
+            index: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The node containing the items, if the list exists.
+    #[inline]
+    #[must_use]
+    pub fn node(&self) -> Option<Node<'a>> {
+        self.list.as_ref().map(|(node, _)| *node)
+    }
+}
+
+impl<T: ?Sized> Clone for Items<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list.clone(),
+            index: self.index,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Viewable> Iterator for Items<'a, T> {
+    type Item = T::Item<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (list, entries) = self.list.as_mut()?;
+        for entry in entries {
+            let index = self.index;
+            self.index += 1;
+            if let Some(item) = T::item(*list, index, entry) {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Viewable> FusedIterator for Items<'_, T> {}
+
+impl<T: ?Sized> fmt::Debug for Items<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Items")
+            .field("node", &self.node().map(|node| node.id()))
+            .field("index", &self.index)
+            .finish()
+    }
+}
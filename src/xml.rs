@@ -41,6 +41,13 @@ pub struct XmlSettings<'a> {
     /// The XML tag to use for the value of unnamed node arguments. Defaults to
     /// `value`, so `<item value="..." />`.
     pub unnamed_argument_attribute: &'a str,
+    /// Number of spaces to indent nested elements with. When zero, all elements
+    /// are written on a single line. Defaults to `0`.
+    pub indent: usize,
+    /// Skip text inside elements that consists only of whitespace, such as the
+    /// indentation written when `indent` is nonzero. Whitespace outside the
+    /// root element is always ignored. Defaults to `false`.
+    pub ignore_whitespace: bool,
 }
 
 impl Default for XmlSettings<'_> {
@@ -51,6 +58,8 @@ impl Default for XmlSettings<'_> {
             name_attribute: "name",
             unnamed_argument_tag: "arg",
             unnamed_argument_attribute: "value",
+            indent: 0,
+            ignore_whitespace: false,
         }
     }
 }
@@ -94,7 +103,7 @@ pub fn document_from_xml(xml: &str) -> Result<DocumentBuffer> {
 ///
 /// If `xml` is not valid XML, this returns an error.
 #[inline]
-pub fn builder_from_xml(xml: &str) -> Result<Builder> {
+pub fn builder_from_xml(xml: &str) -> Result<Builder<'_>> {
     builder_from_xml_with_settings(xml, &XmlSettings::default())
 }
 
@@ -120,6 +129,7 @@ impl XmlSettings<'_> {
                 Event::Start(tag) => break self.read_node_with_body(&tag, &mut reader)?,
                 Event::End(_) => return Err(Error::msg("unexpected end tag")),
                 Event::Empty(tag) => break self.read_node_attributes(&tag)?,
+                Event::Text(bytes_text) if is_whitespace(&bytes_text) => (),
                 Event::Text(bytes_text) => {
                     let mut node = builder::Node::empty();
                     node.push_unnamed_arg(bytes_text.unescape().map_err(Error::custom)?);
@@ -146,10 +156,12 @@ impl XmlSettings<'_> {
             }
         };
 
-        if !eof {
-            let last_event = reader.read_event().map_err(Error::custom)?;
-            if !matches!(last_event, Event::Eof) {
-                return Err(Error::msg("trailing data after root node"));
+        while !eof {
+            match reader.read_event().map_err(Error::custom)? {
+                Event::Eof => eof = true,
+                Event::Text(text) if is_whitespace(&text) => (),
+                Event::Comment(_) | Event::PI(_) => (),
+                _ => return Err(Error::msg("trailing data after root node")),
             }
         }
 
@@ -207,6 +219,7 @@ impl XmlSettings<'_> {
                         qname_ref_to_string(end_tag.name())?
                     )));
                 }
+                Event::Text(text) if self.ignore_whitespace && is_whitespace(&text) => (),
                 Event::Text(text) => {
                     let text = text.unescape().map_err(Error::custom)?;
                     let mut child = builder::Node::empty();
@@ -215,14 +228,13 @@ impl XmlSettings<'_> {
                 }
                 Event::Empty(tag) => {
                     let name = qname_ref_to_string(tag.name())?;
-                    if name == self.unnamed_argument_tag {
-                        if let Some(attr) = tag
+                    if name == self.unnamed_argument_tag
+                        && let Some(attr) = tag
                             .try_get_attribute(self.unnamed_argument_attribute)
                             .map_err(Error::custom)?
-                        {
-                            node.push_unnamed_arg(self.read_value(&attr.value)?);
-                            continue;
-                        }
+                    {
+                        node.push_unnamed_arg(self.read_value(&attr.value)?);
+                        continue;
                     }
 
                     let child = self.read_node_attributes(&tag)?;
@@ -272,7 +284,11 @@ impl XmlSettings<'_> {
 
     fn write_document(&self, doc: &Document) -> Result<String> {
         let mut out = Vec::new();
-        let mut writer = Writer::new(&mut out);
+        let mut writer = if self.indent == 0 {
+            Writer::new(&mut out)
+        } else {
+            Writer::new_with_indent(&mut out, b' ', self.indent)
+        };
         writer
             .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
             .map_err(Error::custom)?;
@@ -326,17 +342,18 @@ impl XmlSettings<'_> {
             for child in node.children() {
                 // If the child is unnamed, untyped, and has a single string
                 // argument, emit it as text.
-                if child.name().is_none() && child.ty().is_none() && child.args().len() == 1 {
-                    if let Some(crate::Arg {
+                if child.name().is_none()
+                    && child.ty().is_none()
+                    && child.args().len() == 1
+                    && let Some(crate::Arg {
                         name: None,
                         value: ValueRef::String(text),
                     }) = child.args().get(0)
-                    {
-                        writer
-                            .write_event(Event::Text(BytesText::new(text)))
-                            .map_err(Error::custom)?;
-                        continue;
-                    }
+                {
+                    writer
+                        .write_event(Event::Text(BytesText::new(text)))
+                        .map_err(Error::custom)?;
+                    continue;
                 }
 
                 self.write_node(writer, child)?;
@@ -389,6 +406,11 @@ fn qname_ref_to_string(qname: QName<'_>) -> Result<&'_ str> {
     core::str::from_utf8(qname.0).map_err(|_| Error::UnrepresentableString)
 }
 
+#[inline]
+fn is_whitespace(text: &BytesText<'_>) -> bool {
+    text.iter().all(u8::is_ascii_whitespace)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -436,4 +458,57 @@ mod tests {
             crate::ValueRef::Uint(123)
         );
     }
+
+    #[test]
+    fn pretty() {
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.set_ty("Root");
+            root.push_named_with("child", |child| {
+                child.push(builder::Arg::new("key", builder::Value::Int(-1)));
+            });
+            root.push(builder::Value::Bool(true));
+        });
+        let doc = builder.build();
+
+        let settings = XmlSettings {
+            indent: 2,
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        let xml = document_to_xml_with_settings(&doc, &settings).unwrap();
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Root>
+  <arg value="true"/>
+  <item name="child" key="-1"/>
+</Root>"#
+        );
+
+        let roundtrip = builder_from_xml_with_settings(&xml, &settings)
+            .unwrap()
+            .build();
+        assert_eq!(roundtrip.as_bytes(), doc.as_bytes());
+    }
+
+    #[test]
+    fn whitespace_text() {
+        let doc = document_from_xml("<a> </a>").unwrap();
+        let text = doc.root().children().get(0).unwrap();
+        assert_eq!(text.args().get(0).unwrap().value, ValueRef::String(" "));
+        assert_eq!(
+            document_to_xml(&doc).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?><a> </a>"#
+        );
+
+        let settings = XmlSettings {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        let doc = builder_from_xml_with_settings("<a> </a>", &settings)
+            .unwrap()
+            .build();
+        assert_eq!(doc.root().children().len(), 0);
+    }
 }
@@ -25,7 +25,7 @@
 use alloc::{
     borrow::{Cow, ToOwned as _},
     boxed::Box,
-    string::ToString as _,
+    string::{String, ToString as _},
     vec::Vec,
 };
 
@@ -48,6 +48,16 @@ pub struct YamlSettings<'a> {
     /// indistinguishable from single-value nodes, so single-element sequences
     /// will not have an `$items` field, but instead a `$value` field.
     pub value_tag: &'a str,
+    /// Number of spaces to indent nested mappings and sequence items with when
+    /// producing a YAML string. Clamped to `2..=9`, the range that the
+    /// indentation indicator of block scalars can express. Default is `2`,
+    /// which is the layout of `serde_yaml`.
+    pub indent: usize,
+    /// Preferred maximum width of lines when producing a YAML string. Longer
+    /// scalars are folded onto indented continuation lines at spaces, which
+    /// YAML reads back as single spaces. Scalars without spaces may still
+    /// exceed the width. Default is `None`, which never folds lines.
+    pub line_width: Option<usize>,
 }
 
 impl Default for YamlSettings<'_> {
@@ -56,6 +66,8 @@ impl Default for YamlSettings<'_> {
         Self {
             items_tag: "$items",
             value_tag: "$value",
+            indent: 2,
+            line_width: None,
         }
     }
 }
@@ -108,7 +120,8 @@ pub fn document_to_yaml_with_settings(
     settings: &YamlSettings,
 ) -> Result<alloc::string::String> {
     let value = settings.node_to_yaml(&doc.root())?;
-    serde_yaml::to_string(&value).map_err(Error::custom)
+    let yaml = serde_yaml::to_string(&value).map_err(Error::custom)?;
+    Ok(reformat(yaml, settings.indent, settings.line_width))
 }
 
 /// Convert YAML to [`Document`].
@@ -388,6 +401,196 @@ impl YamlSettings<'_> {
     }
 }
 
+/// Adjust the layout of YAML produced by `serde_yaml`, which always indents by
+/// two spaces and never breaks long lines.
+///
+/// Each line of `serde_yaml` output starts with a run of two-column units that
+/// are either indentation (`"  "`) or sequence indicators (`"- "`), so changing
+/// the indentation means widening each unit. The content of block scalars is
+/// copied verbatim, except for its base indentation.
+fn reformat(yaml: String, indent: usize, line_width: Option<usize>) -> String {
+    let step = indent.clamp(2, 9);
+    if step == 2 && line_width.is_none() {
+        return yaml;
+    }
+    let scale = |column: usize| column / 2 * step;
+
+    let mut out = String::with_capacity(yaml.len());
+    // Content column of the block scalar being copied, if any.
+    let mut block_scalar = None;
+    for line in yaml.lines() {
+        if let Some(column) = block_scalar {
+            if line.is_empty() {
+                out.push('\n');
+                continue;
+            }
+            if line.len() - line.trim_start_matches(' ').len() >= column {
+                push_spaces(&mut out, scale(column));
+                out.push_str(&line[column..]);
+                out.push('\n');
+                continue;
+            }
+            block_scalar = None;
+        }
+
+        let line_start = out.len();
+        let mut rest = line;
+        let mut column = 0;
+        let mut in_sequence_item = false;
+        loop {
+            if let Some(tail) = rest.strip_prefix("  ") {
+                push_spaces(&mut out, step);
+                in_sequence_item = false;
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("- ") {
+                out.push('-');
+                push_spaces(&mut out, step - 1);
+                in_sequence_item = true;
+                rest = tail;
+            } else {
+                break;
+            }
+            column += 2;
+        }
+
+        let (key, value) = match key_len(rest) {
+            Some(len) => {
+                let value = &rest[len..];
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                (&rest[..rest.len() - value.len()], value)
+            }
+            None => ("", rest),
+        };
+        let (tag, scalar) = match value.strip_prefix('!') {
+            Some(_) => match value.find(' ') {
+                Some(end) => value.split_at(end + 1),
+                None => (value, ""),
+            },
+            None => ("", value),
+        };
+        out.push_str(key);
+        out.push_str(tag);
+
+        // Block scalar content and folded lines must be indented more than the
+        // key or sequence indicator that owns the value.
+        let inner_column = if !key.is_empty() {
+            column + 2
+        } else if in_sequence_item {
+            column
+        } else {
+            2
+        };
+
+        if let Some(header) = scalar.strip_prefix('|') {
+            out.push('|');
+            // The indentation indicator (only emitted when the content starts
+            // with a space) is relative to the parent indentation.
+            match header.strip_prefix(|c: char| c.is_ascii_digit()) {
+                Some(chomping) => {
+                    out.push(char::from_digit(step as u32, 10).unwrap_or('2'));
+                    out.push_str(chomping);
+                }
+                None => out.push_str(header),
+            }
+            block_scalar = Some(inner_column);
+        } else if let Some(line_width) = line_width {
+            let column = out[line_start..].chars().count();
+            fold_scalar(&mut out, scalar, column, scale(inner_column), line_width);
+        } else {
+            out.push_str(scalar);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Length of the `key:` part of a mapping entry, or `None` if `entity` is not
+/// a mapping entry.
+fn key_len(entity: &str) -> Option<usize> {
+    let bytes = entity.as_bytes();
+    let end = match *bytes.first()? {
+        b'"' => {
+            let mut i = 1;
+            loop {
+                match *bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => break i + 1,
+                    _ => i += 1,
+                }
+            }
+        }
+        b'\'' => {
+            let mut i = 1;
+            loop {
+                match *bytes.get(i)? {
+                    b'\'' if bytes.get(i + 1) == Some(&b'\'') => i += 2,
+                    b'\'' => break i + 1,
+                    _ => i += 1,
+                }
+            }
+        }
+        b'|' | b'!' | b'[' | b'{' => return None,
+        // Plain scalars never contain `": "` or end with a colon.
+        _ => match entity.find(": ") {
+            Some(end) => end,
+            None if entity.ends_with(':') => entity.len() - 1,
+            None => return None,
+        },
+    };
+    let is_key = bytes.get(end) == Some(&b':') && matches!(bytes.get(end + 1), None | Some(b' '));
+    is_key.then_some(end + 1)
+}
+
+/// Write `scalar`, breaking it at single spaces so that lines stay within
+/// `line_width` where possible.
+///
+/// Plain, single-quoted, and double-quoted scalars all read a line break
+/// followed by indentation as a single space.
+fn fold_scalar(
+    out: &mut String,
+    scalar: &str,
+    mut column: usize,
+    indent: usize,
+    line_width: usize,
+) {
+    let bytes = scalar.as_bytes();
+    // Only break between two words, and never next to other spaces (which
+    // would be trimmed), escape sequences, or comment indicators.
+    let can_break = |i: usize| {
+        bytes[i] == b' '
+            && i >= 2
+            && i + 2 < bytes.len()
+            && !matches!(bytes[i - 1], b' ' | b'\\')
+            && !matches!(bytes[i + 1], b' ' | b'#')
+    };
+    let ends = (0..bytes.len())
+        .filter(|&i| can_break(i))
+        .chain(core::iter::once(bytes.len()));
+
+    let mut start = 0;
+    for end in ends {
+        let word = &scalar[start..end];
+        let width = word.chars().count();
+        if start != 0 {
+            if column + 1 + width > line_width {
+                out.push('\n');
+                push_spaces(out, indent);
+                column = indent;
+            } else {
+                out.push(' ');
+                column += 1;
+            }
+        }
+        out.push_str(word);
+        column += width;
+        start = end + 1;
+    }
+}
+
+fn push_spaces(out: &mut String, n: usize) {
+    out.extend(core::iter::repeat_n(' ', n));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -433,4 +636,139 @@ mod tests {
             crate::ValueRef::Uint(123)
         );
     }
+
+    #[test]
+    fn indent() {
+        let yaml = "\
+a: !Foo
+- 1
+- 2
+b:
+  c: !Baz 3
+  d: |
+    line 1
+      line 2
+  e: |2-
+      leading
+    space
+  'f: g': plain, \"quoted\"
+h:
+- a: 1
+  b: -2.5
+- - x
+  - !S |-
+    y
+    z
+- !T
+  z: null
+";
+        let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        let doc = document_from_yaml_value(&value);
+        let expected = document_to_yaml_value(&doc).unwrap();
+
+        // Default settings produce the output of `serde_yaml`.
+        let default = document_to_yaml(&doc).unwrap();
+        assert_eq!(default, serde_yaml::to_string(&expected).unwrap());
+
+        let settings = YamlSettings {
+            indent: 4,
+            ..Default::default()
+        };
+        let indented = document_to_yaml_with_settings(&doc, &settings).unwrap();
+        assert_eq!(
+            indented,
+            "\
+a: !Foo
+-   1
+-   2
+b:
+    d: |
+        line 1
+          line 2
+    e: |4-
+          leading
+        space
+    'f: g': plain, \"quoted\"
+    c: !Baz 3
+h:
+-   a: 1
+    b: -2.5
+-   -   x
+    -   !S |-
+        y
+        z
+-   !T
+    z: null
+"
+        );
+
+        for indent in [0, 3, 4, 9, 20] {
+            let settings = YamlSettings {
+                indent,
+                ..Default::default()
+            };
+            let yaml = document_to_yaml_with_settings(&doc, &settings).unwrap();
+            let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(value, expected, "indent = {indent}:\n{yaml}");
+        }
+    }
+
+    #[test]
+    fn line_width() {
+        let long = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                    eiusmod tempor incididunt ut labore et dolore magna aliqua.";
+        let mut builder = Builder::new();
+        builder.with_root(|root| {
+            root.push(builder::Arg::new(
+                "plain",
+                builder::Value::String(long.into()),
+            ));
+            root.push(builder::Arg::new(
+                "quoted",
+                builder::Value::String(alloc::format!("'{long}' # - x:  y \\ z").into()),
+            ));
+            root.push(builder::Arg::new(
+                "escaped",
+                builder::Value::String(alloc::format!("\t{long} \\ \"\u{1}\"").into()),
+            ));
+            root.push_named_with("items", |items| {
+                items.push(builder::Value::String(long.into()));
+                items.push(builder::Value::String("short".into()));
+                items.push(builder::Value::String("x".repeat(100).into()));
+            });
+        });
+        let doc = builder.build();
+        let expected = document_to_yaml_value(&doc).unwrap();
+
+        for (indent, line_width) in [(2, 40), (4, 60), (2, 1)] {
+            let settings = YamlSettings {
+                indent,
+                line_width: Some(line_width),
+                ..Default::default()
+            };
+            let yaml = document_to_yaml_with_settings(&doc, &settings).unwrap();
+            let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(value, expected, "line_width = {line_width}:\n{yaml}");
+        }
+
+        let settings = YamlSettings {
+            line_width: Some(40),
+            ..Default::default()
+        };
+        let yaml = document_to_yaml_with_settings(&doc, &settings).unwrap();
+        assert!(yaml.starts_with(
+            "\
+plain: Lorem ipsum dolor sit amet,
+  consectetur adipiscing elit, sed do
+  eiusmod tempor incididunt ut labore et
+  dolore magna aliqua.
+"
+        ));
+        for line in yaml.lines() {
+            assert!(
+                line.len() <= 40 || !line.trim_start_matches(['-', ' ']).contains(' '),
+                "line too long: {line}"
+            );
+        }
+    }
 }
@@ -32,9 +32,9 @@ fn named_root() {
             strings_len: 4,
             binary_offset: (size_of::<codec::Header>() + size_of::<codec::Node>() + 4) as u32,
             binary_len: 0,
-            reserved1: 0,
-            reserved2: 0,
-            reserved3: 0,
+            index_offset: 0,
+            index_len: 0,
+            reserved: 0,
         }
     );
     let bytes = doc.as_bytes();
@@ -129,3 +129,232 @@ fn debug_display() {
         r#"root = Root {"key1": 123, "dict": {"key": 456}, "list": [789, 0]}"#
     );
 }
+
+#[test]
+fn paths() {
+    use zdoc::{
+        builder::{Node, PathRef, Value},
+        path::PathError,
+    };
+
+    let mut builder = Builder::new();
+    builder.with_root(|root| {
+        root.push_named_with("servers", |servers| {
+            servers.push_named_with("", |server| {
+                server.push_named_arg("port", 80);
+            });
+        });
+    });
+
+    assert!(matches!(
+        builder.get_path("servers[0].port"),
+        Ok(Some(PathRef::Arg(arg))) if matches!(arg.value, Value::Int(80))
+    ));
+    assert!(matches!(builder.get_path("servers[1]"), Ok(None)));
+    assert!(matches!(builder.get_path("servers[0].port.x"), Ok(None)));
+    assert!(matches!(builder.get_path(""), Ok(Some(PathRef::Node(_)))));
+    assert!(matches!(
+        builder.get_path("a..b"),
+        Err(PathError::Syntax(2))
+    ));
+
+    // Replace an existing value, and create intermediate nodes.
+    builder.set_path("servers[0].port", 8080).unwrap();
+    builder.set_path("servers[1].@host", "localhost").unwrap();
+    builder.set_path("servers[1].#type", "Server").unwrap();
+    builder.set_path("options.verbose", true).unwrap();
+    assert_eq!(
+        format!("{:?}", builder.root()),
+        r#"{"servers": [{"port": 8080}, Server {"host": "localhost"}], "options": {"verbose": true}}"#
+    );
+
+    // Replace an argument with a node.
+    builder
+        .set_path("servers[0].port", Node::from_values([1, 2]))
+        .unwrap();
+    assert!(matches!(
+        builder.get_path("servers[0].port[1]"),
+        Ok(Some(PathRef::Arg(arg))) if matches!(arg.value, Value::Int(2))
+    ));
+
+    assert_eq!(
+        builder.set_path("servers[3]", 1),
+        Err(PathError::IndexOutOfBounds(3))
+    );
+    assert_eq!(
+        builder.set_path("servers[1].host.x", 1),
+        Err(PathError::NotANode)
+    );
+    assert_eq!(
+        builder.set_path("servers[1].#type", 1),
+        Err(PathError::InvalidValue)
+    );
+    assert_eq!(builder.set_path("", 1), Err(PathError::Empty));
+
+    assert!(builder.remove_path("servers[1].#type").unwrap().is_some());
+    assert!(builder.remove_path("servers[0]").unwrap().is_some());
+    assert!(builder.remove_path("servers[5]").unwrap().is_none());
+    assert!(builder.remove_path("missing.x").unwrap().is_none());
+    assert_eq!(
+        format!("{:?}", builder.root()),
+        r#"{"servers": {"host": "localhost"}, "options": {"verbose": true}}"#
+    );
+
+    // Index sugar.
+    let root = builder.root_mut();
+    assert_eq!(root["servers[0]"].args().len(), 1);
+    root["a.b"].push_named_arg("c", 1);
+    assert_eq!(root[2]["b"].args().len(), 1);
+    root[1].set_ty("Options");
+    assert_eq!(root["options"].ty(), "Options");
+}
+
+#[test]
+fn path_duplicate_names() {
+    use zdoc::builder::{Node, PathRef};
+
+    let mut builder = Builder::new();
+    builder.with_root(|root| {
+        root.push_named_arg("port", 1);
+        root.push_named_arg("port", 2);
+        root.push_named_with("server", |server| {
+            server.push_named_arg("host", "a");
+        });
+        root.push_named_with("server", |server| {
+            server.push_named_arg("host", "b");
+        });
+    });
+
+    // Paths select the last entry with a name, which is the one readers see.
+    builder.set_path("port", 3).unwrap();
+    builder.set_path("server.host", "c").unwrap();
+    let doc = builder.build();
+    assert_eq!(doc.root().get_i64("port"), Some(3));
+    assert!(matches!(
+        doc.root().children().get("server").unwrap().get_str("host"),
+        Some("c")
+    ));
+    assert_eq!(
+        format!("{:?}", builder.root()),
+        r#"{"port": 1, "port": 3, "server": {"host": "a"}, "server": {"host": "c"}}"#
+    );
+
+    builder.remove_path("@port").unwrap();
+    builder.remove_path("server").unwrap();
+    assert_eq!(
+        format!("{:?}", builder.root()),
+        r#"{"port": 1, "server": {"host": "a"}}"#
+    );
+
+    // Replacing duplicate arguments with a node removes all of them.
+    builder.root_mut().push_named_arg("port", 2);
+    builder.set_path("port", Node::from_values([4, 5])).unwrap();
+    assert!(matches!(
+        builder.get_path("port"),
+        Ok(Some(PathRef::Node(node))) if node.args().len() == 2
+    ));
+    assert_eq!(
+        format!("{:?}", builder.root()),
+        r#"{"port": [4, 5], "server": {"host": "a"}}"#
+    );
+}
+
+#[test]
+fn name_index() {
+    let mut builder = Builder::new();
+    builder.with_root(|root| {
+        for i in 0..1000 {
+            root.push_named_arg(format!("key{i}"), i);
+        }
+        root.push_named_arg("dup", 1);
+        root.push_named_arg("dup", 2);
+        root.push_named_with("dup", |child| {
+            child.push_arg(3);
+        });
+        root.push_named_with("child", |child| {
+            child.push_named_arg("dup", 4);
+        });
+        root.push_named_with("child", |child| {
+            child.push_named_arg("dup", 5);
+        });
+    });
+
+    let plain = builder.build();
+    assert!(!plain.has_name_index());
+    assert_eq!(plain.header().version, codec::VERSION);
+    let indexed = builder.set_build_index(true).build();
+    assert!(indexed.has_name_index());
+    assert_eq!(indexed.header().version, codec::INDEXED_VERSION);
+
+    for doc in [&plain, &indexed] {
+        let root = doc.root();
+        assert!(matches!(root.get_i64("key500"), Some(500)));
+        assert!(root.get("key1000").is_none());
+        assert_eq!(
+            root.args().get_by_name("dup").unwrap().value,
+            ValueRef::Int(2)
+        );
+        assert_eq!(
+            root.children().get("dup").unwrap().value(),
+            Some(ValueRef::Int(3))
+        );
+        assert_eq!(
+            root.get("child").unwrap().as_node().unwrap().get_i64("dup"),
+            Some(5)
+        );
+        assert!(root.get("missing").is_none());
+    }
+}
+
+#[test]
+fn zdoc_macro() {
+    let port = 8080u32;
+    let extra = zdoc::builder::Node::from_values([1, 2]);
+    let root = zdoc::zdoc! {
+        name "app";
+        (Http)server host="a" port=(port) "max-conns"=-1 {
+            tls #true;
+            allow "10.0.0.1" (String::from("10.0.0.2"));
+        }
+        ("quoted type")"quoted name" #null #false 1.5 true;
+        min-length 1;
+        - 1 -2 x=#inf;
+        (extra)
+    };
+
+    let doc = zdoc::text::document_from_text(
+        r#"{
+            name = { "app" }
+            server = (Http) {
+                host = "a", port = 8080u, max-conns = -1
+                tls = { true }
+                allow = { "10.0.0.1", "10.0.0.2" }
+            }
+            "quoted name" = ("quoted type") { null, false, 1.5, true }
+            min-length = { 1 }
+            { 1, -2, x = inf }
+            { 1, 2 }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(root, doc.root());
+    assert_eq!(zdoc::zdoc! {}, zdoc::builder::Node::empty());
+
+    // Types may contain dashes and Rust keywords.
+    let root = zdoc::zdoc! {
+        (type)x;
+        (for)y 1;
+        (my-type)server { (zdoc::builder::Node::from_values([1, 2])) }
+        (match)z
+    };
+    let doc = zdoc::text::document_from_text(
+        "{
+            x = (type) {}
+            y = (for) { 1 }
+            server = (my-type) { { 1, 2 } }
+            z = (match) {}
+        }",
+    )
+    .unwrap();
+    assert_eq!(root, doc.root());
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{
+    Cursor, Document, Node, NodeId,
+    path::{Path, Segment},
+    text::document_from_text,
+};
+
+#[test]
+fn navigate() {
+    let doc = document_from_text(
+        r#"{
+            name = "root"
+            servers = {
+                { host = "a" }
+                { host = "b", ports = { 80, 443 } }
+            }
+            name = { "shadowed by the argument" }
+        }"#,
+    )
+    .unwrap();
+
+    let mut cursor = doc.cursor();
+    assert_eq!(cursor.depth(), 0);
+    assert!(cursor.parent().is_none());
+    assert!(cursor.next_sibling().is_none());
+    assert!(!cursor.goto_parent());
+    assert_eq!(cursor.path().to_string(), "");
+
+    assert!(cursor.goto_first_child());
+    assert_eq!(cursor.node().name(), Some("servers"));
+    assert_eq!(cursor.path().to_string(), "servers");
+    assert_eq!(cursor.next_sibling().unwrap().name(), Some("name"));
+
+    assert!(cursor.goto_child(1));
+    assert!(cursor.goto_first_child());
+    assert_eq!(cursor.node().name(), Some("ports"));
+    assert_eq!(cursor.depth(), 3);
+    assert_eq!(cursor.path().to_string(), "servers[1].ports");
+    assert_eq!(
+        cursor.ancestors().map(|node| node.id()).collect::<Vec<_>>(),
+        [
+            cursor.parent().unwrap().id(),
+            doc.root().children().get(0).unwrap().id(),
+            doc.root().id()
+        ]
+    );
+
+    assert!(cursor.goto_parent());
+    assert!(cursor.goto_prev_sibling());
+    assert_eq!(cursor.node().get_str("host"), Some("a"));
+    assert!(!cursor.goto_prev_sibling());
+    assert!(cursor.goto_next_sibling());
+    assert!(!cursor.goto_next_sibling());
+
+    cursor.reset();
+    assert!(cursor.goto_child(1));
+    assert_eq!(cursor.path().to_string(), "[2]");
+    assert_eq!(cursor.index(), Some(1));
+}
+
+#[test]
+fn node_ids() {
+    let doc = document_from_text("{ a = { b = { c = 1 } }, d = {} }").unwrap();
+    let c = doc
+        .root()
+        .get("a")
+        .unwrap()
+        .as_node()
+        .unwrap()
+        .get("b")
+        .unwrap();
+    let id = c.as_node().unwrap().id();
+    assert_eq!(doc.node(id).unwrap().name(), Some("b"));
+    assert!(doc.node(NodeId(u32::MAX)).is_none());
+
+    let cursor = doc.cursor_at(id).unwrap();
+    assert_eq!(cursor.depth(), 2);
+    assert_eq!(cursor.path().to_string(), "a.b");
+    assert_eq!(cursor.id(), id);
+
+    let root = doc.cursor_at(doc.root().id()).unwrap();
+    assert_eq!(root.depth(), 0);
+
+    let empty = Document::empty();
+    assert_eq!(empty.root().id(), NodeId(0));
+    assert!(empty.node(NodeId(0)).is_none());
+    assert!(empty.cursor_at(NodeId(0)).is_none());
+    assert_eq!(empty.cursor().depth(), 0);
+}
+
+/// Resolve `path` against `node` like readers of documents do.
+fn get_path<'a>(node: Node<'a>, path: &Path) -> Option<Node<'a>> {
+    path.segments()
+        .iter()
+        .try_fold(node, |node, segment| match segment {
+            Segment::Name(name) => node.get(name)?.as_node(),
+            Segment::Index(index) => node.entries().into_iter().nth(*index)?.as_node(),
+            _ => None,
+        })
+}
+
+/// Visit the node of `cursor` and its descendants, and check that their
+/// paths select them.
+fn visit(doc: &Document, cursor: &mut Cursor, paths: &mut Vec<String>) {
+    let path = cursor.path();
+    let node = get_path(doc.root(), &path).map(|node| node.id());
+    assert_eq!(node, Some(cursor.id()), "{path}");
+    paths.push(path.to_string());
+    if cursor.goto_first_child() {
+        visit(doc, cursor, paths);
+        while cursor.goto_next_sibling() {
+            visit(doc, cursor, paths);
+        }
+        cursor.goto_parent();
+    }
+}
+
+#[test]
+fn duplicate_names() {
+    let doc = document_from_text(
+        r"{
+            a = { c = {} }
+            b = 2
+            a = { c = {}, c = {} }
+            b = {}
+        }",
+    )
+    .unwrap();
+
+    let mut paths = Vec::new();
+    visit(&doc, &mut doc.cursor(), &mut paths);
+    assert_eq!(paths, ["", "[1]", "[1].c", "a", "a[0]", "a.c", "[3]"]);
+}
@@ -59,9 +59,9 @@ fn de_no_alloc() {
             strings_len: 43,
             binary_offset: offset_of!(Bin, binary) as u32,
             binary_len: 0,
-            reserved1: 0,
-            reserved2: 0,
-            reserved3: 0,
+            index_offset: 0,
+            index_len: 0,
+            reserved: 0,
         },
         nodes: [
             codec::Node {
@@ -0,0 +1,134 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{
+    Builder,
+    diff::{Op, Patch, PatchError, diff, diff_builder},
+    text::{builder_from_text, document_from_text, document_to_text},
+};
+
+#[track_caller]
+fn roundtrip(old: &str, new: &str) -> Patch<'static> {
+    let old = document_from_text(old).unwrap();
+    let new = document_from_text(new).unwrap();
+    let patch = diff(&old.root(), &new.root());
+
+    let mut builder = Builder::from_document(&old);
+    patch.apply(&mut builder).unwrap();
+    assert_eq!(
+        document_to_text(&builder.build()),
+        document_to_text(&new),
+        "patch:\n{patch}"
+    );
+
+    // The patch also applies after storing it as a document.
+    let stored = patch.to_document();
+    let restored = Patch::from_node(stored.root()).unwrap();
+    let mut builder = Builder::from_document(&old);
+    restored.apply(&mut builder).unwrap();
+    assert_eq!(document_to_text(&builder.build()), document_to_text(&new));
+
+    patch.into_static()
+}
+
+#[test]
+fn diff_and_apply() {
+    let old = r#"(Config) {
+        name = "server"
+        port = 80u
+        ratio = nan
+        "positional"
+        listen = { host = "a", port = 1 }
+        listen = { host = "b", port = 2 }
+        log = (Log) { level = "info" }
+        { 1, 2, 3 }
+    }"#;
+    let new = r#"(Config) {
+        name = "server"
+        port = 8080u
+        ratio = nan
+        "positional"
+        debug = true
+        listen = { host = "a", port = 1 }
+        listen = { host = "c", port = 2 }
+        { 1, 3, 4 }
+    }"#;
+    let patch = roundtrip(old, new);
+    assert_eq!(
+        patch.to_string(),
+        r#"- log: (Log) {level = "info"}
+~ port: 80u -> 8080u
++ debug: true
+~ listen.host: "b" -> "c"
+~ [7][1]: 2 -> 3
+~ [7][2]: 3 -> 4
+"#
+    );
+
+    let same = document_from_text(old).unwrap();
+    assert!(diff(&same.root(), &same.root()).is_empty());
+}
+
+#[test]
+fn reorder_and_duplicates() {
+    roundtrip("{ a = 1, b = 2, c = 3 }", "{ c = 3, a = 1, b = 2 }");
+    roundtrip("{ a = 1, a = 2 }", "{ a = 2 }");
+    roundtrip("{ a = 1, a = 2 }", "{ a = 1, a = 2, a = 3 }");
+    roundtrip(
+        "{ a = {}, b = 1, a = { x = 1 } }",
+        "{ a = { x = 2 }, a = {} }",
+    );
+    roundtrip("{ 1, 2, 3 }", "{ 3, 2, 1 }");
+    roundtrip("{ x = 1, x = { 1 } }", "{ x = { 1 }, x = 2 }");
+    roundtrip("{}", "(T) { a = 1, b = { c = 2 } }");
+    roundtrip("(T) { a = 1, b = { c = 2 } }", "{}");
+    roundtrip(
+        "{ a = (A) { a = (B) {} } }",
+        "{ a = (C) { a = (D) { 1 } } }",
+    );
+
+    let patch = roundtrip("{ a = 1, b = 2, c = 3 }", "{ c = 3, a = 1, b = 2 }");
+    let removed = patch
+        .changes()
+        .iter()
+        .filter(|change| matches!(change.op, Op::Remove { .. }))
+        .count();
+    assert_eq!(removed, 1);
+}
+
+#[test]
+fn apply_conflicts() {
+    let old = builder_from_text("{ a = 1, b = { c = 2 } }").unwrap();
+    let new = builder_from_text("{ a = 2, b = { c = 3 }, d = 4 }").unwrap();
+    let patch = diff_builder(old.root(), new.root());
+    assert_eq!(patch.changes().len(), 3);
+
+    let mut other = builder_from_text("{ a = 1, b = { c = 5 } }").unwrap();
+    assert_eq!(patch.apply(&mut other), Err(PatchError::Conflict(2)));
+    // The builder is not modified when the patch fails.
+    assert_eq!(
+        other.root(),
+        builder_from_text("{ a = 1, b = { c = 5 } }")
+            .unwrap()
+            .root()
+    );
+
+    let mut other = builder_from_text("{ a = 1 }").unwrap();
+    assert_eq!(patch.apply(&mut other), Err(PatchError::NodeNotFound(2)));
+
+    let mut other = builder_from_text("{}").unwrap();
+    assert_eq!(
+        patch.apply(&mut other),
+        Err(PatchError::IndexOutOfBounds(0))
+    );
+
+    let invalid = document_from_text(r#"(Patch) { (set) { path = "a[" } }"#).unwrap();
+    assert!(matches!(
+        Patch::from_node(invalid.root()),
+        Err(PatchError::Path { change: 0, .. })
+    ));
+    let invalid = document_from_text(r#"(Patch) { (move) { path = "" } }"#).unwrap();
+    assert_eq!(
+        Patch::from_node(invalid.root()).unwrap_err(),
+        PatchError::InvalidChange(0)
+    );
+}
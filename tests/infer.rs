@@ -0,0 +1,124 @@
+#![cfg(all(feature = "json", feature = "kdl"))]
+
+use serde_json::json;
+use zdoc::{
+    DocumentBuffer, ValueKind,
+    json::{JsonSettings, document_from_json},
+    kdl::document_to_kdl,
+    path::Path,
+    schema::{InferredSchema, SchemaError, SchemaErrorKind, infer},
+};
+
+fn docs() -> Vec<DocumentBuffer> {
+    [
+        r#"{"name": "a", "port": 80, "tags": ["x", "y"], "server": {"host": "h", "tls": true}}"#,
+        r#"{"name": "b", "port": -5, "tags": ["z"], "extra": null, "ratio": 1}"#,
+        r#"{"name": "c", "port": 1, "tags": ["z"], "ratio": 0.5}"#,
+    ]
+    .into_iter()
+    .map(|json| document_from_json(json).unwrap())
+    .collect()
+}
+
+fn inferred() -> InferredSchema {
+    let docs = docs();
+    infer(docs.iter().map(|doc| doc.root()))
+}
+
+#[test]
+fn schema_document() {
+    let inferred = inferred();
+    assert_eq!(inferred.len(), 3);
+    assert_eq!(
+        document_to_kdl(&inferred.to_builder().build()).unwrap(),
+        r#"document {
+    prop name {
+        required #true
+        type string
+    }
+    prop port {
+        required #true
+        type i64
+    }
+    prop extra {
+        type "null"
+    }
+    prop ratio {
+        type number
+    }
+    node server {
+        max 1
+        prop host {
+            required #true
+            type string
+        }
+        prop tls {
+            required #true
+            type boolean
+        }
+    }
+    node tags {
+        min 1
+        max 1
+        value {
+            min 1
+            type string
+        }
+    }
+}
+"#
+    );
+}
+
+#[test]
+fn validates_examples() {
+    let schema = inferred().to_schema();
+    for doc in docs() {
+        schema.validate(doc.root()).unwrap();
+    }
+
+    let doc = document_from_json(r#"{"name": 1, "port": 2, "tags": []}"#).unwrap();
+    assert_eq!(
+        schema.validate(doc.root()).unwrap_err(),
+        [
+            SchemaError {
+                path: Path::parse("@name").unwrap().into_static(),
+                error: SchemaErrorKind::InvalidType {
+                    expected: "string".into(),
+                    found: ValueKind::Uint,
+                },
+            },
+            SchemaError {
+                path: Path::parse("tags").unwrap().into_static(),
+                error: SchemaErrorKind::TooFewValues { min: 1, found: 0 },
+            },
+        ]
+    );
+}
+
+#[test]
+fn json_schema() {
+    let schema = inferred().to_json_schema(&JsonSettings::default());
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["required"], json!(["name", "port", "tags"]));
+    assert_eq!(schema["additionalProperties"], false);
+    assert_eq!(schema["properties"]["ratio"], json!({ "type": "number" }));
+    assert_eq!(
+        schema["properties"]["server"]["required"],
+        json!(["host", "tls"])
+    );
+    // A single tag is the value itself, not an array.
+    assert_eq!(
+        schema["properties"]["tags"],
+        json!({
+            "anyOf": [
+                { "type": "string" },
+                { "type": "array", "items": { "type": "string" } },
+            ]
+        })
+    );
+}
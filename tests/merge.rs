@@ -0,0 +1,166 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{
+    builder::{
+        MergeChildren, MergeDuplicates, MergeError, MergeErrorKind, MergePolicy, MergeSequences,
+        MergeTypes,
+    },
+    path::Path,
+    text::{builder_from_text, document_from_text},
+};
+
+#[track_caller]
+fn merge(base: &str, overlay: &str, policy: MergePolicy) -> Result<String, MergeError> {
+    let mut builder = builder_from_text(base).unwrap();
+    let overlay_builder = builder_from_text(overlay).unwrap();
+    let mut merged = builder.clone();
+    merged.merge(overlay_builder.root(), policy)?;
+
+    // Merging a document gives the same result.
+    let doc = document_from_text(overlay).unwrap();
+    builder.merge_document(doc.root(), policy).unwrap();
+    assert_eq!(builder.root(), merged.root());
+
+    let mut text = String::new();
+    zdoc::text::write_builder(
+        &mut text,
+        &merged,
+        &zdoc::text::TextSettings {
+            indent: 0,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    Ok(text)
+}
+
+#[test]
+fn layered_config() {
+    let base = r#"(Config) {
+        name = "app"
+        server = { host = "localhost", port = 80u, tls = { enabled = false } }
+        features = { "a", "b" }
+        log = (File) { path = "app.log" }
+    }"#;
+    let env = r#"{
+        server = { port = 8080u, tls = { enabled = true, cert = "x.pem" } }
+        features = { "c" }
+        log = (Stdout) { color = true }
+        debug = true
+    }"#;
+
+    let merged = merge(base, env, MergePolicy::default()).unwrap();
+    assert_eq!(
+        merged,
+        r#"(Config) {name = "app", debug = true, server = {host = "localhost", port = 8080u, tls = {enabled = true, cert = "x.pem"}}, features = {"c"}, log = (Stdout) {color = true}}"#
+    );
+
+    let policy = MergePolicy {
+        children: MergeChildren::Replace,
+        sequences: MergeSequences::Append,
+        types: MergeTypes::Overlay,
+        ..Default::default()
+    };
+    let merged = merge(base, env, policy).unwrap();
+    assert_eq!(
+        merged,
+        r#"(Config) {name = "app", debug = true, server = {port = 8080u, tls = {enabled = true, cert = "x.pem"}}, features = {"c"}, log = (Stdout) {color = true}}"#
+    );
+
+    let policy = MergePolicy {
+        sequences: MergeSequences::Append,
+        types: MergeTypes::Keep,
+        ..Default::default()
+    };
+    let merged = merge(base, env, policy).unwrap();
+    assert!(merged.contains(r#"features = {"a", "b", "c"}"#));
+    assert!(merged.contains(r#"log = (File) {path = "app.log", color = true}"#));
+
+    let policy = MergePolicy {
+        types: MergeTypes::Error,
+        ..Default::default()
+    };
+    assert_eq!(
+        merge(base, env, policy),
+        Err(MergeError {
+            path: Path::parse("log").unwrap().into_static(),
+            error: MergeErrorKind::TypeConflict,
+        })
+    );
+    // An empty type never conflicts.
+    assert!(merge("{ a = (A) {} }", "{ a = { 1 } }", policy).is_ok());
+}
+
+#[test]
+fn entries() {
+    let policy = MergePolicy::default();
+    assert_eq!(
+        merge("{ a = 1 }", "{ a = { b = 2 } }", policy).unwrap(),
+        "{a = {b = 2}}"
+    );
+    assert_eq!(
+        merge("{ a = { b = 2 } }", "{ a = 1 }", policy).unwrap(),
+        "{a = 1}"
+    );
+    assert_eq!(
+        merge("{ 1, 2 }", "{ a = 3 }", policy).unwrap(),
+        "{1, 2, a = 3}"
+    );
+    assert_eq!(merge("{ 1, {} }", "{ 3 }", policy).unwrap(), "{3}");
+    assert_eq!(
+        merge("{}", "name = (T) { 1 }", policy).unwrap(),
+        "name = (T) {1}"
+    );
+    assert_eq!(merge("(A) { 1 }", "(B) { 2 }", policy).unwrap(), "(B) {2}");
+}
+
+#[test]
+fn duplicates() {
+    let last_wins = MergePolicy::default();
+    assert_eq!(
+        merge("{ a = 1, a = 2 }", "{ a = 3 }", last_wins).unwrap(),
+        "{a = 1, a = 3}"
+    );
+    assert_eq!(
+        merge(
+            "{ a = {}, a = { x = 1 } }",
+            "{ a = { y = 2 }, a = { x = 3 } }",
+            last_wins
+        )
+        .unwrap(),
+        "{a = {}, a = {x = 3, y = 2}}"
+    );
+
+    // Like `Node::get()`, arguments are preferred over children.
+    assert_eq!(
+        merge("{ a = 1, a = { x = 1 } }", "{ a = 2 }", last_wins).unwrap(),
+        "{a = 2, a = {x = 1}}"
+    );
+    assert_eq!(
+        merge("{ a = { x = 1 }, a = 1 }", "{ a = { y = 2 } }", last_wins).unwrap(),
+        "{a = {x = 1}, a = {y = 2}}"
+    );
+
+    let error = MergePolicy {
+        duplicates: MergeDuplicates::Error,
+        ..Default::default()
+    };
+    let duplicate = |path: &str| {
+        Err(MergeError {
+            path: Path::parse(path).unwrap().into_static(),
+            error: MergeErrorKind::DuplicateName,
+        })
+    };
+    assert_eq!(
+        merge("{ a = 1, a = 2 }", "{ a = 3 }", error),
+        duplicate("a")
+    );
+    assert_eq!(
+        merge("{}", "{ b = { c = 1, c = {} } }", error),
+        duplicate("b.c")
+    );
+    assert_eq!(
+        merge("{ a = 1, a = 2 }", "{ b = 3 }", error).unwrap(),
+        "{a = 1, a = 2, b = 3}"
+    );
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{
+    Entry, ValueRef,
+    builder::PathRef,
+    query::{Query, QueryError, QueryErrorKind, select},
+    text::{builder_from_text, document_from_text},
+};
+
+const DOC: &str = r#"(Config) {
+    name = "app"
+    server = (Http) { host = "a", port = 80u, tls = { enabled = true } }
+    server = (Http) { host = "b", port = 8080u }
+    server = (Grpc) { host = "c", port = 9000.5 }
+    route = { "/api/users", methods = { "GET", "POST" } }
+    route = { "/static" }
+}"#;
+
+/// Names of the entries matching `query`, followed by their first unnamed
+/// argument, if it is a string.
+#[track_caller]
+fn query(query: &str) -> Vec<String> {
+    let doc = document_from_text(DOC).unwrap();
+    let matches = select(doc.root(), query)
+        .unwrap()
+        .map(|entry| match entry {
+            Entry::Arg(arg) => format!("@{}", arg.name.unwrap_or_default()),
+            Entry::Child(node) => {
+                let name = node.name().unwrap_or_default();
+                let value = node.args().into_iter().find(|arg| arg.name.is_none());
+                match value.and_then(|arg| arg.value.as_str()) {
+                    Some(value) => format!("{name}:{value}"),
+                    None => name.to_owned(),
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Querying a builder gives the same result.
+    let builder = builder_from_text(DOC).unwrap();
+    let parsed = Query::parse(query).unwrap();
+    let builder_matches = parsed
+        .select_builder(builder.root())
+        .map(|entry| match entry {
+            PathRef::Arg(arg) => format!("@{}", arg.name.as_deref().unwrap_or_default()),
+            PathRef::Node(node) => {
+                let value = node
+                    .args
+                    .iter()
+                    .find(|arg| arg.name.is_none())
+                    .and_then(|arg| ValueRef::from(&arg.value).as_str());
+                match value {
+                    Some(value) => format!("{}:{value}", node.name()),
+                    None => node.name().to_owned(),
+                }
+            }
+            PathRef::Type(_) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(matches, builder_matches);
+
+    matches
+}
+
+#[test]
+fn filters() {
+    assert_eq!(query("top()"), [""]);
+    assert_eq!(query("name"), ["@name"]);
+    assert_eq!(query("server[host = b]"), ["server"]);
+    assert_eq!(query("(Grpc)"), ["server"]);
+    assert_eq!(query("()server[port]").len(), 3);
+    assert_eq!(query("top() > []").len(), 6);
+    assert_eq!(query("[tag() = Http][port < 100]"), ["server"]);
+    assert_eq!(query("[name() = host]"), ["@host", "@host", "@host"]);
+    assert_eq!(query("[val() ^= \"/api\"]"), ["route:/api/users"]);
+    assert_eq!(query("route[val(0) $= static]"), ["route:/static"]);
+    assert_eq!(query("[val(1)]"), ["methods:GET"]);
+    assert_eq!(query("[prop(enabled) = #true]"), ["tls"]);
+    assert_eq!(query("server[host *= x]"), Vec::<String>::new());
+    assert_eq!(query("server[host != a]").len(), 2);
+}
+
+#[test]
+fn numbers() {
+    assert_eq!(query("server[port > 8000]").len(), 2);
+    assert_eq!(query("server[port >= 9000.5]").len(), 1);
+    assert_eq!(query("server[port = 80]").len(), 1);
+    assert_eq!(query("server[port = 80.0]").len(), 1);
+    assert_eq!(query("server[port <= -1]").len(), 0);
+    // Strings are not numbers.
+    assert_eq!(query("server[host > 0]").len(), 0);
+}
+
+#[test]
+fn combinators() {
+    assert_eq!(query("top() > server > tls"), ["tls"]);
+    assert_eq!(query("top() > tls"), Vec::<String>::new());
+    assert_eq!(query("top() tls"), ["tls"]);
+    assert_eq!(query("top() >> enabled"), ["@enabled"]);
+    assert_eq!(query("server > port"), ["@port", "@port", "@port"]);
+    assert_eq!(query("(Http) + server"), ["server", "server"]);
+    assert_eq!(query("name ~ route"), ["route:/api/users", "route:/static"]);
+    assert_eq!(query("name + route"), Vec::<String>::new());
+    assert_eq!(query("route + route"), ["route:/static"]);
+    assert_eq!(
+        query("tls || route > methods || top()"),
+        ["", "tls", "methods:GET"]
+    );
+}
+
+#[test]
+fn errors() {
+    let error = |query: &str| Query::parse(query).unwrap_err();
+    assert_eq!(
+        error("server["),
+        QueryError {
+            offset: 7,
+            error: QueryErrorKind::UnexpectedEof
+        }
+    );
+    assert_eq!(error("a > top()").error, QueryErrorKind::MisplacedTop);
+    assert_eq!(error("a > top()").offset, 4);
+    assert_eq!(error("[foo()]").error, QueryErrorKind::UnknownFunction);
+    assert_eq!(error("[a = 1x]").error, QueryErrorKind::InvalidNumber);
+    assert_eq!(error("\"a\\q\"").error, QueryErrorKind::InvalidEscape);
+    assert_eq!(error("a >").error, QueryErrorKind::UnexpectedEof);
+    assert_eq!(error("a > > b").error, QueryErrorKind::ExpectedFilter);
+    assert_eq!(error("a ||").error, QueryErrorKind::UnexpectedEof);
+    assert_eq!(error("a]").error, QueryErrorKind::UnexpectedChar(']'));
+    assert!("top() > a[b = \"c d\"]".parse::<Query>().is_ok());
+}
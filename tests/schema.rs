@@ -0,0 +1,279 @@
+#![cfg(feature = "kdl")]
+
+use zdoc::{
+    ValueKind,
+    builder::{PathRef, Value},
+    kdl::{builder_from_kdl, document_from_kdl},
+    path::Path,
+    schema::{LoadError, LoadErrorKind, Schema, SchemaError, SchemaErrorKind},
+};
+
+const SCHEMA: &str = r##"
+document {
+    info { title "Servers" }
+    node "name" {
+        min 1
+        max 1
+        value { min 1; max 1; type "string"; min-length 1 }
+    }
+    node "server" {
+        min 1
+        tag { enum "Http" "Grpc" #null }
+        prop "port" { required #true; type "u16"; ">=" 1024 }
+        prop "host" { type "string" }
+        children {
+            node "tls" { max 1; value { type "boolean" } }
+            node "mode" { value { enum "fast" "safe" } }
+            node "weight" { value { type "number"; ">" 0; "<=" 1; "%" 0.25 } }
+        }
+    }
+    node "dir" ref=#"[id="dir"]"#
+}
+
+definitions {
+    node "dir" id="dir" {
+        prop "name" { required #true }
+        children {
+            node "dir" ref=#"[id="dir"]"#
+            node "file" { value { max 1 } }
+        }
+    }
+}
+"##;
+
+#[track_caller]
+fn validate(kdl: &str) -> Result<(), Vec<SchemaError>> {
+    let schema = Schema::from_kdl(SCHEMA).unwrap();
+    let result = schema.validate(document_from_kdl(kdl).unwrap().root());
+
+    // Validating a builder gives the same result.
+    let builder = builder_from_kdl(kdl).unwrap();
+    assert_eq!(schema.validate_builder(builder.root()), result);
+
+    result
+}
+
+fn error(path: &str, error: SchemaErrorKind) -> SchemaError {
+    SchemaError {
+        path: Path::parse(path).unwrap().into_static(),
+        error,
+    }
+}
+
+#[test]
+fn valid() {
+    validate(
+        r#"
+        name "app"
+        (Http)server port=8080 host="a" {
+            tls #true
+            mode "safe"
+            weight 0.75
+        }
+        server port=9000
+        dir name="root" {
+            dir name="sub" { file "a"; file "b" }
+        }
+        "#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn errors() {
+    let errors = validate(
+        r#"
+        name ""
+        name "again" 1
+        (Tcp)server port=80 host=1 extra=#true {
+            tls "yes"
+            tls #false
+            mode "slow"
+            weight 0.3
+            weight 2
+            other
+        }
+        server host="b" port=65536
+        dir {
+            dir name="x" { file "a" "b" }
+        }
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        errors,
+        [
+            error("[0][0]", SchemaErrorKind::TooShort { min: 1, len: 0 }),
+            error(
+                "name[1]",
+                SchemaErrorKind::InvalidType {
+                    expected: "string".into(),
+                    found: ValueKind::Int,
+                }
+            ),
+            error("name", SchemaErrorKind::TooManyValues { max: 1, found: 2 }),
+            error("[2].#type", SchemaErrorKind::NotInEnum),
+            error("[2].@port", SchemaErrorKind::OutOfRange),
+            error(
+                "[2].@host",
+                SchemaErrorKind::InvalidType {
+                    expected: "string".into(),
+                    found: ValueKind::Int,
+                }
+            ),
+            error("[2].@extra", SchemaErrorKind::UnexpectedProp),
+            error(
+                "[2][3][0]",
+                SchemaErrorKind::InvalidType {
+                    expected: "boolean".into(),
+                    found: ValueKind::String,
+                }
+            ),
+            error("[2].mode[0]", SchemaErrorKind::NotInEnum),
+            error("[2][6][0]", SchemaErrorKind::NotMultiple),
+            error("[2].weight[0]", SchemaErrorKind::OutOfRange),
+            error("[2].other", SchemaErrorKind::UnexpectedNode),
+            error(
+                "[2]",
+                SchemaErrorKind::TooManyNodes {
+                    name: "tls".into(),
+                    max: 1,
+                    found: 2,
+                }
+            ),
+            error(
+                "server.@port",
+                SchemaErrorKind::InvalidType {
+                    expected: "u16".into(),
+                    found: ValueKind::Int,
+                }
+            ),
+            error("dir", SchemaErrorKind::MissingProp("name".into())),
+            error(
+                "dir.dir.file",
+                SchemaErrorKind::TooManyValues { max: 1, found: 2 }
+            ),
+            error(
+                "",
+                SchemaErrorKind::TooManyNodes {
+                    name: "name".into(),
+                    max: 1,
+                    found: 2,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn duplicate_names() {
+    let kdl = r#"
+        name "app"
+        server port=80 port=8080 host=1 host="a" {
+            mode "slow"
+            mode "fast"
+        }
+        "#;
+    let errors = validate(kdl).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            error("server[0]", SchemaErrorKind::OutOfRange),
+            error(
+                "server[2]",
+                SchemaErrorKind::InvalidType {
+                    expected: "string".into(),
+                    found: ValueKind::Int,
+                }
+            ),
+            error("server[4][0]", SchemaErrorKind::NotInEnum),
+        ]
+    );
+
+    // The paths select the entries that failed, not the last ones with the
+    // same name.
+    let builder = builder_from_kdl(kdl).unwrap();
+    let root = builder.root();
+    assert!(matches!(
+        root.get_path("server[0]"),
+        Ok(Some(PathRef::Arg(arg))) if matches!(arg.value, Value::Int(80))
+    ));
+    assert!(matches!(
+        root.get_path("server[2]"),
+        Ok(Some(PathRef::Arg(arg))) if matches!(arg.value, Value::Int(1))
+    ));
+    assert!(matches!(
+        root.get_path("server[4][0]"),
+        Ok(Some(PathRef::Arg(arg))) if matches!(&arg.value, Value::String(s) if s == "slow")
+    ));
+}
+
+#[test]
+fn missing_nodes() {
+    assert_eq!(
+        validate("").unwrap_err(),
+        [
+            error(
+                "",
+                SchemaErrorKind::TooFewNodes {
+                    name: "name".into(),
+                    min: 1,
+                    found: 0,
+                }
+            ),
+            error(
+                "",
+                SchemaErrorKind::TooFewNodes {
+                    name: "server".into(),
+                    min: 1,
+                    found: 0,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn load_errors() {
+    let load = |kdl: &str| {
+        Schema::from_node(builder_from_kdl(kdl).unwrap().root())
+            .map(|_| ())
+            .map_err(|LoadError { path, error }| (path.to_string(), error))
+    };
+    assert_eq!(
+        load("node"),
+        Err((String::new(), LoadErrorKind::MissingDocument))
+    );
+    assert_eq!(
+        load("document { foo }"),
+        Err(("document.foo".into(), LoadErrorKind::UnknownNode))
+    );
+    assert_eq!(
+        load(r#"document { node { value { pattern "a+" } } }"#),
+        Err((
+            "document.node.value.pattern".into(),
+            LoadErrorKind::Unsupported
+        ))
+    );
+    assert_eq!(
+        load(r#"document { node { prop { type "str" } } }"#),
+        Err(("document.node.prop.type".into(), LoadErrorKind::UnknownType))
+    );
+    assert_eq!(
+        load(r#"document { node { min "1" } }"#),
+        Err(("document.node.min".into(), LoadErrorKind::InvalidValue))
+    );
+    assert_eq!(
+        load(r#"document { node { min "1" }; node }"#),
+        Err(("document[0].min".into(), LoadErrorKind::InvalidValue))
+    );
+    assert_eq!(
+        load(r##"document { node ref=#"[id="x"]"# }"##),
+        Err(("document.node".into(), LoadErrorKind::InvalidRef))
+    );
+    assert_eq!(
+        load(r##"document { node id="x" ref=#"[id="x"]"# }"##),
+        Err(("document.node".into(), LoadErrorKind::InvalidRef))
+    );
+}
@@ -0,0 +1,110 @@
+#![cfg(all(feature = "serde", feature = "json"))]
+
+use serde_json::json;
+use zdoc::{DocumentBuffer, builder, zdoc};
+
+fn example() -> builder::Node<'static> {
+    zdoc! {
+        name "app";
+        ports 1 2 3;
+        (Http)server host="a" port=80;
+        empty;
+        mixed 1 key=#true;
+    }
+}
+
+#[test]
+fn human_readable() {
+    let root = example();
+    let expected = json!({
+        "name": "app",
+        "ports": [1, 2, 3],
+        "server": { "Http": { "host": "a", "port": 80 } },
+        "empty": null,
+        "mixed": { "": [1], "key": true },
+    });
+    assert_eq!(serde_json::to_value(&root).unwrap(), expected);
+
+    let mut builder = zdoc::Builder::new();
+    builder.set_root(root);
+    let doc = builder.build();
+    let json = serde_json::to_string(&*doc).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        expected
+    );
+    assert_eq!(serde_json::to_value(doc.root()).unwrap(), expected);
+
+    // Types are lost, but the structure survives the round trip.
+    let parsed: DocumentBuffer = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+    let node: builder::Node = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_value(&node).unwrap(), expected);
+}
+
+#[test]
+fn embedded() {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Config {
+        name: String,
+        extra: DocumentBuffer,
+    }
+
+    let mut builder = zdoc::Builder::new();
+    builder.set_root(example());
+    let config = Config {
+        name: "config".into(),
+        extra: builder.build(),
+    };
+    let json = serde_json::to_string(&config).unwrap();
+    let parsed: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.name, "config");
+    assert_eq!(
+        serde_json::to_value(&parsed.extra).unwrap(),
+        serde_json::to_value(&config.extra).unwrap()
+    );
+
+    let bytes = bincode::serde::encode_to_vec(&config, bincode::config::standard()).unwrap();
+    let (parsed, _): (Config, _) =
+        bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+    assert_eq!(*parsed.extra, *config.extra);
+}
+
+#[test]
+fn binary() {
+    let config = bincode::config::standard();
+    let mut builder = zdoc::Builder::new();
+    builder.set_root(example());
+    let doc = builder.build();
+
+    // Documents are encoded as their bytes.
+    let bytes = bincode::serde::encode_to_vec(&doc, config).unwrap();
+    let expected = bincode::serde::encode_to_vec(doc.as_bytes(), config).unwrap();
+    assert_eq!(bytes, expected);
+    let (parsed, _): (DocumentBuffer, _) =
+        bincode::serde::decode_from_slice(&bytes, config).unwrap();
+    assert_eq!(*parsed, *doc);
+
+    // Nodes are encoded as documents with the node as the root.
+    let bytes = bincode::serde::encode_to_vec(doc.root(), config).unwrap();
+    let (parsed, _): (DocumentBuffer, _) =
+        bincode::serde::decode_from_slice(&bytes, config).unwrap();
+    assert_eq!(*parsed, *doc);
+    let bytes = bincode::serde::encode_to_vec(builder.root(), config).unwrap();
+    let (parsed, _): (builder::Node, _) =
+        bincode::serde::decode_from_slice(&bytes, config).unwrap();
+    assert_eq!(parsed, *builder.root());
+    assert_eq!(parsed.children()[2].ty(), "Http");
+
+    // The bytes are validated.
+    let bytes = bincode::serde::encode_to_vec(&[1u8, 2, 3][..], config).unwrap();
+    assert!(bincode::serde::decode_from_slice::<DocumentBuffer, _>(&bytes, config).is_err());
+}
+
+#[test]
+fn raw_node() {
+    let json = r#"{"name":"app","ports":[1,2,3]}"#;
+    let raw: zdoc::serde::RawNode = serde_json::from_str(json).unwrap();
+    assert!(matches!(raw, zdoc::serde::RawNode::Builder(_)));
+    assert_eq!(serde_json::to_string(&raw).unwrap(), json);
+}
@@ -1,7 +1,7 @@
 use bytemuck::{bytes_of, cast_slice, cast_slice_mut};
 use zdoc::{
     Document, ValidationError, ValidationErrorKind,
-    codec::{ArgRange, DEFAULT_HEADER, Header, Node, NodeRange, StringRange},
+    codec::{ArgRange, DEFAULT_HEADER, Header, INDEXED_VERSION, Node, NodeRange, StringRange},
 };
 
 #[test]
@@ -35,7 +35,7 @@ fn validate_header_magic() {
 fn validate_header_version() {
     assert_eq!(
         Document::from_slice(bytes_of(&Header {
-            // only version 1 is supported
+            // only versions 1 and 2 are supported
             version: 0,
             ..DEFAULT_HEADER
         })),
@@ -59,6 +59,15 @@ fn validate_header_size() {
             error: ValidationErrorKind::HeaderSize
         })
     );
+    // truncated header
+    let truncated = [0u32; 4];
+    assert_eq!(
+        Document::from_slice(cast_slice(&truncated)),
+        Err(ValidationError {
+            offset: 0,
+            error: ValidationErrorKind::HeaderSize
+        })
+    );
 }
 
 #[test]
@@ -328,29 +337,7 @@ fn validate_header_overlap() {
 fn validate_header_reserved() {
     assert_eq!(
         Document::from_slice(bytes_of(&Header {
-            reserved1: 1,
-            ..DEFAULT_HEADER
-        })),
-        Err(ValidationError {
-            offset: 52,
-            error: ValidationErrorKind::HeaderReservedFieldsMustBeZero,
-        })
-    );
-
-    assert_eq!(
-        Document::from_slice(bytes_of(&Header {
-            reserved2: 1,
-            ..DEFAULT_HEADER
-        })),
-        Err(ValidationError {
-            offset: 56,
-            error: ValidationErrorKind::HeaderReservedFieldsMustBeZero,
-        })
-    );
-
-    assert_eq!(
-        Document::from_slice(bytes_of(&Header {
-            reserved3: 1,
+            reserved: 1,
             ..DEFAULT_HEADER
         })),
         Err(ValidationError {
@@ -452,6 +439,241 @@ fn validate_header_binary() {
     );
 }
 
+#[test]
+fn validate_header_index() {
+    assert_eq!(
+        Document::from_slice(bytes_of(&Header {
+            // version 1 does not have a name index
+            index_offset: 64,
+            ..DEFAULT_HEADER
+        })),
+        Err(ValidationError {
+            offset: 52,
+            error: ValidationErrorKind::HeaderReservedFieldsMustBeZero,
+        })
+    );
+
+    assert_eq!(
+        Document::from_slice(bytes_of(&Header {
+            // within header
+            index_offset: 4,
+            version: INDEXED_VERSION,
+            ..DEFAULT_HEADER
+        })),
+        Err(ValidationError {
+            offset: 52,
+            error: ValidationErrorKind::HeaderIndexOffset,
+        })
+    );
+
+    assert_eq!(
+        Document::from_slice(bytes_of(&Header {
+            // unaligned
+            index_offset: 65,
+            version: INDEXED_VERSION,
+            ..DEFAULT_HEADER
+        })),
+        Err(ValidationError {
+            offset: 52,
+            error: ValidationErrorKind::HeaderIndexOffset,
+        })
+    );
+
+    assert_eq!(
+        Document::from_slice(bytes_of(&Header {
+            // buckets without an offset
+            index_len: 1,
+            version: INDEXED_VERSION,
+            ..DEFAULT_HEADER
+        })),
+        Err(ValidationError {
+            offset: 52,
+            error: ValidationErrorKind::HeaderIndexOffset,
+        })
+    );
+
+    let mut bytes = [0u32; 16 + 6];
+    cast_slice_mut::<_, u8>(&mut bytes)[..64].copy_from_slice(bytes_of(&Header {
+        size: 88,
+        // not a power of two
+        index_offset: 64,
+        index_len: 3,
+        version: INDEXED_VERSION,
+        ..DEFAULT_HEADER
+    }));
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: 56,
+            error: ValidationErrorKind::HeaderIndexLen,
+        })
+    );
+
+    cast_slice_mut::<_, u8>(&mut bytes)[..64].copy_from_slice(bytes_of(&Header {
+        size: 88,
+        // offset+len out of bounds
+        index_offset: 64,
+        index_len: 4,
+        version: INDEXED_VERSION,
+        ..DEFAULT_HEADER
+    }));
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: 56,
+            error: ValidationErrorKind::HeaderIndexLen,
+        })
+    );
+
+    cast_slice_mut::<_, u8>(&mut bytes)[..64].copy_from_slice(bytes_of(&Header {
+        size: 88,
+        index_offset: 64,
+        index_len: 2,
+        strings_offset: 64,
+        strings_len: 1,
+        version: INDEXED_VERSION,
+        ..DEFAULT_HEADER
+    }));
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: 36,
+            error: ValidationErrorKind::HeaderSectionsOverlap,
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "builder")]
+fn validate_index() {
+    use zdoc::{builder::Builder, codec::IndexEntry};
+
+    let mut builder = Builder::new();
+    // Note: Names are 4 bytes, so the document size is a multiple of 4.
+    builder.set_build_index(true).with_root(|root| {
+        root.push_named_arg("aaaa", 1);
+        root.push_named_arg("bbbb", 2);
+        root.push_named_with("aaaa", |_| {});
+    });
+    let doc = builder.build();
+    let header = *doc.header();
+    assert_eq!(header.index_len, 8);
+
+    let index_range = header.index_offset as usize
+        ..header.index_offset as usize + header.index_len as usize * size_of::<IndexEntry>();
+    let index: &[IndexEntry] = cast_slice(&doc.as_bytes()[index_range.clone()]);
+    assert_eq!(index.iter().filter(|bucket| !bucket.is_empty()).count(), 3);
+
+    // Point a bucket at an entry that does not exist.
+    let mut bytes: Vec<u32> = cast_slice(doc.as_bytes()).to_vec();
+    let buckets: &mut [IndexEntry] =
+        cast_slice_mut(&mut cast_slice_mut::<_, u8>(&mut bytes)[index_range.clone()]);
+    let bucket = buckets
+        .iter()
+        .position(|bucket| !bucket.is_empty())
+        .unwrap();
+    buckets[bucket].entry = 3;
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: index_range.start + bucket * size_of::<IndexEntry>(),
+            error: ValidationErrorKind::IndexEntryOutOfBounds,
+        })
+    );
+
+    // Remove a bucket, so a name is missing.
+    let mut bytes: Vec<u32> = cast_slice(doc.as_bytes()).to_vec();
+    let buckets: &mut [IndexEntry] =
+        cast_slice_mut(&mut cast_slice_mut::<_, u8>(&mut bytes)[index_range.clone()]);
+    buckets[bucket] = IndexEntry::EMPTY;
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: header.nodes_offset as usize,
+            error: ValidationErrorKind::IndexMismatch,
+        })
+    );
+
+    // Occupy more than half of the buckets.
+    let mut bytes: Vec<u32> = cast_slice(doc.as_bytes()).to_vec();
+    let buckets: &mut [IndexEntry] =
+        cast_slice_mut(&mut cast_slice_mut::<_, u8>(&mut bytes)[index_range.clone()]);
+    let occupied = buckets[bucket];
+    for bucket in buckets
+        .iter_mut()
+        .filter(|bucket| bucket.is_empty())
+        .take(2)
+    {
+        *bucket = occupied;
+    }
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: 56,
+            error: ValidationErrorKind::IndexFull,
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "builder")]
+fn validate_index_probe_length() {
+    use zdoc::{
+        builder::Builder,
+        codec::{IndexEntry, MAX_INDEX_PROBE, index_hash},
+    };
+
+    // Names that all start probing at the same bucket of a table with 128
+    // buckets.
+    let names = (0..10_000)
+        .map(|i| format!("{i:04}"))
+        .filter(|name| index_hash(0, name.as_bytes()).trailing_zeros() >= 7)
+        .take(MAX_INDEX_PROBE + 2)
+        .collect::<Vec<_>>();
+    assert_eq!(names.len(), MAX_INDEX_PROBE + 2);
+
+    let mut builder = Builder::new();
+    builder.set_build_index(true).with_root(|root| {
+        for name in &names {
+            root.push_named_arg(name.as_str(), 1);
+        }
+    });
+    let doc = builder.build();
+    let header = *doc.header();
+    assert_eq!(header.root_node_index, 0);
+    // The builder grows the table to keep the collisions in reach of lookups.
+    assert_eq!(header.index_len, 256);
+    for name in &names {
+        assert!(doc.root().get(name).is_some());
+    }
+
+    // Put all names in one probe sequence of a table with 128 buckets, which
+    // is longer than lookups search.
+    let mut bytes: Vec<u32> = cast_slice(doc.as_bytes()).to_vec();
+    let index_range =
+        header.index_offset as usize..header.index_offset as usize + 128 * size_of::<IndexEntry>();
+    let buckets: &mut [IndexEntry] =
+        cast_slice_mut(&mut cast_slice_mut::<_, u8>(&mut bytes)[index_range]);
+    buckets.fill(IndexEntry::EMPTY);
+    for (entry, bucket) in buckets.iter_mut().take(names.len()).enumerate() {
+        *bucket = IndexEntry {
+            node: 0,
+            entry: entry as u32,
+        };
+    }
+    cast_slice_mut::<_, u8>(&mut bytes)[..64].copy_from_slice(bytes_of(&Header {
+        index_len: 128,
+        ..header
+    }));
+    assert_eq!(
+        Document::from_slice(cast_slice(&bytes)),
+        Err(ValidationError {
+            offset: header.nodes_offset as usize,
+            error: ValidationErrorKind::IndexMismatch,
+        })
+    );
+}
+
 #[test]
 fn validate_strings() {
     let mut buf = [0u32; 128];
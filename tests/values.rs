@@ -0,0 +1,156 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{ValueError, ValueKind, ValueRef, text::document_from_text};
+
+#[test]
+fn exact_accessors() {
+    assert_eq!(ValueRef::Int(-1).as_i64(), Some(-1));
+    assert_eq!(ValueRef::Uint(1).as_i64(), None);
+    assert_eq!(ValueRef::Uint(1).as_u64(), Some(1));
+    assert_eq!(ValueRef::Int(1).as_f64(), None);
+    assert_eq!(ValueRef::Bool(true).as_bool(), Some(true));
+    assert_eq!(ValueRef::String("a").as_str(), Some("a"));
+    assert_eq!(ValueRef::String("a").as_bytes(), None);
+    assert_eq!(ValueRef::Binary(b"a").as_bytes(), Some(&b"a"[..]));
+    assert!(ValueRef::Null.is_null());
+    assert_eq!(ValueRef::Float(1.0).kind(), ValueKind::Float);
+}
+
+#[test]
+fn lossless_coercions() {
+    assert_eq!(ValueRef::Uint(1).to_i64(), Ok(1));
+    assert_eq!(ValueRef::Int(1).to_u64(), Ok(1));
+    assert_eq!(ValueRef::Float(-2.0).to_i64(), Ok(-2));
+    assert_eq!(ValueRef::Int(1 << 53).to_f64(), Ok(9_007_199_254_740_992.0));
+    assert_eq!(ValueRef::Binary(b"abc").to_str(), Ok("abc"));
+    assert_eq!(ValueRef::String("abc").to_bytes(), Ok(&b"abc"[..]));
+
+    let out_of_range = |expected, found| ValueError::OutOfRange { expected, found };
+    assert_eq!(
+        ValueRef::Int(-1).to_u64(),
+        Err(out_of_range("u64", ValueKind::Int))
+    );
+    assert_eq!(
+        ValueRef::Uint(u64::MAX).to_i64(),
+        Err(out_of_range("i64", ValueKind::Uint))
+    );
+    assert_eq!(
+        ValueRef::Float(0.5).to_i64(),
+        Err(out_of_range("i64", ValueKind::Float))
+    );
+    assert_eq!(
+        ValueRef::Float(9_223_372_036_854_775_808.0).to_i64(),
+        Err(out_of_range("i64", ValueKind::Float))
+    );
+    assert_eq!(
+        ValueRef::Int(i64::MAX).to_f64(),
+        Err(out_of_range("f64", ValueKind::Int))
+    );
+    assert_eq!(
+        ValueRef::Binary(&[0xff]).to_str(),
+        Err(out_of_range("str", ValueKind::Binary))
+    );
+    assert_eq!(
+        ValueRef::String("1").to_i64(),
+        Err(ValueError::TypeMismatch {
+            expected: "i64",
+            found: ValueKind::String
+        })
+    );
+    assert_eq!(
+        ValueRef::Int(1).to_bool().unwrap_err().to_string(),
+        "expected bool, found int"
+    );
+}
+
+#[test]
+fn try_from() {
+    assert_eq!(u8::try_from(ValueRef::Int(255)), Ok(255));
+    assert_eq!(
+        u8::try_from(ValueRef::Int(256)),
+        Err(ValueError::OutOfRange {
+            expected: "u8",
+            found: ValueKind::Int
+        })
+    );
+    assert_eq!(i8::try_from(ValueRef::Uint(127)), Ok(127));
+    assert_eq!(
+        i128::try_from(ValueRef::Uint(u64::MAX)),
+        Ok(u64::MAX.into())
+    );
+    assert_eq!(u128::try_from(ValueRef::Float(1.0)), Ok(1));
+    assert_eq!(f32::try_from(ValueRef::Float(0.5)), Ok(0.5));
+    assert!(f32::try_from(ValueRef::Float(0.1)).is_err());
+    assert_eq!(char::try_from(ValueRef::String("x")), Ok('x'));
+    assert!(char::try_from(ValueRef::String("xy")).is_err());
+    assert_eq!(<&str>::try_from(ValueRef::String("x")), Ok("x"));
+    assert_eq!(<&[u8]>::try_from(ValueRef::Binary(b"x")), Ok(&b"x"[..]));
+    assert_eq!(bool::try_from(ValueRef::Bool(false)), Ok(false));
+}
+
+#[test]
+fn node_shortcuts() {
+    let doc = document_from_text(
+        r#"{
+            port = 8080u
+            host = "localhost"
+            ratio = 0.5
+            enabled = true
+            data = x"00ff"
+            server = { name = "a" }
+            server = { name = "b" }
+            timeout = { 30 }
+            empty = {}
+            tag = "x"
+            tag = "y"
+        }"#,
+    )
+    .unwrap();
+    let root = doc.root();
+
+    assert_eq!(root.get_i64("port"), Some(8080));
+    assert_eq!(root.get_u64("port"), Some(8080));
+    assert_eq!(root.get_f64("port"), Some(8080.0));
+    assert_eq!(root.get_str("host"), Some("localhost"));
+    assert_eq!(root.get_f64("ratio"), Some(0.5));
+    assert_eq!(root.get_i64("ratio"), None);
+    assert_eq!(root.get_bool("enabled"), Some(true));
+    assert_eq!(root.get_bytes("data"), Some(&[0x00, 0xff][..]));
+    assert_eq!(root.get_u64("timeout"), Some(30));
+    assert_eq!(root.get_str("missing"), None);
+
+    assert_eq!(root.get_as::<u16>("port"), Ok(Some(8080)));
+    assert_eq!(root.get_as::<u16>("missing"), Ok(None));
+    assert_eq!(root.get_as::<u16>("empty"), Ok(None));
+    assert_eq!(
+        root.get_as::<u16>("server"),
+        Err(ValueError::TypeMismatch {
+            expected: "u64",
+            found: ValueKind::String
+        })
+    );
+    assert_eq!(
+        root.get_as::<u8>("port"),
+        Err(ValueError::OutOfRange {
+            expected: "u8",
+            found: ValueKind::Uint
+        })
+    );
+
+    let names = root
+        .children_named("server")
+        .map(|server| server.get_str("name").unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+
+    let tags = root
+        .args_named("tag")
+        .map(|tag| tag.value_as::<&str>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, ["x", "y"]);
+
+    let entry = root.get("server").unwrap();
+    assert!(entry.as_arg().is_none());
+    assert_eq!(entry.as_node().unwrap().get_str("name"), Some("b"));
+    assert_eq!(root.get("port").unwrap().value_as::<i32>(), Ok(Some(8080)));
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "builder")]
+
+use zdoc::{
+    Entry, ValueError, ValueKind,
+    text::document_from_text,
+    view::{ViewEntry, ViewError, ViewErrorKind, Viewable},
+};
+use zdoc_derive::ZdocView;
+
+#[derive(ZdocView)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), expect(dead_code))]
+struct Server {
+    host: String,
+    port: u16,
+    tls: Option<bool>,
+}
+
+#[derive(ZdocView)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), expect(dead_code))]
+struct Config {
+    name: String,
+    r#type: Option<String>,
+    servers: Vec<Server>,
+    ports: Vec<u32>,
+    primary: Option<Server>,
+}
+
+const DOC: &str = r#"{
+    name = "app"
+    servers = {
+        { host = "a", port = 80u, tls = true }
+        { host = "b", port = 8080u, tls = null }
+    }
+    ports = { 1u, 2u, 3u }
+    primary = { host = "c", port = 443u }
+}"#;
+
+#[test]
+fn getters() {
+    let doc = document_from_text(DOC).unwrap();
+    let config = ConfigView::new(doc.root());
+    config.validate().unwrap();
+
+    assert_eq!(config.name().unwrap(), "app");
+    assert!(config.r#type().is_none());
+    let servers = config
+        .servers()
+        .map(|server| (server.host().unwrap(), server.port().unwrap(), server.tls()))
+        .collect::<Vec<_>>();
+    assert_eq!(servers, [("a", 80, Some(Ok(true))), ("b", 8080, None)]);
+    assert_eq!(
+        config.ports().collect::<Result<Vec<_>, _>>().unwrap(),
+        [1, 2, 3]
+    );
+    assert_eq!(config.primary().unwrap().unwrap().host().unwrap(), "c");
+}
+
+#[test]
+fn errors() {
+    let doc = document_from_text(
+        r#"{
+            name = 1
+            servers = { { host = "a", port = 70000u }, "b" }
+            ports = { 1u, -2 }
+            primary = {}
+        }"#,
+    )
+    .unwrap();
+    let root = doc.root();
+    let servers = root.children().get("servers").unwrap();
+    let config = ConfigView::new(root);
+
+    let error = config.name().unwrap_err();
+    assert_eq!(
+        error,
+        ViewError {
+            node: root.id(),
+            entry: ViewEntry::Field("name"),
+            error: ViewErrorKind::Value(ValueError::TypeMismatch {
+                expected: "str",
+                found: ValueKind::Int,
+            }),
+        }
+    );
+    assert_eq!(config.validate(), Err(error));
+    assert_eq!(
+        error.to_string(),
+        "expected str, found int, field `name` of node #0"
+    );
+
+    // Arguments in a list of structs are skipped when iterating.
+    let server = config.servers().next().unwrap();
+    assert_eq!(config.servers().count(), 1);
+    assert_eq!(
+        server.port().unwrap_err().error,
+        ViewErrorKind::Value(ValueError::OutOfRange {
+            expected: "u16",
+            found: ValueKind::Uint,
+        })
+    );
+    assert!(server.tls().is_none());
+    // Arguments come before children in a node.
+    assert_eq!(
+        <Vec<Server> as Viewable>::validate(
+            root,
+            ViewEntry::Field("servers"),
+            Some(Entry::Child(servers)),
+        ),
+        Err(ViewError {
+            node: servers.id(),
+            entry: ViewEntry::Item(0),
+            error: ViewErrorKind::ExpectedNode,
+        })
+    );
+
+    let ports = config.ports().collect::<Vec<_>>();
+    assert_eq!(ports[0], Ok(1));
+    assert_eq!(ports[1].unwrap_err().entry, ViewEntry::Item(1));
+
+    // Empty nodes are `None`.
+    assert!(config.primary().is_none());
+}
+
+#[test]
+fn missing() {
+    let doc = document_from_text(r#"{ name = "app", servers = 1 }"#).unwrap();
+    let config = ConfigView::new(doc.root());
+    assert_eq!(config.servers().count(), 0);
+    assert_eq!(config.ports().count(), 0);
+    assert_eq!(
+        config.validate().unwrap_err().error,
+        ViewErrorKind::ExpectedNode
+    );
+
+    let doc = document_from_text(r#"{ name = "app", servers = {} }"#).unwrap();
+    let error = ConfigView::new(doc.root()).validate().unwrap_err();
+    assert_eq!(error.entry, ViewEntry::Field("ports"));
+    assert_eq!(error.error, ViewErrorKind::Missing);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serialized() {
+    let config = Config {
+        name: "app".into(),
+        r#type: Some("web".into()),
+        servers: vec![Server {
+            host: "a".into(),
+            port: 80,
+            tls: Some(false),
+        }],
+        ports: vec![1, 2],
+        primary: None,
+    };
+    let doc = zdoc::serde::to_document(&config).unwrap();
+    let view = ConfigView::new(doc.root());
+    view.validate().unwrap();
+    assert_eq!(view.r#type().unwrap().unwrap(), "web");
+    let server = view.servers().next().unwrap();
+    assert_eq!(server.tls(), Some(Ok(false)));
+    assert_eq!(view.ports().collect::<Vec<_>>(), [Ok(1), Ok(2)]);
+    assert!(view.primary().is_none());
+}